@@ -0,0 +1,152 @@
+use dgame::client::EntityId;
+use dgame::engine::get_collisions;
+use dgame::math::polygon::Polygon;
+use dgame::math::vec::*;
+use dgame::platform::null::NullPlatform;
+use dgame::render::{render, View};
+use dgame::world::grid::{construct_demo_ship, construct_demo_world};
+use dgame::world::Entity;
+use gamemath::{Mat3, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::PI;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Each benchmark runs for at least this long.
+const MEASUREMENT_TIME: Duration = Duration::from_millis(500);
+
+/// Workloads are generated from a fixed seed, so runs are comparable.
+const SEED: u64 = 7;
+
+/// Times geometry and collision hot paths on generated workloads and prints the results.
+/// With `cargo bench -- <filter>`, benchmarks whose name does not contain it are skipped.
+fn main() {
+    // cargo passes `--bench` before the arguments given after `--`
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    let enabled = |name: &str| filter.as_ref().is_none_or(|f| name.contains(f.as_str()));
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    for vertices in [8, 64] {
+        let a = regular_polygon(&mut rng, vertices, 50.0);
+        let b = translation(Vec2::new(25.0, 10.0)) * regular_polygon(&mut rng, vertices, 50.0);
+
+        let name = format!("polygon_intersection/{}", vertices);
+        if enabled(&name) {
+            measure(&name, || a.clone().intersection(b.clone()));
+        }
+
+        let far = translation(Vec2::new(120.0, 0.0)) * b.clone();
+        let name = format!("intercept_polygon/{}", vertices);
+        if enabled(&name) {
+            measure(&name, || a.intercept_polygon(&far, Vec2::new(-100.0, 5.0)));
+        }
+
+        let (a_pieces, far_pieces) = (a.decompose_convex(), far.decompose_convex());
+        let name = format!("swept_collision/{}", vertices);
+        if enabled(&name) {
+            measure(&name, || {
+                a.swept_collision_pieces(&a_pieces, &far, &far_pieces, Vec2::new(-100.0, 5.0))
+            });
+        }
+
+        let b_pieces = b.decompose_convex();
+        let name = format!("penetration/{}", vertices);
+        if enabled(&name) {
+            measure(&name, || Polygon::penetration_pieces(&a_pieces, &b_pieces));
+        }
+
+        let points: Vec<Vec2<f32>> = (0..100)
+            .map(|_| Vec2::new(rng.gen_range(-60.0, 60.0), rng.gen_range(-60.0, 60.0)))
+            .collect();
+        let name = format!("contains_point/{}", vertices);
+        if enabled(&name) {
+            measure(&name, || {
+                points.iter().filter(|p| a.contains_point(**p)).count()
+            });
+        }
+    }
+
+    let transform = translation(Vec2::new(30.0, -20.0)) * Mat3::rotation(0.7);
+    for vertices in [8, 256] {
+        let polygon = regular_polygon(&mut rng, vertices, 50.0);
+        let mut out = Polygon::default();
+        let name = format!("transform_points/{}", vertices);
+        if enabled(&name) {
+            measure(&name, || {
+                polygon.transform_into(transform, &mut out);
+                out.points.len()
+            });
+        }
+    }
+
+    let mut collisions = Vec::new();
+    for count in [10, 50, 500] {
+        let mut entities = scattered_ships(&mut rng, count);
+        let name = format!("get_collisions/{}", count);
+        if enabled(&name) {
+            measure(&name, || {
+                get_collisions(&mut entities, 1.0, &mut collisions);
+                collisions.len()
+            });
+        }
+    }
+
+    let name = "render/demo_world";
+    if enabled(name) {
+        let world = construct_demo_world(SEED);
+        let grid = world.grids.values().next().unwrap();
+        let focus = EntityId::new(grid.get_id(), grid.entities[0].get_id());
+
+        let size = Vec2::new(1600.0, 900.0);
+        let mut view = View::new(size, focus);
+        let mut target = NullPlatform::new(size);
+        measure(name, || render(&world, &focus, &mut view, &mut target));
+    }
+}
+
+fn measure<R, F: FnMut() -> R>(name: &str, mut f: F) {
+    // warm up caches and the allocator
+    black_box(f());
+
+    let started = Instant::now();
+    let mut iterations: u32 = 0;
+    while started.elapsed() < MEASUREMENT_TIME {
+        black_box(f());
+        iterations += 1;
+    }
+    let per_iteration = started.elapsed() / iterations;
+
+    println!(
+        "{:<28} {:>12.2} us/iter ({} iterations)",
+        name,
+        per_iteration.as_secs_f64() * 1e6,
+        iterations
+    );
+}
+
+/// Polygon with vertices on a circle, jittered a little.
+fn regular_polygon(rng: &mut StdRng, vertices: usize, radius: f32) -> Polygon {
+    let points: Vec<Vec2<f32>> = (0..vertices)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f32 / vertices as f32;
+            let radius = radius * rng.gen_range(0.9, 1.0);
+            Vec2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect();
+    Polygon::from(points)
+}
+
+/// Demo ships spread over an area where some of them are about to collide.
+fn scattered_ships(rng: &mut StdRng, count: usize) -> Vec<Entity> {
+    let side = (count as f32).sqrt() * 80.0;
+    (0..count)
+        .map(|_| {
+            let mut ship = construct_demo_ship();
+            ship.position.state = Vec2::new(rng.gen_range(0.0, side), rng.gen_range(0.0, side));
+            ship.position.velocity = Vec2::new(rng.gen_range(-5.0, 5.0), rng.gen_range(-5.0, 5.0));
+            ship.angle.state = rng.gen_range(0.0, 2.0 * PI);
+            ship
+        })
+        .collect()
+}
@@ -0,0 +1,141 @@
+use crate::net::DEFAULT_PORT;
+use crate::ui::menu::MenuChoice;
+use gamemath::Vec2;
+use std::path::PathBuf;
+
+/// Command line arguments.
+#[derive(Default)]
+pub struct Args {
+    /// Run a server in the background and connect to it.
+    pub host: bool,
+
+    /// Run only the server, without a window.
+    pub headless: bool,
+
+    /// Address of the server to connect to.
+    pub connect: Option<String>,
+
+    /// Directory for saved data, instead of the platform default.
+    pub data_dir: Option<PathBuf>,
+
+    /// Save input events to a file on exit.
+    pub record: Option<PathBuf>,
+
+    /// Play back input events from a file and exit when done.
+    pub replay: Option<PathBuf>,
+
+    /// Seed for world generation, instead of a random one.
+    pub seed: Option<u64>,
+
+    /// Scenario to start right away, skipping the menu.
+    pub scenario: Option<MenuChoice>,
+
+    /// Size of the window, instead of 1600x900.
+    pub resolution: Option<Vec2<f32>>,
+
+    /// Saved world to play, instead of generating one.
+    pub load: Option<PathBuf>,
+
+    /// Play a generated world with this many entities and log timings of each frame.
+    pub stress: Option<usize>,
+}
+
+impl Args {
+    pub fn parse() -> Result<Args, String> {
+        let mut args = Args::default();
+
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--host" => args.host = true,
+                "--headless" => args.headless = true,
+                "--connect" => {
+                    let address = iter.next().ok_or("--connect requires an address")?;
+                    args.connect = Some(with_default_port(address));
+                }
+                "--data-dir" => {
+                    let dir = iter.next().ok_or("--data-dir requires a directory")?;
+                    args.data_dir = Some(PathBuf::from(dir));
+                }
+                "--record" => {
+                    let file = iter.next().ok_or("--record requires a file")?;
+                    args.record = Some(PathBuf::from(file));
+                }
+                "--replay" => {
+                    let file = iter.next().ok_or("--replay requires a file")?;
+                    args.replay = Some(PathBuf::from(file));
+                }
+                "--seed" => {
+                    let seed = iter.next().ok_or("--seed requires a number")?;
+                    let seed = seed
+                        .parse()
+                        .map_err(|_| format!("invalid seed: {}", seed))?;
+                    args.seed = Some(seed);
+                }
+                "--scenario" => {
+                    let name = iter.next().ok_or("--scenario requires a name")?;
+                    args.scenario = Some(parse_scenario(&name)?);
+                }
+                "--resolution" => {
+                    let size = iter.next().ok_or("--resolution requires WIDTHxHEIGHT")?;
+                    args.resolution = Some(parse_resolution(&size)?);
+                }
+                "--load" => {
+                    let file = iter.next().ok_or("--load requires a file")?;
+                    args.load = Some(PathBuf::from(file));
+                }
+                "--stress" => {
+                    let count = iter
+                        .next()
+                        .ok_or("--stress requires a number of entities")?;
+                    let count = count
+                        .parse()
+                        .map_err(|_| format!("invalid number of entities: {}", count))?;
+                    args.stress = Some(count);
+                }
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+        if args.load.is_some() && (args.scenario.is_some() || args.host || args.connect.is_some()) {
+            return Err(
+                "--load cannot be combined with --scenario, --host or --connect".to_owned(),
+            );
+        }
+        if args.stress.is_some() && (args.load.is_some() || args.host || args.connect.is_some()) {
+            return Err("--stress cannot be combined with --load, --host or --connect".to_owned());
+        }
+        Ok(args)
+    }
+}
+
+fn parse_scenario(name: &str) -> Result<MenuChoice, String> {
+    match name {
+        "demo" => Ok(MenuChoice::SinglePlayer),
+        "survival" => Ok(MenuChoice::Survival),
+        "tutorial" => Ok(MenuChoice::Tutorial),
+        _ => Err(format!(
+            "unknown scenario: {} (expected demo, survival or tutorial)",
+            name
+        )),
+    }
+}
+
+fn parse_resolution(size: &str) -> Result<Vec2<f32>, String> {
+    let invalid = || format!("invalid resolution: {} (expected WIDTHxHEIGHT)", size);
+
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let width: u32 = width.parse().map_err(|_| invalid())?;
+    let height: u32 = height.parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok(Vec2::new(width as f32, height as f32))
+}
+
+fn with_default_port(address: String) -> String {
+    if address.contains(':') {
+        address
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    }
+}
@@ -1,23 +1,72 @@
-use crate::world::{Entity, World};
-use crate::math::lu::solve_lu;
-use crate::math::vec::*;
-use crate::render::{render, View};
+use crate::engine::TickReport;
+use crate::events::{Events, WorldEvent};
+use crate::world::tutorial;
+use crate::world::{BlockRegistry, Entity, Faction, World};
+use crate::music::Music;
+use crate::net::remote::Remote;
+use crate::net::protocol::ServerMessage;
+use crate::persistence::{
+    self, autosave::Autosave, blueprint, png, svg, watcher::DirectoryWatcher, LoadError,
+};
+use crate::platform::{DrawTarget, Event, WindowEvent};
+use crate::profiling;
+use crate::paths::Paths;
+use crate::render::{render, Textures, View};
+use crate::settings::Settings;
+use crate::statistics::Statistics;
+use crate::ui::action_queue::{ActionQueue, QueuedAction};
+use crate::ui::blueprints::BlueprintLibrary;
+use crate::ui::chat::Chat;
 use crate::ui::hud::Hud;
+use crate::ui::missions::MissionsPanel;
+use crate::ui::power::PowerPanel;
+use crate::ui::statistics::StatisticsScreen;
+use crate::ui::stats::Stats;
+use crate::ui::touch_controls::TouchControls;
+use crate::ui::trade::TradeWindow;
 use crate::ui::user_controls::{Action, UserControls};
 use gamemath::Vec2;
-use sdl2::event::Event;
-use sdl2::render::{Canvas, RenderTarget};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::SystemTime;
 
 pub struct Client {
     pub view: View,
     hud: Hud,
+    chat: Chat,
+    trade: TradeWindow,
+    missions: MissionsPanel,
+    power: PowerPanel,
+    library: BlueprintLibrary,
+    stats: Stats,
+    statistics: Statistics,
+    statistics_screen: StatisticsScreen,
+    settings: Settings,
+    paths: Paths,
+    /// Reloads toolbar blueprints when saved entities change on disk.
+    blueprint_watcher: Option<DirectoryWatcher>,
+    autosave: Autosave,
 
     user_controls: UserControls,
+    touch_controls: TouchControls,
+    /// Actions of all panels, applied in the order of ticks they were issued on.
+    actions: ActionQueue,
+    /// Ticks of the client so far, used to stamp actions.
+    tick: u64,
 
     controlled_entity: EntityId,
+    /// Entity locked as the target, shown on the HUD and used by the autopilot.
+    target: Option<EntityId>,
+    music: Option<Music>,
+    blocks: BlockRegistry,
+
+    /// When connected to a server, actions are sent there instead of being applied locally.
+    remote: Option<Remote>,
+    /// Set by the screenshot key, the frame is saved once it is drawn.
+    screenshot: bool,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct EntityId {
     pub entity_id: u64,
     pub grid_id: u64,
@@ -30,72 +79,527 @@ impl EntityId {
 }
 
 impl Client {
-    pub fn new(resolution: Vec2<f32>, controlled_entity: EntityId) -> Self {
+    pub fn new(
+        resolution: Vec2<f32>,
+        controlled_entity: EntityId,
+        settings: Settings,
+        paths: Paths,
+        blocks: BlockRegistry,
+    ) -> Self {
+        let user_controls = UserControls::new(settings.key_bindings.clone());
         Client {
-            view: View::new(resolution, controlled_entity),
-            hud: Hud::new(resolution),
-            user_controls: UserControls::default(),
+            view: View {
+                follow: settings.camera_follow,
+                textures: Textures::new(&paths),
+                ..View::new(resolution, controlled_entity)
+            },
+            hud: Hud::new(resolution, settings.ui_scale, &blocks)
+                .with_minimap_range(settings.minimap_range),
+            chat: Chat::new(resolution, settings.ui_scale),
+            trade: TradeWindow::new(resolution, settings.ui_scale),
+            missions: MissionsPanel::new(resolution, settings.ui_scale),
+            power: PowerPanel::new(resolution, settings.ui_scale),
+            library: BlueprintLibrary::new(resolution, settings.ui_scale),
+            stats: Stats::new(resolution, settings.ui_scale),
+            statistics: Statistics::load(&paths),
+            statistics_screen: StatisticsScreen::new(resolution, settings.ui_scale),
+            autosave: Autosave::new(settings.autosave_interval, &paths),
+            settings,
+            paths,
+            blueprint_watcher: None,
+            user_controls,
+            touch_controls: TouchControls::default(),
+            actions: ActionQueue::default(),
+            tick: 0,
 
             controlled_entity,
+            target: None,
+            music: None,
+            blocks,
+            remote: None,
+            screenshot: false,
+        }
+    }
+
+    pub fn connect(&mut self, remote: Remote) {
+        self.remote = Some(remote);
+    }
+
+    pub fn play_music(&mut self, music: Music) {
+        self.music = Some(music);
+    }
+
+    /// While typing into chat, keys are not game controls.
+    pub fn is_typing(&self) -> bool {
+        self.chat.is_typing()
+            || self.hud.is_typing()
+            || self.trade.is_open()
+            || self.power.is_open()
+            || self.library.is_open()
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.remote.is_some()
+    }
+
+    /// Replaces the world with latest snapshot from the server and publishes its events.
+    /// Returns false when the connection has been lost.
+    pub fn receive(&mut self, world: &mut World, events: &mut Events) -> bool {
+        let remote = match &mut self.remote {
+            Some(remote) => remote,
+            None => return true,
+        };
+
+        for message in remote.receive() {
+            match message {
+                ServerMessage::Welcome { entity } => {
+                    self.controlled_entity = entity;
+                    self.view.focus = entity;
+                }
+                ServerMessage::Snapshot {
+                    world: snapshot,
+                    events: snapshot_events,
+                } => {
+                    *world = *snapshot;
+                    events.extend(snapshot_events);
+                }
+                ServerMessage::Chat { author, text } => self.chat.push(&author, &text),
+            }
         }
+        !remote.is_closed()
     }
 
     pub fn load(&mut self) {
-        self.hud.load_saved_entities(self.view.size);
+        self.load_blueprints();
+        self.blueprint_watcher = self.paths.entities().ok().map(DirectoryWatcher::new);
     }
 
-    pub fn tick(&mut self, world: &mut World) {
-        self.controlled_entity = world.find_entity(&self.controlled_entity);
+    pub fn tick(&mut self, world: &mut World, events: &mut Events) {
+        for event in events.poll() {
+            self.handle_world_event(world, &event);
+        }
+        match world.find_entity(&self.controlled_entity) {
+            Some(found) => self.controlled_entity = found,
+            None => self.recover_control(world),
+        }
+        self.statistics.tick(world, &self.controlled_entity);
+        // the world of a server is saved there
+        if self.remote.is_none() {
+            self.autosave.tick(world, &self.controlled_entity, &self.paths);
+        }
+
+        if let Some(music) = &mut self.music {
+            music.tick(world, &self.controlled_entity);
+        }
+
+        if let Some(watcher) = &mut self.blueprint_watcher {
+            if watcher.poll() {
+                self.load_blueprints();
+            }
+        }
 
         self.view.tick();
-        self.hud.tick(world, self.controlled_entity);
+        self.view.particles.tick(world);
+        self.target = self.target.and_then(|target| world.find_entity(&target));
+        self.user_controls
+            .tick(world, &self.controlled_entity, self.target);
+        self.hud.set_throttle(self.user_controls.throttle());
+        self.hud.set_assists(
+            self.user_controls.is_holding_heading(),
+            self.user_controls.is_matching_velocity(),
+        );
+        {
+            let _span = profiling::span("hud");
+            self.hud.tick(world, &self.view, self.controlled_entity);
+            self.hud
+                .update_target(world, &self.view, self.controlled_entity, self.target);
+        }
+        self.trade.tick(world, &self.controlled_entity, &self.blocks);
+        self.missions.tick(world, &self.controlled_entity);
+        self.power.tick(world, &self.controlled_entity);
 
-        let actions = self
-            .user_controls
-            .poll_actions()
-            .chain(self.hud.poll_actions());
+        self.actions.set_tick(self.tick);
+        self.actions.append(self.user_controls.poll_actions());
+        self.actions.append(self.hud.poll_actions());
+        self.actions.extend(self.chat.poll_actions());
+        self.actions.extend(self.trade.poll_actions());
+        self.actions.extend(self.power.poll_actions());
+        self.actions.extend(self.library.poll_actions());
 
-        for action in actions {
+        let mut outgoing = Vec::new();
+        for QueuedAction { action, .. } in self.actions.poll() {
             let action = Client::map_action(&self.view, action);
+            if let Action::JoinEntity { .. } = &action {
+                self.statistics.block_placed();
+            }
             if let Action::LoadEntity { filename } = action {
-                Client::spawn_entity(world, filename, self.controlled_entity);
-            } else if let Some(entity) = world.get_entity_mut(&self.controlled_entity) {
-                entity.apply_action(action);
+                let entity = self
+                    .paths
+                    .entities()
+                    .map_err(LoadError::from)
+                    .and_then(|dir| Entity::load_from_file(dir.join(filename).into()));
+                match (entity, &mut self.remote) {
+                    (Ok(entity), Some(remote)) => remote.spawn_entity(entity),
+                    (Ok(entity), None) => {
+                        world.spawn_blueprint(&self.controlled_entity, entity, &self.blocks)
+                    }
+                    (Err(e), _) => eprintln!("cannot load entity: {}", e),
+                }
+            } else if let Action::SaveEntity = action {
+                if let Some(entity) = world.get_entity_mut(&self.controlled_entity) {
+                    match entity.save_to_file(&self.paths) {
+                        Ok(()) => tutorial::blueprint_saved(world),
+                        Err(e) => eprintln!("cannot save entity: {}", e),
+                    }
+                }
+            } else if let Action::SaveEntityAs { name } = action {
+                if let Some(entity) = world.get_entity_mut(&self.controlled_entity) {
+                    let mut blueprint = entity.clone();
+                    blueprint.name = Some(name);
+                    match blueprint.save_to_file(&self.paths) {
+                        Ok(()) => {
+                            self.load_blueprints();
+                            tutorial::blueprint_saved(world);
+                        }
+                        Err(e) => eprintln!("cannot save entity: {}", e),
+                    }
+                }
+            } else if let Action::ExportEntity = action {
+                if let Some(entity) = world.get_entity_mut(&self.controlled_entity) {
+                    let svg = svg::export_entity(entity);
+                    let written = self.paths.exports().and_then(|dir| {
+                        let path = dir.join(entity.file_name() + ".svg");
+                        persistence::write_atomic(&path, svg.as_bytes()).map(|_| path)
+                    });
+                    match written {
+                        Ok(path) => println!("exported {}", path.display()),
+                        Err(e) => eprintln!("cannot export entity: {}", e),
+                    }
+                }
+            } else if let Action::ExportText = action {
+                if let Some(entity) = world.get_entity(&self.controlled_entity) {
+                    match entity.save_ron_to_file(&self.paths) {
+                        Ok(path) => {
+                            println!("exported {}", path.display());
+                            self.load_blueprints();
+                        }
+                        Err(e) => eprintln!("cannot export entity: {}", e),
+                    }
+                }
+            } else if let Action::DeleteBlueprint { filename } = action {
+                let deleted = self
+                    .paths
+                    .entities()
+                    .and_then(|dir| blueprint::delete(&dir.join(filename)));
+                match deleted {
+                    Ok(()) => self.load_blueprints(),
+                    Err(e) => eprintln!("cannot delete blueprint: {}", e),
+                }
+            } else if let Action::RenameBlueprint { filename, name } = action {
+                self.rename_blueprint(&filename, name);
+            } else if let Action::ScaleUi { factor } = action {
+                self.settings.scale_ui(factor);
+                self.hud.set_ui_scale(self.settings.ui_scale);
+                self.chat.set_ui_scale(self.settings.ui_scale);
+                self.trade.set_ui_scale(self.settings.ui_scale);
+                self.missions.set_ui_scale(self.settings.ui_scale);
+                self.power.set_ui_scale(self.settings.ui_scale);
+                self.library.set_ui_scale(self.settings.ui_scale);
+                self.stats.set_ui_scale(self.settings.ui_scale);
+                self.statistics_screen.set_ui_scale(self.settings.ui_scale);
+                self.save_settings();
+            } else if let Action::ToggleStats = action {
+                self.stats.toggle();
+            } else if let Action::ToggleStatistics = action {
+                self.statistics_screen.toggle();
+            } else if let Action::ToggleRotationLock = action {
+                self.view.rotation_lock = !self.view.rotation_lock;
+            } else if let Action::ToggleDebugDraw = action {
+                self.view.debug_draw = !self.view.debug_draw;
+            } else if let Action::Screenshot = action {
+                self.screenshot = true;
+            } else if let Action::SendChat { text } = action {
+                if let Some(remote) = &mut self.remote {
+                    remote.send_chat(text);
+                } else {
+                    self.chat.push("you", &text);
+                }
+            } else if let Action::SelectTarget { position } = action {
+                let controlled = self.controlled_entity;
+                self.target = world
+                    .entity_at(controlled.grid_id, position)
+                    .or_else(|| {
+                        // small targets are easy to miss, so the first one on the way counts
+                        let origin = world.get_entity(&controlled)?.position.state;
+                        let towards = position - origin;
+                        world
+                            .raycast(controlled.grid_id, origin, towards, towards.length())
+                            .map(|hit| hit.entity)
+                    })
+                    .filter(|target| target.entity_id != controlled.entity_id);
+            } else if let Action::TargetNearestHostile = action {
+                self.target = world.nearest_entity(&self.controlled_entity, |entity| {
+                    world.components.faction(entity) == Faction::Hostile
+                });
+            } else if let Action::CycleControl = action {
+                if let Some(next) = world.next_entity_of(&self.controlled_entity, Faction::Player) {
+                    self.take_control(next);
+                }
+            } else if self.remote.is_some() {
+                outgoing.push(action);
+            } else {
+                world.apply_action(&self.controlled_entity, action, &self.blocks, events);
+            }
+        }
+
+        if let Some(remote) = &mut self.remote {
+            if !outgoing.is_empty() {
+                remote.send_actions(outgoing);
             }
         }
+
+        // actions issued before the next tick belong to it
+        self.tick += 1;
+        self.user_controls.set_tick(self.tick);
+        self.hud.set_tick(self.tick);
+    }
+
+    fn handle_world_event(&mut self, world: &World, event: &WorldEvent) {
+        if let WorldEvent::FramesShifted(shifts) = event {
+            self.view.follow(world, shifts);
+        }
+        if let WorldEvent::BlueprintBought {
+            buyer,
+            station,
+            index,
+        } = event
+        {
+            if *buyer == self.controlled_entity.entity_id {
+                self.save_bought_blueprint(world, *station, *index);
+            }
+        }
+        self.hud.handle_world_event(event, self.controlled_entity);
+        self.statistics.handle_world_event(event, &self.controlled_entity);
+    }
+
+    fn resize(&mut self, size: Vec2<f32>) {
+        self.view.resize(size);
+        self.hud.set_view_size(size);
+        self.chat.set_view_size(size);
+        self.trade.set_view_size(size);
+        self.missions.set_view_size(size);
+        self.power.set_view_size(size);
+        self.library.set_view_size(size);
+        self.stats.set_view_size(size);
+        self.statistics_screen.set_view_size(size);
+    }
+
+    pub fn report_tick(&mut self, report: &TickReport) {
+        self.stats.report_tick(report);
+    }
+
+    fn take_control(&mut self, entity: EntityId) {
+        self.controlled_entity = entity;
+        self.target = None;
+        self.view.focus = entity;
+        self.hud.reset_focus();
+    }
+
+    /// The controlled entity is gone, so control another entity of the player.
+    /// A server may still send it in a later snapshot, so remote clients wait.
+    fn recover_control(&mut self, world: &World) {
+        if self.is_remote() {
+            return;
+        }
+        if let Some(next) = world.next_entity_of(&self.controlled_entity, Faction::Player) {
+            eprintln!("controlled entity is gone, switching to another one");
+            self.take_control(next);
+        }
+    }
+
+    /// Saves a blueprint bought from a station, once the purchase went through,
+    /// so it can be spawned from the toolbar.
+    fn save_bought_blueprint(&mut self, world: &World, station: u64, index: usize) {
+        let blueprint = world
+            .components
+            .stations
+            .get(station)
+            .and_then(|station| station.blueprints.get(index));
+
+        if let Some(blueprint) = blueprint {
+            match blueprint.entity.save_to_file(&self.paths) {
+                Ok(()) => self.load_blueprints(),
+                Err(e) => eprintln!("cannot save blueprint: {}", e),
+            }
+        }
+    }
+
+    /// Refreshes the toolbar and the library with saved entities on disk.
+    fn load_blueprints(&mut self) {
+        self.hud.load_saved_entities(&self.paths);
+        self.library.load(&self.paths);
+    }
+
+    /// Saves the blueprint under the new name, in the same format, and removes the old file.
+    fn rename_blueprint(&mut self, filename: &str, name: String) {
+        let dir = match self.paths.entities() {
+            Ok(dir) => dir,
+            Err(e) => return eprintln!("cannot rename blueprint: {}", e),
+        };
+        let old = dir.join(filename);
+        let mut entity = match Entity::load_from_file(old.clone().into()) {
+            Ok(entity) => entity,
+            Err(e) => return eprintln!("cannot rename blueprint: {}", e),
+        };
+        entity.name = Some(name);
+        entity.redistribute_weight();
+
+        let saved = if old.extension().is_some() {
+            entity.save_ron_to_file(&self.paths)
+        } else {
+            entity
+                .save_to_file(&self.paths)
+                .map(|()| dir.join(entity.file_name()))
+        };
+        match saved {
+            Ok(new) if new != old => {
+                if let Err(e) = blueprint::delete(&old) {
+                    eprintln!("cannot remove renamed blueprint: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("cannot rename blueprint: {}", e),
+        }
+        self.load_blueprints();
+    }
+
+    /// Creates a blueprint from an SVG image dropped on the window.
+    fn import_hull(&mut self, filename: &str) {
+        let path = std::path::Path::new(filename);
+        let hull = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|svg| svg::import_hull(&svg));
+
+        match hull {
+            Ok(hull) => {
+                let mut entity = Entity::new(hull, Vec::new());
+                entity.name = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+                match entity.save_to_file(&self.paths) {
+                    Ok(()) => self.load_blueprints(),
+                    Err(e) => eprintln!("cannot save imported hull: {}", e),
+                }
+            }
+            Err(e) => eprintln!("cannot import {}: {}", filename, e),
+        }
+    }
+
+    /// Adds this session to statistics of previous sessions on disk.
+    pub fn save_statistics(&self) {
+        if let Err(e) = self.statistics.save(&self.paths) {
+            eprintln!("cannot save statistics: {}", e);
+        }
+    }
+
+    fn save_settings(&self) {
+        if let Err(e) = self.settings.save(&self.paths) {
+            eprintln!("cannot save settings: {}", e);
+        }
     }
 
     fn map_action(view: &View, a: Action) -> Action {
-        let invert_transform = view.last_grid_to_screen;
         match a {
             Action::JoinEntity { mut entity } => {
-                entity.position.state =
-                    solve_lu(&invert_transform, entity.position.state.into_homogeneous())
-                        .into_cartesian();
+                entity.position.state = view.screen_to_grid(entity.position.state);
+                entity.angle.state += view.rotation;
                 Action::JoinEntity { entity }
             }
+            Action::MoveBlock {
+                index,
+                offset,
+                angle,
+            } => Action::MoveBlock {
+                index,
+                offset: view.screen_to_grid(offset),
+                angle: angle + view.rotation,
+            },
+            Action::SelectTarget { position } => Action::SelectTarget {
+                position: view.screen_to_grid(position),
+            },
+            Action::UpdateShape { new_shape } => Action::UpdateShape {
+                new_shape: Box::new(view.last_screen_to_grid * *new_shape),
+            },
+            Action::CutShape { cut } => Action::CutShape {
+                cut: Box::new(view.last_screen_to_grid * *cut),
+            },
             _ => a,
         }
     }
 
-    fn spawn_entity(world: &mut World, filename: String, controlling: EntityId) {
-        if let Ok(entity) = Entity::load_from_file(filename.into()) {
-            if let Some(grid) = world.grids.get_mut(&controlling.grid_id) {
-                let position = grid
-                    .get_entity(controlling.entity_id)
-                    .map(|e| e.position.state)
-                    .unwrap_or_default();
-                grid.spawn_entity(position, entity);
+    /// Draws the world with entities at `alpha` of the way from the previous tick.
+    pub fn render(&mut self, world: &World, alpha: f32, canvas: &mut dyn DrawTarget) {
+        self.view.alpha = alpha;
+        {
+            let _span = profiling::span("render");
+            render(&world, &self.controlled_entity, &mut self.view, canvas);
+            self.hud.render(canvas);
+            self.chat.render(canvas);
+            self.trade.render(canvas);
+            self.missions.render(canvas);
+            self.power.render(canvas);
+            self.library.render(canvas);
+        }
+        self.stats.tick(world, &self.view);
+        self.stats.render(canvas);
+        self.statistics_screen.render(&self.statistics, canvas);
+
+        if self.screenshot {
+            self.screenshot = false;
+            match self.save_screenshot(canvas) {
+                Ok(path) => println!("saved screenshot {}", path.display()),
+                Err(e) => eprintln!("cannot save screenshot: {}", e),
             }
         }
     }
 
-    pub fn render<T: RenderTarget>(&mut self, world: &World, canvas: &mut Canvas<T>) {
-        render(&world, &self.controlled_entity, &mut self.view, canvas);
-        self.hud.render(canvas);
+    /// Writes what was drawn to a PNG file named after the current time.
+    fn save_screenshot(&self, canvas: &mut dyn DrawTarget) -> Result<PathBuf, String> {
+        let size = canvas.size();
+        let pixels = canvas.read_pixels()?;
+        let png = png::encode(size.x as u32, size.y as u32, &pixels);
+        let millis = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = self
+            .paths
+            .screenshots()
+            .map_err(|e| e.to_string())?
+            .join(format!("screenshot-{}.png", millis));
+        persistence::write_atomic(&path, &png).map_err(|e| e.to_string())?;
+        Ok(path)
     }
 
     pub fn handle_event(&mut self, event: &Event) -> bool {
-        self.hud.handle_event(event) || self.user_controls.handle_event(event, &self.view)
+        if let Event::DropFile { filename, .. } = event {
+            self.import_hull(filename);
+            return true;
+        }
+        if let Event::Window {
+            win_event: WindowEvent::SizeChanged(width, height),
+            ..
+        } = event
+        {
+            self.resize(Vec2::new(*width as f32, *height as f32));
+            return true;
+        }
+
+        self.chat.handle_event(event)
+            || self.library.handle_event(event)
+            || self.hud.handle_event(event)
+            || self.trade.handle_event(event)
+            || self.missions.handle_event(event)
+            || self.power.handle_event(event)
+            || self.touch_controls.handle_event(event, &mut self.view)
+            || self.user_controls.handle_event(event, &self.view)
     }
 }
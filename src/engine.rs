@@ -1,52 +1,178 @@
-use crate::world::{Grid, World, Entity};
-use crate::render::View;
+use crate::events::{Events, WorldEvent};
+use crate::math::bounding_box::{BoundingBox, RectBounds};
+use crate::math::shape::Shape;
+use crate::math::vec::*;
+use crate::profiling;
+use crate::world::{debris, gravity, Grid, World, Entity};
 use gamemath::Vec2;
-use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-pub fn engine_tick(world: &mut World, view: &mut View) {
-    world.split_grids();
-    
-    absorb_common_insists(world, view);
+/// Time a tick may take before grid maintenance is deferred.
+const TICK_BUDGET: Duration = Duration::from_millis(8);
 
-    for grid in world.grids.values_mut() {
-        grid.tick_parent_relation();
+/// Grid maintenance runs at least this often, even when ticks are over budget.
+const MAX_DEFERRED_TICKS: u32 = 30;
 
-        entities_tick(grid);
+/// Entities may move this fraction of their size in one sub-step.
+const SUBSTEP_MOTION: f32 = 0.25;
+
+/// Most sub-steps a tick is split into.
+const MAX_SUBSTEPS: u32 = 8;
+
+/// Colliding entities are advanced this fraction of the way to the contact, so they
+/// do not end up touching and collide again when moving apart.
+const CONTACT_ALPHA: f32 = 0.9;
+
+/// Simulated time that passes in one tick.
+pub const TICK_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Most ticks run to catch up at once. After a longer stall, the simulation slows down
+/// instead of spending even more time catching up.
+const MAX_CATCH_UP_TICKS: u32 = 5;
+
+/// Converts real time into a number of ticks, so the world is simulated at a stable rate
+/// regardless of how long frames take. Time left over is carried into the next call.
+#[derive(Default)]
+pub struct Accumulator {
+    accumulated: Duration,
+    last: Option<Instant>,
+}
+
+impl Accumulator {
+    /// Number of ticks that are due since the last call. The first call runs one tick.
+    pub fn ticks_due(&mut self) -> u32 {
+        let now = Instant::now();
+        let last = match self.last.replace(now) {
+            Some(last) => last,
+            None => return 1,
+        };
+        self.accumulated += now - last;
+
+        let mut ticks = 0;
+        while self.accumulated >= TICK_DURATION && ticks < MAX_CATCH_UP_TICKS {
+            self.accumulated -= TICK_DURATION;
+            ticks += 1;
+        }
+        if ticks == MAX_CATCH_UP_TICKS {
+            self.accumulated = self.accumulated.min(TICK_DURATION);
+        }
+        ticks
     }
 
-    // world.relink();
+    /// Share of the next tick that has passed, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        let elapsed = self.last.map_or(Duration::default(), |last| last.elapsed());
+        ((self.accumulated + elapsed).as_secs_f32() / TICK_DURATION.as_secs_f32()).min(1.0)
+    }
+
+    /// Time until the next tick is due.
+    pub fn until_next_tick(&self) -> Duration {
+        let elapsed = self.last.map_or(Duration::default(), |last| last.elapsed());
+        TICK_DURATION.saturating_sub(self.accumulated + elapsed)
+    }
+}
+
+/// Simulates the world. Keeps buffers between ticks, so they are not allocated every tick.
+#[derive(Default)]
+pub struct Engine {
+    collisions: Vec<Collision>,
+    /// Share of the sub-step each entity moves before its earliest contact.
+    contact_alphas: Vec<f32>,
+    /// Positions and angles of entities at the start of a tick.
+    starts: Vec<(Vec2<f32>, f32)>,
+    /// Pairs of entities that collided in the current tick.
+    collision_pairs: usize,
 
-    world.join_grids();
+    over_budget: bool,
+    deferred_ticks: u32,
 }
 
-fn absorb_common_insists(world: &mut World, view: &mut View) {
-    view.focus = world.find_entity(&view.focus);
+/// How a tick went.
+pub struct TickReport {
+    pub duration: Duration,
+    /// Splitting and joining of grids was skipped, because the previous tick was over budget.
+    pub deferred_maintenance: bool,
+    /// Pairs of entities that collided, over all grids and sub-steps.
+    pub collisions: usize,
+}
+
+impl Engine {
+    /// Advances the world by one tick, publishing what happened to `events`.
+    pub fn tick(&mut self, world: &mut World, events: &mut Events) -> TickReport {
+        let started = Instant::now();
+        self.collision_pairs = 0;
+
+        let defer = self.over_budget && self.deferred_ticks < MAX_DEFERRED_TICKS;
+        if defer {
+            self.deferred_ticks += 1;
+        } else {
+            self.deferred_ticks = 0;
+
+            let _span = profiling::span("split");
+            world.split_grids();
+            world.migrate_entities();
+        }
+
+        {
+            let _span = profiling::span("gravity");
+            gravity::tick(world);
+        }
+
+        {
+            let _span = profiling::span("absorb");
+            let shifts = world.absorb_common_insists();
+            events.publish(WorldEvent::FramesShifted(shifts));
+        }
+
+        for grid in world.grids.values_mut() {
+            grid.tick_parent_relation();
+
+            entities_tick(grid, events, self);
+        }
+
+        // world.relink();
+
+        if !defer {
+            let _span = profiling::span("join");
+            world.join_grids();
+            debris::consolidate(world);
+        }
 
-    let common_insist = world.absorb_common_insist(view.focus.grid_id);
-    if let Some(common_insist) = common_insist {
-      view.offset += common_insist.state;
-      view.stars_position += common_insist;
+        let duration = started.elapsed();
+        self.over_budget = duration > TICK_BUDGET;
+        TickReport {
+            duration,
+            deferred_maintenance: defer,
+            collisions: self.collision_pairs,
+        }
     }
-    view.stars_position.velocity *= 0.999;
-    view.stars_position.state += view.stars_position.velocity;
 }
 
-fn entities_tick(grid: &mut Grid) {
+fn entities_tick(grid: &mut Grid, events: &mut Events, buffers: &mut Engine) {
+    buffers.starts.clear();
+    grid.contacts.clear();
+    buffers.starts.extend(
+        grid.entities
+            .iter()
+            .map(|entity| (entity.position.state, entity.angle.state)),
+    );
+
     // update velocity
+    let forces_span = profiling::span("forces");
     for entity in &mut grid.entities {
         entity.tick();
 
         let mut dv = Vec2::default();
         let mut dfv = 0.0;
 
-        // Center gravity
-        // const distance = c.r.difference(massPoint.r);
-        // const force = c.mass * massPoint.mass * G / distance.length / distance.length;
-        // const a = force / massPoint.mass;
-        // dv.add(distance.product(a / distance.length));
-
         // Thrust
         let thrust = entity.force();
+        if entity.is_asleep() {
+            if thrust.force.length() == 0.0 && thrust.torque == 0.0 && entity.is_still() {
+                continue;
+            }
+            entity.wake();
+        }
         dv += thrust.force * (1.0 / entity.mass);
         dfv += thrust.torque / entity.mass_angular;
 
@@ -54,63 +180,281 @@ fn entities_tick(grid: &mut Grid) {
         entity.angle.velocity += dfv;
     }
 
-    // collision detection
-    let collisions = get_collisions(&grid.entities);
-    // update state
-    for (index, entity) in &mut grid.entities.iter_mut().enumerate() {
-        if let Some(_collision) = collisions.get(&index) {
-            entity.position.velocity = Vec2::default();
-            entity.angle.velocity = 0.0;
+    drop(forces_span);
+
+    // collision detection, in sub-steps when entities move far compared to their size
+    let _span = profiling::span("collisions");
+    let substeps = substeps(&grid.entities);
+    let fraction = 1.0 / substeps as f32;
+    let collisions = &mut buffers.collisions;
+    let contact_alphas = &mut buffers.contact_alphas;
+    for _ in 0..substeps {
+        get_collisions(&mut grid.entities, fraction, collisions);
+        contact_alphas.clear();
+        contact_alphas.resize(grid.entities.len(), 1.0);
+        for collision in collisions.iter() {
+            grid.contacts.extend_from_slice(&collision.intersections);
+            events.publish(WorldEvent::Collision {
+                grid_id: grid.get_id(),
+                entities: [
+                    grid.entities[collision.a].get_id(),
+                    grid.entities[collision.b].get_id(),
+                ],
+            });
+            for index in [collision.a, collision.b] {
+                contact_alphas[index] = contact_alphas[index].min(collision.alpha);
+            }
         }
 
-        entity.position.state += entity.position.velocity;
-        entity.angle.state += entity.angle.velocity;
+        // update state
+        for (entity, alpha) in grid.entities.iter_mut().zip(contact_alphas.iter_mut()) {
+            if *alpha < 1.0 {
+                // advance to just before the earliest contact
+                *alpha *= CONTACT_ALPHA;
+            }
+            entity.position.state += entity.position.velocity * fraction * *alpha;
+            entity.angle.state += entity.angle.velocity * fraction * *alpha;
+        }
+
+        // collisions are found in a fixed order of pairs, so all copies of the world
+        // resolve them the same way
+        buffers.collision_pairs += collisions.len();
+        for collision in collisions.iter() {
+            push_apart(&mut grid.entities, collision);
+            resolve_collision(&mut grid.entities, collision);
+        }
+
+        // the rest of the sub-step is moved with velocities after the contacts, and
+        // anything it runs into is caught by the next collision check
+        for (entity, alpha) in grid.entities.iter_mut().zip(contact_alphas.iter()) {
+            if *alpha < 1.0 {
+                entity.position.state += entity.position.velocity * fraction * (1.0 - alpha);
+                entity.angle.state += entity.angle.velocity * fraction * (1.0 - alpha);
+            }
+        }
+
+        // projected for rendering and, if nothing moves it, the next collision check
+        for entity in &mut grid.entities {
+            entity.update_grid_shape();
+        }
+    }
+    for (entity, (position, angle)) in grid.entities.iter_mut().zip(&buffers.starts) {
+        entity.update_sleep();
+        entity.record_motion(*position, *angle);
+    }
+    grid.update_bounds();
+}
+
+/// Number of sub-steps needed so that no entity moves, by its velocity and spin, further
+/// than a fraction of its size in one step.
+fn substeps(entities: &[Entity]) -> u32 {
+    let mut substeps: u32 = 1;
+    for entity in entities.iter().filter(|entity| !entity.is_asleep()) {
+        let size = entity.shape.bounding_box().size();
+        if size <= 0.0 {
+            continue;
+        }
+        let motion = entity.position.velocity.length() + entity.angle.velocity.abs() * size * 0.5;
+        let needed = (motion / (size * SUBSTEP_MOTION)).ceil() as u32;
+        substeps = substeps.max(needed.min(MAX_SUBSTEPS));
     }
+    substeps
 }
 
-fn get_collisions(entities: &[Entity]) -> HashMap<usize, Collision> {
-    let mut collisions = HashMap::new();
-    // polygon cache
-    let mut polys = Vec::with_capacity(entities.len());
-    for entity in entities {
-        polys.push(entity.projection_to_grid() * entity.shape.clone());
+/// Moves entities that already overlapped before the sub-step out of each other, along the
+/// normal of the contact. The lighter entity is moved the larger share of the depth.
+fn push_apart(entities: &mut [Entity], collision: &Collision) {
+    let normal = match collision.normal {
+        Some(normal) if collision.depth > 0.0 => normal,
+        _ => return,
+    };
+    let (low, high) = entities.split_at_mut(collision.a);
+    let (a, b) = (&mut high[0], &mut low[collision.b]);
+
+    let inverse = |value: f32| if value > 0.0 { 1.0 / value } else { 0.0 };
+    let (mass_a, mass_b) = (inverse(a.mass), inverse(b.mass));
+    let total = mass_a + mass_b;
+    if total <= 0.0 {
+        return;
     }
+    a.position.state += normal * (collision.depth * mass_a / total);
+    b.position.state -= normal * (collision.depth * mass_b / total);
+}
 
-    for (index, entity) in entities.iter().enumerate() {
-        for (collided_index, collided_entity) in entities.iter().enumerate() {
-            if index <= collided_index {
-                continue;
-            }
+/// Exchanges an impulse between the two entities of a collision. The impulse acts along
+/// the contact normal, or the line between their centers when there is none, and is
+/// scaled by restitution and friction of the surfaces at the contact.
+fn resolve_collision(entities: &mut [Entity], collision: &Collision) {
+    let (low, high) = entities.split_at_mut(collision.a);
+    let (a, b) = (&mut high[0], &mut low[collision.b]);
+    a.wake();
+    b.wake();
 
-            let res = polys[collided_index].intercept_polygon(
-                &polys[index],
-                entity.position.velocity - collided_entity.position.velocity,
-            );
-
-            if let Some((alpha, intersections)) = res {
-                collisions.insert(
-                    index,
-                    Collision {
-                        alpha,
-                        intersections: intersections.clone(),
-                    },
-                );
-                collisions.insert(
-                    collided_index,
-                    Collision {
-                        alpha,
-                        intersections: intersections.clone(),
-                    },
-                );
+    let normal = match collision.normal {
+        Some(normal) => normal * -1.0,
+        None => {
+            let between = b.position.state - a.position.state;
+            let distance = between.length();
+            if distance <= f32::EPSILON {
+                return;
             }
+            between * (1.0 / distance)
         }
+    };
+    let intersections = &collision.intersections;
+    let contact = if intersections.is_empty() {
+        (a.position.state + b.position.state) * 0.5
+    } else {
+        intersections
+            .iter()
+            .fold(Vec2::default(), |sum, p| sum + *p)
+            * (1.0 / intersections.len() as f32)
+    };
+
+    let arm_a = contact - a.position.state;
+    let arm_b = contact - b.position.state;
+    let relative = (b.position.velocity + arm_b.perpendicular() * b.angle.velocity)
+        - (a.position.velocity + arm_a.perpendicular() * a.angle.velocity);
+    let approach = relative.dot(normal);
+    if approach >= 0.0 {
+        return;
     }
 
-    collisions
+    let (restitution, friction) = a.material_at(contact).contact(&b.material_at(contact));
+    let inverse = |value: f32| if value > 0.0 { 1.0 / value } else { 0.0 };
+    let (mass_a, mass_b) = (inverse(a.mass), inverse(b.mass));
+    let (inertia_a, inertia_b) = (inverse(a.mass_angular), inverse(b.mass_angular));
+    // inverse of the mass the impulse meets along `direction`
+    let resistance = |direction: Vec2<f32>| {
+        let torque_a = arm_a.perpendicular().dot(direction);
+        let torque_b = arm_b.perpendicular().dot(direction);
+        mass_a + mass_b + torque_a * torque_a * inertia_a + torque_b * torque_b * inertia_b
+    };
+    let mut apply = |impulse: Vec2<f32>| {
+        b.position.velocity += impulse * mass_b;
+        b.angle.velocity += arm_b.perpendicular().dot(impulse) * inertia_b;
+        a.position.velocity -= impulse * mass_a;
+        a.angle.velocity -= arm_a.perpendicular().dot(impulse) * inertia_a;
+    };
+
+    let normal_impulse = -(1.0 + restitution) * approach / resistance(normal);
+    apply(normal * normal_impulse);
+
+    let sliding = relative - normal * approach;
+    let speed = sliding.length();
+    if speed > f32::EPSILON {
+        let tangent = sliding * (1.0 / speed);
+        let friction_impulse = (speed / resistance(tangent)).min(friction * normal_impulse);
+        apply(tangent * -friction_impulse);
+    }
+}
+
+/// Finds collisions between entities into `collisions`, one for each pair that touches,
+/// while they move by `fraction` of their velocity.
+pub fn get_collisions(entities: &mut [Entity], fraction: f32, collisions: &mut Vec<Collision>) {
+    collisions.clear();
+    for entity in entities.iter_mut() {
+        entity.update_grid_shape();
+    }
+    let entities = &*entities;
+    let poly = |index: usize| entities[index].grid_shape().unwrap();
+    let pieces = |index: usize| entities[index].grid_pieces().unwrap();
+
+    for (index, collided_index) in candidate_pairs(entities, fraction) {
+        let entity = &entities[index];
+        let collided_entity = &entities[collided_index];
+
+        // sweeping only finds entities moving into each other, not ones already inside
+        let overlap =
+            Shape::separation_pieces(pieces(collided_index), pieces(index), Vec2::default());
+        if let Some((depth, normal)) = overlap.filter(|(depth, _)| *depth > 0.0) {
+            collisions.push(Collision {
+                a: index,
+                b: collided_index,
+                alpha: 0.0,
+                intersections: poly(collided_index).contacts(poly(index)),
+                normal: Some(normal),
+                depth,
+            });
+            continue;
+        }
+
+        let path = (entity.position.velocity - collided_entity.position.velocity) * fraction;
+        let res = poly(collided_index).swept_collision_pieces(
+            pieces(collided_index),
+            poly(index),
+            pieces(index),
+            path,
+        );
+
+        if let Some((alpha, intersections)) = res {
+            // the axis that separates the pieces that touch is the normal of the contact
+            let normal =
+                Shape::separation_pieces(pieces(collided_index), pieces(index), path * alpha)
+                    .map(|(_, normal)| normal);
+            collisions.push(Collision {
+                a: index,
+                b: collided_index,
+                alpha,
+                intersections,
+                normal,
+                depth: 0.0,
+            });
+        }
+    }
+}
+
+/// Pairs of entities whose bounds, swept over `fraction` of the motion of a tick, overlap,
+/// except pairs of sleeping entities.
+/// Bounds are sorted along x, so each entity is only compared to entities next to it.
+/// Pairs are `(index, collided_index)` with the larger index first, sorted.
+fn candidate_pairs(entities: &[Entity], fraction: f32) -> Vec<(usize, usize)> {
+    let mut bounds: Vec<(usize, RectBounds)> = entities
+        .iter()
+        .enumerate()
+        .map(|(index, entity)| {
+            let mut bounds = entity.grid_shape().unwrap().bounding_box();
+            let mut moved = bounds.clone();
+            moved.translate(entity.position.velocity * fraction);
+            bounds += moved;
+            (index, bounds)
+        })
+        .collect();
+    bounds.sort_by(|a, b| {
+        a.1.top_left
+            .x
+            .partial_cmp(&b.1.top_left.x)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut pairs = Vec::new();
+    for (position, (index, a)) in bounds.iter().enumerate() {
+        for (other, b) in &bounds[position + 1..] {
+            if b.top_left.x > a.bottom_right.x {
+                break;
+            }
+            // sleeping entities do not move into each other
+            if entities[*index].is_asleep() && entities[*other].is_asleep() {
+                continue;
+            }
+            if a.intersects(b) {
+                pairs.push((*index.max(other), *index.min(other)));
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs
 }
 
-#[allow(dead_code)]
-struct Collision {
+/// Contact between entities of indices `a` and `b`, where `a > b`.
+pub struct Collision {
+    a: usize,
+    b: usize,
+    /// Share of the motion after which the entities touch.
     alpha: f32,
     intersections: Vec<Vec2<f32>>,
+    /// Unit normal of the contact, pointing away from `b`.
+    normal: Option<Vec2<f32>>,
+    /// How deep the entities overlapped before they moved, zero when they were apart.
+    depth: f32,
 }
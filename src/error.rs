@@ -0,0 +1,40 @@
+use std::fmt;
+
+/// Error the game recovers from, by reporting it instead of aborting.
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Encode(rmp_serde::encode::Error),
+    EncodeText(ron::Error),
+    /// Grid with this id is referenced, but does not exist.
+    MissingGrid(u64),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Encode(e) => write!(f, "cannot encode: {}", e),
+            Error::EncodeText(e) => write!(f, "cannot encode as text: {}", e),
+            Error::MissingGrid(id) => write!(f, "grid {} does not exist", id),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<rmp_serde::encode::Error> for Error {
+    fn from(e: rmp_serde::encode::Error) -> Self {
+        Error::Encode(e)
+    }
+}
+
+impl From<ron::Error> for Error {
+    fn from(e: ron::Error) -> Self {
+        Error::EncodeText(e)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::math::vec::*;
+use crate::ui::user_controls::Action;
+use crate::world::{FrameShifts, Insist};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use std::collections::HashMap;
+
+/// Something that happened in the world during a tick.
+#[serde_as]
+#[derive(Serialize, Deserialize)]
+pub enum WorldEvent {
+    /// Motion absorbed into frames of reference of the grids.
+    FramesShifted(#[serde_as(as = "HashMap<_, Insist<Vec2Serde<f32>>>")] FrameShifts),
+
+    /// Two entities of a grid collided and were stopped.
+    Collision { grid_id: u64, entities: [u64; 2] },
+
+    /// The entity paid a station for a copy of the blueprint at `index` of its blueprints.
+    BlueprintBought {
+        buyer: u64,
+        station: u64,
+        index: usize,
+    },
+
+    /// A player steered the entity, which its scripts see in `on_action`.
+    Steered { entity: u64, action: Action },
+}
+
+/// Events published by the engine, waiting for the client to react to them.
+#[derive(Default)]
+pub struct Events {
+    queue: Vec<WorldEvent>,
+}
+
+impl Events {
+    pub fn publish(&mut self, event: WorldEvent) {
+        self.queue.push(event);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, WorldEvent> {
+        self.queue.iter()
+    }
+
+    pub fn poll(&mut self) -> std::vec::Drain<'_, WorldEvent> {
+        self.queue.drain(..)
+    }
+}
+
+impl Extend<WorldEvent> for Events {
+    fn extend<I: IntoIterator<Item = WorldEvent>>(&mut self, events: I) {
+        self.queue.extend(events);
+    }
+}
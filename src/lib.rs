@@ -0,0 +1,22 @@
+pub mod cli;
+pub mod client;
+pub mod engine;
+pub mod error;
+pub mod events;
+pub mod math;
+pub mod memory;
+pub mod music;
+pub mod net;
+pub mod particles;
+pub mod paths;
+pub mod persistence;
+pub mod platform;
+pub mod profiling;
+pub mod recording;
+pub mod render;
+pub mod scripting;
+pub mod settings;
+pub mod stars;
+pub mod statistics;
+pub mod ui;
+pub mod world;
@@ -1,19 +1,28 @@
-mod client;
-mod engine;
-mod math;
-mod render;
-mod stars;
-mod ui;
-mod world;
-
-use client::{Client, EntityId};
-use engine::engine_tick;
+use dgame::cli;
+use dgame::client::{Client, EntityId};
+use dgame::engine::{Accumulator, Engine, TICK_DURATION};
+use dgame::events::Events;
+use dgame::music::Music;
+use dgame::net::{remote::Remote, server::Server, DEFAULT_PORT};
+use dgame::paths::Paths;
+use dgame::platform::{
+    sdl::SdlPlatform, AudioOutput, Color, DisplayMode, DrawTarget, Event, InputSource, Keycode,
+    Mod, Window,
+};
+use dgame::recording::{Recorder, Replay};
+use dgame::scripting::Scripts;
+use dgame::settings::Settings;
+use dgame::ui::menu::{Menu, MenuChoice};
+use dgame::world::difficulty::DifficultyLevel;
+use dgame::world::grid::construct_demo_world;
+use dgame::world::stress::construct_stress_world;
+use dgame::world::survival::construct_survival_world;
+use dgame::world::tutorial::construct_tutorial_world;
+use dgame::world::{systems_tick, BlockRegistry, Faction, World};
 use gamemath::Vec2;
-use world::grid::construct_demo_world;
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
+use std::net::TcpListener;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 fn is_exit_event(event: &Event) -> bool {
     match event {
@@ -26,45 +35,363 @@ fn is_exit_event(event: &Event) -> bool {
     }
 }
 
+fn is_fullscreen_toggle(event: &Event) -> bool {
+    match event {
+        Event::KeyDown {
+            keycode: Some(Keycode::Return),
+            keymod,
+            repeat: false,
+            ..
+        } => keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+        _ => false,
+    }
+}
+
+/// Display mode of the window and the one Alt+Enter switches to from a window.
+struct DisplayToggle {
+    current: DisplayMode,
+    fullscreen: DisplayMode,
+}
+
+impl DisplayToggle {
+    /// Starts in the mode from settings. Borderless is used for fullscreen when settings
+    /// ask for a window.
+    fn new(settings: &Settings) -> Self {
+        let fullscreen = match settings.display_mode {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            mode => mode,
+        };
+        DisplayToggle {
+            current: settings.display_mode,
+            fullscreen,
+        }
+    }
+
+    /// Switches between a window and fullscreen. The view and the HUD are laid out again
+    /// on the window event announcing the new size.
+    fn toggle<P: Window>(&mut self, platform: &mut P) {
+        let mode = if self.current == DisplayMode::Windowed {
+            self.fullscreen
+        } else {
+            DisplayMode::Windowed
+        };
+        match platform.set_display_mode(mode) {
+            Ok(()) => self.current = mode,
+            Err(e) => eprintln!("cannot change display mode: {}", e),
+        }
+    }
+}
+
+fn listen() -> std::io::Result<TcpListener> {
+    TcpListener::bind(("0.0.0.0", DEFAULT_PORT))
+}
+
+/// Shows the main menu until the player picks how to play. Returns None on exit.
+fn run_menu<P: Window + InputSource>(
+    platform: &mut P,
+    settings: &Settings,
+    display: &mut DisplayToggle,
+) -> Option<(MenuChoice, DifficultyLevel)> {
+    let mut menu = Menu::new(
+        settings.ui_scale,
+        settings.last_scenario.as_deref(),
+        settings.difficulty,
+    );
+    loop {
+        for event in platform.poll_events() {
+            if is_exit_event(&event) {
+                return None;
+            }
+            if is_fullscreen_toggle(&event) {
+                display.toggle(platform);
+                continue;
+            }
+            menu.handle_event(&event);
+        }
+        if let Some(choice) = menu.take_choice() {
+            return Some((choice, menu.difficulty()));
+        }
+
+        menu.tick();
+
+        platform.set_draw_color(Color::rgb(0, 0, 0));
+        platform.clear();
+        menu.render(platform);
+        platform.present();
+
+        ::std::thread::sleep(::std::time::Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
+
+/// Generates the world of a single player scenario.
+fn construct_world(choice: &MenuChoice, seed: u64) -> World {
+    match choice {
+        MenuChoice::Survival => construct_survival_world(seed),
+        MenuChoice::Tutorial => construct_tutorial_world(seed),
+        _ => construct_demo_world(seed),
+    }
+}
+
+/// Reads a saved world, exiting when it cannot be loaded.
+fn load_world(file: &Path) -> World {
+    match World::load_from_file(file) {
+        Ok(world) => world,
+        Err(e) => {
+            eprintln!("cannot load {}: {}", file.display(), e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Sets up the world and, in multiplayer, the connection to the server.
+/// Servers that are connected to choose their own difficulty and shape tolerance.
+fn start_game(
+    choice: MenuChoice,
+    settings: &Settings,
+    seed: u64,
+    paths: &Paths,
+) -> std::io::Result<(World, EntityId, Option<Remote>)> {
+    let difficulty = settings.difficulty;
+    let shape_tolerance = settings.shape_tolerance;
+    let address = match choice {
+        MenuChoice::SinglePlayer | MenuChoice::Survival | MenuChoice::Tutorial => {
+            let mut world = construct_world(&choice, seed);
+            world.difficulty = difficulty.difficulty();
+            world.shape_tolerance = shape_tolerance;
+            let grid_id = *world.grids.iter().next().unwrap().0;
+            let entity_id = world.grids[&grid_id].entities[0].get_id();
+            return Ok((world, EntityId::new(grid_id, entity_id), None));
+        }
+        MenuChoice::Host => {
+            let listener = listen()?;
+            let scripts = Scripts::load(paths);
+            std::thread::spawn(move || {
+                let mut world = construct_demo_world(seed);
+                world.difficulty = difficulty.difficulty();
+                world.shape_tolerance = shape_tolerance;
+                let blocks = BlockRegistry::with_builtin();
+                Server::new(listener, world, scripts, blocks)
+                    .expect("cannot start server")
+                    .run()
+            });
+            format!("127.0.0.1:{}", DEFAULT_PORT)
+        }
+        MenuChoice::Connect { address } => address,
+    };
+
+    let (remote, world, controlled) = Remote::connect(&address)?;
+    Ok((world, controlled, Some(remote)))
+}
+
+/// Prints timings of a frame in stress mode, one line per frame.
+fn log_frame(frame: u64, tick: Duration, render: Duration, world: &World) {
+    let entities: usize = world.grids.values().map(|grid| grid.entities.len()).sum();
+    println!(
+        "frame {:>6}: tick {:>7.2} ms, render {:>7.2} ms, {} grids, {} entities",
+        frame,
+        tick.as_secs_f64() * 1e3,
+        render.as_secs_f64() * 1e3,
+        world.grids.len(),
+        entities
+    );
+}
+
 fn main() {
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
-    let attributes = video_subsystem.gl_attr();
-
-    attributes.set_multisample_buffers(1);
-    attributes.set_multisample_samples(5);
-
-    let resolution = Vec2::new(1600.0, 900.0);
-    let window = video_subsystem
-        .window("Example", resolution.x as u32, resolution.y as u32)
-        .build()
-        .unwrap();
-
-    let mut canvas: Canvas<Window> = window.into_canvas().present_vsync().build().unwrap();
-    let mut event_pump = sdl_context.event_pump().unwrap();
-    let mut world = construct_demo_world();
-    let grid_id = *world.grids.iter().next().unwrap().0;
-    let entity_id = world.grids[&grid_id].entities[0].get_id();
-    let mut client = Client::new(resolution, EntityId::new(grid_id, entity_id));
+    let args = match cli::Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let paths = Paths::resolve(args.data_dir);
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+    if args.load.is_none() {
+        println!("world seed: {}", seed);
+    }
+
+    if args.headless {
+        let listener = listen().expect("cannot start server");
+        println!("listening on port {}", DEFAULT_PORT);
+        let world = match &args.load {
+            Some(file) => load_world(file),
+            None => construct_world(
+                args.scenario.as_ref().unwrap_or(&MenuChoice::SinglePlayer),
+                seed,
+            ),
+        };
+        let blocks = BlockRegistry::with_builtin();
+        Server::new(listener, world, Scripts::load(&paths), blocks)
+            .expect("cannot start server")
+            .run();
+        return;
+    }
+
+    let resolution = args.resolution.unwrap_or_else(|| Vec2::new(1600.0, 900.0));
+    let mut settings = Settings::load(&paths);
+    let mut platform = SdlPlatform::new(
+        "Example",
+        resolution,
+        settings.display_mode,
+        settings.display as i32,
+    )
+    .unwrap();
+    let mut display = DisplayToggle::new(&settings);
+
+    let mut recorder = args.record.map(Recorder::new);
+    let mut replay = args.replay.map(|file| match Replay::load(&file) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("cannot load replay: {}", e);
+            std::process::exit(2);
+        }
+    });
+
+    // recorded input starts in the game, not in the menu
+    let skip_menu = recorder.is_some() || replay.is_some();
+
+    let (mut world, controlled, remote) = if let Some(file) = &args.load {
+        let world = load_world(file);
+        let controlled = match world.next_entity_of(&EntityId::new(0, 0), Faction::Player) {
+            Some(controlled) => controlled,
+            None => {
+                eprintln!("cannot load {}: no player entity", file.display());
+                std::process::exit(2);
+            }
+        };
+        (world, controlled, None)
+    } else if let Some(count) = args.stress {
+        let world = construct_stress_world(count, seed);
+        let controlled = world
+            .next_entity_of(&EntityId::new(0, 0), Faction::Player)
+            .unwrap();
+        (world, controlled, None)
+    } else if args.host || args.connect.is_some() || args.scenario.is_some() || skip_menu {
+        let choice = match (args.connect, args.scenario) {
+            (Some(address), _) => MenuChoice::Connect { address },
+            (None, _) if args.host => MenuChoice::Host,
+            (None, Some(scenario)) => scenario,
+            (None, None) => MenuChoice::SinglePlayer,
+        };
+        start_game(choice, &settings, seed, &paths).expect("cannot start game")
+    } else {
+        loop {
+            let (choice, difficulty) = match run_menu(&mut platform, &settings, &mut display) {
+                Some(choice) => choice,
+                None => return,
+            };
+            settings.last_scenario = Some(choice.scenario());
+            settings.difficulty = difficulty;
+            if let Err(e) = settings.save(&paths) {
+                eprintln!("cannot save settings: {}", e);
+            }
+            match start_game(choice, &settings, seed, &paths) {
+                Ok(game) => break game,
+                Err(e) => eprintln!("cannot start game: {}", e),
+            }
+        }
+    };
+    let mut scripts = Scripts::load(&paths);
+    let blocks = BlockRegistry::with_builtin();
+    let music = Music::load(&paths, &settings);
+    if let Err(e) = platform.play(music.source()) {
+        eprintln!("cannot play music: {}", e);
+    }
+    let mut client = Client::new(platform.size(), controlled, settings, paths, blocks);
+    client.play_music(music);
+    if let Some(remote) = remote {
+        client.connect(remote);
+    }
 
     client.load();
 
+    let mut engine = Engine::default();
+    let mut events = Events::default();
+    let mut accumulator = Accumulator::default();
+    let mut frame: u64 = 0;
     'running: loop {
-        for event in event_pump.poll_iter() {
-            if is_exit_event(&event) {
+        for event in platform.poll_events() {
+            if is_exit_event(&event) && !client.is_typing() {
                 break 'running;
             }
+            if is_fullscreen_toggle(&event) {
+                display.toggle(&mut platform);
+                continue;
+            }
+            if let Some(recorder) = &mut recorder {
+                recorder.record(&event);
+            }
             client.handle_event(&event);
         }
 
-        engine_tick(&mut world, &mut client.view);
+        if let Some(replay) = &mut replay {
+            if replay.is_finished() {
+                break 'running;
+            }
+            for event in replay.tick() {
+                client.handle_event(&event);
+            }
+        }
+        if let Some(recorder) = &mut recorder {
+            recorder.tick();
+        }
+
+        // recorded input is replayed frame by frame, so it needs one tick per frame
+        let ticks = if recorder.is_some() || replay.is_some() {
+            1
+        } else {
+            accumulator.ticks_due()
+        };
+
+        let mut tick_duration = Duration::default();
+        if client.is_remote() {
+            if !client.receive(&mut world, &mut events) {
+                eprintln!("connection to server lost");
+                break 'running;
+            }
+            client.tick(&mut world, &mut events);
+        } else {
+            for _ in 0..ticks {
+                let report = engine.tick(&mut world, &mut events);
+                client.report_tick(&report);
+                tick_duration += report.duration;
+                systems_tick(&mut world, &events, &mut scripts);
+
+                client.tick(&mut world, &mut events);
+            }
+        }
+
+        // remote worlds arrive whole and recordings run a tick per frame, so neither is
+        // drawn between ticks
+        let alpha = if client.is_remote() || recorder.is_some() || replay.is_some() {
+            1.0
+        } else {
+            accumulator.alpha()
+        };
+        let render_started = Instant::now();
+        client.render(&world, alpha, &mut platform);
+
+        platform.present();
 
-        client.tick(&mut world);
+        if args.stress.is_some() {
+            log_frame(frame, tick_duration, render_started.elapsed(), &world);
+        }
+        frame += 1;
 
-        client.render(&world, &mut canvas);
+        if recorder.is_some() || replay.is_some() {
+            std::thread::sleep(TICK_DURATION);
+        } else {
+            std::thread::sleep(accumulator.until_next_tick());
+        }
+    }
 
-        canvas.present();
+    client.save_statistics();
 
-        ::std::thread::sleep(::std::time::Duration::new(0, 1_000_000_000u32 / 60));
+    if let Some(recorder) = recorder {
+        if let Err(e) = recorder.save() {
+            eprintln!("cannot save recording: {}", e);
+        }
     }
 }
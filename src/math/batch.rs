@@ -0,0 +1,54 @@
+use gamemath::{Mat3, Vec3};
+
+/// Points are transformed in chunks of this many, as separate coordinate lanes,
+/// so the compiler can use SIMD instructions for each row of the matrix.
+const LANES: usize = 8;
+
+/// Applies `transform` to all `points` in place.
+pub fn transform_points(transform: &Mat3, points: &mut [Vec3<f32>]) {
+    let mut chunks = points.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        let (x, y, z) = transform_lanes(transform, chunk);
+        for (i, point) in chunk.iter_mut().enumerate() {
+            *point = Vec3::new(x[i], y[i], z[i]);
+        }
+    }
+    for point in chunks.into_remainder() {
+        *point = *transform * *point;
+    }
+}
+
+/// Appends transformed `points` to `out`.
+pub fn transform_points_into(transform: &Mat3, points: &[Vec3<f32>], out: &mut Vec<Vec3<f32>>) {
+    out.reserve(points.len());
+
+    let chunks = points.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    for chunk in chunks {
+        let (x, y, z) = transform_lanes(transform, chunk);
+        out.extend((0..LANES).map(|i| Vec3::new(x[i], y[i], z[i])));
+    }
+    out.extend(remainder.iter().map(|point| *transform * *point));
+}
+
+type Lane = [f32; LANES];
+
+fn transform_lanes(transform: &Mat3, chunk: &[Vec3<f32>]) -> (Lane, Lane, Lane) {
+    let mut x = [0.0; LANES];
+    let mut y = [0.0; LANES];
+    let mut z = [0.0; LANES];
+    for (i, point) in chunk.iter().enumerate() {
+        x[i] = point.x;
+        y[i] = point.y;
+        z[i] = point.z;
+    }
+
+    let row = |r: Vec3<f32>| -> Lane {
+        let mut out = [0.0; LANES];
+        for i in 0..LANES {
+            out[i] = r.x * x[i] + r.y * y[i] + r.z * z[i];
+        }
+        out
+    };
+    (row(transform[0]), row(transform[1]), row(transform[2]))
+}
@@ -3,7 +3,7 @@ use super::vec::*;
 use crate::world::Grid;
 use gamemath::{Mat3, Vec2};
 
-#[derive(Default)]
+#[derive(Default, Debug, Clone)]
 pub struct RectBounds {
     pub top_left: Vec2<f32>,
     pub bottom_right: Vec2<f32>,
@@ -35,6 +35,18 @@ impl RectBounds {
         }
     }
 
+    pub fn intersects(&self, other: &RectBounds) -> bool {
+        self.top_left.x <= other.bottom_right.x
+            && other.top_left.x <= self.bottom_right.x
+            && self.top_left.y <= other.bottom_right.y
+            && other.top_left.y <= self.bottom_right.y
+    }
+
+    pub fn translate(&mut self, offset: Vec2<f32>) {
+        self.top_left += offset;
+        self.bottom_right += offset;
+    }
+
     pub fn expand(mut self, value: f32) -> RectBounds {
         self.top_left -= value.into();
         self.bottom_right += value.into();
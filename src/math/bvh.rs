@@ -0,0 +1,105 @@
+use super::bounding_box::RectBounds;
+use std::cmp::Ordering;
+
+/// Tree of bounding boxes, numbered by their position in the list the tree was built
+/// from, that finds the boxes overlapping a query without testing all of them.
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+    /// The root is the first node, when there is one.
+    nodes: Vec<Node>,
+    leaves: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    bounds: RectBounds,
+    content: Content,
+}
+
+#[derive(Clone, Debug)]
+enum Content {
+    Leaf(usize),
+    /// Indices of the two child nodes.
+    Branch(usize, usize),
+}
+
+impl Bvh {
+    pub fn new(bounds: Vec<RectBounds>) -> Bvh {
+        let leaves = bounds.len();
+        let mut items: Vec<(usize, RectBounds)> = bounds.into_iter().enumerate().collect();
+        let mut nodes = Vec::with_capacity(leaves * 2);
+        if !items.is_empty() {
+            build(&mut items, &mut nodes);
+        }
+        Bvh { nodes, leaves }
+    }
+
+    /// Number of boxes the tree was built from.
+    pub fn leaves(&self) -> usize {
+        self.leaves
+    }
+
+    /// Numbers of the boxes that overlap `bounds`, in no particular order.
+    pub fn query(&self, bounds: &RectBounds) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.intersects(bounds) {
+                continue;
+            }
+            match node.content {
+                Content::Leaf(item) => found.push(item),
+                Content::Branch(left, right) => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Adds the subtree over `items` to `nodes` and returns the index of its root.
+/// Items are split in halves along the longer side of their bounds.
+fn build(items: &mut [(usize, RectBounds)], nodes: &mut Vec<Node>) -> usize {
+    let mut bounds = items[0].1.clone();
+    for (_, item) in &items[1..] {
+        bounds += item.clone();
+    }
+    if let [(item, _)] = items {
+        nodes.push(Node {
+            bounds,
+            content: Content::Leaf(*item),
+        });
+        return nodes.len() - 1;
+    }
+
+    let size = bounds.bottom_right - bounds.top_left;
+    let center = |b: &RectBounds| {
+        if size.x >= size.y {
+            b.top_left.x + b.bottom_right.x
+        } else {
+            b.top_left.y + b.bottom_right.y
+        }
+    };
+    items.sort_by(|a, b| {
+        center(&a.1)
+            .partial_cmp(&center(&b.1))
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let index = nodes.len();
+    nodes.push(Node {
+        bounds,
+        content: Content::Leaf(0),
+    });
+    let (left, right) = items.split_at_mut(items.len() / 2);
+    let left = build(left, nodes);
+    let right = build(right, nodes);
+    nodes[index].content = Content::Branch(left, right);
+    index
+}
@@ -0,0 +1,68 @@
+use super::polygon::Polygon;
+use super::segment::Segment;
+use super::vec::*;
+use gamemath::{Mat2, Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use std::f32::consts::PI;
+
+/// Points closer than `radius` to the segment between `a` and `b`.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Capsule {
+    #[serde_as(as = "Vec2Serde<f32>")]
+    pub a: Vec2<f32>,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    pub b: Vec2<f32>,
+    pub radius: f32,
+}
+
+impl Capsule {
+    pub fn new(a: Vec2<f32>, b: Vec2<f32>, radius: f32) -> Self {
+        Capsule { a, b, radius }
+    }
+
+    pub fn segment(&self) -> Segment {
+        Segment::new(self.a, self.b)
+    }
+
+    pub fn area(&self) -> f32 {
+        let length = (self.b - self.a).length();
+        2.0 * self.radius * length + PI * self.radius * self.radius
+    }
+
+    pub fn centroid(&self) -> Vec2<f32> {
+        (self.a + self.b) * 0.5
+    }
+
+    pub fn contains_point(&self, point: Vec2<f32>) -> bool {
+        self.segment().distance_to_point(point) <= self.radius
+    }
+
+    /// Moves the ends and scales the radius by the average scale of the transform.
+    pub fn transform(&mut self, transform: Mat3) {
+        self.a = (transform * self.a.into_homogeneous()).into_cartesian();
+        self.b = (transform * self.b.into_homogeneous()).into_cartesian();
+        self.radius *= scale_of(&transform);
+    }
+
+    /// Polygon with `segments` vertices on each of the round ends.
+    pub fn outline(&self, segments: usize) -> Polygon {
+        let along = self.b - self.a;
+        let direction = if along.length_squared() > 0.0 {
+            along.normalized()
+        } else {
+            Vec2::new(1.0, 0.0)
+        };
+        let cap = |center: Vec2<f32>, facing: Vec2<f32>| {
+            (0..segments).map(move |i| {
+                let angle = PI * (i as f32 / (segments - 1).max(1) as f32 - 0.5);
+                center + Mat2::rotation(angle) * facing * self.radius
+            })
+        };
+        let points: Vec<Vec2<f32>> = cap(self.b, direction)
+            .chain(cap(self.a, direction * -1.0))
+            .collect();
+        Polygon::from(points)
+    }
+}
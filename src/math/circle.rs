@@ -0,0 +1,64 @@
+use super::polygon::Polygon;
+use super::segment::Segment;
+use super::vec::*;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use std::f32::consts::PI;
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Circle {
+    #[serde_as(as = "Vec2Serde<f32>")]
+    pub center: Vec2<f32>,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: Vec2<f32>, radius: f32) -> Self {
+        Circle { center, radius }
+    }
+
+    pub fn area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+
+    pub fn contains_point(&self, point: Vec2<f32>) -> bool {
+        (point - self.center).length_squared() <= self.radius * self.radius
+    }
+
+    /// Moves the center and scales the radius by the average scale of the transform.
+    pub fn transform(&mut self, transform: Mat3) {
+        self.center = (transform * self.center.into_homogeneous()).into_cartesian();
+        self.radius *= scale_of(&transform);
+    }
+
+    /// First point where the segment crosses the circle, as the fraction of the segment
+    /// and the point.
+    pub fn intersect_line_segment(&self, segment: Segment) -> Option<(f32, Vec2<f32>)> {
+        let direction = segment.direction();
+        let from_center = segment.a - self.center;
+        let a = direction.dot(direction);
+        let b = 2.0 * direction.dot(from_center);
+        let c = from_center.dot(from_center) - self.radius * self.radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if a == 0.0 || discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        IntoIterator::into_iter([(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)])
+            .find(|alpha| (0.0..=1.0).contains(alpha))
+            .map(|alpha| (alpha, segment.a + direction * alpha))
+    }
+
+    /// Polygon with `segments` vertices on the circle.
+    pub fn outline(&self, segments: usize) -> Polygon {
+        let points: Vec<Vec2<f32>> = (0..segments)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f32 / segments as f32;
+                self.center + Vec2::new(angle.cos(), angle.sin()) * self.radius
+            })
+            .collect();
+        Polygon::from(points)
+    }
+}
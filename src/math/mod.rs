@@ -1,6 +1,10 @@
+pub mod batch;
 pub mod bounding_box;
+pub mod bvh;
+pub mod capsule;
+pub mod circle;
 pub mod line;
-pub mod lu;
 pub mod polygon;
 pub mod segment;
+pub mod shape;
 pub mod vec;
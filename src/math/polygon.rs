@@ -1,3 +1,4 @@
+use super::batch;
 use super::line::Line;
 use super::segment::Segment;
 use super::vec::*;
@@ -7,35 +8,143 @@ use serde_with::serde_as;
 use std::iter::Iterator;
 
 #[serde_as]
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Polygon {
     /// Vertices of the polygon in homogeneous coordinates.
     #[serde_as(as = "Vec<Vec3Serde<f32>>")]
     pub points: Vec<Vec3<f32>>,
 }
 
+/// Direction in which the vertices go around, as seen on the screen, where y points down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
 impl Polygon {
     pub fn is_empty(self: &Self) -> bool {
         self.points.is_empty()
     }
 
-    pub fn to_segments(&self) -> Vec<Segment> {
-        (0..self.points.len())
-            .map(|i1| {
-                let i2 = (i1 + 1) % self.points.len();
+    /// Edges of the polygon, without collecting them.
+    pub fn segments(&self) -> impl Iterator<Item = Segment> + '_ {
+        (0..self.points.len()).map(move |i1| {
+            let i2 = (i1 + 1) % self.points.len();
 
-                Segment {
-                    a: self.points[i1].into_cartesian(),
-                    b: self.points[i2].into_cartesian(),
-                }
+            Segment {
+                a: self.points[i1].into_cartesian(),
+                b: self.points[i2].into_cartesian(),
+            }
+        })
+    }
+
+    /// Edges of the transformed polygon, without cloning it.
+    pub fn segments_transformed<'a>(
+        &'a self,
+        transform: &Mat3,
+    ) -> impl Iterator<Item = Segment> + 'a {
+        let transform = *transform;
+        let project = move |point: &Vec3<f32>| (transform * *point).into_cartesian();
+        let first = self.points.first().map(project);
+
+        self.points
+            .iter()
+            .skip(1)
+            .map(project)
+            .chain(first)
+            .scan(first, |previous, b| {
+                let a = previous.replace(b)?;
+                Some(Segment { a, b })
             })
-            .collect()
+    }
+
+    pub fn transform(&mut self, transform: Mat3) {
+        batch::transform_points(&transform, &mut self.points);
+    }
+
+    /// Writes the transformed polygon to `out`, reusing its allocation.
+    pub fn transform_into(&self, transform: Mat3, out: &mut Polygon) {
+        out.points.clear();
+        batch::transform_points_into(&transform, &self.points, &mut out.points);
     }
 
     pub fn intersection(self, right: Self) -> Vec<Self> {
         clipping::intersection(self, right)
     }
 
+    /// Parts of this polygon outside of `right`, wound like this polygon.
+    /// When `right` is strictly inside, this polygon is returned unchanged,
+    /// because polygons cannot have holes.
+    pub fn difference(self, right: Self) -> Vec<Self> {
+        clipping::difference(self, right)
+    }
+
+    /// Convex pieces that together cover the polygon, wound like it.
+    /// Convex polygons are returned whole.
+    pub fn decompose_convex(&self) -> Vec<Polygon> {
+        decomposition::decompose_convex(self)
+    }
+
+    /// Triangles covering the polygon, found by clipping ears.
+    /// A polygon that crosses itself is covered only partially.
+    pub fn triangulate(&self) -> Vec<[Vec2<f32>; 3]> {
+        let points: Vec<Vec2<f32>> = self.points.iter().map(|p| p.into_cartesian()).collect();
+        let winding = self.area_and_centroid().0.signum();
+        let turn = |a: Vec2<f32>, b: Vec2<f32>, c: Vec2<f32>| {
+            ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)) * winding
+        };
+
+        let mut remaining: Vec<usize> = (0..points.len()).collect();
+        let mut triangles = Vec::with_capacity(points.len().saturating_sub(2));
+        while remaining.len() > 3 {
+            let count = remaining.len();
+            let corner = |i: usize| {
+                (
+                    points[remaining[(i + count - 1) % count]],
+                    points[remaining[i]],
+                    points[remaining[(i + 1) % count]],
+                )
+            };
+            let is_ear = |i: usize| {
+                let (a, b, c) = corner(i);
+                // no other vertex may be strictly inside of the ear
+                turn(a, b, c) > 0.0
+                    && remaining.iter().all(|&j| {
+                        let p = points[j];
+                        p == a
+                            || p == b
+                            || p == c
+                            || turn(a, b, p) <= 0.0
+                            || turn(b, c, p) <= 0.0
+                            || turn(c, a, p) <= 0.0
+                    })
+            };
+
+            if let Some(ear) = (0..count).find(|&i| is_ear(i)) {
+                let (a, b, c) = corner(ear);
+                triangles.push([a, b, c]);
+                remaining.remove(ear);
+            } else if let Some(flat) = (0..count).find(|&i| {
+                let (a, b, c) = corner(i);
+                turn(a, b, c) == 0.0
+            }) {
+                // vertices on a straight edge are no ears, but they are not needed either
+                remaining.remove(flat);
+            } else {
+                return triangles;
+            }
+        }
+        if remaining.len() == 3 {
+            triangles.push([
+                points[remaining[0]],
+                points[remaining[1]],
+                points[remaining[2]],
+            ]);
+        }
+        triangles
+    }
+
     pub fn intrude_point(self: &mut Self, point: Vec2<f32>) {
         let point_hom = point.into_homogeneous();
         let distances: Vec<f32> = self
@@ -77,7 +186,7 @@ impl Polygon {
 
         let horizontal_line = Line::horizontal(point.y);
 
-        for segment in self.to_segments() {
+        for segment in self.segments() {
             if let Some(intersection) = segment.intersection_line(&horizontal_line) {
                 if point == intersection {
                     // on polygon edge
@@ -91,6 +200,128 @@ impl Polygon {
         is_in
     }
 
+    /// Distance from the point to the outline, zero for points inside.
+    pub fn distance_to_point(&self, point: Vec2<f32>) -> f32 {
+        if self.contains_point(point) {
+            return 0.0;
+        }
+        self.segments()
+            .map(|edge| edge.distance_to_point(point))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Distance from the segment to the outline, zero for segments that are inside or
+    /// cross it.
+    #[allow(dead_code)]
+    pub fn distance_to_segment(&self, segment: &Segment) -> f32 {
+        if self.contains_point(segment.a) {
+            return 0.0;
+        }
+        self.segments()
+            .map(|edge| edge.distance_to_segment(segment))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Whether the polygon encloses an area and none of its edges cross each other.
+    pub fn is_simple(&self) -> bool {
+        let all = self.points.len();
+        if all < 3 || self.area_and_centroid().0.abs() <= f32::EPSILON {
+            return false;
+        }
+
+        let segments: Vec<Segment> = self.segments().collect();
+        for i in 0..all {
+            for j in (i + 1)..all {
+                // neighbouring edges meet at their common vertex
+                if j == i + 1 || (i == 0 && j == all - 1) {
+                    continue;
+                }
+                if segments[i].intersection(&segments[j]).is_some() {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Simple polygons that the outline splits into where it crosses itself.
+    /// Loops that enclose no area are dropped.
+    pub fn simple_loops(&self) -> Vec<Polygon> {
+        let points = self.points.iter().map(|p| p.into_cartesian()).collect();
+        let mut loops = Vec::new();
+        split_loops(points, &mut loops);
+        loops
+    }
+
+    /// Replaces an outline that crosses itself with the largest of its simple loops.
+    /// Returns false when no loop encloses an area, leaving the polygon as it is.
+    pub fn repair(&mut self) -> bool {
+        if self.is_simple() {
+            return true;
+        }
+        let largest = self.simple_loops().into_iter().max_by(|a, b| {
+            let area = |p: &Polygon| p.area_and_centroid().0.abs();
+            area(a)
+                .partial_cmp(&area(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        match largest {
+            Some(largest) => {
+                *self = largest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Smallest convex polygon containing this one.
+    pub fn convex_hull(&self) -> Polygon {
+        let points: Vec<Vec2<f32>> = self.points.iter().map(|p| p.into_cartesian()).collect();
+        construct_convex_hull(&points)
+    }
+
+    /// Drops vertices that are closer than `tolerance` to the outline without them,
+    /// by Douglas–Peucker. The polygon is kept as it is when the simplified outline
+    /// would lose its area or cross itself.
+    pub fn simplify(&mut self, tolerance: f32) {
+        let all = self.points.len();
+        if all <= 3 {
+            return;
+        }
+        let points: Vec<Vec2<f32>> = self.points.iter().map(|p| p.into_cartesian()).collect();
+
+        // the outline is split at two vertices far apart, which are corners of its convex hull
+        let farthest_from = |from: usize| {
+            (0..all)
+                .max_by(|a, b| {
+                    let distance = |i: usize| (points[i] - points[from]).length_squared();
+                    distance(*a)
+                        .partial_cmp(&distance(*b))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(from)
+        };
+        let start = farthest_from(0);
+        let end = farthest_from(start);
+        let end = if end > start { end } else { end + all };
+
+        let mut keep = vec![false; all];
+        keep[start] = true;
+        keep[end % all] = true;
+        keep_farthest(&points, start, end, tolerance, &mut keep);
+        keep_farthest(&points, end, start + all, tolerance, &mut keep);
+
+        let simplified = Polygon {
+            points: (0..all)
+                .filter(|i| keep[*i])
+                .map(|i| self.points[i])
+                .collect(),
+        };
+        if simplified.points.len() < all && simplified.is_simple() {
+            *self = simplified;
+        }
+    }
+
     pub fn contains_polygon(&self, right: &Polygon) -> bool {
         right
             .points
@@ -98,6 +329,30 @@ impl Polygon {
             .all(|p| self.contains_point(p.into_cartesian()))
     }
 
+    /// Area that is negative when wound counter-clockwise.
+    pub fn signed_area(&self) -> f32 {
+        self.area_and_centroid().0
+    }
+
+    /// None for polygons without area.
+    pub fn winding(&self) -> Option<Winding> {
+        let area = self.signed_area();
+        if area < 0.0 {
+            Some(Winding::CounterClockwise)
+        } else if area > 0.0 {
+            Some(Winding::Clockwise)
+        } else {
+            None
+        }
+    }
+
+    /// Reverses clockwise polygons, so they are wound like all polygons in the game.
+    pub fn ensure_ccw(&mut self) {
+        if self.winding() == Some(Winding::Clockwise) {
+            self.points.reverse();
+        }
+    }
+
     pub fn area_and_centroid(&self) -> (f32, Vec2<f32>) {
         let all = self.points.len();
 
@@ -135,8 +390,8 @@ impl Polygon {
         let mut first_intersection = None;
         let mut min_alpha = -1.0;
 
-        for edge in &self.to_segments() {
-            if let Some((alpha_p, _alpha_q)) = segment.intersection(edge) {
+        for edge in self.segments() {
+            if let Some((alpha_p, _alpha_q)) = segment.intersection(&edge) {
                 if first_intersection == None || alpha_p < min_alpha {
                     min_alpha = alpha_p;
                     first_intersection = Some(segment.a + segment_direction * alpha_p);
@@ -157,7 +412,7 @@ impl Polygon {
         let mut on_new_intersection = |(alpha, intersection)| {
             if min_alpha < 0.0 || alpha < min_alpha {
                 min_alpha = alpha;
-                intersections = Vec::new();
+                intersections = vec![intersection];
             } else if (alpha - min_alpha).abs() < std::f32::EPSILON {
                 intersections.push(intersection);
             }
@@ -188,31 +443,156 @@ impl Polygon {
             None
         }
     }
+
+    /// First contact of `poly`, moving along `path`, with this polygon, as the fraction
+    /// of the path travelled and the points where the polygons touch then.
+    /// Polygons that already overlap are not reported, so they can move apart.
+    pub fn swept_collision(
+        &self,
+        poly: &Polygon,
+        path: Vec2<f32>,
+    ) -> Option<(f32, Vec<Vec2<f32>>)> {
+        self.swept_collision_pieces(
+            &self.decompose_convex(),
+            poly,
+            &poly.decompose_convex(),
+            path,
+        )
+    }
+
+    /// Like `swept_collision`, with convex pieces of both polygons decomposed by the caller.
+    pub fn swept_collision_pieces(
+        &self,
+        pieces: &[Polygon],
+        poly: &Polygon,
+        poly_pieces: &[Polygon],
+        path: Vec2<f32>,
+    ) -> Option<(f32, Vec<Vec2<f32>>)> {
+        let alpha = pieces
+            .iter()
+            .flat_map(|a| poly_pieces.iter().map(move |b| (a, b)))
+            .filter_map(|(a, b)| sweep::time_of_impact(a, b, path))
+            .fold(None, |min: Option<f32>, alpha| {
+                Some(min.map_or(alpha, |min| min.min(alpha)))
+            })?;
+
+        let moved = translation(path * alpha) * poly.clone();
+        Some((alpha, sweep::contacts(self, &moved)))
+    }
+
+    /// How deep convex `poly` reaches into this convex polygon, and the unit normal it
+    /// should be pushed along to get out, found by the separating axis theorem.
+    /// Polygons that do not overlap have no penetration.
+    pub fn penetration(&self, poly: &Polygon) -> Option<(f32, Vec2<f32>)> {
+        sat::separation(self, poly, Vec2::default()).filter(|(depth, _)| *depth > 0.0)
+    }
+
+    /// Like `penetration`, for any polygons given by their convex pieces. The deepest
+    /// overlap of two pieces is taken.
+    pub fn penetration_pieces(
+        pieces: &[Polygon],
+        poly_pieces: &[Polygon],
+    ) -> Option<(f32, Vec2<f32>)> {
+        sat::deepest(pieces, poly_pieces, Vec2::default()).filter(|(depth, _)| *depth > 0.0)
+    }
+}
+
+/// Splits the outline at the first crossing of its edges into two shorter outlines,
+/// until none of them crosses itself.
+fn split_loops(mut points: Vec<Vec2<f32>>, loops: &mut Vec<Polygon>) {
+    points.dedup();
+    while points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    let all = points.len();
+    if all < 3 {
+        return;
+    }
+
+    for i in 0..all {
+        for j in (i + 2)..all {
+            // neighbouring edges meet at their common vertex
+            if i == 0 && j == all - 1 {
+                continue;
+            }
+            let a = Segment::new(points[i], points[i + 1]);
+            let b = Segment::new(points[j], points[(j + 1) % all]);
+            if let Some((alpha, _)) = a.intersection(&b) {
+                let crossing = a.a + a.direction() * alpha;
+
+                let mut inner = vec![crossing];
+                inner.extend_from_slice(&points[i + 1..=j]);
+
+                let mut outer = points[j + 1..].to_vec();
+                outer.extend_from_slice(&points[..=i]);
+                outer.push(crossing);
+
+                split_loops(inner, loops);
+                split_loops(outer, loops);
+                return;
+            }
+        }
+    }
+
+    let polygon = Polygon::from(points);
+    if polygon.area_and_centroid().0.abs() > f32::EPSILON {
+        loops.push(polygon);
+    }
+}
+
+/// Marks the vertex between `first` and `last` farthest from the line between them, when it is
+/// farther than `tolerance`, and continues on both sides of it.
+/// Indices past the last vertex wrap around to the first one.
+fn keep_farthest(
+    points: &[Vec2<f32>],
+    first: usize,
+    last: usize,
+    tolerance: f32,
+    keep: &mut [bool],
+) {
+    let all = points.len();
+    let chord = Segment::new(points[first % all], points[last % all]);
+    let farthest = ((first + 1)..last)
+        .map(|i| (i, chord.distance_to_point(points[i % all])))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    if let Some((index, distance)) = farthest {
+        if distance > tolerance {
+            keep[index % all] = true;
+            keep_farthest(points, first, index, tolerance, keep);
+            keep_farthest(points, index, last, tolerance, keep);
+        }
+    }
 }
 
+/// Wound counter-clockwise, whichever way the points go.
 impl From<Vec<Vec2<f32>>> for Polygon {
     fn from(points: Vec<Vec2<f32>>) -> Polygon {
-        Polygon {
+        let mut polygon = Polygon {
             points: points.iter().map(|p| p.into_homogeneous()).collect(),
-        }
+        };
+        polygon.ensure_ccw();
+        polygon
     }
 }
 
+/// Wound counter-clockwise, whichever way the points go.
 impl From<&[[f32; 2]]> for Polygon {
     fn from(points: &[[f32; 2]]) -> Polygon {
-        Polygon {
+        let mut polygon = Polygon {
             points: points.iter().map(|p| Vec3::new(p[0], p[1], 1.0)).collect(),
-        }
+        };
+        polygon.ensure_ccw();
+        polygon
     }
 }
 
 impl std::ops::Mul<Polygon> for Mat3 {
     type Output = Polygon;
 
-    fn mul(self, right: Polygon) -> Polygon {
-        Polygon {
-            points: right.points.iter().map(|p| self * *p).collect(),
-        }
+    fn mul(self, mut right: Polygon) -> Polygon {
+        right.transform(self);
+        right
     }
 }
 
@@ -232,6 +612,173 @@ pub fn construct_rect_poly_centered(width: f32, height: f32) -> Polygon {
     construct_rect_poly(-width / 2.0, width / 2.0, -height / 2.0, height / 2.0)
 }
 
+/// Smallest convex polygon containing all points.
+pub fn construct_convex_hull(points: &[Vec2<f32>]) -> Polygon {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| {
+        (a.x, a.y)
+            .partial_cmp(&(b.x, b.y))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let cross = |o: Vec2<f32>, a: Vec2<f32>, b: Vec2<f32>| {
+        (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+    };
+
+    // Andrew's monotone chain, lower and then upper half
+    let mut hull: Vec<Vec2<f32>> = Vec::new();
+    for pass in 0..2 {
+        let start = hull.len();
+        for point in sorted.iter().copied() {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    Polygon::from(hull)
+}
+
+pub(super) mod sweep {
+    use crate::math::polygon::*;
+
+    /// Points this close to the other polygon, beyond the closest one, are contacts as well.
+    const CONTACT_TOLERANCE: f32 = 1e-3;
+
+    /// Fraction of `path` after which convex `b`, moving along it, touches convex `a`.
+    /// `b` touches `a` exactly when the offset it moved by is in the Minkowski difference
+    /// `a - b`, so the offset is clipped against the difference, edge by edge.
+    pub fn time_of_impact(a: &Polygon, b: &Polygon, path: Vec2<f32>) -> Option<f32> {
+        let differences: Vec<Vec2<f32>> = a
+            .points
+            .iter()
+            .flat_map(|p| {
+                b.points
+                    .iter()
+                    .map(move |q| p.into_cartesian() - q.into_cartesian())
+            })
+            .collect();
+        let difference = construct_convex_hull(&differences);
+        let inside = difference.area_and_centroid().0.signum();
+        if difference.points.len() < 3 || inside == 0.0 {
+            return None;
+        }
+
+        let cross = |u: Vec2<f32>, v: Vec2<f32>| (u.x * v.y - u.y * v.x) * inside;
+        let mut enter = f32::NEG_INFINITY;
+        let mut exit = f32::INFINITY;
+        for edge in difference.segments() {
+            let direction = edge.direction();
+            // offset is inside of the edge while start + along * alpha >= 0
+            let start = cross(direction, -edge.a);
+            let along = cross(direction, path);
+            if along == 0.0 {
+                if start < 0.0 {
+                    return None;
+                }
+            } else if along > 0.0 {
+                enter = enter.max(-start / along);
+            } else {
+                exit = exit.min(-start / along);
+            }
+        }
+
+        if !(0.0..=1.0).contains(&enter) || enter > exit {
+            None
+        } else {
+            Some(enter)
+        }
+    }
+
+    /// Vertices of either polygon that lie on the outline of the other one, without repeats.
+    /// When none does exactly, the closest vertices are taken.
+    pub fn contacts(a: &Polygon, b: &Polygon) -> Vec<Vec2<f32>> {
+        let candidates: Vec<(f32, Vec2<f32>)> = a
+            .points
+            .iter()
+            .map(|p| (p.into_cartesian(), b))
+            .chain(b.points.iter().map(|p| (p.into_cartesian(), a)))
+            .map(|(point, other)| (other.distance_to_point(point), point))
+            .collect();
+
+        let closest = candidates
+            .iter()
+            .map(|(distance, _)| *distance)
+            .fold(f32::INFINITY, f32::min);
+        let mut contacts: Vec<Vec2<f32>> = Vec::new();
+        for (distance, point) in candidates {
+            // vertices of both polygons often touch each other
+            if distance <= closest + CONTACT_TOLERANCE
+                && contacts
+                    .iter()
+                    .all(|contact| (*contact - point).length() > CONTACT_TOLERANCE)
+            {
+                contacts.push(point);
+            }
+        }
+        contacts
+    }
+}
+
+pub(super) mod sat {
+    use crate::math::polygon::*;
+
+    /// Overlap of convex `b`, moved by `offset`, with convex `a` along the axis where it is
+    /// the smallest, and the unit normal that pushes `b` out of `a` along that axis.
+    /// Polygons that are apart have a negative overlap, the gap between them.
+    /// Only normals of the edges need to be tested, as two convex polygons are apart
+    /// exactly when one of them separates them.
+    pub fn separation(a: &Polygon, b: &Polygon, offset: Vec2<f32>) -> Option<(f32, Vec2<f32>)> {
+        a.segments()
+            .chain(b.segments())
+            .map(|edge| edge.direction().perpendicular())
+            .filter(|normal| normal.length_squared() > 0.0)
+            .map(|normal| {
+                let axis = normal.normalized();
+                let (min_a, max_a) = project(a, axis, 0.0);
+                let (min_b, max_b) = project(b, axis, offset.dot(axis));
+                // `b` leaves either along the axis or against it, whichever is shorter
+                let along = max_a - min_b;
+                let against = max_b - min_a;
+                if along < against {
+                    (along, axis)
+                } else {
+                    (against, axis * -1.0)
+                }
+            })
+            .min_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    /// Separation of the pair of pieces that overlap the most, or are the closest.
+    pub fn deepest(
+        pieces: &[Polygon],
+        other_pieces: &[Polygon],
+        offset: Vec2<f32>,
+    ) -> Option<(f32, Vec2<f32>)> {
+        pieces
+            .iter()
+            .flat_map(|a| other_pieces.iter().map(move |b| (a, b)))
+            .filter_map(|(a, b)| separation(a, b, offset))
+            .max_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    /// Interval the polygon covers on the axis, shifted by `shift`.
+    fn project(polygon: &Polygon, axis: Vec2<f32>, shift: f32) -> (f32, f32) {
+        polygon
+            .points
+            .iter()
+            .map(|p| p.into_cartesian().dot(axis) + shift)
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), t| {
+                (min.min(t), max.max(t))
+            })
+    }
+}
+
 mod clipping {
     use crate::math::{polygon::Polygon, segment::Segment, vec::*};
     use gamemath::{Vec2, Vec3};
@@ -248,8 +795,67 @@ mod clipping {
             return vec![a_poly];
         }
 
-        let mut a = PolygonLinked::new(&a_poly);
-        let mut b = PolygonLinked::new(&b_poly);
+        let (mut a, mut b) = match link(&a_poly, &b_poly) {
+            Some(linked) => linked,
+            None => {
+                if b_poly.contains_point(a_poly.points[0].into_cartesian()) {
+                    return vec![b_poly];
+                } else if a_poly.contains_point(b_poly.points[0].into_cartesian()) {
+                    return vec![a_poly];
+                } else {
+                    return vec![a_poly, b_poly];
+                }
+            }
+        };
+
+        mark_entries(&mut a, &b_poly, false);
+        mark_entries(&mut b, &a_poly, false);
+
+        let mut polygons = trace(&mut a, &mut b);
+        polygons.truncate(1);
+        polygons
+    }
+
+    /// Parts of `a_poly` outside of `b_poly`, wound like `a_poly`.
+    pub fn difference(a_poly: Polygon, b_poly: Polygon) -> Vec<Polygon> {
+        if a_poly.is_empty() {
+            return vec![];
+        }
+        if b_poly.is_empty() {
+            return vec![a_poly];
+        }
+
+        let (mut a, mut b) = match link(&a_poly, &b_poly) {
+            Some(linked) => linked,
+            None => {
+                if b_poly.contains_point(a_poly.points[0].into_cartesian()) {
+                    return vec![];
+                }
+                // b within a would leave a hole, which polygons cannot have
+                return vec![a_poly];
+            }
+        };
+
+        // walk a outside of b and b inside of a
+        mark_entries(&mut a, &b_poly, false);
+        mark_entries(&mut b, &a_poly, true);
+
+        // loops are walked either way around, depending on where they start
+        let winding = a_poly.area_and_centroid().0.signum();
+        let mut polygons = trace(&mut a, &mut b);
+        for polygon in &mut polygons {
+            if polygon.area_and_centroid().0.signum() != winding {
+                polygon.points.reverse();
+            }
+        }
+        polygons
+    }
+
+    /// Inserts intersections of edges into both polygons.
+    /// Returns None when the edges do not intersect.
+    fn link(a_poly: &Polygon, b_poly: &Polygon) -> Option<(PolygonLinked, PolygonLinked)> {
+        let mut a = PolygonLinked::new(a_poly);
+        let mut b = PolygonLinked::new(b_poly);
 
         let mut intersection_found = false;
 
@@ -278,6 +884,7 @@ mod clipping {
                         Intersection {
                             alpha: alpha_a,
                             entry: false,
+                            visited: false,
                             neighbor: None,
                         },
                     );
@@ -288,6 +895,7 @@ mod clipping {
                         Intersection {
                             alpha: alpha_b,
                             entry: false,
+                            visited: false,
                             neighbor: Some(new_a_node),
                         },
                     );
@@ -305,71 +913,76 @@ mod clipping {
             }
         }
 
-        if !intersection_found {
-            if b_poly.contains_point(a.nodes[0].r) {
-                return vec![b_poly];
-            } else if a_poly.contains_point(b.nodes[0].r) {
-                return vec![a_poly];
-            } else {
-                return vec![a_poly, b_poly];
-            }
+        if intersection_found {
+            Some((a, b))
+        } else {
+            None
         }
+    }
 
-        {
-            // figure out which intersection in poly a are entries into poly b
-            let mut inside = b_poly.contains_point(a.nodes[0].r);
-            let mut pos_a = 0;
-            loop {
-                if let Some(intersection) = &mut a.nodes[pos_a].intersection {
-                    inside = !inside;
-                    intersection.entry = inside;
-                }
-                pos_a = a.nodes[pos_a].next;
-                if pos_a == 0 {
-                    break;
-                }
+    /// Figures out which intersections in `poly` are entries into `other`.
+    /// Inverted marks make the walk go the other way around `other`.
+    fn mark_entries(poly: &mut PolygonLinked, other: &Polygon, invert: bool) {
+        let mut inside = other.contains_point(poly.nodes[0].r) != invert;
+        let mut pos = 0;
+        loop {
+            if let Some(intersection) = &mut poly.nodes[pos].intersection {
+                inside = !inside;
+                intersection.entry = inside;
+            }
+            pos = poly.nodes[pos].next;
+            if pos == 0 {
+                break;
             }
         }
+    }
 
-        {
-            // figure out which intersection in poly b are entries into poly a
-            let mut inside = a_poly.contains_point(b.nodes[0].r);
-            let mut pos_b = 0;
-            loop {
-                if let Some(intersection) = &mut b.nodes[pos_b].intersection {
-                    inside = !inside;
-                    intersection.entry = inside;
-                }
-                pos_b = b.nodes[pos_b].next;
-                if pos_b == 0 {
-                    break;
-                }
+    /// Walks from each intersection not visited yet, switching polygons at intersections.
+    fn trace(a: &mut PolygonLinked, b: &mut PolygonLinked) -> Vec<Polygon> {
+        let mut polygons = Vec::new();
+
+        let mut pos_a = 0;
+        loop {
+            let unvisited = a.nodes[pos_a]
+                .intersection
+                .as_ref()
+                .is_some_and(|intersection| !intersection.visited);
+            if unvisited {
+                polygons.push(trace_from(a, b, pos_a));
+            }
+            pos_a = a.nodes[pos_a].next;
+            if pos_a == 0 {
+                break;
             }
         }
+        polygons
+    }
+
+    fn trace_from(a: &mut PolygonLinked, b: &mut PolygonLinked, start: usize) -> Polygon {
         let mut points: Vec<Vec3<f32>> = Vec::new();
-        let first_intersection = BiPolygonNode {
-            index: a.find_forward_intersection(0),
+        let mut current = BiPolygonNode {
+            index: start,
             is_in_a: true,
         };
-        let mut current: BiPolygonNode = first_intersection;
         loop {
-            let direction = current.get(&a, &b).intersection.as_ref().unwrap().entry;
+            current.visit(a, b);
+            let direction = current.get(a, b).intersection.as_ref().unwrap().entry;
             loop {
-                let node = current.get(&a, &b);
+                let node = current.get(a, b);
                 points.push(node.r.into_homogeneous());
                 current.step_to(if direction { node.prev } else { node.next });
 
-                if let Some(..) = current.get(&a, &b).intersection {
+                if current.get(a, b).intersection.is_some() {
                     break;
                 }
             }
-            current.step_over(&a, &b);
-            if current == first_intersection {
+            current.step_over(a, b);
+            if current.get(a, b).intersection.as_ref().unwrap().visited {
                 break;
             }
         }
 
-        return vec![Polygon { points: points }];
+        Polygon { points }
     }
 
     /// Reference to a node in one of two polygons
@@ -399,12 +1012,26 @@ mod clipping {
         fn step_to(&mut self, index: usize) {
             self.index = index;
         }
+
+        /// Marks the intersection at this node and its neighbor.
+        fn visit(&self, a: &mut PolygonLinked, b: &mut PolygonLinked) {
+            let (this, other) = if self.is_in_a { (a, b) } else { (b, a) };
+            if let Some(intersection) = &mut this.nodes[self.index].intersection {
+                intersection.visited = true;
+                if let Some(neighbor) = intersection.neighbor {
+                    if let Some(intersection) = &mut other.nodes[neighbor].intersection {
+                        intersection.visited = true;
+                    }
+                }
+            }
+        }
     }
 
     struct Intersection {
         neighbor: Option<usize>,
         alpha: f32,
         entry: bool,
+        visited: bool,
     }
 
     struct PolygonLinkedNode {
@@ -505,9 +1132,172 @@ mod clipping {
         fn find_forward_non_intersection(&self, start: usize) -> usize {
             self.find_forward(start, |node| node.intersection.is_none())
         }
+    }
+}
+
+mod decomposition {
+    use crate::math::{line::Line, polygon::Polygon, segment::Segment, vec::*};
+    use gamemath::Vec2;
+
+    /// Splits of a polygon, after which the pieces are returned as they are.
+    /// Guards against degenerate polygons that do not get simpler when split.
+    const MAX_DEPTH: usize = 64;
+
+    pub fn decompose_convex(polygon: &Polygon) -> Vec<Polygon> {
+        let mut points: Vec<Vec2<f32>> =
+            polygon.points.iter().map(|p| p.into_cartesian()).collect();
+        if points.len() < 4 {
+            return vec![polygon.clone()];
+        }
+
+        // the algorithm expects counter-clockwise winding
+        let clockwise = polygon.area_and_centroid().0 < 0.0;
+        if clockwise {
+            points.reverse();
+        }
+
+        let mut pieces = Vec::new();
+        decompose(points, 0, &mut pieces);
+
+        pieces
+            .into_iter()
+            .map(|mut piece| {
+                if clockwise {
+                    piece.reverse();
+                }
+                Polygon::from(piece)
+            })
+            .collect()
+    }
+
+    /// Bayazit's algorithm: each reflex vertex is connected to a vertex it can see or
+    /// to a point on the edge opposite to it, and both halves are decomposed further.
+    /// https://mpen.ca/406/bayazit
+    fn decompose(points: Vec<Vec2<f32>>, depth: usize, pieces: &mut Vec<Vec<Vec2<f32>>>) {
+        let all = points.len();
+        let at = |i: usize| points[i % all];
+
+        let reflex = (0..all).find(|&i| right(at(i + all - 1), at(i), at(i + 1)));
+        let i = match reflex {
+            Some(i) if depth < MAX_DEPTH => i,
+            _ => {
+                pieces.push(points);
+                return;
+            }
+        };
+        let (prev, this, next) = (at(i + all - 1), at(i), at(i + 1));
+
+        // closest points where extensions of edges at the reflex vertex hit other edges
+        let mut lower: Option<(f32, Vec2<f32>, usize)> = None;
+        let mut upper: Option<(f32, Vec2<f32>, usize)> = None;
+        for j in 0..all {
+            let (edge_start, edge_end) = (at(j + all - 1), at(j));
+            if left(prev, this, edge_end) && right_on(prev, this, edge_start) {
+                if let Some(p) = line_intersection(prev, this, edge_end, edge_start) {
+                    let distance = (p - this).length_squared();
+                    if right(next, this, p) && lower.is_none_or(|(d, ..)| distance < d) {
+                        lower = Some((distance, p, j));
+                    }
+                }
+            }
+            let (edge_start, edge_end) = (at(j), at(j + 1));
+            if left(next, this, edge_end) && right_on(next, this, edge_start) {
+                if let Some(p) = line_intersection(next, this, edge_start, edge_end) {
+                    let distance = (p - this).length_squared();
+                    if left(prev, this, p) && upper.is_none_or(|(d, ..)| distance < d) {
+                        upper = Some((distance, p, j));
+                    }
+                }
+            }
+        }
+        let ((_, lower_point, lower_index), (_, upper_point, upper_index)) = match (lower, upper) {
+            (Some(lower), Some(upper)) => (lower, upper),
+            _ => {
+                pieces.push(points);
+                return;
+            }
+        };
 
-        fn find_forward_intersection(&self, start: usize) -> usize {
-            self.find_forward(start, |node| node.intersection.is_some())
+        let (mut lower_poly, mut upper_poly);
+        if lower_index == (upper_index + 1) % all {
+            // no vertices between the hits, so the split goes to a new point between them
+            let split = (lower_point + upper_point) * 0.5;
+            lower_poly = wrapping_range(&points, i, upper_index);
+            lower_poly.push(split);
+            upper_poly = vec![split];
+            upper_poly.extend(wrapping_range(&points, lower_index, i));
+        } else {
+            // split at the closest vertex visible from the reflex vertex
+            let mut best: Option<(f32, usize)> = None;
+            let end = if lower_index > upper_index {
+                upper_index + all
+            } else {
+                upper_index
+            };
+            for j in lower_index..=end {
+                let candidate = at(j);
+                if left_on(prev, this, candidate) && right_on(next, this, candidate) {
+                    let distance = (candidate - this).length_squared();
+                    if best.is_none_or(|(d, _)| distance < d) {
+                        best = Some((distance, j % all));
+                    }
+                }
+            }
+            let best = best.map_or(lower_index, |(_, j)| j);
+            lower_poly = wrapping_range(&points, i, best);
+            upper_poly = wrapping_range(&points, best, i);
+        }
+
+        // a split that does not simplify the polygon would recurse forever
+        if lower_poly.len() >= all || upper_poly.len() >= all {
+            pieces.push(points);
+            return;
         }
+        if lower_poly.len() < upper_poly.len() {
+            decompose(lower_poly, depth + 1, pieces);
+            decompose(upper_poly, depth + 1, pieces);
+        } else {
+            decompose(upper_poly, depth + 1, pieces);
+            decompose(lower_poly, depth + 1, pieces);
+        }
+    }
+
+    /// Points from index `from` to index `to` inclusive, continuing over the end.
+    fn wrapping_range(points: &[Vec2<f32>], from: usize, to: usize) -> Vec<Vec2<f32>> {
+        let count = (to + points.len() - from) % points.len() + 1;
+        (from..from + count)
+            .map(|i| points[i % points.len()])
+            .collect()
+    }
+
+    /// Twice the signed area of the triangle, positive when counter-clockwise.
+    fn area(a: Vec2<f32>, b: Vec2<f32>, c: Vec2<f32>) -> f32 {
+        (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)
+    }
+
+    fn left(a: Vec2<f32>, b: Vec2<f32>, c: Vec2<f32>) -> bool {
+        area(a, b, c) > 0.0
+    }
+
+    fn left_on(a: Vec2<f32>, b: Vec2<f32>, c: Vec2<f32>) -> bool {
+        area(a, b, c) >= 0.0
+    }
+
+    fn right(a: Vec2<f32>, b: Vec2<f32>, c: Vec2<f32>) -> bool {
+        area(a, b, c) < 0.0
+    }
+
+    fn right_on(a: Vec2<f32>, b: Vec2<f32>, c: Vec2<f32>) -> bool {
+        area(a, b, c) <= 0.0
+    }
+
+    /// Intersection of the line through `a1` and `a2` with the line through `b1` and `b2`.
+    fn line_intersection(
+        a1: Vec2<f32>,
+        a2: Vec2<f32>,
+        b1: Vec2<f32>,
+        b2: Vec2<f32>,
+    ) -> Option<Vec2<f32>> {
+        Line::from(Segment::new(a1, a2)).intersection(&Line::from(Segment::new(b1, b2)))
     }
 }
@@ -25,6 +25,49 @@ impl Segment {
         d.dot(a_to_point) / d.length_squared()
     }
 
+    /// Point of the segment closest to `point`.
+    pub fn closest_point(&self, point: Vec2<f32>) -> Vec2<f32> {
+        if self.direction().length_squared() == 0.0 {
+            return self.a;
+        }
+        let alpha = self.project_point(point).clamp(0.0, 1.0);
+        self.a + self.direction() * alpha
+    }
+
+    /// Distance from the point to the closest point of the segment.
+    pub fn distance_to_point(&self, point: Vec2<f32>) -> f32 {
+        (point - self.closest_point(point)).length()
+    }
+
+    /// Closest points of this and the other segment, the same point when they cross.
+    pub fn closest_points(&self, other: &Segment) -> (Vec2<f32>, Vec2<f32>) {
+        if let Some((alpha, _)) = self.intersection(other) {
+            let point = self.a + self.direction() * alpha;
+            return (point, point);
+        }
+        // otherwise one of the closest points is an end of a segment
+        let candidates = [
+            (self.a, other.closest_point(self.a)),
+            (self.b, other.closest_point(self.b)),
+            (self.closest_point(other.a), other.a),
+            (self.closest_point(other.b), other.b),
+        ];
+        let distance = |(p, q): &(Vec2<f32>, Vec2<f32>)| (*q - *p).length_squared();
+        let mut closest = candidates[0];
+        for candidate in &candidates[1..] {
+            if distance(candidate) < distance(&closest) {
+                closest = *candidate;
+            }
+        }
+        closest
+    }
+
+    /// Distance between the closest points of this and the other segment.
+    pub fn distance_to_segment(&self, other: &Segment) -> f32 {
+        let (p, q) = self.closest_points(other);
+        (q - p).length()
+    }
+
     pub fn intersection_line(self, line: &Line) -> Option<Vec2<f32>> {
         line.intersection(&self.into())
             .map(|p| {
@@ -39,6 +82,7 @@ impl Segment {
             .flatten()
     }
 
+    /// Parallel segments do not intersect, even when they overlap.
     pub fn intersection(&self, other: &Segment) -> Option<(f32, f32)> {
         let other_dir_perpendicular = other.direction().perpendicular();
         let self_dir_perpendicular = self.direction().perpendicular();
@@ -46,11 +90,11 @@ impl Segment {
         let wec_p1 = (self.a - other.a).dot(other_dir_perpendicular);
         let wec_p2 = (self.b - other.a).dot(other_dir_perpendicular);
 
-        if wec_p1 * wec_p2 <= 0.0 {
+        if wec_p1 * wec_p2 <= 0.0 && wec_p1 != wec_p2 {
             let wec_q1 = (other.a - self.a).dot(self_dir_perpendicular);
             let wec_q2 = (other.b - self.a).dot(self_dir_perpendicular);
 
-            if wec_q1 * wec_q2 <= 0.0 {
+            if wec_q1 * wec_q2 <= 0.0 && wec_q1 != wec_q2 {
                 return Some((wec_p1 / (wec_p1 - wec_p2), wec_q1 / (wec_q1 - wec_q2)));
             }
         }
@@ -0,0 +1,444 @@
+use super::bounding_box::{BoundingBox, RectBounds};
+use super::capsule::Capsule;
+use super::circle::Circle;
+use super::polygon::{self, construct_convex_hull, Polygon, Winding};
+use super::segment::Segment;
+use super::vec::*;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Vertices of polygons approximating round shapes, where an operation needs a polygon.
+pub const ROUND_SEGMENTS: usize = 32;
+
+/// Outline of an entity or a block. Round shapes are kept exact instead of approximated
+/// by polygons with many vertices.
+/// Polygons are stored as they were before there were other shapes, so older saves load.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Shape {
+    Polygon(Polygon),
+    Circle(Circle),
+    Capsule(Capsule),
+}
+
+impl Shape {
+    pub fn polygon(&self) -> Option<&Polygon> {
+        match self {
+            Shape::Polygon(polygon) => Some(polygon),
+            _ => None,
+        }
+    }
+
+    /// The polygon itself, or a polygon approximating a round shape.
+    pub fn to_polygon(&self) -> Cow<'_, Polygon> {
+        match self {
+            Shape::Polygon(polygon) => Cow::Borrowed(polygon),
+            Shape::Circle(circle) => Cow::Owned(circle.outline(ROUND_SEGMENTS)),
+            Shape::Capsule(capsule) => Cow::Owned(capsule.outline(ROUND_SEGMENTS / 2)),
+        }
+    }
+
+    /// Area is signed like the area of a counter-clockwise polygon.
+    pub fn area_and_centroid(&self) -> (f32, Vec2<f32>) {
+        match self {
+            Shape::Polygon(polygon) => polygon.area_and_centroid(),
+            Shape::Circle(circle) => (-circle.area(), circle.center),
+            Shape::Capsule(capsule) => (-capsule.area(), capsule.centroid()),
+        }
+    }
+
+    pub fn radius_of_gyration(&self, offset: Vec2<f32>) -> f32 {
+        self.to_polygon().radius_of_gyration(offset)
+    }
+
+    pub fn contains_point(&self, point: Vec2<f32>) -> bool {
+        match self {
+            Shape::Polygon(polygon) => polygon.contains_point(point),
+            Shape::Circle(circle) => circle.contains_point(point),
+            Shape::Capsule(capsule) => capsule.contains_point(point),
+        }
+    }
+
+    /// First point where the segment crosses the outline, as the fraction of the segment
+    /// and the point. Capsules are approximated by a polygon.
+    pub fn intersect_line_segment(&self, segment: Segment) -> Option<(f32, Vec2<f32>)> {
+        match self {
+            Shape::Circle(circle) => circle.intersect_line_segment(segment),
+            _ => self.to_polygon().intersect_line_segment(segment),
+        }
+    }
+
+    /// Outward unit normal of the outline where it is closest to `point`.
+    pub fn normal_at(&self, point: Vec2<f32>) -> Vec2<f32> {
+        let outward = match self {
+            Shape::Polygon(polygon) => {
+                let edge = polygon.segments().min_by(|x, y| {
+                    x.distance_to_point(point)
+                        .partial_cmp(&y.distance_to_point(point))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+                let normal =
+                    edge.map_or_else(Vec2::default, |edge| edge.direction().perpendicular());
+                match polygon.winding() {
+                    Some(Winding::Clockwise) => normal * -1.0,
+                    _ => normal,
+                }
+            }
+            Shape::Circle(circle) => point - circle.center,
+            Shape::Capsule(capsule) => point - capsule.segment().closest_point(point),
+        };
+        if outward.length_squared() > 0.0 {
+            outward.normalized()
+        } else {
+            outward
+        }
+    }
+
+    /// Round shapes are convex, so they contain a polygon when they contain its vertices.
+    pub fn contains_polygon(&self, right: &Polygon) -> bool {
+        right
+            .points
+            .iter()
+            .all(|p| self.contains_point(p.into_cartesian()))
+    }
+
+    /// Round shapes stay round, so transforms should scale evenly.
+    pub fn transform(&mut self, transform: Mat3) {
+        match self {
+            Shape::Polygon(polygon) => polygon.transform(transform),
+            Shape::Circle(circle) => circle.transform(transform),
+            Shape::Capsule(capsule) => capsule.transform(transform),
+        }
+    }
+
+    /// Writes the transformed shape to `out`, reusing the allocation of a polygon.
+    pub fn transform_into(&self, transform: Mat3, out: &mut Shape) {
+        match (self, &mut *out) {
+            (Shape::Polygon(polygon), Shape::Polygon(out)) => {
+                polygon.transform_into(transform, out)
+            }
+            _ => *out = transform * self.clone(),
+        }
+    }
+
+    /// Convex pieces that together cover the shape. Round shapes are convex already.
+    pub fn decompose_convex(&self) -> Vec<Shape> {
+        match self {
+            Shape::Polygon(polygon) => polygon
+                .decompose_convex()
+                .into_iter()
+                .map(Shape::Polygon)
+                .collect(),
+            _ => vec![self.clone()],
+        }
+    }
+
+    /// Like `Polygon::swept_collision`, for any shapes.
+    pub fn swept_collision(&self, shape: &Shape, path: Vec2<f32>) -> Option<(f32, Vec<Vec2<f32>>)> {
+        self.swept_collision_pieces(
+            &self.decompose_convex(),
+            shape,
+            &shape.decompose_convex(),
+            path,
+        )
+    }
+
+    /// Like `swept_collision`, with convex pieces of both shapes decomposed by the caller.
+    pub fn swept_collision_pieces(
+        &self,
+        pieces: &[Shape],
+        shape: &Shape,
+        shape_pieces: &[Shape],
+        path: Vec2<f32>,
+    ) -> Option<(f32, Vec<Vec2<f32>>)> {
+        let alpha = pieces
+            .iter()
+            .flat_map(|a| shape_pieces.iter().map(move |b| (a, b)))
+            .filter_map(|(a, b)| time_of_impact(a, b, path))
+            .fold(None, |min: Option<f32>, alpha| {
+                Some(min.map_or(alpha, |min| min.min(alpha)))
+            })?;
+
+        let moved = translation(path * alpha) * shape.clone();
+        Some((alpha, self.contacts(&moved)))
+    }
+
+    /// Points where `shape` touches this shape, or where they are closest.
+    pub fn contacts(&self, shape: &Shape) -> Vec<Vec2<f32>> {
+        match (self, shape) {
+            (Shape::Polygon(a), Shape::Polygon(b)) => polygon::sweep::contacts(a, b),
+            _ => vec![round_contact(self, shape)],
+        }
+    }
+
+    /// How deep `shape` reaches into this shape, and the unit normal it should be pushed
+    /// along to get out. Shapes that do not overlap have no penetration.
+    pub fn penetration(&self, shape: &Shape) -> Option<(f32, Vec2<f32>)> {
+        Shape::separation_pieces(
+            &self.decompose_convex(),
+            &shape.decompose_convex(),
+            Vec2::default(),
+        )
+        .filter(|(depth, _)| *depth > 0.0)
+    }
+
+    /// Overlap of the pair of convex pieces that overlap the most, or are the closest,
+    /// when `shape_pieces` are moved by `offset`, and the normal that pushes them apart.
+    /// The overlap is negative when all pieces are apart.
+    pub fn separation_pieces(
+        pieces: &[Shape],
+        shape_pieces: &[Shape],
+        offset: Vec2<f32>,
+    ) -> Option<(f32, Vec2<f32>)> {
+        pieces
+            .iter()
+            .flat_map(|a| shape_pieces.iter().map(move |b| (a, b)))
+            .filter_map(|(a, b)| separation(a, b, offset))
+            .max_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    /// Points that are within the radius of the shape, and the radius.
+    /// Polygons are their own core, with no radius.
+    fn core(&self) -> (Vec<Vec2<f32>>, f32) {
+        match self {
+            Shape::Polygon(polygon) => (
+                polygon.points.iter().map(|p| p.into_cartesian()).collect(),
+                0.0,
+            ),
+            Shape::Circle(circle) => (vec![circle.center], circle.radius),
+            Shape::Capsule(capsule) => (vec![capsule.a, capsule.b], capsule.radius),
+        }
+    }
+}
+
+/// Fraction of `path` after which convex `b`, moving along it, touches convex `a`.
+/// Shapes that already overlap are not reported.
+fn time_of_impact(a: &Shape, b: &Shape, path: Vec2<f32>) -> Option<f32> {
+    if let (Shape::Polygon(a), Shape::Polygon(b)) = (a, b) {
+        return polygon::sweep::time_of_impact(a, b, path);
+    }
+
+    // `b` touches `a` when the offset it moved by is closer than both radii to the
+    // Minkowski difference of their cores, so the path is tested against the outline of
+    // the difference, grown by the radii
+    let (core_a, radius_a) = a.core();
+    let (core_b, radius_b) = b.core();
+    let radius = radius_a + radius_b;
+    let differences: Vec<Vec2<f32>> = core_a
+        .iter()
+        .flat_map(|p| core_b.iter().map(move |q| *p - *q))
+        .collect();
+
+    let hull = construct_convex_hull(&differences);
+    let (points, edges): (Vec<Vec2<f32>>, Vec<Segment>) = if hull.points.len() >= 3 {
+        if hull.contains_point(Vec2::default()) {
+            return None;
+        }
+        let points = hull.points.iter().map(|p| p.into_cartesian()).collect();
+        (points, hull.segments().collect())
+    } else {
+        // a point or a line, where all edges between the points are on the outline
+        let mut edges = Vec::new();
+        for (i, p) in differences.iter().enumerate() {
+            for q in &differences[i + 1..] {
+                edges.push(Segment::new(*p, *q));
+            }
+        }
+        (differences, edges)
+    };
+
+    let start_distance = points
+        .iter()
+        .map(|p| p.length())
+        .chain(edges.iter().map(|e| e.distance_to_point(Vec2::default())))
+        .fold(f32::INFINITY, f32::min);
+    if start_distance < radius {
+        return None;
+    }
+
+    let ray = Segment::new(Vec2::default(), path);
+    let around_points = points.iter().filter_map(|p| ray_circle(path, *p, radius));
+    let along_edges = edges.iter().flat_map(|edge| {
+        let direction = edge.direction();
+        let normal = if direction.length_squared() > 0.0 {
+            direction.perpendicular().normalized() * radius
+        } else {
+            Vec2::default()
+        };
+        IntoIterator::into_iter([normal, normal * -1.0]).filter_map(move |normal| {
+            let offset = Segment::new(edge.a + normal, edge.b + normal);
+            ray.intersection(&offset).map(|(alpha, _)| alpha)
+        })
+    });
+    around_points
+        .chain(along_edges)
+        .fold(None, |min: Option<f32>, alpha| {
+            Some(min.map_or(alpha, |min| min.min(alpha)))
+        })
+}
+
+/// Separating axis test of convex `a` and `b` moved by `offset`, like
+/// `polygon::sat::separation`. Round shapes are separated either by a normal of an edge
+/// or by the line between the closest points of their cores.
+fn separation(a: &Shape, b: &Shape, offset: Vec2<f32>) -> Option<(f32, Vec2<f32>)> {
+    if let (Shape::Polygon(a), Shape::Polygon(b)) = (a, b) {
+        return polygon::sat::separation(a, b, offset);
+    }
+
+    let (core_a, radius_a) = a.core();
+    let (core_b, radius_b) = b.core();
+    let core_b: Vec<Vec2<f32>> = core_b.into_iter().map(|p| p + offset).collect();
+
+    let mut axes: Vec<Vec2<f32>> = edge_normals(&core_a).chain(edge_normals(&core_b)).collect();
+    let (on_a, on_b) = closest_points(&core_a, &core_b);
+    if (on_b - on_a).length_squared() > 0.0 {
+        axes.push(on_b - on_a);
+    } else if axes.is_empty() {
+        // concentric circles, any axis separates them as well as any other
+        axes.push(Vec2::new(1.0, 0.0));
+    }
+
+    let project = |core: &[Vec2<f32>], radius: f32, axis: Vec2<f32>| {
+        let (min, max) = core
+            .iter()
+            .map(|p| p.dot(axis))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), t| {
+                (min.min(t), max.max(t))
+            });
+        (min - radius, max + radius)
+    };
+    axes.into_iter()
+        .map(|axis| {
+            let axis = axis.normalized();
+            let (min_a, max_a) = project(&core_a, radius_a, axis);
+            let (min_b, max_b) = project(&core_b, radius_b, axis);
+            let along = max_a - min_b;
+            let against = max_b - min_a;
+            if along < against {
+                (along, axis)
+            } else {
+                (against, axis * -1.0)
+            }
+        })
+        .min_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+/// Normals of the edges of an outline, or of a segment, given by their vertices.
+fn edge_normals(points: &[Vec2<f32>]) -> impl Iterator<Item = Vec2<f32>> + '_ {
+    let edges = match points.len() {
+        0 | 1 => 0,
+        2 => 1,
+        n => n,
+    };
+    (0..edges)
+        .map(move |i| (points[(i + 1) % points.len()] - points[i]).perpendicular())
+        .filter(|normal| normal.length_squared() > 0.0)
+}
+
+/// Fraction of `path`, from the origin, where it first reaches the circle.
+fn ray_circle(path: Vec2<f32>, center: Vec2<f32>, radius: f32) -> Option<f32> {
+    let a = path.dot(path);
+    let b = -2.0 * path.dot(center);
+    let c = center.dot(center) - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if a == 0.0 || discriminant < 0.0 {
+        return None;
+    }
+    let alpha = (-b - discriminant.sqrt()) / (2.0 * a);
+    if (0.0..=1.0).contains(&alpha) {
+        Some(alpha)
+    } else {
+        None
+    }
+}
+
+/// Where shapes, of which at least one is round, touch: on the surface of `a` towards the
+/// closest point of the core of `b`.
+fn round_contact(a: &Shape, b: &Shape) -> Vec2<f32> {
+    let (core_a, radius_a) = a.core();
+    let (core_b, _) = b.core();
+    let (on_a, on_b) = closest_points(&core_a, &core_b);
+    let between = on_b - on_a;
+    if between.length_squared() > 0.0 {
+        on_a + between.normalized() * radius_a
+    } else {
+        on_a
+    }
+}
+
+/// Closest points of two outlines, or of a point or a segment, given by their vertices.
+fn closest_points(a: &[Vec2<f32>], b: &[Vec2<f32>]) -> (Vec2<f32>, Vec2<f32>) {
+    let edges = |points: &[Vec2<f32>]| -> Vec<Segment> {
+        match points.len() {
+            1 => vec![Segment::new(points[0], points[0])],
+            2 => vec![Segment::new(points[0], points[1])],
+            n => (0..n)
+                .map(|i| Segment::new(points[i], points[(i + 1) % n]))
+                .collect(),
+        }
+    };
+    let (edges_a, edges_b) = (edges(a), edges(b));
+
+    edges_a
+        .iter()
+        .flat_map(|edge| edges_b.iter().map(move |other| edge.closest_points(other)))
+        .min_by(|x, y| {
+            let distance = |(p, q): &(Vec2<f32>, Vec2<f32>)| (*q - *p).length_squared();
+            distance(x)
+                .partial_cmp(&distance(y))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap_or_default()
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Shape::Polygon(Polygon::default())
+    }
+}
+
+impl From<Polygon> for Shape {
+    fn from(polygon: Polygon) -> Shape {
+        Shape::Polygon(polygon)
+    }
+}
+
+impl From<Circle> for Shape {
+    fn from(circle: Circle) -> Shape {
+        Shape::Circle(circle)
+    }
+}
+
+impl From<Capsule> for Shape {
+    fn from(capsule: Capsule) -> Shape {
+        Shape::Capsule(capsule)
+    }
+}
+
+impl std::ops::Mul<Shape> for Mat3 {
+    type Output = Shape;
+
+    fn mul(self, mut right: Shape) -> Shape {
+        right.transform(self);
+        right
+    }
+}
+
+impl BoundingBox for Shape {
+    fn bounding_box_transformed(&self, position: &Mat3) -> RectBounds {
+        let round = |center: Vec2<f32>, radius: f32| {
+            let center = (*position * center.into_homogeneous()).into_cartesian();
+            RectBounds::new(center).expand(radius * scale_of(position))
+        };
+        match self {
+            Shape::Polygon(polygon) => polygon.bounding_box_transformed(position),
+            Shape::Circle(circle) => round(circle.center, circle.radius),
+            Shape::Capsule(capsule) => {
+                let mut bounds = round(capsule.a, capsule.radius);
+                bounds += round(capsule.b, capsule.radius);
+                bounds
+            }
+        }
+    }
+}
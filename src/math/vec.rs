@@ -25,6 +25,45 @@ impl<T: std::ops::Neg<Output = T> + Copy> Perpendicular for Vec2<T> {
     }
 }
 
+pub trait Invert: Sized {
+    /// Inverse, or none for transforms that cannot be undone.
+    fn invert(&self) -> Option<Self>;
+}
+
+impl Invert for Mat3 {
+    fn invert(&self) -> Option<Mat3> {
+        let m = |row: usize, column: usize| self[row][column];
+        // cofactors, transposed
+        let mut inverse: Mat3 = (
+            (
+                m(1, 1) * m(2, 2) - m(1, 2) * m(2, 1),
+                m(0, 2) * m(2, 1) - m(0, 1) * m(2, 2),
+                m(0, 1) * m(1, 2) - m(0, 2) * m(1, 1),
+            ),
+            (
+                m(1, 2) * m(2, 0) - m(1, 0) * m(2, 2),
+                m(0, 0) * m(2, 2) - m(0, 2) * m(2, 0),
+                m(0, 2) * m(1, 0) - m(0, 0) * m(1, 2),
+            ),
+            (
+                m(1, 0) * m(2, 1) - m(1, 1) * m(2, 0),
+                m(0, 1) * m(2, 0) - m(0, 0) * m(2, 1),
+                m(0, 0) * m(1, 1) - m(0, 1) * m(1, 0),
+            ),
+        )
+            .into();
+        let determinant =
+            m(0, 0) * inverse[0][0] + m(0, 1) * inverse[1][0] + m(0, 2) * inverse[2][0];
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        for row in &mut inverse.rows {
+            *row *= 1.0 / determinant;
+        }
+        Some(inverse)
+    }
+}
+
 pub trait IntoHomogeneous<T> {
     fn into_homogeneous(self: &Self) -> Vec3<T>;
 }
@@ -89,14 +128,12 @@ pub fn translation(vector: Vec2<f32>) -> Mat3 {
     ((1.0, 0.0, vector.x), (0.0, 1.0, vector.y), (0.0, 0.0, 1.0)).into()
 }
 
-pub fn phase_out(val: f32) -> f32 {
-    if val > 0.0 {
-        return (val - (0.05 * (val + 1.0))).max(0.0);
-    }
-    if val < 0.0 {
-        return (val - (0.05 * (val - 1.0))).min(0.0);
-    }
-    0.0
+/// Factor by which the transform scales areas, square rooted.
+/// For transforms that scale evenly, this is the scale of lengths.
+pub fn scale_of(transform: &Mat3) -> f32 {
+    let x = *transform * Vec3::new(1.0, 0.0, 0.0);
+    let y = *transform * Vec3::new(0.0, 1.0, 0.0);
+    (x.x * y.y - x.y * y.x).abs().sqrt()
 }
 
 // #[serde(remote = "Vec2")]
@@ -190,3 +227,33 @@ impl<'de, T: Serialize + Deserialize<'de>> DeserializeAs<'de, Vec3<T>> for Vec3S
         Ok(v.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Mat3, b: Mat3) {
+        for row in 0..3 {
+            for column in 0..3 {
+                let (a, b) = (a[row][column], b[row][column]);
+                assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_transform() {
+        let m = translation(Vec2::new(120.0, -35.0))
+            * Mat3::rotation(0.7)
+            * Mat3::identity().scaled(2.5.into());
+        assert_close(m.invert().unwrap() * m, Mat3::identity());
+        assert_close(m * m.invert().unwrap(), Mat3::identity());
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        let m: Mat3 = ((1.0, 2.0, 3.0), (2.0, 4.0, 6.0), (0.0, 0.0, 1.0)).into();
+        assert!(m.invert().is_none());
+        assert!(Mat3::identity().scaled(0.0.into()).invert().is_none());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::math::shape::Shape;
+use crate::stars::Stars;
+use crate::world::{Block, Entity, World};
+use gamemath::Vec3;
+use std::mem::{size_of, size_of_val};
+
+/// Approximate memory held by parts of the game, in bytes, by name of the part.
+pub fn usage(world: &World, stars: &Stars) -> Vec<(&'static str, usize)> {
+    let entities: Vec<&Entity> = world.grids.values().flat_map(|g| &g.entities).collect();
+
+    let entity_bytes = entities
+        .iter()
+        .map(|e| {
+            size_of::<Entity>()
+                + e.blocks.capacity() * size_of::<Box<dyn Block>>()
+                + e.blocks.iter().map(|b| size_of_val(&**b)).sum::<usize>()
+                + e.name.as_ref().map_or(0, |n| n.capacity())
+        })
+        .sum();
+
+    let polygon_bytes = entities
+        .iter()
+        .map(|e| {
+            shape_size(&e.shape)
+                + e.grid_shape().map_or(0, shape_size)
+                + e.blocks
+                    .iter()
+                    .map(|b| shape_size(b.shape()))
+                    .sum::<usize>()
+        })
+        .sum();
+
+    let star_bytes = stars.points.capacity() * size_of::<Vec3<f32>>();
+
+    vec![
+        ("entities", entity_bytes),
+        ("polygons", polygon_bytes),
+        ("stars", star_bytes),
+    ]
+}
+
+/// Round shapes hold no vertices.
+fn shape_size(shape: &Shape) -> usize {
+    shape.polygon().map_or(0, |polygon| {
+        polygon.points.capacity() * size_of::<Vec3<f32>>()
+    })
+}
+
+/// Number and total size of live heap allocations,
+/// when built with the `alloc-stats` feature.
+pub fn live_allocations() -> Option<(usize, usize)> {
+    #[cfg(feature = "alloc-stats")]
+    return Some(counting::live());
+
+    #[cfg(not(feature = "alloc-stats"))]
+    None
+}
+
+#[cfg(feature = "alloc-stats")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+    /// System allocator that counts live allocations.
+    struct CountingAllocator;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                COUNT.fetch_add(1, Ordering::Relaxed);
+                BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            COUNT.fetch_sub(1, Ordering::Relaxed);
+            BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+                BYTES.fetch_add(new_size, Ordering::Relaxed);
+            }
+            new_ptr
+        }
+    }
+
+    pub fn live() -> (usize, usize) {
+        (COUNT.load(Ordering::Relaxed), BYTES.load(Ordering::Relaxed))
+    }
+}
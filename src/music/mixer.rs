@@ -0,0 +1,72 @@
+use crate::platform::{AudioSource, AUDIO_FREQUENCY};
+use std::sync::Arc;
+
+/// Time in which the old track fades out and the new one fades in.
+const CROSSFADE_SECONDS: f32 = 3.0;
+
+/// Track being played, looped until faded out.
+struct Voice {
+    /// Interleaved stereo samples.
+    samples: Arc<Vec<f32>>,
+    position: usize,
+    gain: f32,
+    fading_in: bool,
+}
+
+/// Mixes the current track with tracks that are fading out.
+pub struct Mixer {
+    voices: Vec<Voice>,
+    volume: f32,
+}
+
+impl Mixer {
+    pub fn new(volume: f32) -> Self {
+        Mixer {
+            voices: Vec::new(),
+            volume,
+        }
+    }
+
+    /// Fades out what is playing and fades in `samples`, if any.
+    pub fn crossfade_to(&mut self, samples: Option<Vec<f32>>) {
+        for voice in &mut self.voices {
+            voice.fading_in = false;
+        }
+        if let Some(samples) = samples.filter(|s| s.len() >= 2) {
+            self.voices.push(Voice {
+                samples: Arc::new(samples),
+                position: 0,
+                gain: 0.0,
+                fading_in: true,
+            });
+        }
+    }
+}
+
+impl AudioSource for Mixer {
+    fn fill(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+
+        let fade_step = 1.0 / (CROSSFADE_SECONDS * AUDIO_FREQUENCY as f32);
+        for voice in &mut self.voices {
+            for frame in out.chunks_exact_mut(2) {
+                let target = if voice.fading_in { 1.0 } else { 0.0 };
+                if voice.gain < target {
+                    voice.gain = (voice.gain + fade_step).min(target);
+                } else if voice.gain > target {
+                    voice.gain = (voice.gain - fade_step).max(target);
+                }
+
+                if voice.position + 1 >= voice.samples.len() {
+                    voice.position = 0;
+                }
+                let gain = voice.gain * self.volume;
+                frame[0] += voice.samples[voice.position] * gain;
+                frame[1] += voice.samples[voice.position + 1] * gain;
+                voice.position += 2;
+            }
+        }
+        self.voices
+            .retain(|voice| voice.fading_in || voice.gain > 0.0);
+    }
+}
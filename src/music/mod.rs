@@ -0,0 +1,143 @@
+mod mixer;
+mod wav;
+
+use crate::client::EntityId;
+use crate::paths::Paths;
+use crate::platform::AudioSource;
+use crate::settings::Settings;
+use crate::world::{Faction, World};
+use mixer::Mixer;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Hostile entities closer than this to the controlled entity start combat music.
+const HOSTILE_DISTANCE: f32 = 1500.0;
+
+/// Combat music continues until hostiles are this much further away, so it does not flap.
+const CALM_DISTANCE: f32 = HOSTILE_DISTANCE * 1.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mood {
+    Calm,
+    Combat,
+}
+
+/// Background music, which crossfades between calm and combat tracks.
+/// Tracks are WAV files in the music directory. Names starting with `combat` are combat tracks.
+pub struct Music {
+    mixer: Arc<Mutex<Mixer>>,
+    calm: Vec<PathBuf>,
+    combat: Vec<PathBuf>,
+    mood: Option<Mood>,
+    played: usize,
+}
+
+impl Music {
+    pub fn load(paths: &Paths, settings: &Settings) -> Music {
+        let mut music = Music {
+            mixer: Arc::new(Mutex::new(Mixer::new(
+                settings.audio_volume * settings.music_volume,
+            ))),
+            calm: Vec::new(),
+            combat: Vec::new(),
+            mood: None,
+            played: 0,
+        };
+
+        let entries = match paths.music().and_then(std::fs::read_dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("cannot list music: {}", e);
+                return music;
+            }
+        };
+        let mut tracks: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wav"))
+            .collect();
+        tracks.sort();
+
+        for track in tracks {
+            let name = track.file_name().unwrap_or_default().to_string_lossy();
+            if name.starts_with("combat") {
+                music.combat.push(track);
+            } else {
+                music.calm.push(track);
+            }
+        }
+        music
+    }
+
+    /// Samples for the audio output.
+    pub fn source(&self) -> Arc<Mutex<dyn AudioSource>> {
+        self.mixer.clone()
+    }
+
+    /// Switches to a track of the other mood, when hostiles come near or leave.
+    pub fn tick(&mut self, world: &World, focus: &EntityId) {
+        let distance = match self.mood {
+            Some(Mood::Combat) => CALM_DISTANCE,
+            _ => HOSTILE_DISTANCE,
+        };
+        let mood = if hostile_near(world, focus, distance) {
+            Mood::Combat
+        } else {
+            Mood::Calm
+        };
+
+        if self.mood != Some(mood) {
+            self.mood = Some(mood);
+            self.play(mood);
+        }
+    }
+
+    fn play(&mut self, mood: Mood) {
+        let tracks = match mood {
+            Mood::Calm => &self.calm,
+            Mood::Combat => &self.combat,
+        };
+        let samples = if tracks.is_empty() {
+            None
+        } else {
+            let track = &tracks[self.played % tracks.len()];
+            self.played += 1;
+
+            match wav::load(track) {
+                Ok(samples) => Some(samples),
+                Err(e) => {
+                    eprintln!("cannot load music {}: {}", track.display(), e);
+                    None
+                }
+            }
+        };
+
+        if let Ok(mut mixer) = self.mixer.lock() {
+            mixer.crossfade_to(samples);
+        }
+    }
+}
+
+fn hostile_near(world: &World, focus: &EntityId, distance: f32) -> bool {
+    let position = match world.get_entity(focus) {
+        Some(entity) => entity.position.state,
+        None => return false,
+    };
+
+    for relation in world.get_relations(focus.grid_id, Default::default()) {
+        let grid = match world.grids.get(&relation.id) {
+            Some(grid) => grid,
+            None => continue,
+        };
+        for entity in &grid.entities {
+            if world.components.faction(entity.get_id()) != Faction::Hostile {
+                continue;
+            }
+            let offset = entity.position.state + relation.position.state - position;
+            if offset.length() < distance {
+                return true;
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,87 @@
+use crate::platform::AUDIO_FREQUENCY;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+
+/// Reads a WAV file of 16-bit integer or 32-bit float samples,
+/// as interleaved stereo at the output frequency.
+pub fn load(path: &Path) -> Result<Vec<f32>, Error> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid("not a WAV file"));
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut chunks = &bytes[12..];
+    while chunks.len() >= 8 {
+        let id = &chunks[0..4];
+        let size = u32::from_le_bytes([chunks[4], chunks[5], chunks[6], chunks[7]]) as usize;
+        let body = chunks
+            .get(8..8 + size)
+            .ok_or_else(|| invalid("truncated chunk"))?;
+        match id {
+            b"fmt " if body.len() >= 16 => format = Some(body),
+            b"data" => data = Some(body),
+            _ => {}
+        }
+        // chunks are padded to even size
+        chunks = chunks.get(8 + size + size % 2..).unwrap_or_default();
+    }
+    let format = format.ok_or_else(|| invalid("missing format"))?;
+    let data = data.ok_or_else(|| invalid("missing data"))?;
+
+    let u16_at = |i: usize| u16::from_le_bytes([format[i], format[i + 1]]);
+    let encoding = u16_at(0);
+    let channels = u16_at(2) as usize;
+    let frequency = u32::from_le_bytes([format[4], format[5], format[6], format[7]]);
+    let bits = u16_at(14);
+    if channels == 0 || frequency == 0 {
+        return Err(invalid("no channels"));
+    }
+
+    let samples: Vec<f32> = match (encoding, bits) {
+        (FORMAT_PCM, 16) => data
+            .chunks_exact(2)
+            .map(|s| i16::from_le_bytes([s[0], s[1]]) as f32 / 32768.0)
+            .collect(),
+        (FORMAT_FLOAT, 32) => data
+            .chunks_exact(4)
+            .map(|s| f32::from_le_bytes([s[0], s[1], s[2], s[3]]))
+            .collect(),
+        _ => return Err(invalid("unsupported sample format")),
+    };
+
+    let frames: Vec<[f32; 2]> = samples
+        .chunks_exact(channels)
+        .map(|frame| [frame[0], frame[channels.min(2) - 1]])
+        .collect();
+    Ok(resample(&frames, frequency))
+}
+
+/// Converts frames to the output frequency, by interpolating between neighbouring frames.
+fn resample(frames: &[[f32; 2]], frequency: u32) -> Vec<f32> {
+    if frames.is_empty() {
+        return Vec::new();
+    }
+    let step = frequency as f64 / AUDIO_FREQUENCY as f64;
+    let length = (frames.len() as f64 / step) as usize;
+
+    let mut out = Vec::with_capacity(length * 2);
+    for i in 0..length {
+        let position = i as f64 * step;
+        let index = position as usize;
+        let alpha = (position - index as f64) as f32;
+        let a = frames[index];
+        let b = frames[(index + 1).min(frames.len() - 1)];
+        out.push(a[0] + (b[0] - a[0]) * alpha);
+        out.push(a[1] + (b[1] - a[1]) * alpha);
+    }
+    out
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_owned())
+}
@@ -0,0 +1,128 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+
+/// Largest message accepted from the peer. Longer lengths close the connection.
+const MAX_MESSAGE_LEN: usize = 32 << 20;
+
+/// Most bytes waiting to be sent. A peer that falls further behind is disconnected.
+const MAX_OUTGOING: usize = 64 << 20;
+
+/// Non-blocking TCP stream of messages.
+/// Each message is encoded with MessagePack and prefixed with its length as big-endian u32.
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    closed: bool,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Connection {
+            stream,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            closed: false,
+        })
+    }
+
+    pub fn connect(address: &str) -> io::Result<Self> {
+        Connection::new(TcpStream::connect(address)?)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Whether messages sent earlier have not all been written to the stream yet.
+    pub fn is_sending(&self) -> bool {
+        !self.outgoing.is_empty()
+    }
+
+    pub fn send<T: Serialize>(&mut self, message: &T) {
+        match rmp_serde::to_vec(message) {
+            Ok(bytes) => self.send_encoded(&bytes),
+            Err(e) => eprintln!("cannot encode message: {}", e),
+        }
+    }
+
+    /// Sends a message that has already been encoded, so it can be shared between connections.
+    pub fn send_encoded(&mut self, bytes: &[u8]) {
+        if self.closed {
+            return;
+        }
+        if self.outgoing.len() + 4 + bytes.len() > MAX_OUTGOING {
+            eprintln!("peer does not keep up, disconnecting");
+            self.close();
+            return;
+        }
+        self.outgoing
+            .extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        self.outgoing.extend_from_slice(bytes);
+        self.flush();
+    }
+
+    fn flush(&mut self) {
+        while !self.closed && !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => self.closed = true,
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => self.closed = true,
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        self.outgoing = Vec::new();
+        self.incoming = Vec::new();
+    }
+
+    /// Returns all messages that have been fully received.
+    pub fn receive<T: DeserializeOwned>(&mut self) -> Vec<T> {
+        self.flush();
+
+        let mut buffer = [0u8; 4096];
+        while !self.closed {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => self.closed = true,
+                Ok(read) => self.incoming.extend_from_slice(&buffer[..read]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => self.closed = true,
+            }
+        }
+
+        let mut messages = Vec::new();
+        let mut start = 0;
+        while self.incoming.len() >= start + 4 {
+            let mut length = [0u8; 4];
+            length.copy_from_slice(&self.incoming[start..start + 4]);
+            let length = u32::from_be_bytes(length) as usize;
+            if length > MAX_MESSAGE_LEN {
+                eprintln!("message of {} bytes is too long, disconnecting", length);
+                self.close();
+                return messages;
+            }
+            let end = start + 4 + length;
+            if self.incoming.len() < end {
+                break;
+            }
+
+            match rmp_serde::from_read_ref(&self.incoming[start + 4..end]) {
+                Ok(message) => messages.push(message),
+                Err(e) => eprintln!("cannot decode message: {}", e),
+            }
+            start = end;
+        }
+        self.incoming.drain(..start);
+        messages
+    }
+}
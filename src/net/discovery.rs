@@ -0,0 +1,86 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+pub const DISCOVERY_PORT: u16 = 7879;
+
+const QUERY: &[u8] = b"dgame?";
+const REPLY: &[u8] = b"dgame!";
+
+const QUERY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Answers LAN discovery queries with the port the server listens on.
+pub struct Beacon {
+    socket: UdpSocket,
+    game_port: u16,
+}
+
+impl Beacon {
+    pub fn bind(game_port: u16) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT))?;
+        socket.set_nonblocking(true)?;
+        Ok(Beacon { socket, game_port })
+    }
+
+    pub fn tick(&self) {
+        let mut buffer = [0u8; 16];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((len, source)) if &buffer[..len] == QUERY => {
+                    let mut reply = REPLY.to_vec();
+                    reply.extend_from_slice(&self.game_port.to_be_bytes());
+                    self.socket.send_to(&reply, source).ok();
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+/// Finds servers on the local network by broadcasting queries.
+pub struct Discovery {
+    socket: UdpSocket,
+    last_query: Option<Instant>,
+    servers: Vec<SocketAddr>,
+}
+
+impl Discovery {
+    pub fn new() -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.set_broadcast(true)?;
+        socket.set_nonblocking(true)?;
+        Ok(Discovery {
+            socket,
+            last_query: None,
+            servers: Vec::new(),
+        })
+    }
+
+    pub fn servers(&self) -> &[SocketAddr] {
+        &self.servers
+    }
+
+    pub fn tick(&mut self) {
+        if self.last_query.is_none_or(|q| q.elapsed() > QUERY_INTERVAL) {
+            self.socket
+                .send_to(QUERY, ("255.255.255.255", DISCOVERY_PORT))
+                .ok();
+            self.last_query = Some(Instant::now());
+        }
+
+        let mut buffer = [0u8; 16];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((len, source)) if len == REPLY.len() + 2 && buffer.starts_with(REPLY) => {
+                    let port = u16::from_be_bytes([buffer[len - 2], buffer[len - 1]]);
+                    let server = SocketAddr::new(source.ip(), port);
+                    if !self.servers.contains(&server) {
+                        self.servers.push(server);
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod connection;
+pub mod discovery;
+pub mod protocol;
+pub mod remote;
+pub mod server;
+
+pub const DEFAULT_PORT: u16 = 7878;
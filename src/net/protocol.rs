@@ -0,0 +1,35 @@
+use crate::client::EntityId;
+use crate::events::WorldEvent;
+use crate::ui::user_controls::Action;
+use crate::world::{Entity, World};
+use serde::{Deserialize, Serialize};
+
+/// Messages act on the entity the server gave the client, which clients cannot choose.
+#[derive(Serialize, Deserialize)]
+pub enum ClientMessage {
+    Actions {
+        actions: Vec<Action>,
+    },
+    /// Builds a blueprint next to the entity, paid with its building material.
+    SpawnEntity {
+        entity: Box<Entity>,
+    },
+    Chat {
+        text: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum ServerMessage {
+    /// Assigns the entity the client controls.
+    Welcome { entity: EntityId },
+    Snapshot {
+        world: Box<World>,
+        /// What happened in the tick that produced the world.
+        events: Vec<WorldEvent>,
+    },
+    Chat {
+        author: String,
+        text: String,
+    },
+}
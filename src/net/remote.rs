@@ -0,0 +1,66 @@
+use super::connection::Connection;
+use super::protocol::{ClientMessage, ServerMessage};
+use crate::client::EntityId;
+use crate::ui::user_controls::Action;
+use crate::world::{Entity, World};
+use std::io::{self, ErrorKind};
+use std::time::{Duration, Instant};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Client side of the connection to a server.
+pub struct Remote {
+    connection: Connection,
+}
+
+impl Remote {
+    /// Connects to a server and waits for the controlled entity and the first snapshot of the world.
+    pub fn connect(address: &str) -> io::Result<(Remote, World, EntityId)> {
+        let mut connection = Connection::connect(address)?;
+
+        let started = Instant::now();
+        let mut welcome = None;
+        let mut snapshot = None;
+        while started.elapsed() < CONNECT_TIMEOUT && !connection.is_closed() {
+            for message in connection.receive() {
+                match message {
+                    ServerMessage::Welcome { entity } => welcome = Some(entity),
+                    ServerMessage::Snapshot { world, .. } => snapshot = Some(*world),
+                    ServerMessage::Chat { .. } => {}
+                }
+            }
+            if let (Some(entity), Some(world)) = (welcome, snapshot.take()) {
+                return Ok((Remote { connection }, world, entity));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        Err(io::Error::new(
+            ErrorKind::TimedOut,
+            "server did not send the world",
+        ))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.connection.is_closed()
+    }
+
+    pub fn receive(&mut self) -> Vec<ServerMessage> {
+        self.connection.receive()
+    }
+
+    /// Sends actions of the entity the server gave this client.
+    pub fn send_actions(&mut self, actions: Vec<Action>) {
+        self.connection.send(&ClientMessage::Actions { actions });
+    }
+
+    /// Asks the server to build a blueprint next to the entity of this client.
+    pub fn spawn_entity(&mut self, entity: Entity) {
+        self.connection.send(&ClientMessage::SpawnEntity {
+            entity: Box::new(entity),
+        });
+    }
+
+    pub fn send_chat(&mut self, text: String) {
+        self.connection.send(&ClientMessage::Chat { text });
+    }
+}
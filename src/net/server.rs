@@ -0,0 +1,244 @@
+use super::connection::Connection;
+use super::discovery::Beacon;
+use super::protocol::{ClientMessage, ServerMessage};
+use crate::client::EntityId;
+use crate::engine::{Accumulator, Engine};
+use crate::events::Events;
+use crate::scripting::Scripts;
+use crate::ui::chat::MAX_MESSAGE_LEN;
+use crate::world::grid::construct_demo_ship;
+use crate::world::{systems_tick, BlockRegistry, Faction, World};
+use std::io::ErrorKind;
+use std::net::TcpListener;
+
+/// Checksum of the world is logged every this many ticks, about ten seconds, so logs of
+/// runs from the same save and input can be compared for determinism breaks.
+const CHECKSUM_INTERVAL: u64 = 600;
+
+/// Owns the world and simulates it. Clients only act through the entities they were given.
+pub struct Server {
+    listener: TcpListener,
+    beacon: Option<Beacon>,
+    world: World,
+    engine: Engine,
+    /// Whether the last tick deferred grid maintenance, to log only when it starts.
+    deferring: bool,
+    events: Events,
+    scripts: Scripts,
+    blocks: BlockRegistry,
+    clients: Vec<RemoteClient>,
+    joined_count: usize,
+    tick: u64,
+}
+
+struct RemoteClient {
+    connection: Connection,
+    name: String,
+    /// Ship spawned for the client, the only entity its messages act on.
+    entity: EntityId,
+}
+
+impl Server {
+    pub fn new(
+        listener: TcpListener,
+        world: World,
+        scripts: Scripts,
+        blocks: BlockRegistry,
+    ) -> std::io::Result<Self> {
+        listener.set_nonblocking(true)?;
+
+        // discovery is optional, another server on this machine may have the port
+        let beacon = match Beacon::bind(listener.local_addr()?.port()) {
+            Ok(beacon) => Some(beacon),
+            Err(e) => {
+                eprintln!("LAN discovery disabled: {}", e);
+                None
+            }
+        };
+
+        Ok(Server {
+            listener,
+            beacon,
+            world,
+            engine: Engine::default(),
+            deferring: false,
+            events: Events::default(),
+            scripts,
+            blocks,
+            clients: Vec::new(),
+            joined_count: 0,
+            tick: 0,
+        })
+    }
+
+    pub fn run(mut self) {
+        let mut accumulator = Accumulator::default();
+        loop {
+            for _ in 0..accumulator.ticks_due() {
+                self.tick();
+            }
+
+            std::thread::sleep(accumulator.until_next_tick());
+        }
+    }
+
+    pub fn tick(&mut self) {
+        if let Some(beacon) = &self.beacon {
+            beacon.tick();
+        }
+        self.accept_clients();
+
+        let mut broadcast = Vec::new();
+        for client in &mut self.clients {
+            for message in client.connection.receive() {
+                broadcast.extend(Server::handle_message(
+                    &mut self.world,
+                    &self.blocks,
+                    &client.name,
+                    &mut client.entity,
+                    message,
+                    &mut self.events,
+                ));
+            }
+        }
+        // ships of clients that left would stay in the world as players
+        let world = &mut self.world;
+        self.clients.retain(|client| {
+            if !client.connection.is_closed() {
+                return true;
+            }
+            if let Some(entity) = world.find_entity(&client.entity) {
+                world.remove_entity(&entity);
+            }
+            false
+        });
+
+        for message in broadcast {
+            for client in &mut self.clients {
+                client.connection.send(&message);
+            }
+        }
+
+        let report = self.engine.tick(&mut self.world, &mut self.events);
+        if report.deferred_maintenance && !self.deferring {
+            eprintln!(
+                "tick took {:?}, deferring grid maintenance",
+                report.duration
+            );
+        }
+        self.deferring = report.deferred_maintenance;
+        systems_tick(&mut self.world, &self.events, &mut self.scripts);
+
+        self.tick += 1;
+        if self.tick.is_multiple_of(CHECKSUM_INTERVAL) {
+            println!(
+                "tick {}: world checksum {:016x}",
+                self.tick,
+                self.world.checksum()
+            );
+        }
+
+        self.broadcast_snapshot();
+    }
+
+    fn accept_clients(&mut self) {
+        loop {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    eprintln!("cannot accept client: {}", e);
+                    break;
+                }
+            };
+            let mut connection = match Connection::new(stream) {
+                Ok(connection) => connection,
+                Err(e) => {
+                    eprintln!("cannot accept client: {}", e);
+                    continue;
+                }
+            };
+
+            // each client gets a ship of its own
+            if let Some(grid) = self.world.grids.values_mut().next() {
+                let ship = construct_demo_ship();
+                let entity = EntityId::new(grid.get_id(), ship.get_id());
+                grid.spawn_entity(Default::default(), ship);
+                self.world
+                    .components
+                    .factions
+                    .insert(entity.entity_id, Faction::Player);
+
+                connection.send(&ServerMessage::Welcome { entity });
+
+                self.joined_count += 1;
+                let name = format!("player {}", self.joined_count);
+                self.clients.push(RemoteClient {
+                    connection,
+                    name,
+                    entity,
+                });
+            }
+        }
+    }
+
+    /// Applies a message from a client, acting on `entity` of the client and following it
+    /// when it moves to another grid. Returns a message for all clients, if any.
+    fn handle_message(
+        world: &mut World,
+        blocks: &BlockRegistry,
+        author: &str,
+        entity: &mut EntityId,
+        message: ClientMessage,
+        events: &mut Events,
+    ) -> Option<ServerMessage> {
+        if let Some(found) = world.find_entity(entity) {
+            *entity = found;
+        }
+        match message {
+            ClientMessage::Actions { actions } => {
+                if world.get_entity(entity).is_some() {
+                    for action in actions {
+                        world.apply_action(entity, action, blocks, events);
+                    }
+                }
+                None
+            }
+            ClientMessage::SpawnEntity { entity: blueprint } => {
+                if world.get_entity(entity).is_some() {
+                    world.spawn_blueprint(entity, *blueprint, blocks);
+                }
+                None
+            }
+            // clients can send longer messages than their chat lets them type
+            ClientMessage::Chat { text } => Some(ServerMessage::Chat {
+                author: author.to_owned(),
+                text: text.chars().take(MAX_MESSAGE_LEN).collect(),
+            }),
+        }
+    }
+
+    fn broadcast_snapshot(&mut self) {
+        let snapshot = ServerMessage::Snapshot {
+            world: Box::new(std::mem::take(&mut self.world)),
+            events: self.events.poll().collect(),
+        };
+        let encoded = rmp_serde::to_vec(&snapshot);
+        if let ServerMessage::Snapshot { world, .. } = snapshot {
+            self.world = *world;
+        }
+
+        match encoded {
+            Ok(bytes) => {
+                // each snapshot holds the whole world, so clients that are still receiving
+                // the previous one skip this one instead of queueing it
+                for client in &mut self.clients {
+                    if !client.connection.is_sending() {
+                        client.connection.send_encoded(&bytes);
+                    }
+                }
+            }
+            Err(e) => eprintln!("cannot encode snapshot: {}", e),
+        }
+    }
+}
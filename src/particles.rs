@@ -0,0 +1,140 @@
+use crate::math::vec::*;
+use crate::platform::{Color, DrawTarget};
+use crate::world::{FrameShifts, Insist, World};
+use gamemath::{Mat2, Mat3, Vec2};
+use rand::{thread_rng, Rng};
+use std::collections::HashMap;
+
+/// Particles kept at once, over all grids. New ones are not spawned past it.
+const MAX_PARTICLES: usize = 4000;
+
+/// Exhaust particles spawned per tick by a thruster at full throttle.
+const EXHAUST_RATE: f32 = 3.0;
+/// Speed of exhaust relative to its thruster, per tick.
+const EXHAUST_SPEED: f32 = 1.5;
+/// Exhaust leaves the nozzle spread by up to this angle to either side.
+const EXHAUST_SPREAD: f32 = 0.2;
+const EXHAUST_LIFETIME: u32 = 30;
+
+const EXHAUST_HOT: Color = Color::rgb(255, 210, 120);
+const EXHAUST_COLD: Color = Color::rgb(90, 30, 10);
+
+struct Particle {
+    position: Insist<Vec2<f32>>,
+    age: u32,
+    lifetime: u32,
+}
+
+/// Short lived specks drawn over the grids, such as thruster exhaust. They are only seen
+/// by this client and do not take part in the simulation.
+#[derive(Default)]
+pub struct Particles {
+    /// By id of the grid the particles move in.
+    grids: HashMap<u64, Vec<Particle>>,
+    count: usize,
+}
+
+impl Particles {
+    /// Adds a particle at a place in a grid, which lives for `lifetime` ticks.
+    pub fn spawn(&mut self, grid_id: u64, position: Insist<Vec2<f32>>, lifetime: u32) {
+        if self.count >= MAX_PARTICLES {
+            return;
+        }
+        self.count += 1;
+        self.grids.entry(grid_id).or_default().push(Particle {
+            position,
+            age: 0,
+            lifetime,
+        });
+    }
+
+    /// Emits exhaust of all thrusters, moves particles and removes the ones that faded
+    /// or whose grid is gone.
+    pub fn tick(&mut self, world: &World) {
+        self.grids
+            .retain(|grid_id, _| world.grids.contains_key(grid_id));
+        for particles in self.grids.values_mut() {
+            for particle in particles.iter_mut() {
+                particle.position.state += particle.position.velocity;
+                particle.age += 1;
+            }
+            particles.retain(|particle| particle.age < particle.lifetime);
+        }
+        self.grids.retain(|_, particles| !particles.is_empty());
+        self.count = self.grids.values().map(Vec::len).sum();
+
+        self.emit_exhaust(world);
+    }
+
+    /// Keeps particles in place when the frames of reference of their grids change.
+    pub fn follow(&mut self, shifts: &FrameShifts) {
+        for (grid_id, particles) in &mut self.grids {
+            if let Some(shift) = shifts.get(grid_id) {
+                for particle in particles {
+                    particle.position += *shift;
+                }
+            }
+        }
+    }
+
+    /// Particles spawned at nozzles of blocks, as many as their exhaust is strong. They
+    /// carry the velocity of the entity at the nozzle.
+    fn emit_exhaust(&mut self, world: &World) {
+        let mut rng = thread_rng();
+        for (grid_id, grid) in &world.grids {
+            for entity in &grid.entities {
+                let rotation = Mat2::rotation(entity.angle.state);
+                for block in &entity.blocks {
+                    let exhaust = match block.exhaust() {
+                        Some(exhaust) => exhaust,
+                        None => continue,
+                    };
+                    let to_entity = block.transform();
+                    let nozzle = (to_entity * exhaust.nozzle.into_homogeneous()).into_cartesian();
+                    let direction = Mat2::rotation(block.angle()) * exhaust.direction;
+
+                    let arm = rotation * nozzle;
+                    let spin = Vec2::new(-arm.y, arm.x) * entity.angle.velocity;
+                    let base = Insist {
+                        state: entity.position.state + arm,
+                        velocity: entity.position.velocity + spin,
+                    };
+
+                    let count = (exhaust.strength * EXHAUST_RATE + rng.gen::<f32>()) as usize;
+                    for _ in 0..count {
+                        let spread = rng.gen_range(-EXHAUST_SPREAD, EXHAUST_SPREAD);
+                        let speed = EXHAUST_SPEED * rng.gen_range(0.5, 1.0);
+                        let mut position = base;
+                        position.velocity += rotation * Mat2::rotation(spread) * direction * speed;
+                        // spread along the first tick, so a fast ship leaves no gaps
+                        position.state += position.velocity * rng.gen::<f32>();
+                        self.spawn(*grid_id, position, EXHAUST_LIFETIME);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws particles of a grid as streaks along their velocity, fading as they age.
+    pub fn render(&self, grid_id: u64, position: Mat3, canvas: &mut dyn DrawTarget) {
+        let particles = match self.grids.get(&grid_id) {
+            Some(particles) => particles,
+            None => return,
+        };
+        for particle in particles {
+            let fade = particle.age as f32 / particle.lifetime as f32;
+            canvas.set_draw_color(blend(EXHAUST_HOT, EXHAUST_COLD, fade));
+            let head = particle.position.state;
+            let tail = head - particle.position.velocity;
+            canvas.draw_line(
+                (position * head.into_homogeneous()).into_cartesian(),
+                (position * tail.into_homogeneous()).into_cartesian(),
+            );
+        }
+    }
+}
+
+fn blend(a: Color, b: Color, amount: f32) -> Color {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount) as u8;
+    Color::rgb(mix(a.r, b.r), mix(a.g, b.g), mix(a.b, b.b))
+}
@@ -0,0 +1,135 @@
+use std::io;
+use std::path::PathBuf;
+
+/// Environment variable with the data directory, used when none is given on the command line.
+pub const DATA_DIR_VAR: &str = "DGAME_DATA_DIR";
+
+/// Locations of files written by the game.
+#[derive(Clone, Debug)]
+pub struct Paths {
+    pub data: PathBuf,
+    pub config: PathBuf,
+}
+
+impl Paths {
+    /// Uses the given directory, or the one from the environment, or `./data` if it exists
+    /// from older versions, or the platform data directory, or `./data` as a last resort.
+    /// Unless the platform directories are used, configuration is stored there as well.
+    pub fn resolve(data_dir: Option<PathBuf>) -> Self {
+        let data_dir = data_dir
+            .or_else(|| {
+                std::env::var_os(DATA_DIR_VAR)
+                    .filter(|dir| !dir.is_empty())
+                    .map(PathBuf::from)
+            })
+            .or_else(|| Some(PathBuf::from("./data")).filter(|dir| dir.is_dir()));
+        let config = data_dir
+            .clone()
+            .or_else(platform_config_dir)
+            .unwrap_or_else(|| PathBuf::from("./data"));
+        let data = data_dir
+            .or_else(platform_data_dir)
+            .unwrap_or_else(|| PathBuf::from("./data"));
+        Paths { data, config }
+    }
+
+    /// Directory of saved entities, created if it does not exist.
+    pub fn entities(&self) -> io::Result<PathBuf> {
+        let dir = self.data.join("entities");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Directory of exported images, created if it does not exist.
+    pub fn exports(&self) -> io::Result<PathBuf> {
+        let dir = self.data.join("exports");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Directory of captured frames, created if it does not exist.
+    pub fn screenshots(&self) -> io::Result<PathBuf> {
+        let dir = self.data.join("screenshots");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Directory of autosaved worlds, created if it does not exist.
+    pub fn saves(&self) -> io::Result<PathBuf> {
+        let dir = self.data.join("saves");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Directory of entity scripts, created if it does not exist.
+    pub fn scripts(&self) -> io::Result<PathBuf> {
+        let dir = self.data.join("scripts");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Directory of background music, created if it does not exist.
+    pub fn music(&self) -> io::Result<PathBuf> {
+        let dir = self.data.join("music");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Directory of images drawn over blocks, created if it does not exist.
+    pub fn textures(&self) -> io::Result<PathBuf> {
+        let dir = self.data.join("textures");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Player statistics over all sessions, in a directory created if it does not exist.
+    pub fn statistics_file(&self) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(&self.data)?;
+        Ok(self.data.join("statistics.toml"))
+    }
+
+    /// Settings file, in a directory created if it does not exist.
+    pub fn settings_file(&self) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(&self.config)?;
+        Ok(self.config.join("settings.toml"))
+    }
+}
+
+#[cfg(windows)]
+fn platform_data_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("dgame"))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_data_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join("Library/Application Support/dgame"))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn platform_data_dir() -> Option<PathBuf> {
+    let xdg = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+    let home = || std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"));
+    xdg.or_else(home).map(|dir| dir.join("dgame"))
+}
+
+#[cfg(windows)]
+fn platform_config_dir() -> Option<PathBuf> {
+    platform_data_dir()
+}
+
+#[cfg(target_os = "macos")]
+fn platform_config_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Preferences/dgame"))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn platform_config_dir() -> Option<PathBuf> {
+    let xdg = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+    let home = || std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"));
+    xdg.or_else(home).map(|dir| dir.join("dgame"))
+}
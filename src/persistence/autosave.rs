@@ -0,0 +1,86 @@
+use crate::client::EntityId;
+use crate::engine::TICK_DURATION;
+use crate::error::Error;
+use crate::paths::Paths;
+use crate::world::World;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Number of autosaves kept, the oldest one is overwritten by the next.
+pub const AUTOSAVE_SLOTS: usize = 3;
+
+/// Periodically saves the world, and the controlled entity separately, into rotating slots
+/// of the saves directory.
+pub struct Autosave {
+    /// Zero disables autosaving.
+    interval_ticks: u64,
+    ticks: u64,
+    next_slot: usize,
+}
+
+impl Autosave {
+    /// Continues after the most recent existing slot, so restarting does not overwrite it.
+    pub fn new(interval_seconds: u32, paths: &Paths) -> Self {
+        let next_slot = paths
+            .saves()
+            .ok()
+            .and_then(|dir| latest_slot(&dir))
+            .map_or(0, |slot| (slot + 1) % AUTOSAVE_SLOTS);
+
+        Autosave {
+            interval_ticks: (Duration::from_secs(interval_seconds as u64).as_nanos()
+                / TICK_DURATION.as_nanos()) as u64,
+            ticks: 0,
+            next_slot,
+        }
+    }
+
+    pub fn tick(&mut self, world: &World, controlled: &EntityId, paths: &Paths) {
+        if self.interval_ticks == 0 {
+            return;
+        }
+        self.ticks += 1;
+        if self.ticks < self.interval_ticks {
+            return;
+        }
+        self.ticks = 0;
+
+        match save(world, controlled, paths, self.next_slot) {
+            Ok(()) => self.next_slot = (self.next_slot + 1) % AUTOSAVE_SLOTS,
+            Err(e) => eprintln!("cannot autosave: {}", e),
+        }
+    }
+}
+
+/// World file of a slot, loadable with `--load`.
+pub fn world_file(dir: &Path, slot: usize) -> PathBuf {
+    dir.join(format!("autosave-{}.world", slot))
+}
+
+fn entity_file(dir: &Path, slot: usize) -> PathBuf {
+    dir.join(format!("autosave-{}.entity", slot))
+}
+
+fn save(world: &World, controlled: &EntityId, paths: &Paths, slot: usize) -> Result<(), Error> {
+    let dir = paths.saves()?;
+
+    world.save_to_file(&world_file(&dir, slot))?;
+
+    if let Some(entity) = world.get_entity(controlled) {
+        let bytes = super::encode(entity)?;
+        super::write_atomic(&entity_file(&dir, slot), &bytes)?;
+    }
+    Ok(())
+}
+
+fn latest_slot(dir: &Path) -> Option<usize> {
+    (0..AUTOSAVE_SLOTS)
+        .filter_map(|slot| {
+            let modified = std::fs::metadata(world_file(dir, slot))
+                .and_then(|m| m.modified())
+                .ok()?;
+            Some((modified, slot))
+        })
+        .max_by_key(|(modified, _): &(SystemTime, usize)| *modified)
+        .map(|(_, slot)| slot)
+}
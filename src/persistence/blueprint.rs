@@ -0,0 +1,78 @@
+use crate::settings::toml::{self, Value};
+use crate::world::Entity;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Summary of a saved entity, listed in the blueprint library.
+/// Stored next to the entity, with `.meta.toml` appended to its file name.
+#[derive(Clone, Debug)]
+pub struct BlueprintMeta {
+    pub name: String,
+    pub mass: f32,
+    pub blocks: usize,
+}
+
+impl BlueprintMeta {
+    /// Expects the mass of the entity to be up to date.
+    pub fn of(entity: &Entity) -> Self {
+        BlueprintMeta {
+            name: entity.name.clone().unwrap_or_else(|| entity.file_name()),
+            mass: entity.mass,
+            blocks: entity.blocks.len(),
+        }
+    }
+
+    /// Returns None when the metadata is missing or invalid.
+    pub fn read(entity_file: &Path) -> Option<BlueprintMeta> {
+        let text = std::fs::read_to_string(meta_file(entity_file)).ok()?;
+        let table = toml::parse(&text).ok()?;
+
+        let name = match table.get("name") {
+            Some(Value::String(name)) => name.clone(),
+            _ => return None,
+        };
+        let mass = match table.get("mass") {
+            Some(Value::Number(mass)) => *mass as f32,
+            _ => return None,
+        };
+        let blocks = match table.get("blocks") {
+            Some(Value::Number(blocks)) => *blocks as usize,
+            _ => return None,
+        };
+        Some(BlueprintMeta { name, mass, blocks })
+    }
+
+    pub fn write(&self, entity_file: &Path) -> io::Result<()> {
+        let mut out = String::new();
+        toml::write_value(&mut out, "name", &Value::String(self.name.clone()));
+        toml::write_value(&mut out, "mass", &Value::Number(self.mass as f64));
+        toml::write_value(&mut out, "blocks", &Value::Number(self.blocks as f64));
+
+        super::write_atomic(&meta_file(entity_file), out.as_bytes())
+    }
+}
+
+/// Removes a saved entity and its metadata. Backups are kept, to recover from mistakes.
+pub fn delete(entity_file: &Path) -> io::Result<()> {
+    std::fs::remove_file(entity_file)?;
+
+    let meta = meta_file(entity_file);
+    if meta.exists() {
+        std::fs::remove_file(meta)?;
+    }
+    Ok(())
+}
+
+/// Backup of the metadata, `.meta.bak`, does not collide with backup of the entity.
+fn meta_file(entity_file: &Path) -> PathBuf {
+    append_extension(entity_file, "meta.toml")
+}
+
+/// Unlike `Path::with_extension`, keeps the existing extension of text entities.
+fn append_extension(file: &Path, extension: &str) -> PathBuf {
+    let mut name = OsString::from(file.as_os_str());
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
@@ -0,0 +1,93 @@
+use super::{LoadError, FORMAT_VERSION, UNVERSIONED};
+use crate::math::{polygon::Polygon, vec::*};
+use crate::world::{Block, Entity, Faction, Insist, World};
+use gamemath::Vec2;
+use serde::Deserialize;
+use serde_with::serde_as;
+
+pub fn entity(version: u32, payload: &[u8]) -> Result<Entity, LoadError> {
+    match version {
+        UNVERSIONED => Ok(rmp_serde::from_read_ref::<_, EntityV1>(payload)?.into()),
+        // version 2 entities have no name, which defaults to none
+        2 | 3 => Ok(rmp_serde::from_read_ref::<_, EntityV3>(payload)?.into()),
+        // version 4 entities have no material, which defaults to the default hull,
+        // shapes up to version 5 are all polygons, which read as shapes,
+        // and entities up to version 6 have no color, which defaults to none
+        4 | 5 | 6 | FORMAT_VERSION => Ok(rmp_serde::from_read_ref(payload)?),
+        _ => Err(LoadError::UnsupportedVersion(version)),
+    }
+}
+
+/// Worlds were first saved in format version 4.
+pub fn world(version: u32, payload: &[u8]) -> Result<World, LoadError> {
+    match version {
+        // version 4 entities have no material and grids no mutual gravity,
+        // which both default, shapes up to version 5 are all polygons
+        // and entities up to version 6 have no color
+        4 | 5 | 6 | FORMAT_VERSION => Ok(rmp_serde::from_read_ref(payload)?),
+        _ => Err(LoadError::UnsupportedVersion(version)),
+    }
+}
+
+/// Entity before factions were introduced.
+#[serde_as]
+#[derive(Deserialize)]
+struct EntityV1 {
+    id: u64,
+    shape: Polygon,
+
+    #[serde_as(as = "Insist<Vec2Serde<f32>>")]
+    position: Insist<Vec2<f32>>,
+    angle: Insist<f32>,
+
+    blocks: Vec<Box<dyn Block>>,
+
+    #[allow(dead_code)]
+    mass: f32,
+    #[allow(dead_code)]
+    mass_angular: f32,
+}
+
+impl From<EntityV1> for Entity {
+    fn from(old: EntityV1) -> Self {
+        let mut entity = Entity::new(old.shape, old.blocks).with_id(old.id);
+        entity.position = old.position;
+        entity.angle = old.angle;
+        entity
+    }
+}
+
+/// Entity before factions were moved to world components.
+#[serde_as]
+#[derive(Deserialize)]
+struct EntityV3 {
+    id: u64,
+    shape: Polygon,
+
+    #[serde_as(as = "Insist<Vec2Serde<f32>>")]
+    position: Insist<Vec2<f32>>,
+    angle: Insist<f32>,
+
+    blocks: Vec<Box<dyn Block>>,
+
+    #[allow(dead_code)]
+    mass: f32,
+    #[allow(dead_code)]
+    mass_angular: f32,
+
+    // faction of a blueprint is decided by whoever spawns it
+    #[allow(dead_code)]
+    faction: Faction,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+impl From<EntityV3> for Entity {
+    fn from(old: EntityV3) -> Self {
+        let mut entity = Entity::new(old.shape, old.blocks).with_id(old.id);
+        entity.position = old.position;
+        entity.angle = old.angle;
+        entity.name = old.name;
+        entity
+    }
+}
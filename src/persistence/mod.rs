@@ -0,0 +1,139 @@
+pub mod autosave;
+pub mod blueprint;
+mod migration;
+pub mod png;
+pub mod svg;
+pub mod watcher;
+
+use crate::math::shape::Shape;
+use crate::world::{Entity, World};
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+
+/// Version of the format written by this build.
+/// Bump it when saved structures change and add a migration from the previous version.
+pub const FORMAT_VERSION: u32 = 7;
+
+/// Saved files start with this magic, followed by the format version as big-endian u32.
+const MAGIC: &[u8; 4] = b"dgm\0";
+
+/// Files written before versioning was introduced have no header.
+const UNVERSIONED: u32 = 1;
+
+/// Extension of entities saved as text. Text has no header and is read in the current format.
+pub const TEXT_EXTENSION: &str = "ron";
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Decode(rmp_serde::decode::Error),
+    DecodeText(ron::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Decode(e) => write!(f, "cannot decode: {}", e),
+            LoadError::DecodeText(e) => write!(f, "cannot parse: {}", e),
+            LoadError::UnsupportedVersion(version) => write!(
+                f,
+                "format version {} is newer than supported version {}",
+                version, FORMAT_VERSION
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<rmp_serde::decode::Error> for LoadError {
+    fn from(e: rmp_serde::decode::Error) -> Self {
+        LoadError::Decode(e)
+    }
+}
+
+impl From<ron::Error> for LoadError {
+    fn from(e: ron::Error) -> Self {
+        LoadError::DecodeText(e)
+    }
+}
+
+/// Encodes a value with the header of the current format version.
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+    bytes.extend(rmp_serde::to_vec(value)?);
+    Ok(bytes)
+}
+
+/// Encodes a value as indented text, meant to be edited by hand.
+pub fn encode_text<T: Serialize>(value: &T) -> Result<String, ron::Error> {
+    ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+}
+
+/// Decodes an entity saved by this or any older build.
+pub fn decode_entity(bytes: &[u8]) -> Result<Entity, LoadError> {
+    let (version, payload) = read_header(bytes);
+    let mut entity = migration::entity(version, payload)?;
+    normalize_winding(&mut entity);
+    Ok(entity)
+}
+
+/// Decodes an entity written by `encode_text`, possibly edited by hand.
+pub fn decode_entity_text(text: &str) -> Result<Entity, LoadError> {
+    let mut entity = ron::de::from_str(text)?;
+    normalize_winding(&mut entity);
+    Ok(entity)
+}
+
+/// Decodes a world saved by this or any older build.
+pub fn decode_world(bytes: &[u8]) -> Result<World, LoadError> {
+    let (version, payload) = read_header(bytes);
+    let mut world = migration::world(version, payload)?;
+    for grid in world.grids.values_mut() {
+        grid.entities.iter_mut().for_each(normalize_winding);
+    }
+    Ok(world)
+}
+
+/// Older builds and hand edits may have saved hulls wound the other way.
+/// Decoded entities have nothing cached for their shape yet.
+fn normalize_winding(entity: &mut Entity) {
+    if let Shape::Polygon(polygon) = &mut entity.shape {
+        polygon.ensure_ccw();
+    }
+}
+
+/// Writes a file so that a crash leaves either the old or the new version in place.
+/// The previous version is kept with `.bak` extension.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let temp = path.with_extension("tmp");
+    {
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+
+    if path.exists() {
+        std::fs::rename(path, path.with_extension("bak"))?;
+    }
+    std::fs::rename(&temp, path)
+}
+
+fn read_header(bytes: &[u8]) -> (u32, &[u8]) {
+    if bytes.len() >= 8 && bytes.starts_with(MAGIC) {
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&bytes[4..8]);
+        (u32::from_be_bytes(version), &bytes[8..])
+    } else {
+        (UNVERSIONED, bytes)
+    }
+}
@@ -0,0 +1,212 @@
+use crate::math::bounding_box::RectBounds;
+use crate::math::polygon::Polygon;
+use crate::math::vec::*;
+use crate::world::Entity;
+use gamemath::Vec2;
+use std::fmt::Write;
+
+const MARGIN: f32 = 10.0;
+const HULL_COLOR: &str = "#ffffff";
+const BLOCK_COLOR: &str = "#c86420";
+const BACKGROUND_COLOR: &str = "#000000";
+
+/// Draws outlines of the hull and the blocks of an entity, in entity coordinates.
+pub fn export_entity(entity: &Entity) -> String {
+    let hull = entity.shape.to_polygon();
+    let blocks: Vec<Polygon> = entity.blocks.iter().map(|block| block.outline()).collect();
+
+    let mut bounds: Option<RectBounds> = None;
+    for point in hull
+        .points
+        .iter()
+        .chain(blocks.iter().flat_map(|b| &b.points))
+    {
+        let point = point.into_cartesian();
+        match &mut bounds {
+            Some(bounds) => *bounds += point,
+            None => bounds = Some(RectBounds::new(point)),
+        }
+    }
+    let bounds = bounds.unwrap_or_default().expand(MARGIN);
+    let size = bounds.bottom_right - bounds.top_left;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        bounds.top_left.x, bounds.top_left.y, size.x, size.y
+    )
+    .unwrap();
+    if let Some(name) = &entity.name {
+        writeln!(svg, "  <title>{}</title>", escape(name)).unwrap();
+    }
+    writeln!(
+        svg,
+        r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+        bounds.top_left.x, bounds.top_left.y, size.x, size.y, BACKGROUND_COLOR
+    )
+    .unwrap();
+
+    write_polygon(&mut svg, &hull, HULL_COLOR);
+    for block in &blocks {
+        write_polygon(&mut svg, block, BLOCK_COLOR);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn write_polygon(svg: &mut String, polygon: &Polygon, color: &str) {
+    let points: Vec<String> = polygon
+        .points
+        .iter()
+        .map(|p| p.into_cartesian())
+        .map(|p| format!("{},{}", p.x, p.y))
+        .collect();
+    writeln!(
+        svg,
+        r#"  <polygon points="{}" fill="none" stroke="{}" stroke-width="1"/>"#,
+        points.join(" "),
+        color
+    )
+    .unwrap();
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Reads the largest polygon or path of an SVG image, centered on its centroid.
+/// Curves are approximated with lines between their end points.
+pub fn import_hull(svg: &str) -> Result<Polygon, String> {
+    let mut shapes = Vec::new();
+    for (element, attribute) in &[("polygon", "points"), ("polyline", "points"), ("path", "d")] {
+        for value in attribute_values(svg, element, attribute) {
+            let points = if *element == "path" {
+                parse_path(value)?
+            } else {
+                parse_numbers(value)?
+                    .chunks_exact(2)
+                    .map(|p| Vec2::new(p[0], p[1]))
+                    .collect()
+            };
+            if points.len() >= 3 {
+                shapes.push(Polygon::from(points));
+            }
+        }
+    }
+
+    let hull = shapes
+        .into_iter()
+        .max_by(|a, b| {
+            let a = a.area_and_centroid().0.abs();
+            let b = b.area_and_centroid().0.abs();
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .ok_or("no polygon or path found")?;
+
+    let (_, centroid) = hull.area_and_centroid();
+    Ok(translation(-centroid) * hull)
+}
+
+/// Values of an attribute of all elements with the given name.
+fn attribute_values<'a>(svg: &'a str, element: &str, attribute: &str) -> Vec<&'a str> {
+    let open = format!("<{}", element);
+    let key = format!(" {}=\"", attribute);
+
+    let mut values = Vec::new();
+    let mut rest = svg;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let tag = &rest[..rest.find('>').unwrap_or(rest.len())];
+        if !tag.starts_with(char::is_whitespace) {
+            continue;
+        }
+        if let Some(value_start) = tag.find(&key) {
+            let value = &tag[value_start + key.len()..];
+            values.push(&value[..value.find('"').unwrap_or(value.len())]);
+        }
+    }
+    values
+}
+
+fn parse_numbers(text: &str) -> Result<Vec<f32>, String> {
+    let mut numbers = Vec::new();
+    let mut number = String::new();
+    let mut finish = |number: &mut String| -> Result<(), String> {
+        if !number.is_empty() {
+            numbers.push(
+                number
+                    .parse()
+                    .map_err(|_| format!("invalid number: {}", number))?,
+            );
+            number.clear();
+        }
+        Ok(())
+    };
+
+    for c in text.chars() {
+        match c {
+            '-' | '+' if !number.ends_with(['e', 'E']) => {
+                finish(&mut number)?;
+                number.push(c);
+            }
+            '.' if number.contains('.') && !number.contains(['e', 'E']) => {
+                finish(&mut number)?;
+                number.push(c);
+            }
+            '0'..='9' | '.' | 'e' | 'E' => number.push(c),
+            _ => finish(&mut number)?,
+        }
+    }
+    finish(&mut number)?;
+    Ok(numbers)
+}
+
+/// Points of the first subpath.
+fn parse_path(d: &str) -> Result<Vec<Vec2<f32>>, String> {
+    let mut points: Vec<Vec2<f32>> = Vec::new();
+    let mut current = Vec2::default();
+
+    let mut rest = d.trim_start();
+    while let Some(command) = rest.chars().next() {
+        rest = &rest[command.len_utf8()..];
+        let end = rest.find(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E');
+        let (params, next) = rest.split_at(end.unwrap_or(rest.len()));
+        rest = next;
+        let params = parse_numbers(params)?;
+
+        let relative = command.is_ascii_lowercase();
+        let origin = |current: Vec2<f32>| if relative { current } else { Vec2::default() };
+        let arity = match command.to_ascii_uppercase() {
+            'Z' => {
+                if !points.is_empty() {
+                    break;
+                }
+                continue;
+            }
+            'H' | 'V' => 1,
+            'M' | 'L' | 'T' => 2,
+            'S' | 'Q' => 4,
+            'C' => 6,
+            'A' => 7,
+            _ => return Err(format!("unknown path command: {}", command)),
+        };
+
+        for (index, params) in params.chunks_exact(arity).enumerate() {
+            current = match command.to_ascii_uppercase() {
+                'H' => Vec2::new(origin(current).x + params[0], current.y),
+                'V' => Vec2::new(current.x, origin(current).y + params[0]),
+                // the end point is always the last pair
+                _ => origin(current) + Vec2::new(params[arity - 2], params[arity - 1]),
+            };
+            let starts_subpath = command.eq_ignore_ascii_case(&'M') && index == 0;
+            if starts_subpath && !points.is_empty() {
+                return Ok(points);
+            }
+            points.push(current);
+        }
+    }
+    Ok(points)
+}
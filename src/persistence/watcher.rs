@@ -0,0 +1,55 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Detects changed, added or removed files in a directory by polling their metadata.
+pub struct DirectoryWatcher {
+    dir: PathBuf,
+    last_poll: Instant,
+    files: Vec<(OsString, SystemTime, u64)>,
+}
+
+impl DirectoryWatcher {
+    pub fn new(dir: PathBuf) -> Self {
+        let files = scan(&dir);
+        DirectoryWatcher {
+            dir,
+            last_poll: Instant::now(),
+            files,
+        }
+    }
+
+    /// Returns true if the directory has changed since the last poll.
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+
+        let files = scan(&self.dir);
+        if files == self.files {
+            return false;
+        }
+        self.files = files;
+        true
+    }
+}
+
+fn scan(dir: &Path) -> Vec<(OsString, SystemTime, u64)> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((e.file_name(), modified, metadata.len()))
+        })
+        .collect();
+    files.sort();
+    files
+}
@@ -0,0 +1,193 @@
+//! Thin layer between the game and the windowing library.
+//!
+//! Only backends use the library directly. Game code draws through `DrawTarget` alone,
+//! so a new backend implements it, `Window`, `InputSource` and `AudioOutput`.
+//! Input events keep SDL's representation, so other backends translate their events
+//! into it.
+
+pub mod null;
+pub mod sdl;
+
+use gamemath::Vec2;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+pub use sdl2::event::{Event, WindowEvent};
+pub use sdl2::keyboard::{Keycode, Mod};
+pub use sdl2::mouse::{MouseButton, MouseState, MouseWheelDirection};
+
+/// Samples per second of audio output, for each channel.
+pub const AUDIO_FREQUENCY: i32 = 44_100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const RED: Color = Color::rgb(255, 0, 0);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+}
+
+/// Image loaded by a backend, to be drawn with `DrawTarget::draw_texture`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextureId(pub usize);
+
+/// Surface that lines and points are drawn on, in pixel coordinates.
+pub trait DrawTarget {
+    fn set_draw_color(&mut self, color: Color);
+
+    /// Fills the whole surface with the draw color.
+    fn clear(&mut self);
+
+    fn draw_line(&mut self, a: Vec2<f32>, b: Vec2<f32>);
+
+    /// Draws lines joining consecutive points. Backends that draw in batches should
+    /// draw them at once.
+    fn draw_lines(&mut self, points: &[Vec2<f32>]) {
+        for pair in points.windows(2) {
+            self.draw_line(pair[0], pair[1]);
+        }
+    }
+
+    fn draw_points(&mut self, points: &[Vec2<f32>]);
+
+    /// Fills a triangle with the draw color.
+    fn fill_triangle(&mut self, triangle: [Vec2<f32>; 3]) {
+        for (y, left, right) in scanlines(triangle, self.size()) {
+            self.draw_line(Vec2::new(left, y), Vec2::new(right, y));
+        }
+    }
+
+    /// Fills triangles with the draw color, like `draw_lines` one batch.
+    fn fill_triangles(&mut self, triangles: &[[Vec2<f32>; 3]]) {
+        for triangle in triangles {
+            self.fill_triangle(*triangle);
+        }
+    }
+
+    /// Reads back what was drawn since the last clear, as 8-bit RGB pixels row by row
+    /// from the top.
+    fn read_pixels(&mut self) -> Result<Vec<u8>, String> {
+        Err("reading pixels is not supported".to_owned())
+    }
+
+    /// Loads an image from a BMP file, to be drawn with `draw_texture`.
+    fn load_texture(&mut self, _path: &Path) -> Result<TextureId, String> {
+        Err("textures are not supported".to_owned())
+    }
+
+    /// Draws a texture stretched to `size`, centered at `center` and turned by `angle`.
+    fn draw_texture(
+        &mut self,
+        _texture: TextureId,
+        _center: Vec2<f32>,
+        _size: Vec2<f32>,
+        _angle: f32,
+    ) {
+    }
+
+    fn size(&self) -> Vec2<f32>;
+
+    fn center(&self) -> Vec2<f32> {
+        self.size() * 0.5
+    }
+}
+
+/// Horizontal spans `(y, left, right)` covering a triangle, one for each row of pixels.
+/// Spans are clipped to a surface of `size`.
+pub fn scanlines(triangle: [Vec2<f32>; 3], size: Vec2<f32>) -> Vec<(f32, f32, f32)> {
+    let mut sorted = triangle;
+    sorted.sort_by(|a, b| a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal));
+    let [top, middle, bottom] = sorted;
+    let x_at = |a: Vec2<f32>, b: Vec2<f32>, y: f32| {
+        if b.y == a.y {
+            a.x
+        } else {
+            a.x + (b.x - a.x) * (y - a.y) / (b.y - a.y)
+        }
+    };
+
+    let mut spans = Vec::new();
+    let mut y = top.y.max(0.0).ceil();
+    let last = bottom.y.min(size.y);
+    while y <= last {
+        let long = x_at(top, bottom, y);
+        let short = if y < middle.y {
+            x_at(top, middle, y)
+        } else {
+            x_at(middle, bottom, y)
+        };
+        let left = long.min(short).max(0.0);
+        let right = long.max(short).min(size.x);
+        if left <= right {
+            spans.push((y, left, right));
+        }
+        y += 1.0;
+    }
+    spans
+}
+
+/// How the window covers its display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    /// Changes the resolution of the display to the size of the window.
+    Fullscreen,
+    /// Covers the display without changing its resolution.
+    Borderless,
+}
+
+impl DisplayMode {
+    const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Fullscreen,
+        DisplayMode::Borderless,
+    ];
+
+    /// Name stored in settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "windowed",
+            DisplayMode::Fullscreen => "fullscreen",
+            DisplayMode::Borderless => "borderless",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DisplayMode> {
+        DisplayMode::ALL
+            .iter()
+            .find(|mode| mode.name() == name)
+            .copied()
+    }
+}
+
+/// Window that shows what was drawn when presented.
+pub trait Window: DrawTarget {
+    fn present(&mut self);
+
+    /// Switches between a window and covering the display. The new size is announced
+    /// with a window event.
+    fn set_display_mode(&mut self, mode: DisplayMode) -> Result<(), String>;
+}
+
+/// Generates interleaved stereo samples, called from the audio thread.
+pub trait AudioSource: Send {
+    fn fill(&mut self, out: &mut [f32]);
+}
+
+pub trait AudioOutput {
+    /// Starts playing samples of `source`, replacing what was played before.
+    fn play(&mut self, source: Arc<Mutex<dyn AudioSource>>) -> Result<(), String>;
+}
+
+pub trait InputSource {
+    /// Events received since the last poll.
+    fn poll_events(&mut self) -> Vec<Event>;
+}
@@ -0,0 +1,55 @@
+use super::{AudioOutput, AudioSource, Color, DisplayMode, DrawTarget, Event, InputSource, Window};
+use gamemath::Vec2;
+use std::sync::{Arc, Mutex};
+
+/// Backend without a window, which draws nothing, plays nothing and receives no input.
+/// Runs the client where there is no display, for example in benchmarks.
+pub struct NullPlatform {
+    size: Vec2<f32>,
+}
+
+impl NullPlatform {
+    pub fn new(size: Vec2<f32>) -> Self {
+        NullPlatform { size }
+    }
+}
+
+impl DrawTarget for NullPlatform {
+    fn set_draw_color(&mut self, _color: Color) {}
+
+    fn clear(&mut self) {}
+
+    fn draw_line(&mut self, _a: Vec2<f32>, _b: Vec2<f32>) {}
+
+    fn draw_lines(&mut self, _points: &[Vec2<f32>]) {}
+
+    fn draw_points(&mut self, _points: &[Vec2<f32>]) {}
+
+    fn fill_triangle(&mut self, _triangle: [Vec2<f32>; 3]) {}
+
+    fn fill_triangles(&mut self, _triangles: &[[Vec2<f32>; 3]]) {}
+
+    fn size(&self) -> Vec2<f32> {
+        self.size
+    }
+}
+
+impl Window for NullPlatform {
+    fn present(&mut self) {}
+
+    fn set_display_mode(&mut self, _mode: DisplayMode) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl AudioOutput for NullPlatform {
+    fn play(&mut self, _source: Arc<Mutex<dyn AudioSource>>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl InputSource for NullPlatform {
+    fn poll_events(&mut self) -> Vec<Event> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,220 @@
+use super::{
+    scanlines, AudioOutput, AudioSource, Color, DisplayMode, DrawTarget, Event, InputSource,
+    TextureId, Window, AUDIO_FREQUENCY,
+};
+use gamemath::Vec2;
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{Canvas, Texture};
+use sdl2::surface::Surface;
+use sdl2::video::FullscreenType;
+use sdl2::{EventPump, Sdl};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Window, audio and input of an SDL application.
+pub struct SdlPlatform {
+    context: Sdl,
+    canvas: Canvas<sdl2::video::Window>,
+    event_pump: EventPump,
+    audio: Option<AudioDevice<SdlAudio>>,
+    /// Indexed by `TextureId`, freed along with the canvas.
+    textures: Vec<Texture>,
+}
+
+impl SdlPlatform {
+    /// Opens a window centered on the display with index `display`, or on the first one
+    /// when there is no such display.
+    pub fn new(
+        title: &str,
+        resolution: Vec2<f32>,
+        mode: DisplayMode,
+        display: i32,
+    ) -> Result<Self, String> {
+        let sdl_context = sdl2::init()?;
+        let video_subsystem = sdl_context.video()?;
+        let attributes = video_subsystem.gl_attr();
+
+        attributes.set_multisample_buffers(1);
+        attributes.set_multisample_samples(5);
+
+        let bounds = video_subsystem
+            .display_bounds(display)
+            .or_else(|_| video_subsystem.display_bounds(0))?;
+        let (width, height) = (resolution.x as u32, resolution.y as u32);
+        let mut window = video_subsystem
+            .window(title, width, height)
+            .position(
+                bounds.x() + (bounds.width() as i32 - width as i32) / 2,
+                bounds.y() + (bounds.height() as i32 - height as i32) / 2,
+            )
+            .resizable()
+            .build()
+            .map_err(|e| e.to_string())?;
+        window.set_fullscreen(fullscreen_type(mode))?;
+
+        let canvas = window
+            .into_canvas()
+            .present_vsync()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let event_pump = sdl_context.event_pump()?;
+        Ok(SdlPlatform {
+            context: sdl_context,
+            canvas,
+            event_pump,
+            audio: None,
+            textures: Vec::new(),
+        })
+    }
+}
+
+impl DrawTarget for SdlPlatform {
+    fn set_draw_color(&mut self, color: Color) {
+        self.canvas
+            .set_draw_color(sdl2::pixels::Color::RGB(color.r, color.g, color.b));
+    }
+
+    fn clear(&mut self) {
+        self.canvas.clear();
+    }
+
+    fn draw_line(&mut self, a: Vec2<f32>, b: Vec2<f32>) {
+        self.canvas
+            .draw_line(into_point(a), into_point(b))
+            .expect("Draw line");
+    }
+
+    fn draw_lines(&mut self, points: &[Vec2<f32>]) {
+        let points: Vec<Point> = points.iter().copied().map(into_point).collect();
+        self.canvas.draw_lines(&points[..]).expect("Draw lines");
+    }
+
+    fn draw_points(&mut self, points: &[Vec2<f32>]) {
+        let points: Vec<Point> = points.iter().copied().map(into_point).collect();
+        self.canvas.draw_points(&points[..]).expect("Draw points");
+    }
+
+    fn fill_triangle(&mut self, triangle: [Vec2<f32>; 3]) {
+        self.fill_triangles(&[triangle]);
+    }
+
+    /// Spans are filled as rectangles one pixel high, in a single call.
+    fn fill_triangles(&mut self, triangles: &[[Vec2<f32>; 3]]) {
+        let size = self.size();
+        let rects: Vec<Rect> = triangles
+            .iter()
+            .flat_map(|triangle| scanlines(*triangle, size))
+            .map(|(y, left, right)| Rect::new(left as i32, y as i32, (right - left) as u32 + 1, 1))
+            .collect();
+        self.canvas.fill_rects(&rects).expect("Fill rects");
+    }
+
+    fn read_pixels(&mut self) -> Result<Vec<u8>, String> {
+        self.canvas.read_pixels(None, PixelFormatEnum::RGB24)
+    }
+
+    fn load_texture(&mut self, path: &Path) -> Result<TextureId, String> {
+        let surface = Surface::load_bmp(path)?;
+        let texture = self
+            .canvas
+            .texture_creator()
+            .create_texture_from_surface(surface)
+            .map_err(|e| e.to_string())?;
+        self.textures.push(texture);
+        Ok(TextureId(self.textures.len() - 1))
+    }
+
+    fn draw_texture(&mut self, texture: TextureId, center: Vec2<f32>, size: Vec2<f32>, angle: f32) {
+        let texture = match self.textures.get(texture.0) {
+            Some(texture) => texture,
+            None => return,
+        };
+        let corner = center - size * 0.5;
+        let rect = Rect::new(
+            corner.x as i32,
+            corner.y as i32,
+            size.x.max(1.0) as u32,
+            size.y.max(1.0) as u32,
+        );
+        self.canvas
+            .copy_ex(
+                texture,
+                None,
+                rect,
+                angle.to_degrees() as f64,
+                None,
+                false,
+                false,
+            )
+            .expect("Copy texture");
+    }
+
+    fn size(&self) -> Vec2<f32> {
+        let viewport = self.canvas.viewport();
+        Vec2::new(viewport.width() as f32, viewport.height() as f32)
+    }
+}
+
+impl Window for SdlPlatform {
+    fn present(&mut self) {
+        self.canvas.present();
+    }
+
+    fn set_display_mode(&mut self, mode: DisplayMode) -> Result<(), String> {
+        self.canvas
+            .window_mut()
+            .set_fullscreen(fullscreen_type(mode))
+    }
+}
+
+fn fullscreen_type(mode: DisplayMode) -> FullscreenType {
+    match mode {
+        DisplayMode::Windowed => FullscreenType::Off,
+        DisplayMode::Fullscreen => FullscreenType::True,
+        DisplayMode::Borderless => FullscreenType::Desktop,
+    }
+}
+
+impl AudioOutput for SdlPlatform {
+    fn play(&mut self, source: Arc<Mutex<dyn AudioSource>>) -> Result<(), String> {
+        let desired = AudioSpecDesired {
+            freq: Some(AUDIO_FREQUENCY),
+            channels: Some(2),
+            samples: None,
+        };
+        let device = self
+            .context
+            .audio()?
+            .open_playback(None, &desired, |_| SdlAudio { source })?;
+        device.resume();
+        self.audio = Some(device);
+        Ok(())
+    }
+}
+
+struct SdlAudio {
+    source: Arc<Mutex<dyn AudioSource>>,
+}
+
+impl AudioCallback for SdlAudio {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        match self.source.lock() {
+            Ok(mut source) => source.fill(out),
+            Err(_) => out.fill(0.0),
+        }
+    }
+}
+
+impl InputSource for SdlPlatform {
+    fn poll_events(&mut self) -> Vec<Event> {
+        self.event_pump.poll_iter().collect()
+    }
+}
+
+fn into_point(vec: Vec2<f32>) -> Point {
+    Point::new(vec.x as i32, vec.y as i32)
+}
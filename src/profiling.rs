@@ -0,0 +1,38 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+thread_local! {
+    static RECORDED: RefCell<Vec<(&'static str, Duration)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Measures time until dropped, recording it under a name.
+/// Spans are recorded per thread, so the client does not see spans of a hosted server.
+pub struct Span {
+    name: &'static str,
+    started: Instant,
+}
+
+pub fn span(name: &'static str) -> Span {
+    Span {
+        name,
+        started: Instant::now(),
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        RECORDED.with(|recorded| {
+            let mut recorded = recorded.borrow_mut();
+            match recorded.iter_mut().find(|(name, _)| *name == self.name) {
+                Some((_, total)) => *total += elapsed,
+                None => recorded.push((self.name, elapsed)),
+            }
+        });
+    }
+}
+
+/// Returns total time of each span recorded since the last call, in order of first use.
+pub fn take() -> Vec<(&'static str, Duration)> {
+    RECORDED.with(|recorded| std::mem::take(&mut *recorded.borrow_mut()))
+}
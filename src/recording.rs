@@ -0,0 +1,392 @@
+use crate::error::Error;
+use crate::platform::{Event, Keycode, Mod, MouseButton, MouseState, MouseWheelDirection};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Input event that the client reacts to, in a form that can be saved.
+#[derive(Serialize, Deserialize)]
+enum RecordedEvent {
+    KeyDown {
+        keycode: i32,
+        keymod: u16,
+        repeat: bool,
+    },
+    KeyUp {
+        keycode: i32,
+        keymod: u16,
+        repeat: bool,
+    },
+    TextInput {
+        text: String,
+    },
+    MouseMotion {
+        x: i32,
+        y: i32,
+        xrel: i32,
+        yrel: i32,
+        state: u32,
+    },
+    MouseButtonDown {
+        button: u8,
+        x: i32,
+        y: i32,
+    },
+    MouseButtonUp {
+        button: u8,
+        x: i32,
+        y: i32,
+    },
+    MouseWheel {
+        x: i32,
+        y: i32,
+    },
+    FingerDown {
+        finger: i64,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    },
+    FingerMotion {
+        finger: i64,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    },
+    FingerUp {
+        finger: i64,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    },
+    MultiGesture {
+        d_theta: f32,
+        d_dist: f32,
+        x: f32,
+        y: f32,
+        fingers: u16,
+    },
+}
+
+impl RecordedEvent {
+    fn from_event(event: &Event) -> Option<Self> {
+        Some(match event.clone() {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                keymod,
+                repeat,
+                ..
+            } => RecordedEvent::KeyDown {
+                keycode: keycode as i32,
+                keymod: keymod.bits(),
+                repeat,
+            },
+            Event::KeyUp {
+                keycode: Some(keycode),
+                keymod,
+                repeat,
+                ..
+            } => RecordedEvent::KeyUp {
+                keycode: keycode as i32,
+                keymod: keymod.bits(),
+                repeat,
+            },
+            Event::TextInput { text, .. } => RecordedEvent::TextInput { text },
+            Event::MouseMotion {
+                x,
+                y,
+                xrel,
+                yrel,
+                mousestate,
+                ..
+            } => RecordedEvent::MouseMotion {
+                x,
+                y,
+                xrel,
+                yrel,
+                state: mousestate.to_sdl_state(),
+            },
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => RecordedEvent::MouseButtonDown {
+                button: mouse_btn as u8,
+                x,
+                y,
+            },
+            Event::MouseButtonUp {
+                mouse_btn, x, y, ..
+            } => RecordedEvent::MouseButtonUp {
+                button: mouse_btn as u8,
+                x,
+                y,
+            },
+            Event::MouseWheel { x, y, .. } => RecordedEvent::MouseWheel { x, y },
+            Event::FingerDown {
+                finger_id,
+                x,
+                y,
+                dx,
+                dy,
+                ..
+            } => RecordedEvent::FingerDown {
+                finger: finger_id,
+                x,
+                y,
+                dx,
+                dy,
+            },
+            Event::FingerMotion {
+                finger_id,
+                x,
+                y,
+                dx,
+                dy,
+                ..
+            } => RecordedEvent::FingerMotion {
+                finger: finger_id,
+                x,
+                y,
+                dx,
+                dy,
+            },
+            Event::FingerUp {
+                finger_id,
+                x,
+                y,
+                dx,
+                dy,
+                ..
+            } => RecordedEvent::FingerUp {
+                finger: finger_id,
+                x,
+                y,
+                dx,
+                dy,
+            },
+            Event::MultiGesture {
+                d_theta,
+                d_dist,
+                x,
+                y,
+                num_fingers,
+                ..
+            } => RecordedEvent::MultiGesture {
+                d_theta,
+                d_dist,
+                x,
+                y,
+                fingers: num_fingers,
+            },
+            _ => return None,
+        })
+    }
+
+    fn into_event(self) -> Event {
+        match self {
+            RecordedEvent::KeyDown {
+                keycode,
+                keymod,
+                repeat,
+            } => Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: Keycode::from_i32(keycode),
+                scancode: None,
+                keymod: Mod::from_bits_truncate(keymod),
+                repeat,
+            },
+            RecordedEvent::KeyUp {
+                keycode,
+                keymod,
+                repeat,
+            } => Event::KeyUp {
+                timestamp: 0,
+                window_id: 0,
+                keycode: Keycode::from_i32(keycode),
+                scancode: None,
+                keymod: Mod::from_bits_truncate(keymod),
+                repeat,
+            },
+            RecordedEvent::TextInput { text } => Event::TextInput {
+                timestamp: 0,
+                window_id: 0,
+                text,
+            },
+            RecordedEvent::MouseMotion {
+                x,
+                y,
+                xrel,
+                yrel,
+                state,
+            } => Event::MouseMotion {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mousestate: MouseState::from_sdl_state(state),
+                x,
+                y,
+                xrel,
+                yrel,
+            },
+            RecordedEvent::MouseButtonDown { button, x, y } => Event::MouseButtonDown {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mouse_btn: MouseButton::from_ll(button),
+                clicks: 1,
+                x,
+                y,
+            },
+            RecordedEvent::MouseButtonUp { button, x, y } => Event::MouseButtonUp {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mouse_btn: MouseButton::from_ll(button),
+                clicks: 1,
+                x,
+                y,
+            },
+            RecordedEvent::MouseWheel { x, y } => Event::MouseWheel {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                x,
+                y,
+                direction: MouseWheelDirection::Normal,
+            },
+            RecordedEvent::FingerDown {
+                finger,
+                x,
+                y,
+                dx,
+                dy,
+            } => Event::FingerDown {
+                timestamp: 0,
+                touch_id: 0,
+                finger_id: finger,
+                x,
+                y,
+                dx,
+                dy,
+                pressure: 1.0,
+            },
+            RecordedEvent::FingerMotion {
+                finger,
+                x,
+                y,
+                dx,
+                dy,
+            } => Event::FingerMotion {
+                timestamp: 0,
+                touch_id: 0,
+                finger_id: finger,
+                x,
+                y,
+                dx,
+                dy,
+                pressure: 1.0,
+            },
+            RecordedEvent::FingerUp {
+                finger,
+                x,
+                y,
+                dx,
+                dy,
+            } => Event::FingerUp {
+                timestamp: 0,
+                touch_id: 0,
+                finger_id: finger,
+                x,
+                y,
+                dx,
+                dy,
+                pressure: 1.0,
+            },
+            RecordedEvent::MultiGesture {
+                d_theta,
+                d_dist,
+                x,
+                y,
+                fingers,
+            } => Event::MultiGesture {
+                timestamp: 0,
+                touch_id: 0,
+                d_theta,
+                d_dist,
+                x,
+                y,
+                num_fingers: fingers,
+            },
+        }
+    }
+}
+
+/// Records input events passed to the client, along with the tick they happened in.
+pub struct Recorder {
+    path: PathBuf,
+    tick: u64,
+    events: Vec<(u64, RecordedEvent)>,
+}
+
+impl Recorder {
+    pub fn new(path: PathBuf) -> Self {
+        Recorder {
+            path,
+            tick: 0,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        if let Some(event) = RecordedEvent::from_event(event) {
+            self.events.push((self.tick, event));
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.tick += 1;
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let bytes = rmp_serde::to_vec(&self.events)?;
+
+        let mut file = std::fs::File::create(&self.path)?;
+        Ok(file.write_all(&bytes)?)
+    }
+}
+
+/// Plays back recorded input events at the ticks they were recorded in.
+pub struct Replay {
+    tick: u64,
+    events: VecDeque<(u64, RecordedEvent)>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+        let events = rmp_serde::from_read_ref(&bytes).map_err(|e| e.to_string())?;
+        Ok(Replay { tick: 0, events })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Returns events of the current tick and advances to the next one.
+    pub fn tick(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        while self
+            .events
+            .front()
+            .is_some_and(|(tick, _)| *tick <= self.tick)
+        {
+            events.push(self.events.pop_front().unwrap().1.into_event());
+        }
+        self.tick += 1;
+        events
+    }
+}
@@ -1,189 +1,648 @@
 use crate::client::EntityId;
-use crate::math::{bounding_box::BoundingBox, polygon::Polygon, segment::Segment, vec::*};
+use crate::math::{
+    bounding_box::{BoundingBox, RectBounds},
+    capsule::Capsule,
+    circle::Circle,
+    polygon::Polygon,
+    segment::Segment,
+    shape::Shape,
+    vec::*,
+};
+use crate::particles::Particles;
+use crate::paths::Paths;
+use crate::platform::{Color, DrawTarget, TextureId};
 use crate::stars::Stars;
-use crate::world::{Entity, Grid, Insist, World};
+use crate::world::{
+    Block, Components, Entity, Faction, FrameShifts, Grid, GridRelation, Insist, World,
+};
 use gamemath::{Mat2, Mat3, Vec2, Vec3};
-use sdl2::pixels::Color;
-use sdl2::rect::Point;
-use sdl2::render::{Canvas, RenderTarget};
+use std::collections::HashMap;
 use std::f32::consts::PI;
+use std::path::PathBuf;
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+
+/// Damage of a block from which the hull around it starts missing chunks.
+const CARVE_DAMAGE: f32 = 0.5;
+/// Distance from a wrecked block within which the hull is carved.
+const CARVE_RADIUS: f32 = 25.0;
+/// Length of pieces the hull outline is split into when carved.
+const CARVE_STEP: f32 = 4.0;
+
+/// Ticks of motion shown by velocity arrows when debug drawing.
+const DEBUG_VELOCITY_SCALE: f32 = 20.0;
+
+/// Outlines of entities without a color of their own, by faction.
+const PLAYER_TINT: Color = Color::rgb(130, 210, 255);
+const HOSTILE_TINT: Color = Color::rgb(255, 110, 90);
+const DERELICT_TINT: Color = Color::rgb(170, 150, 110);
+const NEUTRAL_TINT: Color = Color::rgb(255, 255, 255);
+/// Neutral entities without any gameplay state, such as debris and asteroids.
+const BARE_TINT: Color = Color::rgb(150, 150, 150);
+
+/// Fill of hulls, under the fills of blocks.
+const HULL_FILL: Color = Color::rgb(25, 25, 40);
+
+/// How the camera catches up with the focused entity after it is displaced.
+#[derive(Clone, Copy, Debug)]
+pub struct CameraFollow {
+    /// Pull towards the entity per tick, for each grid unit of distance.
+    pub stiffness: f32,
+    /// Share of the camera velocity lost per tick.
+    pub damping: f32,
+    /// Distance from the entity within which the camera is not pulled.
+    pub deadzone: f32,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        // critically damped, settles in about a second
+        CameraFollow {
+            stiffness: 0.01,
+            damping: 0.2,
+            deadzone: 0.0,
+        }
+    }
+}
+
+/// Images of blocks, loaded from the textures directory when they are first drawn.
+#[derive(Default)]
+pub struct Textures {
+    /// Without it, blocks are drawn as outlines.
+    dir: Option<PathBuf>,
+    /// Textures that failed to load are kept as `None`, so they are not loaded again.
+    loaded: HashMap<&'static str, Option<TextureId>>,
+}
+
+impl Textures {
+    pub fn new(paths: &Paths) -> Self {
+        let dir = match paths.textures() {
+            Ok(dir) => Some(dir),
+            Err(e) => {
+                eprintln!("cannot open textures: {}", e);
+                None
+            }
+        };
+        Textures {
+            dir,
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Texture named `name`, loaded from `<name>.bmp` the first time it is asked for.
+    pub fn get(&mut self, name: &'static str, canvas: &mut dyn DrawTarget) -> Option<TextureId> {
+        let dir = self.dir.as_ref()?;
+        *self.loaded.entry(name).or_insert_with(|| {
+            let path = dir.join(format!("{}.bmp", name));
+            if !path.exists() {
+                return None;
+            }
+            canvas
+                .load_texture(&path)
+                .map_err(|e| eprintln!("cannot load texture {}: {}", name, e))
+                .ok()
+        })
+    }
+}
 
 /// Represents view used to render the grids.
 pub struct View {
-    // Relative to focused grid
-    pub offset: Vec2<f32>,
+    /// Relative to focused grid, pulled back to zero as set by `follow`.
+    pub offset: Insist<Vec2<f32>>,
+    pub follow: CameraFollow,
     pub size: Vec2<f32>,
 
+    /// Camera displacement set by the user, in grid units.
+    pub pan: Vec2<f32>,
+    pub zoom: f32,
+    /// Turns the view with the focused entity, so its nose points up the screen.
+    pub rotation_lock: bool,
+    /// Angle the camera is turned by within the focused grid, as it was last rendered.
+    pub rotation: f32,
+
     pub stars_position: Insist<Vec2<f32>>,
     pub stars: Stars,
+    pub particles: Particles,
 
     pub last_grid_to_screen: Mat3,
+    /// Inverse of `last_grid_to_screen`.
+    pub last_screen_to_grid: Mat3,
 
     pub focus: EntityId,
+
+    /// Share of the next tick that has passed, to draw entities between ticks.
+    pub alpha: f32,
+
+    pub textures: Textures,
+    /// Draws velocities, forces, centers of mass and contacts over the entities.
+    pub debug_draw: bool,
 }
 
 impl View {
     pub fn new(size: Vec2<f32>, focus: EntityId) -> View {
         View {
-            offset: Vec2::default(),
+            offset: Insist::default(),
+            follow: CameraFollow::default(),
             size,
 
+            pan: Vec2::default(),
+            zoom: 1.0,
+            rotation_lock: false,
+            rotation: 0.0,
+
             stars_position: Insist::default(),
             stars: Stars::new(size),
+            particles: Particles::default(),
 
             last_grid_to_screen: Mat3::default(),
+            last_screen_to_grid: Mat3::default(),
 
             focus,
+
+            alpha: 1.0,
+
+            textures: Textures::default(),
+            debug_draw: false,
         }
     }
 
+    /// Pulls the camera back towards the focused entity, like a damped spring.
     pub fn tick(&mut self) {
-        self.offset = Vec2 {
-            x: phase_out(self.offset.x),
-            y: phase_out(self.offset.y),
+        let distance = self.offset.state.length();
+        let stretch = if distance > self.follow.deadzone {
+            self.offset.state * (1.0 - self.follow.deadzone / distance)
+        } else {
+            Vec2::default()
         };
+        self.offset.velocity -=
+            stretch * self.follow.stiffness + self.offset.velocity * self.follow.damping;
+        self.offset.state += self.offset.velocity;
+    }
+
+    /// Follows the focused entity through the changes of grids' frames of reference.
+    pub fn follow(&mut self, world: &World, shifts: &FrameShifts) {
+        if let Some(shift) = shifts.get(&self.focus.grid_id) {
+            self.offset += *shift;
+            self.stars_position += *shift;
+        }
+        self.particles.follow(shifts);
+        self.stars_position.velocity *= 0.999;
+        self.stars_position.state += self.stars_position.velocity;
+
+        if let Some(focus) = world.find_entity(&self.focus) {
+            self.focus = focus;
+        }
+    }
+
+    /// Adapts to a new size of the window, with a star field to cover it.
+    pub fn resize(&mut self, size: Vec2<f32>) {
+        self.size = size;
+        self.stars = Stars::new(size);
+    }
+
+    /// Moves the camera by a distance in screen coordinates.
+    pub fn pan_by(&mut self, screen_delta: Vec2<f32>) {
+        self.pan += Mat2::rotation(self.rotation) * screen_delta * (1.0 / self.zoom);
+    }
+
+    pub fn zoom_by(&mut self, factor: f32) {
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    /// Point on the screen in coordinates of the focused grid, as it was last rendered.
+    pub fn screen_to_grid(&self, point: Vec2<f32>) -> Vec2<f32> {
+        (self.last_screen_to_grid * point.into_homogeneous()).into_cartesian()
+    }
+
+    /// Point in coordinates of the focused grid on the screen, as it was last rendered.
+    pub fn grid_to_screen(&self, point: Vec2<f32>) -> Vec2<f32> {
+        (self.last_grid_to_screen * point.into_homogeneous()).into_cartesian()
+    }
+
+    /// Direction in the focused grid turned as it appears on the screen.
+    pub fn grid_to_screen_direction(&self, direction: Vec2<f32>) -> Vec2<f32> {
+        Mat2::rotation(-self.rotation) * direction
     }
 }
 
-pub fn render<T: RenderTarget>(
-    world: &World,
-    focus: &EntityId,
-    view: &mut View,
-    canvas: &mut Canvas<T>,
-) {
-    canvas.set_draw_color(Color::RGB(0, 0, 0));
+pub fn render(world: &World, focus: &EntityId, view: &mut View, canvas: &mut dyn DrawTarget) {
+    canvas.set_draw_color(Color::rgb(0, 0, 0));
     canvas.clear();
 
     render_stars(view, canvas);
 
-    let center = translation(into_vec(canvas.viewport().center()));
-    let position = center * translation(view.offset);
+    view.rotation = match world.get_entity(focus) {
+        Some(entity) if view.rotation_lock => entity.interpolated(view.alpha).1,
+        _ => 0.0,
+    };
+
+    let center = translation(canvas.center());
+    let position = center
+        * Mat3::rotation(-view.rotation)
+        * Mat3::identity().scaled(view.zoom.into())
+        * translation(view.offset.state + view.pan);
 
     view.last_grid_to_screen = position;
+    view.last_screen_to_grid = position.invert().unwrap_or_default();
+
+    let screen = RectBounds {
+        top_left: Vec2::default(),
+        bottom_right: canvas.size(),
+    };
 
     let relations = world.get_relations(focus.grid_id, Insist::default());
+    render_gravity_wells(world, &relations, position, canvas);
     for relation in relations {
         let grid_position = position * translation(relation.position.state);
 
-        world.grids[&relation.id].render(grid_position, canvas);
+        let grid = &world.grids[&relation.id];
+        if grid
+            .bounds()
+            .polygon()
+            .bounding_box_transformed(&grid_position)
+            .intersects(&screen)
+        {
+            render_grid(
+                grid,
+                Some(&world.components),
+                grid_position,
+                view.alpha,
+                &mut view.textures,
+                view.debug_draw,
+                canvas,
+            );
+        }
+        view.particles.render(relation.id, grid_position, canvas);
+    }
+}
+
+/// Segments of the circle that marks the range of a gravity well.
+const WELL_RANGE_SEGMENTS: usize = 96;
+
+/// Dim circle around each gravity well, where its pull ends.
+fn render_gravity_wells(
+    world: &World,
+    relations: &[GridRelation],
+    position: Mat3,
+    canvas: &mut dyn DrawTarget,
+) {
+    canvas.set_draw_color(Color::rgb(60, 40, 90));
+    for relation in relations {
+        for entity in &world.grids[&relation.id].entities {
+            let well = match world.components.gravity.get(entity.get_id()) {
+                Some(well) => well,
+                None => continue,
+            };
+            let center = relation.position.state + entity.position.state;
+            let points: Vec<Vec2<f32>> = (0..WELL_RANGE_SEGMENTS)
+                .map(|i| {
+                    let angle = 2.0 * PI * i as f32 / WELL_RANGE_SEGMENTS as f32;
+                    center + Vec2::new(angle.cos(), angle.sin()) * well.range
+                })
+                .collect();
+            Polygon::from(points).render(position, canvas);
+        }
+    }
+}
+
+pub trait Render {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget);
+}
+
+impl Render for Grid {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget) {
+        let textures = &mut Textures::default();
+        render_grid(self, None, position, 1.0, textures, false, canvas);
+    }
+}
+
+/// Entities of the grid, drawn at `alpha` of the way from the previous tick and tinted
+/// by their factions in `components`.
+fn render_grid(
+    grid: &Grid,
+    components: Option<&Components>,
+    position: Mat3,
+    alpha: f32,
+    textures: &mut Textures,
+    debug: bool,
+    canvas: &mut dyn DrawTarget,
+) {
+    for entity in &grid.entities {
+        render_filled(entity, position, alpha, canvas);
+    }
+    for entity in &grid.entities {
+        let tint = entity_tint(entity, components);
+        render_entity(entity, position, alpha, tint, textures, canvas);
+    }
+    {
+        canvas.set_draw_color(Color::rgb(50, 50, 80));
+        grid.bounds().expand(1.0).polygon().render(position, canvas);
+    }
+    if debug {
+        render_physics(grid, position, alpha, canvas);
+    }
+}
+
+impl Render for Entity {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget) {
+        let tint = entity_tint(self, None);
+        render_entity(self, position, 1.0, tint, &mut Textures::default(), canvas);
+    }
+}
+
+/// Color of the entity's outline, its own or else one of its faction.
+/// Without components, entities are drawn as neutral.
+fn entity_tint(entity: &Entity, components: Option<&Components>) -> Color {
+    if let Some([r, g, b]) = entity.color {
+        return Color::rgb(r, g, b);
+    }
+    let components = match components {
+        Some(components) => components,
+        None => return NEUTRAL_TINT,
+    };
+    match components.faction(entity.get_id()) {
+        Faction::Player => PLAYER_TINT,
+        Faction::Hostile => HOSTILE_TINT,
+        Faction::Derelict => DERELICT_TINT,
+        Faction::Neutral if components.is_bare(entity.get_id()) => BARE_TINT,
+        Faction::Neutral => NEUTRAL_TINT,
     }
 }
 
-pub trait Render<T: RenderTarget> {
-    fn render(&self, position: Mat3, canvas: &mut Canvas<T>);
+/// Placement of the entity at `alpha` of the way from the previous tick.
+fn interpolated_position(entity: &Entity, alpha: f32) -> Mat3 {
+    let (position, angle) = entity.interpolated(alpha);
+    translation(position) * Mat3::rotation(angle)
 }
 
-impl<T: RenderTarget> Render<T> for Grid {
-    fn render(&self, position: Mat3, canvas: &mut Canvas<T>) {
-        canvas.set_draw_color(Color::RGB(255, 255, 255));
-        for entity in &self.entities {
-            entity.render(position, canvas);
+fn render_entity(
+    entity: &Entity,
+    position: Mat3,
+    alpha: f32,
+    tint: Color,
+    textures: &mut Textures,
+    canvas: &mut dyn DrawTarget,
+) {
+    let entity_position = position * interpolated_position(entity, alpha);
+    canvas.set_draw_color(tint);
+
+    for (index, block) in entity.blocks.iter().enumerate() {
+        let block_position = entity_position * block.transform();
+        match block.texture().and_then(|name| textures.get(name, canvas)) {
+            Some(texture) => render_texture(texture, block.shape(), block_position, canvas),
+            None => block.shape().render(block_position, canvas),
         }
-        {
-            canvas.set_draw_color(Color::RGB(50, 50, 80));
-            self.bounding_box()
-                .expand(1.0)
-                .polygon()
-                .render(position, canvas);
+
+        let damage = entity.block_damage(index);
+        if damage > 0.0 {
+            canvas.set_draw_color(Color::rgb(220, 90, 60));
+            render_cracks(&block.shape().to_polygon(), damage, block_position, canvas);
+            canvas.set_draw_color(tint);
         }
     }
-}
 
-impl<T: RenderTarget> Render<T> for Entity {
-    fn render(&self, position: Mat3, canvas: &mut Canvas<T>) {
-        let entity_position =
-            position * translation(self.position.state) * Mat3::rotation(self.angle.state);
+    match (carve_hull(entity), entity.grid_shape()) {
+        (Some(carved), _) => carved.render(entity_position, canvas),
+        // projected where the entity is now, not where it is drawn between ticks
+        (None, Some(shape)) if alpha >= 1.0 => shape.render(position, canvas),
+        (None, _) => entity.shape.render(entity_position, canvas),
+    }
+}
 
-        for block in &self.blocks {
-            let block_position =
-                entity_position * translation(block.offset()) * Mat3::rotation(block.angle());
-            block.shape().render(block_position, canvas);
+/// Velocity, forces of blocks and center of mass of each entity of the grid, and points
+/// where entities touched in the last tick.
+fn render_physics(grid: &Grid, position: Mat3, alpha: f32, canvas: &mut dyn DrawTarget) {
+    for entity in &grid.entities {
+        let entity_position = position * interpolated_position(entity, alpha);
 
-            {
-                let force_point = block.force();
-                let force = Mat2::rotation(block.angle()) * force_point.force;
+        canvas.set_draw_color(Color::rgb(200, 120, 60));
+        for block in &entity.blocks {
+            let force = Mat2::rotation(block.angle()) * block.force().force;
+            if force.length() > 0.0 {
                 force.render(entity_position * translation(block.offset()), canvas);
             }
         }
 
-        self.shape.render(entity_position, canvas);
-
+        canvas.set_draw_color(Color::rgb(255, 255, 255));
         ().render(entity_position, canvas);
+
+        let velocity = entity.position.velocity * DEBUG_VELOCITY_SCALE;
+        if velocity.length() > 0.0 {
+            canvas.set_draw_color(Color::rgb(60, 200, 60));
+            let center = entity.interpolated(alpha).0;
+            velocity.render(position * translation(center), canvas);
+        }
+    }
+
+    canvas.set_draw_color(Color::rgb(220, 60, 60));
+    for contact in &grid.contacts {
+        ().render(position * translation(*contact), canvas);
     }
 }
 
-impl<T: RenderTarget> Render<T> for Polygon {
-    fn render(&self, position: Mat3, canvas: &mut Canvas<T>) {
-        let lines = (position * self.clone()).to_segments();
-        for line in lines {
-            canvas
-                .draw_line(into_point(line.a), into_point(line.b))
-                .expect("Draw line");
+/// Texture stretched over the bounds of `shape`, which may be turned and scaled, but
+/// not skewed, by `position`.
+fn render_texture(texture: TextureId, shape: &Shape, position: Mat3, canvas: &mut dyn DrawTarget) {
+    let bounds = shape.bounding_box();
+    let center = (bounds.top_left + bounds.bottom_right) * 0.5;
+    let origin = (position * Vec2::default().into_homogeneous()).into_cartesian();
+    let axis = (position * Vec2::new(1.0, 0.0).into_homogeneous()).into_cartesian() - origin;
+    canvas.draw_texture(
+        texture,
+        (position * center.into_homogeneous()).into_cartesian(),
+        (bounds.bottom_right - bounds.top_left) * axis.length(),
+        axis.y.atan2(axis.x),
+    );
+}
+
+/// Solid hull and blocks, drawn under the outlines.
+fn render_filled(entity: &Entity, position: Mat3, alpha: f32, canvas: &mut dyn DrawTarget) {
+    let entity_position = position * interpolated_position(entity, alpha);
+
+    canvas.set_draw_color(HULL_FILL);
+    match carve_hull(entity) {
+        Some(carved) => fill_polygon(&carved, entity_position, canvas),
+        None => fill_polygon(&entity.shape.to_polygon(), entity_position, canvas),
+    }
+
+    for block in &entity.blocks {
+        let block_position =
+            entity_position * translation(block.offset()) * Mat3::rotation(block.angle());
+        canvas.set_draw_color(block_fill(block.as_ref()));
+        fill_polygon(&block.shape().to_polygon(), block_position, canvas);
+    }
+}
+
+fn fill_polygon(polygon: &Polygon, position: Mat3, canvas: &mut dyn DrawTarget) {
+    let triangles: Vec<[Vec2<f32>; 3]> = polygon
+        .triangulate()
+        .into_iter()
+        .map(|triangle| triangle.map(|p| (position * p.into_homogeneous()).into_cartesian()))
+        .collect();
+    canvas.fill_triangles(&triangles);
+}
+
+/// Fill of a block by its type, dim enough for outlines to stand out.
+fn block_fill(block: &dyn Block) -> Color {
+    match block.typetag_name() {
+        "Thruster" => Color::rgb(90, 50, 30),
+        "Cockpit" => Color::rgb(30, 60, 90),
+        "Generator" => Color::rgb(80, 80, 30),
+        "Drill" | "Refinery" => Color::rgb(70, 55, 40),
+        "CargoBay" | "FuelPump" => Color::rgb(45, 65, 45),
+        "LifeSupport" => Color::rgb(30, 75, 70),
+        "RepairArm" => Color::rgb(75, 40, 75),
+        _ => Color::rgb(55, 55, 65),
+    }
+}
+
+/// Draws zigzag lines from the center of a damaged block towards its corners,
+/// more of them the more it is damaged.
+fn render_cracks(shape: &Polygon, damage: f32, position: Mat3, canvas: &mut dyn DrawTarget) {
+    let corners = shape.points.len();
+    let cracks = (damage * corners as f32).ceil() as usize;
+    for (i, corner) in shape.points.iter().take(cracks).enumerate() {
+        let corner = corner.into_cartesian() * 0.8;
+        let side = if i % 2 == 0 { 0.15 } else { -0.15 };
+        let bend = corner * 0.4 + Mat2::rotation(PI * 0.5) * corner * side;
+        let points = [Vec2::default(), bend, corner]
+            .map(|p| (position * p.into_homogeneous()).into_cartesian());
+        canvas.draw_line(points[0], points[1]);
+        canvas.draw_line(points[1], points[2]);
+    }
+}
+
+/// Outline of the hull with jagged chunks missing around badly damaged blocks,
+/// or `None` when no block is damaged enough.
+fn carve_hull(entity: &Entity) -> Option<Polygon> {
+    let chunks: Vec<(Vec2<f32>, f32)> = entity
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| (block.offset(), entity.block_damage(index)))
+        .filter(|(_, damage)| *damage > CARVE_DAMAGE)
+        .map(|(center, damage)| (center, (damage - CARVE_DAMAGE) / (1.0 - CARVE_DAMAGE)))
+        .collect();
+    if chunks.is_empty() {
+        return None;
+    }
+
+    let mut points = Vec::new();
+    let hull = entity.shape.to_polygon();
+    for segment in hull.segments() {
+        let steps = ((segment.b - segment.a).length() / CARVE_STEP)
+            .ceil()
+            .max(1.0) as usize;
+        for step in 0..steps {
+            let mut point = segment.a + (segment.b - segment.a) * (step as f32 / steps as f32);
+            // every other point is pushed in less, for a jagged edge
+            let jag = if points.len() % 2 == 0 { 1.0 } else { 0.6 };
+            for (center, strength) in &chunks {
+                let distance = (*center - point).length();
+                if distance < CARVE_RADIUS && distance > 0.0 {
+                    let depth = (CARVE_RADIUS - distance) * strength * jag;
+                    point = point + (*center - point).normalized() * depth.min(distance);
+                }
+            }
+            points.push(point);
+        }
+    }
+    Some(Polygon::from(points))
+}
+
+impl Render for Polygon {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget) {
+        let mut points: Vec<Vec2<f32>> = self
+            .points
+            .iter()
+            .map(|p| (position * *p).into_cartesian())
+            .collect();
+        // closes the outline
+        if let Some(first) = points.first().copied() {
+            points.push(first);
+        }
+        canvas.draw_lines(&points);
+    }
+}
+
+/// Vertices drawn on a round outline with the given radius on the screen, so small
+/// shapes stay cheap and large ones smooth.
+fn round_segments(radius: f32, position: &Mat3) -> usize {
+    ((radius * scale_of(position)).sqrt() * 4.0).clamp(12.0, 96.0) as usize
+}
+
+impl Render for Circle {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget) {
+        self.outline(round_segments(self.radius, &position))
+            .render(position, canvas);
+    }
+}
+
+impl Render for Capsule {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget) {
+        self.outline(round_segments(self.radius, &position) / 2)
+            .render(position, canvas);
+    }
+}
+
+impl Render for Shape {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget) {
+        match self {
+            Shape::Polygon(polygon) => polygon.render(position, canvas),
+            Shape::Circle(circle) => circle.render(position, canvas),
+            Shape::Capsule(capsule) => capsule.render(position, canvas),
         }
     }
 }
 
 /// Render vector
-impl<T: RenderTarget> Render<T> for Vec2<f32> {
-    fn render(&self, position: Mat3, canvas: &mut Canvas<T>) {
-        let points: Vec<Vec2<f32>> = [
+impl Render for Vec2<f32> {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget) {
+        let points = [
             Vec2::default(),
             *self,
             *self + (Mat2::rotation(PI * 0.9) * *self).normalized() * 10.0,
             *self + (Mat2::rotation(-PI * 0.9) * *self).normalized() * 10.0,
         ]
-        .iter()
-        .map(|p| (position * p.into_homogeneous()).into_cartesian())
-        .collect();
-        let lines = [
-            Segment::new(points[0], points[1]),
-            Segment::new(points[2], points[1]),
-            Segment::new(points[1], points[3]),
-        ];
-        for line in &lines {
-            canvas
-                .draw_line(into_point(line.a), into_point(line.b))
-                .expect("Draw line");
-        }
+        .map(|p| (position * p.into_homogeneous()).into_cartesian());
+        canvas.draw_line(points[0], points[1]);
+        canvas.draw_lines(&[points[2], points[1], points[3]]);
     }
 }
 
 /// Render point
-impl<T: RenderTarget> Render<T> for () {
-    fn render(&self, position: Mat3, canvas: &mut Canvas<T>) {
-        let points: Vec<Vec2<f32>> = [
+impl Render for () {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget) {
+        let points = [
             Vec2::new(-3.0, -3.0),
             Vec2::new(3.0, 3.0),
             Vec2::new(3.0, -3.0),
             Vec2::new(-3.0, 3.0),
         ]
-        .iter()
-        .map(|p| (position * p.into_homogeneous()).into_cartesian())
-        .collect();
+        .map(|p| (position * p.into_homogeneous()).into_cartesian());
         let lines = [
             Segment::new(points[0], points[1]),
             Segment::new(points[2], points[3]),
         ];
         for line in &lines {
-            canvas
-                .draw_line(into_point(line.a), into_point(line.b))
-                .expect("Draw line");
+            canvas.draw_line(line.a, line.b);
         }
     }
 }
 
-fn render_stars<T: RenderTarget>(view: &View, canvas: &mut Canvas<T>) {
+fn render_stars(view: &View, canvas: &mut dyn DrawTarget) {
     let color = (view.stars_position.velocity.length() * 2.0).min(120.0) as u8 + 80;
-    canvas.set_draw_color(Color::RGB(color, color, color));
+    canvas.set_draw_color(Color::rgb(color, color, color));
 
-    let center = into_vec(canvas.viewport().center());
+    let center = canvas.center();
     let stars = &view.stars;
 
     let view_position = modulo(&view.stars_position.state, &stars.field_size);
 
     let star_offset = Vec3::from(view_position - center);
 
-    let points: Vec<Point> = stars
+    let points: Vec<Vec2<f32>> = stars
         .points
         .iter()
         .map(|point| {
@@ -195,10 +654,9 @@ fn render_stars<T: RenderTarget>(view: &View, canvas: &mut Canvas<T>) {
             };
             wrapped.into_cartesian() + center
         })
-        .map(into_point)
         .collect();
 
-    canvas.draw_points(&points[..]).expect("Draw star points");
+    canvas.draw_points(&points[..]);
 }
 
 /// Maps value to the interval of width `width` centered around zero.
@@ -212,10 +670,24 @@ fn around_zero(value: f32, width: f32) -> f32 {
     }
 }
 
-pub fn into_point(vec: Vec2<f32>) -> Point {
-    Point::new(vec.x as i32, vec.y as i32)
-}
-
-pub fn into_vec(point: Point) -> Vec2<f32> {
-    Vec2::new(point.x as f32, point.y as f32)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_to_grid_round_trip() {
+        let mut view = View::new(Vec2::new(800.0, 600.0), EntityId::new(0, 0));
+        let position = translation(Vec2::new(400.0, 300.0))
+            * Mat3::rotation(-0.3)
+            * Mat3::identity().scaled(1.7.into())
+            * translation(Vec2::new(-52.0, 18.0));
+        view.last_grid_to_screen = position;
+        view.last_screen_to_grid = position.invert().unwrap();
+
+        let points = [(0.0, 0.0), (123.0, 456.0), (800.0, 7.5)];
+        for point in points.iter().map(|&(x, y)| Vec2::new(x, y)) {
+            let round_trip = view.grid_to_screen(view.screen_to_grid(point));
+            assert!((round_trip - point).length() < 1e-3, "{:?}", round_trip);
+        }
+    }
 }
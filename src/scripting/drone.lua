@@ -0,0 +1,16 @@
+-- Wanders around, turning away from whatever it hits.
+
+function on_tick(entity)
+    if tick % 180 == 0 then
+        entity:accelerate(0, -1, 0.5)
+        entity:rotate(1, 0.3)
+    end
+    if entity.speed > 2 then
+        entity:stop()
+    end
+end
+
+function on_collision(entity, other)
+    entity:accelerate(0, 1, 1)
+    entity:rotate(-1, 1)
+end
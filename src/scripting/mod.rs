@@ -0,0 +1,161 @@
+mod script;
+
+use crate::client::EntityId;
+use crate::events::{Events, WorldEvent};
+use crate::paths::Paths;
+use crate::persistence::watcher::DirectoryWatcher;
+use crate::ui::user_controls::Action;
+use crate::world::{life_support, Entity, World};
+use script::{Hook, Script};
+use std::collections::{HashMap, HashSet};
+
+/// Extension of script files in the scripts directory.
+const EXTENSION: &str = "lua";
+
+/// Scripts shipped with the game, which files in the scripts directory can override.
+const BUILTIN: &[(&str, &str)] = &[("drone", include_str!("drone.lua"))];
+
+/// Runs Lua scripts of entities that have a script component, and scripts named after a
+/// block type, like `Thruster.lua`, for every block of that type.
+/// Scripts can only read their entity and emit actions for it.
+pub struct Scripts {
+    scripts: HashMap<String, Script>,
+    /// Scripts that raised an error, which do not run until they are reloaded.
+    failed: HashSet<String>,
+    paths: Paths,
+    watcher: Option<DirectoryWatcher>,
+    tick: u64,
+}
+
+impl Scripts {
+    pub fn load(paths: &Paths) -> Self {
+        let mut scripts = Scripts {
+            scripts: HashMap::new(),
+            failed: HashSet::new(),
+            paths: paths.clone(),
+            watcher: paths.scripts().ok().map(DirectoryWatcher::new),
+            tick: 0,
+        };
+        scripts.reload();
+        scripts
+    }
+
+    fn reload(&mut self) {
+        self.scripts.clear();
+        self.failed.clear();
+        for (name, source) in BUILTIN {
+            self.insert(name, source);
+        }
+
+        let entries = match self.paths.scripts().and_then(std::fs::read_dir) {
+            Ok(entries) => entries,
+            Err(e) => return eprintln!("cannot read scripts: {}", e),
+        };
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.extension().is_none_or(|ext| ext != EXTENSION) {
+                continue;
+            }
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            match std::fs::read_to_string(&path) {
+                Ok(source) => self.insert(&name, &source),
+                Err(e) => eprintln!("cannot read script {}: {}", name, e),
+            }
+        }
+    }
+
+    fn insert(&mut self, name: &str, source: &str) {
+        match Script::load(name, source) {
+            Ok(script) => {
+                self.scripts.insert(name.to_owned(), script);
+            }
+            Err(e) => eprintln!("cannot load script {}: {}", name, e),
+        }
+    }
+
+    /// Runs handlers of scripted entities and blocks for the tick the engine has just made.
+    pub fn tick(&mut self, world: &mut World, events: &Events) {
+        if self.watcher.as_mut().is_some_and(|w| w.poll()) {
+            self.reload();
+        }
+        self.tick += 1;
+
+        // hooks of each entity that run after `on_tick`
+        let mut hooks: HashMap<u64, Vec<Hook>> = HashMap::new();
+        for event in events.iter() {
+            match event {
+                WorldEvent::Collision {
+                    entities: [a, b], ..
+                } => {
+                    hooks
+                        .entry(*a)
+                        .or_default()
+                        .push(Hook::Collision { other: *b });
+                    hooks
+                        .entry(*b)
+                        .or_default()
+                        .push(Hook::Collision { other: *a });
+                }
+                WorldEvent::Steered { entity, action } => {
+                    hooks.entry(*entity).or_default().push(Hook::Action(action));
+                }
+                _ => {}
+            }
+        }
+
+        let mut pending = Vec::new();
+        for (grid_id, grid) in &world.grids {
+            for entity in &grid.entities {
+                let entity_hooks = hooks.get(&entity.get_id()).map_or(&[][..], |h| &h[..]);
+                let mut actions = Vec::new();
+                if let Some(name) = world.components.scripts.get(entity.get_id()) {
+                    self.run(name, entity_hooks, entity, None, &mut actions);
+                }
+                for (index, block) in entity.blocks.iter().enumerate() {
+                    let name = block.typetag_name();
+                    self.run(name, entity_hooks, entity, Some(index), &mut actions);
+                }
+                if !actions.is_empty() {
+                    pending.push((EntityId::new(*grid_id, entity.get_id()), actions));
+                }
+            }
+        }
+
+        // scripts only steer, which entities without a crewed cockpit ignore
+        for (id, actions) in pending {
+            if !life_support::can_control(world, &id) {
+                continue;
+            }
+            if let Some(entity) = world.get_entity_mut(&id) {
+                for action in actions {
+                    entity.apply_action(action);
+                }
+            }
+        }
+    }
+
+    /// Runs `on_tick` and then the other hooks of the script called `name`, if there is one.
+    fn run(
+        &mut self,
+        name: &str,
+        hooks: &[Hook],
+        entity: &Entity,
+        block: Option<usize>,
+        actions: &mut Vec<Action>,
+    ) {
+        let script = match self.scripts.get(name) {
+            Some(script) if !self.failed.contains(name) => script,
+            _ => return,
+        };
+        let tick = self.tick;
+        let result = std::iter::once(&Hook::Tick)
+            .chain(hooks)
+            .try_for_each(|hook| {
+                actions.extend(script.run(*hook, entity, block, tick)?);
+                Ok::<_, mlua::Error>(())
+            });
+        if let Err(e) = result {
+            eprintln!("script {} stopped: {}", name, e);
+            self.failed.insert(name.to_owned());
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crate::ui::user_controls::Action;
+use crate::world::Entity;
+use gamemath::Vec2;
+use mlua::{Function, HookTriggers, Lua, LuaOptions, MultiValue, StdLib, Table, Value};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+/// Instructions between checks of the budget of a running handler.
+const INSTRUCTION_STEP: u32 = 1000;
+
+/// Steps of `INSTRUCTION_STEP` instructions a handler may run each time it is called.
+const INSTRUCTION_BUDGET: u32 = 100;
+
+/// Memory all of the state of a script may take, in bytes.
+const MEMORY_LIMIT: usize = 16 << 20;
+
+/// Moment in which a handler of a script runs.
+#[derive(Clone, Copy)]
+pub enum Hook<'a> {
+    Tick,
+    /// A player steered the entity.
+    Action(&'a Action),
+    /// The entity collided with the other one.
+    Collision {
+        other: u64,
+    },
+}
+
+/// Lua script that controls entities or gives blocks a behavior.
+///
+/// Scripts define any of `on_tick(entity)`, `on_action(entity, action)` and
+/// `on_collision(entity, other)`. Behaviors of blocks get their block after the entity,
+/// as in `on_tick(entity, block)`. The global `tick` counts ticks since scripts were loaded:
+///
+/// ```lua
+/// -- turn every two seconds, but not too fast
+/// function on_tick(entity)
+///     if tick % 120 == 0 then entity:rotate(1, 0.5) end
+///     if entity.spin > 0.05 then entity:rotate(-1, 1) end
+/// end
+/// ```
+///
+/// `entity` has `id`, `x`, `y`, `vx`, `vy`, `speed`, `angle`, `spin`, `mass` and `blocks`,
+/// the number of its blocks. It is steered with `entity:accelerate(x, y, [throttle])`,
+/// `entity:rotate(direction, [throttle])`, `entity:fire(index, [throttle])` and
+/// `entity:stop()`. `block` has `index`, `type`, `x`, `y`, `angle` and `damage`, and
+/// `action` has a `kind` of `accelerate`, `rotate` or `fire` with the same arguments.
+///
+/// Scripts cannot reach files or the operating system, and a handler that runs for too
+/// long is stopped with an error.
+pub struct Script {
+    lua: Lua,
+    /// Steps of instructions the running handler has left.
+    budget: Arc<AtomicU32>,
+}
+
+impl Hook<'_> {
+    fn handler(self) -> &'static str {
+        match self {
+            Hook::Tick => "on_tick",
+            Hook::Action(_) => "on_action",
+            Hook::Collision { .. } => "on_collision",
+        }
+    }
+}
+
+impl Script {
+    pub fn load(name: &str, source: &str) -> mlua::Result<Self> {
+        let libs = StdLib::MATH | StdLib::STRING | StdLib::TABLE;
+        let lua = Lua::new_with(libs, LuaOptions::default())?;
+        lua.set_memory_limit(MEMORY_LIMIT)?;
+        for unsafe_function in &["dofile", "loadfile", "load"] {
+            lua.globals().set(*unsafe_function, Value::Nil)?;
+        }
+
+        let budget = Arc::new(AtomicU32::new(INSTRUCTION_BUDGET));
+        let steps = budget.clone();
+        let triggers = HookTriggers::new().every_nth_instruction(INSTRUCTION_STEP);
+        lua.set_hook(triggers, move |_, _| {
+            match steps.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |s| s.checked_sub(1)) {
+                Ok(_) => Ok(()),
+                Err(_) => Err(mlua::Error::RuntimeError("script runs too long".into())),
+            }
+        });
+
+        lua.load(source).set_name(name).exec()?;
+        Ok(Script { lua, budget })
+    }
+
+    /// Calls the handler of the hook for the entity, or for its block at `block`.
+    /// Returns actions to apply to the entity.
+    pub fn run(
+        &self,
+        hook: Hook,
+        entity: &Entity,
+        block: Option<usize>,
+        tick: u64,
+    ) -> mlua::Result<Vec<Action>> {
+        let handler: Option<Function> = self.lua.globals().get(hook.handler())?;
+        let handler = match handler {
+            Some(handler) => handler,
+            None => return Ok(Vec::new()),
+        };
+        self.lua.globals().set("tick", tick)?;
+        self.budget.store(INSTRUCTION_BUDGET, Ordering::Relaxed);
+
+        let actions = RefCell::new(Vec::new());
+        self.lua.scope(|scope| {
+            let view = self.entity_view(entity)?;
+            view.set(
+                "accelerate",
+                scope.create_function(
+                    |_, (_, x, y, throttle): (Table, f32, f32, Option<f32>)| {
+                        actions.borrow_mut().push(Action::Accelerate {
+                            direction: Vec2::new(finite(x)?, finite(y)?),
+                            throttle: throttle_of(throttle)?,
+                        });
+                        Ok(())
+                    },
+                )?,
+            )?;
+            view.set(
+                "rotate",
+                scope.create_function(
+                    |_, (_, direction, throttle): (Table, f32, Option<f32>)| {
+                        actions.borrow_mut().push(Action::Rotate {
+                            direction: finite(direction)?.clamp(-1.0, 1.0),
+                            throttle: throttle_of(throttle)?,
+                        });
+                        Ok(())
+                    },
+                )?,
+            )?;
+            view.set(
+                "fire",
+                scope.create_function(|_, (_, index, throttle): (Table, usize, Option<f32>)| {
+                    actions.borrow_mut().push(Action::FireBlock {
+                        index,
+                        throttle: throttle_of(throttle)?,
+                    });
+                    Ok(())
+                })?,
+            )?;
+            view.set(
+                "stop",
+                scope.create_function(|_, _: Table| {
+                    actions.borrow_mut().push(Action::Accelerate {
+                        direction: Vec2::default(),
+                        throttle: 0.0,
+                    });
+                    Ok(())
+                })?,
+            )?;
+
+            let mut args = vec![Value::Table(view)];
+            if let Some(index) = block {
+                args.push(Value::Table(self.block_view(entity, index)?));
+            }
+            match hook {
+                Hook::Tick => {}
+                Hook::Action(action) => args.push(Value::Table(self.action_view(action)?)),
+                Hook::Collision { other } => args.push(Value::Integer(other as i64)),
+            }
+            handler.call::<_, ()>(MultiValue::from_vec(args))
+        })?;
+        Ok(actions.into_inner())
+    }
+
+    fn entity_view(&self, entity: &Entity) -> mlua::Result<Table<'_>> {
+        let view = self.lua.create_table()?;
+        view.set("id", entity.get_id() as i64)?;
+        view.set("x", entity.position.state.x)?;
+        view.set("y", entity.position.state.y)?;
+        view.set("vx", entity.position.velocity.x)?;
+        view.set("vy", entity.position.velocity.y)?;
+        view.set("speed", entity.position.velocity.length())?;
+        view.set("angle", entity.angle.state)?;
+        view.set("spin", entity.angle.velocity)?;
+        view.set("mass", entity.mass)?;
+        view.set("blocks", entity.blocks.len())?;
+        Ok(view)
+    }
+
+    fn block_view(&self, entity: &Entity, index: usize) -> mlua::Result<Table<'_>> {
+        let block = &entity.blocks[index];
+        let view = self.lua.create_table()?;
+        view.set("index", index)?;
+        view.set("type", block.typetag_name())?;
+        view.set("x", block.offset().x)?;
+        view.set("y", block.offset().y)?;
+        view.set("angle", block.angle())?;
+        view.set("damage", entity.block_damage(index))?;
+        Ok(view)
+    }
+
+    fn action_view(&self, action: &Action) -> mlua::Result<Table<'_>> {
+        let view = self.lua.create_table()?;
+        match action {
+            Action::Accelerate {
+                direction,
+                throttle,
+            } => {
+                view.set("kind", "accelerate")?;
+                view.set("x", direction.x)?;
+                view.set("y", direction.y)?;
+                view.set("throttle", *throttle)?;
+            }
+            Action::Rotate {
+                direction,
+                throttle,
+            } => {
+                view.set("kind", "rotate")?;
+                view.set("direction", *direction)?;
+                view.set("throttle", *throttle)?;
+            }
+            Action::FireBlock { index, throttle } => {
+                view.set("kind", "fire")?;
+                view.set("index", *index)?;
+                view.set("throttle", *throttle)?;
+            }
+            _ => {}
+        }
+        Ok(view)
+    }
+}
+
+/// Throttle between 0 and 1, full when it is not given.
+fn throttle_of(throttle: Option<f32>) -> mlua::Result<f32> {
+    Ok(finite(throttle.unwrap_or(1.0))?.clamp(0.0, 1.0))
+}
+
+fn finite(value: f32) -> mlua::Result<f32> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(mlua::Error::RuntimeError(format!(
+            "invalid number {}",
+            value
+        )))
+    }
+}
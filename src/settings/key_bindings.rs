@@ -0,0 +1,108 @@
+use super::toml::{Table, Value};
+use crate::platform::Keycode;
+
+/// Keys of user controls. Arrow keys always work for acceleration, in addition to these.
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    pub up: Keycode,
+    pub down: Keycode,
+    pub left: Keycode,
+    pub right: Keycode,
+    pub rotate_left: Keycode,
+    pub rotate_right: Keycode,
+    pub throttle_up: Keycode,
+    pub throttle_down: Keycode,
+    pub manual_thrusters: Keycode,
+    pub cycle_control: Keycode,
+    pub save_entity: Keycode,
+    pub load_entity: Keycode,
+    pub export_entity: Keycode,
+    pub export_text: Keycode,
+    pub toggle_stats: Keycode,
+    pub toggle_statistics: Keycode,
+    pub hold_heading: Keycode,
+    pub match_velocity: Keycode,
+    pub target_hostile: Keycode,
+    pub toggle_rotation_lock: Keycode,
+    pub toggle_debug_draw: Keycode,
+    pub screenshot: Keycode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            up: Keycode::W,
+            down: Keycode::S,
+            left: Keycode::A,
+            right: Keycode::D,
+            rotate_left: Keycode::E,
+            rotate_right: Keycode::Q,
+            throttle_up: Keycode::Equals,
+            throttle_down: Keycode::Minus,
+            manual_thrusters: Keycode::M,
+            cycle_control: Keycode::Tab,
+            save_entity: Keycode::F5,
+            load_entity: Keycode::F6,
+            export_entity: Keycode::F8,
+            export_text: Keycode::F9,
+            toggle_stats: Keycode::F3,
+            toggle_statistics: Keycode::F4,
+            hold_heading: Keycode::H,
+            match_velocity: Keycode::V,
+            target_hostile: Keycode::R,
+            toggle_rotation_lock: Keycode::C,
+            toggle_debug_draw: Keycode::F7,
+            screenshot: Keycode::F12,
+        }
+    }
+}
+
+impl KeyBindings {
+    fn fields_mut(&mut self) -> [(&'static str, &mut Keycode); 22] {
+        [
+            ("up", &mut self.up),
+            ("down", &mut self.down),
+            ("left", &mut self.left),
+            ("right", &mut self.right),
+            ("rotate_left", &mut self.rotate_left),
+            ("rotate_right", &mut self.rotate_right),
+            ("throttle_up", &mut self.throttle_up),
+            ("throttle_down", &mut self.throttle_down),
+            ("manual_thrusters", &mut self.manual_thrusters),
+            ("cycle_control", &mut self.cycle_control),
+            ("save_entity", &mut self.save_entity),
+            ("load_entity", &mut self.load_entity),
+            ("export_entity", &mut self.export_entity),
+            ("export_text", &mut self.export_text),
+            ("toggle_stats", &mut self.toggle_stats),
+            ("toggle_statistics", &mut self.toggle_statistics),
+            ("hold_heading", &mut self.hold_heading),
+            ("match_velocity", &mut self.match_velocity),
+            ("target_hostile", &mut self.target_hostile),
+            ("toggle_rotation_lock", &mut self.toggle_rotation_lock),
+            ("toggle_debug_draw", &mut self.toggle_debug_draw),
+            ("screenshot", &mut self.screenshot),
+        ]
+    }
+
+    /// Reads bindings from `section`, keeping defaults for missing or unknown keys.
+    pub fn read(&mut self, table: &Table, section: &str) {
+        for (name, keycode) in self.fields_mut().iter_mut() {
+            if let Some(Value::String(key)) = table.get(&format!("{}.{}", section, name)) {
+                match Keycode::from_name(key) {
+                    Some(key) => **keycode = key,
+                    None => eprintln!("unknown key for {}: {}", name, key),
+                }
+            }
+        }
+    }
+
+    pub fn entries(&self) -> Vec<(&'static str, Value)> {
+        let mut bindings = self.clone();
+        bindings
+            .fields_mut()
+            .iter()
+            .map(|(name, keycode)| (*name, Value::String(keycode.name())))
+            .collect()
+    }
+}
@@ -0,0 +1,199 @@
+mod key_bindings;
+pub mod toml;
+
+pub use key_bindings::KeyBindings;
+
+use crate::paths::Paths;
+use crate::persistence;
+use crate::platform::DisplayMode;
+use crate::render::CameraFollow;
+use crate::ui::hud::DEFAULT_MINIMAP_RANGE;
+use crate::world::difficulty::DifficultyLevel;
+use crate::world::DEFAULT_SHAPE_TOLERANCE;
+use toml::Value;
+
+pub const MIN_UI_SCALE: f32 = 0.5;
+pub const MAX_UI_SCALE: f32 = 4.0;
+
+/// Client preferences, stored in the settings file.
+pub struct Settings {
+    /// Size multiplier of all HUD elements.
+    pub ui_scale: f32,
+    /// From 0 to 1.
+    pub audio_volume: f32,
+    /// From 0 to 1, relative to audio volume.
+    pub music_volume: f32,
+    pub key_bindings: KeyBindings,
+    /// How the game was last started, preselected in the menu.
+    pub last_scenario: Option<String>,
+    /// Difficulty of new games, preselected in the menu.
+    pub difficulty: DifficultyLevel,
+    /// Seconds between autosaves, zero disables them.
+    pub autosave_interval: u32,
+    /// Of worlds started here, see `World::shape_tolerance`.
+    pub shape_tolerance: f32,
+    /// How the camera eases back onto the controlled entity.
+    pub camera_follow: CameraFollow,
+    /// Distance from the controlled entity to the edge of the minimap.
+    pub minimap_range: f32,
+    /// How the window is shown when the game starts.
+    pub display_mode: DisplayMode,
+    /// Index of the monitor the window opens on.
+    pub display: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            ui_scale: 1.0,
+            audio_volume: 1.0,
+            music_volume: 0.5,
+            key_bindings: KeyBindings::default(),
+            last_scenario: None,
+            difficulty: DifficultyLevel::default(),
+            autosave_interval: 300,
+            shape_tolerance: DEFAULT_SHAPE_TOLERANCE,
+            camera_follow: CameraFollow::default(),
+            minimap_range: DEFAULT_MINIMAP_RANGE,
+            display_mode: DisplayMode::default(),
+            display: 0,
+        }
+    }
+}
+
+impl Settings {
+    pub fn scale_ui(&mut self, factor: f32) {
+        self.ui_scale = (self.ui_scale * factor).clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    }
+
+    /// Reads the settings file. Missing or invalid values are replaced by defaults.
+    pub fn load(paths: &Paths) -> Settings {
+        let mut settings = Settings::default();
+
+        let text = match paths.settings_file().and_then(std::fs::read_to_string) {
+            Ok(text) => text,
+            Err(_) => return settings,
+        };
+        let table = match toml::parse(&text) {
+            Ok(table) => table,
+            Err(e) => {
+                eprintln!("invalid settings file: {}", e);
+                return settings;
+            }
+        };
+
+        if let Some(Value::Number(ui_scale)) = table.get("ui_scale") {
+            settings.ui_scale = (*ui_scale as f32).clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+        }
+        if let Some(Value::Number(volume)) = table.get("audio_volume") {
+            settings.audio_volume = (*volume as f32).clamp(0.0, 1.0);
+        }
+        if let Some(Value::Number(volume)) = table.get("music_volume") {
+            settings.music_volume = (*volume as f32).clamp(0.0, 1.0);
+        }
+        if let Some(Value::String(scenario)) = table.get("last_scenario") {
+            settings.last_scenario = Some(scenario.clone());
+        }
+        if let Some(Value::String(name)) = table.get("difficulty") {
+            match DifficultyLevel::from_name(name) {
+                Some(level) => settings.difficulty = level,
+                None => eprintln!("unknown difficulty {}", name),
+            }
+        }
+        if let Some(Value::Number(seconds)) = table.get("autosave_interval") {
+            settings.autosave_interval = seconds.max(0.0) as u32;
+        }
+        if let Some(Value::Number(tolerance)) = table.get("shape_tolerance") {
+            settings.shape_tolerance = (*tolerance as f32).max(0.0);
+        }
+        if let Some(Value::Number(stiffness)) = table.get("camera_stiffness") {
+            settings.camera_follow.stiffness = (*stiffness as f32).clamp(0.0, 1.0);
+        }
+        if let Some(Value::Number(damping)) = table.get("camera_damping") {
+            settings.camera_follow.damping = (*damping as f32).clamp(0.0, 1.0);
+        }
+        if let Some(Value::Number(deadzone)) = table.get("camera_deadzone") {
+            settings.camera_follow.deadzone = (*deadzone as f32).max(0.0);
+        }
+        if let Some(Value::Number(range)) = table.get("minimap_range") {
+            settings.minimap_range = (*range as f32).max(1.0);
+        }
+        if let Some(Value::String(name)) = table.get("display_mode") {
+            match DisplayMode::from_name(name) {
+                Some(mode) => settings.display_mode = mode,
+                None => eprintln!("unknown display mode {}", name),
+            }
+        }
+        if let Some(Value::Number(display)) = table.get("display") {
+            settings.display = display.max(0.0) as u32;
+        }
+        settings.key_bindings.read(&table, "key_bindings");
+        settings
+    }
+
+    pub fn save(&self, paths: &Paths) -> std::io::Result<()> {
+        let mut out = String::new();
+        toml::write_value(&mut out, "ui_scale", &Value::Number(self.ui_scale as f64));
+        toml::write_value(
+            &mut out,
+            "audio_volume",
+            &Value::Number(self.audio_volume as f64),
+        );
+        toml::write_value(
+            &mut out,
+            "music_volume",
+            &Value::Number(self.music_volume as f64),
+        );
+        if let Some(scenario) = &self.last_scenario {
+            toml::write_value(&mut out, "last_scenario", &Value::String(scenario.clone()));
+        }
+        toml::write_value(
+            &mut out,
+            "difficulty",
+            &Value::String(self.difficulty.name().to_owned()),
+        );
+        toml::write_value(
+            &mut out,
+            "autosave_interval",
+            &Value::Number(self.autosave_interval as f64),
+        );
+        toml::write_value(
+            &mut out,
+            "shape_tolerance",
+            &Value::Number(self.shape_tolerance as f64),
+        );
+        toml::write_value(
+            &mut out,
+            "camera_stiffness",
+            &Value::Number(self.camera_follow.stiffness as f64),
+        );
+        toml::write_value(
+            &mut out,
+            "camera_damping",
+            &Value::Number(self.camera_follow.damping as f64),
+        );
+        toml::write_value(
+            &mut out,
+            "camera_deadzone",
+            &Value::Number(self.camera_follow.deadzone as f64),
+        );
+        toml::write_value(
+            &mut out,
+            "minimap_range",
+            &Value::Number(self.minimap_range as f64),
+        );
+        toml::write_value(
+            &mut out,
+            "display_mode",
+            &Value::String(self.display_mode.name().to_owned()),
+        );
+        toml::write_value(&mut out, "display", &Value::Number(self.display as f64));
+
+        out.push_str("\n[key_bindings]\n");
+        for (name, value) in self.key_bindings.entries() {
+            toml::write_value(&mut out, name, &value);
+        }
+
+        persistence::write_atomic(&paths.settings_file()?, out.as_bytes())
+    }
+}
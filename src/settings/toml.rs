@@ -0,0 +1,101 @@
+//! Subset of TOML used for the settings file: sections, and keys with string, number or boolean values.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Number(f64),
+    Bool(bool),
+}
+
+/// Values keyed by section and key, joined with a dot.
+pub type Table = HashMap<String, Value>;
+
+pub fn parse(text: &str) -> Result<Table, String> {
+    let mut table = Table::new();
+    let mut section = String::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: &str| format!("line {}: {}", index + 1, message);
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or_else(|| error("unclosed section"))?;
+            section = name.trim().to_owned() + ".";
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected key = value"))?;
+        let value = parse_value(value.trim()).ok_or_else(|| error("invalid value"))?;
+        table.insert(section.clone() + key.trim(), value);
+    }
+    Ok(table)
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(value: &str) -> Option<Value> {
+    if let Some(string) = value.strip_prefix('"') {
+        let string = string.strip_suffix('"')?;
+        let mut result = String::new();
+        let mut chars = string.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next()? {
+                    'n' => result.push('\n'),
+                    't' => result.push('\t'),
+                    c @ '"' | c @ '\\' => result.push(c),
+                    _ => return None,
+                }
+            } else {
+                result.push(c);
+            }
+        }
+        return Some(Value::String(result));
+    }
+    match value {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        _ => value.parse().ok().map(Value::Number),
+    }
+}
+
+pub fn write_value(out: &mut String, key: &str, value: &Value) {
+    match value {
+        Value::String(string) => {
+            let escaped = string
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\t', "\\t");
+            writeln!(out, "{} = \"{}\"", key, escaped).unwrap();
+        }
+        Value::Number(number) => writeln!(out, "{} = {:?}", key, number).unwrap(),
+        Value::Bool(boolean) => writeln!(out, "{} = {}", key, boolean).unwrap(),
+    }
+}
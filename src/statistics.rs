@@ -0,0 +1,201 @@
+use crate::client::EntityId;
+use crate::events::WorldEvent;
+use crate::paths::Paths;
+use crate::persistence;
+use crate::settings::toml::{self, Value};
+use crate::world::{Faction, World};
+use gamemath::Vec2;
+use std::collections::{HashMap, HashSet};
+
+/// What the player has done, summed over a session or over all sessions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Counters {
+    pub distance: f32,
+    pub blocks_placed: u64,
+    pub asteroids_mined: u64,
+    pub collisions_survived: u64,
+    pub enemies_destroyed: u64,
+}
+
+impl Counters {
+    fn fields_mut(&mut self) -> [(&'static str, Counter<'_>); 5] {
+        [
+            ("distance", Counter::Float(&mut self.distance)),
+            ("blocks_placed", Counter::Int(&mut self.blocks_placed)),
+            ("asteroids_mined", Counter::Int(&mut self.asteroids_mined)),
+            (
+                "collisions_survived",
+                Counter::Int(&mut self.collisions_survived),
+            ),
+            (
+                "enemies_destroyed",
+                Counter::Int(&mut self.enemies_destroyed),
+            ),
+        ]
+    }
+}
+
+enum Counter<'a> {
+    Float(&'a mut f32),
+    Int(&'a mut u64),
+}
+
+/// Counters of the controlled entity, gathered by the client each tick.
+/// Totals over previous sessions are kept in the statistics file.
+#[derive(Default)]
+pub struct Statistics {
+    pub session: Counters,
+    /// Including the current session.
+    pub total: Counters,
+
+    /// Position of the controlled entity in its grid in the previous tick.
+    last_position: Option<EntityPosition>,
+    /// Motion absorbed into the frame of the grid of the controlled entity.
+    frame_velocity: Vec2<f32>,
+    frame_shift: Vec2<f32>,
+    collided: bool,
+    /// Collided in the previous tick, so a collision that lasts counts once.
+    was_colliding: bool,
+    /// Entities the controlled entity collided with in this tick.
+    touched: Vec<u64>,
+    /// Ore of asteroids when they were last touched.
+    asteroid_ore: HashMap<u64, f32>,
+    mined: HashSet<u64>,
+    /// Hostile entities that could be destroyed, in the previous tick.
+    enemies: HashSet<u64>,
+}
+
+struct EntityPosition {
+    grid_id: u64,
+    state: Vec2<f32>,
+}
+
+impl Statistics {
+    /// Reads totals from the statistics file. Missing or invalid values are zero.
+    pub fn load(paths: &Paths) -> Statistics {
+        let mut statistics = Statistics::default();
+
+        let text = match paths.statistics_file().and_then(std::fs::read_to_string) {
+            Ok(text) => text,
+            Err(_) => return statistics,
+        };
+        let table = match toml::parse(&text) {
+            Ok(table) => table,
+            Err(e) => {
+                eprintln!("invalid statistics file: {}", e);
+                return statistics;
+            }
+        };
+        for (name, counter) in statistics.total.fields_mut().iter_mut() {
+            if let Some(Value::Number(value)) = table.get(*name) {
+                match counter {
+                    Counter::Float(counter) => **counter = *value as f32,
+                    Counter::Int(counter) => **counter = value.max(0.0) as u64,
+                }
+            }
+        }
+        statistics
+    }
+
+    pub fn save(&self, paths: &Paths) -> std::io::Result<()> {
+        let mut out = String::new();
+        let mut total = self.total;
+        for (name, counter) in total.fields_mut().iter() {
+            let value = match counter {
+                Counter::Float(counter) => **counter as f64,
+                Counter::Int(counter) => **counter as f64,
+            };
+            toml::write_value(&mut out, name, &Value::Number(value));
+        }
+        persistence::write_atomic(&paths.statistics_file()?, out.as_bytes())
+    }
+
+    pub fn handle_world_event(&mut self, event: &WorldEvent, focus: &EntityId) {
+        match event {
+            WorldEvent::FramesShifted(shifts) => {
+                if let Some(shift) = shifts.get(&focus.grid_id) {
+                    self.frame_shift += shift.state;
+                    self.frame_velocity += shift.velocity;
+                }
+            }
+            WorldEvent::Collision { entities, .. } if entities.contains(&focus.entity_id) => {
+                if !self.collided && !self.was_colliding {
+                    self.add(|c| c.collisions_survived += 1);
+                }
+                self.collided = true;
+
+                let other = entities.iter().find(|e| **e != focus.entity_id);
+                self.touched.extend(other);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn block_placed(&mut self) {
+        self.add(|c| c.blocks_placed += 1);
+    }
+
+    /// Called after world events of the tick are handled.
+    pub fn tick(&mut self, world: &World, focus: &EntityId) {
+        self.tick_distance(world, focus);
+        self.tick_mining(world);
+        self.tick_enemies(world);
+    }
+
+    fn tick_distance(&mut self, world: &World, focus: &EntityId) {
+        let position = world.get_entity(focus).map(|e| e.position.state);
+
+        // motion of the frame counts as motion of the entity, until it moves to another grid
+        let grid_changed = self
+            .last_position
+            .as_ref()
+            .is_none_or(|last| last.grid_id != focus.grid_id);
+        if grid_changed {
+            self.frame_velocity = Vec2::default();
+        } else if let (Some(position), Some(last)) = (position, &self.last_position) {
+            let moved = position + self.frame_shift + self.frame_velocity - last.state;
+            self.add(|c| c.distance += moved.length());
+        }
+        self.frame_shift = Vec2::default();
+
+        self.last_position = position.map(|state| EntityPosition {
+            grid_id: focus.grid_id,
+            state,
+        });
+    }
+
+    /// Asteroids count as mined once they lose ore while touched by the controlled entity.
+    fn tick_mining(&mut self, world: &World) {
+        self.was_colliding = self.collided;
+        self.collided = false;
+
+        for id in std::mem::take(&mut self.touched) {
+            let ore = match world.components.ore.get(id) {
+                Some(ore) => *ore,
+                None => continue,
+            };
+            let before = self.asteroid_ore.insert(id, ore);
+            if before.is_some_and(|before| ore < before) && self.mined.insert(id) {
+                self.add(|c| c.asteroids_mined += 1);
+            }
+        }
+    }
+
+    fn tick_enemies(&mut self, world: &World) {
+        let enemies: HashSet<u64> = world
+            .components
+            .health
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| world.components.faction(*id) == Faction::Hostile)
+            .collect();
+        let destroyed = self.enemies.difference(&enemies).count() as u64;
+        self.add(|c| c.enemies_destroyed += destroyed);
+        self.enemies = enemies;
+    }
+
+    fn add(&mut self, change: impl Fn(&mut Counters)) {
+        change(&mut self.session);
+        change(&mut self.total);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::ui::user_controls::Action;
+
+/// Action and the client tick it was issued on.
+pub struct QueuedAction {
+    pub tick: u64,
+    pub action: Action,
+}
+
+/// Actions issued by the UI, waiting to be applied in the order of their ticks.
+/// Actions pushed between two ticks belong to the upcoming one.
+#[derive(Default)]
+pub struct ActionQueue {
+    tick: u64,
+    queue: Vec<QueuedAction>,
+}
+
+impl ActionQueue {
+    /// Moves on to `tick`, after which newly pushed actions are issued on it.
+    pub fn set_tick(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    /// Issues an action on the current tick.
+    pub fn push(&mut self, action: Action) {
+        let tick = self.tick;
+        self.push_at(tick, action);
+    }
+
+    /// Buffers an action until `tick`, or issues it as is if that tick has passed.
+    pub fn push_at(&mut self, tick: u64, action: Action) {
+        self.queue.push(QueuedAction { tick, action });
+    }
+
+    /// Takes over actions of another queue, keeping the ticks they were issued on.
+    pub fn append(&mut self, queued: Vec<QueuedAction>) {
+        self.queue.extend(queued);
+    }
+
+    /// Removes actions issued up to the current tick, oldest first and otherwise in
+    /// the order they were pushed. Actions buffered for later ticks stay queued.
+    pub fn poll(&mut self) -> Vec<QueuedAction> {
+        let tick = self.tick;
+        let (mut due, buffered): (Vec<_>, Vec<_>) =
+            self.queue.drain(..).partition(|queued| queued.tick <= tick);
+        self.queue = buffered;
+        due.sort_by_key(|queued| queued.tick);
+        due
+    }
+}
+
+impl Extend<Action> for ActionQueue {
+    fn extend<I: IntoIterator<Item = Action>>(&mut self, actions: I) {
+        for action in actions {
+            self.push(action);
+        }
+    }
+}
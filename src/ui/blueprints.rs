@@ -0,0 +1,293 @@
+use super::text::Text;
+use super::user_controls::Action;
+use crate::math::bounding_box::BoundingBox;
+use crate::math::vec::*;
+use crate::paths::Paths;
+use crate::persistence::blueprint::BlueprintMeta;
+use crate::platform::{Color, DrawTarget, Event, Keycode};
+use crate::render::Render;
+use crate::world::Entity;
+use gamemath::{Mat3, Vec2};
+use std::path::Path;
+
+/// Rows shown at once, the list scrolls to keep the selected one visible.
+const VISIBLE_ROWS: usize = 6;
+
+const ROW_HEIGHT: f32 = 48.0;
+const LINE_HEIGHT: f32 = 12.0;
+const PANEL_PADDING: f32 = 30.0;
+/// Part of the row height taken by the thumbnail.
+const THUMBNAIL_FILL: f32 = 0.8;
+const MAX_NAME_LEN: usize = 32;
+
+struct Blueprint {
+    /// Relative to the entities directory.
+    file_name: String,
+    meta: BlueprintMeta,
+    /// Centered at the origin, for the thumbnail.
+    entity: Entity,
+}
+
+/// Saved entities with their names, mass and block count, opened with L.
+/// Enter spawns the selected blueprint, F2 renames and Delete removes it.
+pub struct BlueprintLibrary {
+    open: bool,
+    blueprints: Vec<Blueprint>,
+    selected: usize,
+    /// New name of the selected blueprint, while it is being typed.
+    rename: Option<String>,
+    view_size: Vec2<f32>,
+    ui_scale: f32,
+
+    action_queue: Vec<Action>,
+}
+
+impl BlueprintLibrary {
+    pub fn new(view_size: Vec2<f32>, ui_scale: f32) -> Self {
+        BlueprintLibrary {
+            open: false,
+            blueprints: Vec::new(),
+            selected: 0,
+            rename: None,
+            view_size,
+            ui_scale,
+            action_queue: Vec::new(),
+        }
+    }
+
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.ui_scale = ui_scale;
+    }
+
+    pub fn set_view_size(&mut self, view_size: Vec2<f32>) {
+        self.view_size = view_size;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn poll_actions(&mut self) -> std::vec::Drain<'_, Action> {
+        self.action_queue.drain(..)
+    }
+
+    /// Reads saved entities from disk. Metadata missing from older saves is written.
+    pub fn load(&mut self, paths: &Paths) {
+        self.blueprints.clear();
+
+        let dir = match paths.entities() {
+            Ok(dir) => dir,
+            Err(_) => return,
+        };
+        for path in Entity::list_saved(paths).unwrap_or_else(|_| vec![]) {
+            let mut entity = match Entity::load_from_file(path.clone()) {
+                Ok(entity) => entity,
+                Err(e) => {
+                    eprintln!("cannot load blueprint {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            entity.redistribute_weight();
+            entity.position = Default::default();
+            entity.angle = Default::default();
+
+            let path = Path::new(&path);
+            let meta = BlueprintMeta::read(path).unwrap_or_else(|| {
+                let meta = BlueprintMeta::of(&entity);
+                if let Err(e) = meta.write(path) {
+                    eprintln!("cannot write blueprint metadata: {}", e);
+                }
+                meta
+            });
+            let file_name = path
+                .strip_prefix(&dir)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .into_owned();
+
+            self.blueprints.push(Blueprint {
+                file_name,
+                meta,
+                entity,
+            });
+        }
+        self.blueprints
+            .sort_by_key(|blueprint| blueprint.meta.name.to_lowercase());
+        self.selected = self.selected.min(self.blueprints.len().saturating_sub(1));
+    }
+
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if self.rename.is_some() {
+            return self.handle_rename_event(event);
+        }
+        if self.open {
+            if let Event::MouseWheel { y, .. } = event {
+                if *y > 0 {
+                    self.select_previous();
+                } else if *y < 0 {
+                    self.select_next();
+                }
+                return true;
+            }
+        }
+
+        let keycode = match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => *keycode,
+            _ => return false,
+        };
+        if keycode == Keycode::L {
+            self.open = !self.open;
+            return true;
+        }
+        if !self.open {
+            return false;
+        }
+
+        match keycode {
+            Keycode::Up => self.select_previous(),
+            Keycode::Down => self.select_next(),
+            Keycode::Return | Keycode::KpEnter => {
+                if let Some(blueprint) = self.blueprints.get(self.selected) {
+                    self.action_queue.push(Action::LoadEntity {
+                        filename: blueprint.file_name.clone(),
+                    });
+                }
+            }
+            Keycode::Delete => {
+                if let Some(blueprint) = self.blueprints.get(self.selected) {
+                    self.action_queue.push(Action::DeleteBlueprint {
+                        filename: blueprint.file_name.clone(),
+                    });
+                }
+            }
+            Keycode::F2 => {
+                if let Some(blueprint) = self.blueprints.get(self.selected) {
+                    self.rename = Some(blueprint.meta.name.clone());
+                }
+            }
+            Keycode::Escape => self.open = false,
+            _ => return false,
+        }
+        true
+    }
+
+    fn handle_rename_event(&mut self, event: &Event) -> bool {
+        let name = match &mut self.rename {
+            Some(name) => name,
+            None => return false,
+        };
+        match event {
+            Event::TextInput { text, .. } => {
+                if name.chars().count() < MAX_NAME_LEN {
+                    name.push_str(text);
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::Backspace => {
+                    name.pop();
+                }
+                Keycode::Escape => self.rename = None,
+                Keycode::Return | Keycode::KpEnter => {
+                    let name = name.trim().to_owned();
+                    if let Some(blueprint) = self.blueprints.get(self.selected) {
+                        if !name.is_empty() && name != blueprint.meta.name {
+                            self.action_queue.push(Action::RenameBlueprint {
+                                filename: blueprint.file_name.clone(),
+                                name,
+                            });
+                        }
+                    }
+                    self.rename = None;
+                }
+                _ => {}
+            },
+            Event::KeyUp { .. } => {}
+            _ => return false,
+        }
+        true
+    }
+
+    fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    fn select_next(&mut self) {
+        if self.selected + 1 < self.blueprints.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn render(&self, canvas: &mut dyn DrawTarget) {
+        if !self.open {
+            return;
+        }
+        let row = ROW_HEIGHT * self.ui_scale;
+        let height = LINE_HEIGHT * self.ui_scale;
+        let left = PANEL_PADDING * self.ui_scale;
+        let mut top = self.view_size.y * 0.5 - row * VISIBLE_ROWS as f32 * 0.5;
+
+        canvas.set_draw_color(Color::rgb(200, 200, 60));
+        if self.blueprints.is_empty() {
+            Text::new("no saved blueprints", height)
+                .render(translation(Vec2::new(left, top)), canvas);
+            return;
+        }
+        let header = format!(
+            "blueprints {}/{}  enter spawn  f2 rename  del delete",
+            self.selected + 1,
+            self.blueprints.len()
+        );
+        Text::new(&header, height).render(translation(Vec2::new(left, top)), canvas);
+        top += height * 2.0;
+
+        let first = (self.selected + 1).saturating_sub(VISIBLE_ROWS);
+        for (index, blueprint) in self
+            .blueprints
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(VISIBLE_ROWS)
+        {
+            let selected = index == self.selected;
+            canvas.set_draw_color(if selected {
+                Color::rgb(200, 200, 60)
+            } else {
+                Color::rgb(128, 128, 172)
+            });
+
+            let thumbnail_center = Vec2::new(left + row * 0.5, top + row * 0.5);
+            let bb = blueprint.entity.shape.bounding_box();
+            let diagonal = bb.bottom_right - bb.top_left;
+            let scale = THUMBNAIL_FILL * row / diagonal.x.max(diagonal.y).max(1.0);
+            blueprint.entity.render(
+                translation(thumbnail_center) * Mat3::identity().scaled(Vec2::from(scale)),
+                canvas,
+            );
+
+            let name = match (&self.rename, selected) {
+                (Some(name), true) => format!("{}_", name),
+                _ => blueprint.meta.name.clone(),
+            };
+            let text_left = left + row * 1.2;
+            Text::new(&name.to_lowercase(), height).render(
+                translation(Vec2::new(text_left, top + row * 0.5 - height * 1.2)),
+                canvas,
+            );
+            let details = format!(
+                "mass {:.0}  blocks {}",
+                blueprint.meta.mass, blueprint.meta.blocks
+            );
+            Text::new(&details, height).render(
+                translation(Vec2::new(text_left, top + row * 0.5 + height * 0.2)),
+                canvas,
+            );
+            top += row;
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use super::text::Text;
+use super::user_controls::Action;
+use crate::math::vec::*;
+use crate::platform::{Color, DrawTarget, Event, Keycode};
+use crate::render::Render;
+use gamemath::Vec2;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const HISTORY_LEN: usize = 100;
+/// Longest message, in characters, that can be typed or is relayed by servers.
+pub const MAX_MESSAGE_LEN: usize = 200;
+
+/// Number of lines shown while typing.
+const VISIBLE_LINES: usize = 10;
+
+/// While not typing, a line is shown for this long after it was received.
+const LINE_LIFETIME: Duration = Duration::from_secs(10);
+
+const LINE_HEIGHT: f32 = 12.0;
+const LINE_SPACING: f32 = 6.0;
+const CHAT_PADDING: f32 = 30.0;
+
+struct ChatLine {
+    text: String,
+    received: Instant,
+}
+
+/// Chat history with an input line, opened with Enter.
+pub struct Chat {
+    history: VecDeque<ChatLine>,
+    input: Option<String>,
+    view_size: Vec2<f32>,
+    ui_scale: f32,
+
+    action_queue: Vec<Action>,
+}
+
+impl Chat {
+    pub fn new(view_size: Vec2<f32>, ui_scale: f32) -> Self {
+        Chat {
+            history: VecDeque::new(),
+            input: None,
+            view_size,
+            ui_scale,
+            action_queue: Vec::new(),
+        }
+    }
+
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.ui_scale = ui_scale;
+    }
+
+    pub fn set_view_size(&mut self, view_size: Vec2<f32>) {
+        self.view_size = view_size;
+    }
+
+    pub fn is_typing(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn poll_actions(&mut self) -> std::vec::Drain<'_, Action> {
+        self.action_queue.drain(..)
+    }
+
+    pub fn push(&mut self, author: &str, text: &str) {
+        if self.history.len() >= HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(ChatLine {
+            text: format!("{}: {}", author, text),
+            received: Instant::now(),
+        });
+    }
+
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        let input = match &mut self.input {
+            Some(input) => input,
+            None => {
+                if let Event::KeyDown {
+                    keycode: Some(Keycode::Return),
+                    ..
+                } = event
+                {
+                    self.input = Some(String::new());
+                    return true;
+                }
+                return false;
+            }
+        };
+
+        match event {
+            Event::TextInput { text, .. } => {
+                if input.chars().count() < MAX_MESSAGE_LEN {
+                    input.push_str(text);
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::Backspace => {
+                    input.pop();
+                }
+                Keycode::Escape => self.input = None,
+                Keycode::Return | Keycode::KpEnter => {
+                    let text = input.trim().to_owned();
+                    if !text.is_empty() {
+                        self.action_queue.push(Action::SendChat { text });
+                    }
+                    self.input = None;
+                }
+                _ => {}
+            },
+            Event::KeyUp { .. } => {}
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn render(&self, canvas: &mut dyn DrawTarget) {
+        let height = LINE_HEIGHT * self.ui_scale;
+        let step = height + LINE_SPACING * self.ui_scale;
+        let left = CHAT_PADDING * self.ui_scale;
+        let mut bottom = self.view_size.y - CHAT_PADDING * self.ui_scale - height;
+
+        if let Some(input) = &self.input {
+            canvas.set_draw_color(Color::rgb(200, 200, 60));
+            Text::new(&format!("> {}_", input), height)
+                .render(translation(Vec2::new(left, bottom)), canvas);
+            bottom -= step;
+        }
+
+        canvas.set_draw_color(Color::rgb(128, 128, 172));
+        let lines = self
+            .history
+            .iter()
+            .rev()
+            .take(VISIBLE_LINES)
+            .take_while(|line| self.is_typing() || line.received.elapsed() < LINE_LIFETIME);
+        for line in lines {
+            Text::new(&line.text, height).render(translation(Vec2::new(left, bottom)), canvas);
+            bottom -= step;
+        }
+    }
+}
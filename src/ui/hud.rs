@@ -1,73 +1,363 @@
 use crate::client::EntityId;
-use crate::world::{Entity, Thruster, GridRelation, Insist, World};
+use crate::events::WorldEvent;
+use crate::world::balance::{self, Imbalance};
+use crate::world::combat::Health;
+use crate::world::industry::Cargo;
+use crate::world::gravity;
+use crate::world::missions;
+use crate::world::life_support::{self, Oxygen};
+use crate::world::supply::{Resupply, Tank};
+use crate::world::survival::Survival;
+use crate::world::tutorial::Step;
+use crate::world::{Block, BlockRegistry, Entity, GridRelation, Insist, World};
 use crate::math::bounding_box::BoundingBox;
-use crate::math::polygon::{construct_rect_poly, construct_rect_poly_centered, Polygon};
+use crate::math::circle::Circle;
+use crate::math::polygon::{
+    construct_convex_hull, construct_rect_poly, construct_rect_poly_centered, Polygon,
+};
 use crate::math::segment::Segment;
+use crate::math::shape::Shape;
 use crate::math::vec::*;
-use crate::render::{into_vec, Render};
+use crate::paths::Paths;
+use crate::platform::{Color, DrawTarget, Event, Keycode, Mod};
+use crate::render::{Render, View};
+use crate::ui::text::Text;
+use crate::ui::action_queue::{ActionQueue, QueuedAction};
 use crate::ui::user_controls::Action;
-use gamemath::{Mat3, Vec2};
-use sdl2::event::Event;
-use sdl2::pixels::Color;
-use sdl2::render::{Canvas, RenderTarget};
+use gamemath::{Mat2, Mat3, Vec2};
 
 const TRACKER_PADDING: i32 = 30;
 
+const THROTTLE_GAUGE_SIZE: Vec2<f32> = Vec2 { x: 10.0, y: 100.0 };
+
+/// Length of the gravity arrow, in pixels before UI scaling.
+const GRAVITY_ARROW_LENGTH: f32 = 20.0;
+
+/// For how long the throttle gauge flashes after the controlled entity collides.
+const IMPACT_FLASH_TICKS: u32 = 20;
+
+const OBJECTIVE_MARKER_SIZE: f32 = 12.0;
+
+const PROMPT_HEIGHT: f32 = 12.0;
+
+const TARGET_MARKER_SIZE: f32 = 30.0;
+const LEAD_MARKER_SIZE: f32 = 8.0;
+
+/// Speed of projectiles, per tick, that the lead indicator aims for. Projectiles
+/// inherit velocity of the entity they are fired from.
+const PROJECTILE_SPEED: f32 = 8.0;
+const MAX_NAME_LEN: usize = 32;
+
+/// Length of thrust vectors in the build overlay, in entity units per unit of force.
+const THRUST_OVERLAY_SCALE: f32 = 4.0;
+
+/// Half-size of the center of mass marker in the build overlay, in pixels.
+const CENTER_OF_MASS_SIZE: f32 = 3.0;
+
+/// Half-length of the mirror axis drawn in build mode, in entity units.
+const MIRROR_AXIS_LENGTH: f32 = 200.0;
+
+/// Rotation applied to a dragged block per mouse wheel step.
+const BLOCK_ROTATION_STEP: f32 = std::f32::consts::PI / 12.0;
+
+/// Radius of the minimap, before UI scaling.
+const MINIMAP_RADIUS: f32 = 60.0;
+/// Distance from the controlled entity to the edge of the minimap, unless set otherwise.
+pub const DEFAULT_MINIMAP_RANGE: f32 = 2000.0;
+const MINIMAP_DOT_SIZE: f32 = 3.0;
+
 pub struct Hud {
     pub grid_trackers: Vec<GridRelation>,
 
     elements: Vec<HudElement>,
+    view_size: Vec2<f32>,
+    ui_scale: f32,
+    /// Virtual controls are shown once a touch screen is used.
+    touch_enabled: bool,
+    throttle: f32,
+    /// Whether user controls hold the heading of the controlled entity.
+    heading_hold: bool,
+    /// Whether user controls match velocity of a target entity.
+    velocity_match: bool,
+    /// Ticks left to flash the throttle gauge.
+    impact: u32,
+
+    build_mode: bool,
+    build_target: Option<BuildTarget>,
+    block_drag: Option<BlockDrag>,
+    /// Outline of a new hull, drawn in build mode after pressing O.
+    hull_draft: Option<HullDraft>,
+    /// Whether placed blocks and hull outlines get a twin mirrored across the
+    /// longitudinal axis of the entity. Toggled with X in build mode.
+    mirror: bool,
+
+    /// Name being typed for the blueprint, opened with Ctrl+S.
+    name_prompt: Option<String>,
+
+    /// Cargo of the controlled entity.
+    cargo: Option<Cargo>,
+    fuel: Option<Tank>,
+    health: Option<Health>,
+    resupply: Option<Resupply>,
+    oxygen: Option<Oxygen>,
+    /// Whether the controlled entity has blocks but no crewed cockpit to fly it.
+    control_lost: bool,
+    /// Acceleration of the controlled entity by gravity wells, per tick, as it appears
+    /// on the screen.
+    gravity: Vec2<f32>,
+    /// Locked target of the controlled entity.
+    target: Option<TargetReadout>,
+    /// Screen positions of entities that contracts of the controlled entity are about.
+    objectives: Vec<Vec2<f32>>,
+    /// Progress of the wave-survival run, if this is one.
+    survival: Option<Survival>,
+    /// Current step of the tutorial scenario, if this is one.
+    tutorial: Option<Step>,
+    minimap: Minimap,
+
+    action_queue: ActionQueue,
+}
+
+/// Snapshot of the controlled entity, used to pick blocks in build mode.
+struct BuildTarget {
+    entity_to_screen: Mat3,
+    screen_to_entity: Mat3,
+    /// Angle of the entity as it appears on the screen.
+    angle: f32,
+    zoom: f32,
+    blocks: Vec<Box<dyn Block>>,
+    /// Directions in which the design would spin when thrusting.
+    imbalances: Vec<Imbalance>,
+    /// Combined thrust at full throttle, for directions all around the entity.
+    envelope: Vec<Vec2<f32>>,
+}
+
+impl BuildTarget {
+    /// Reflects a screen position across the longitudinal axis of the entity.
+    fn mirror_point(&self, screen: Vec2<f32>) -> Vec2<f32> {
+        let local = (self.screen_to_entity * screen.into_homogeneous()).into_cartesian();
+        let mirrored = Vec2::new(-local.x, local.y);
+        (self.entity_to_screen * mirrored.into_homogeneous()).into_cartesian()
+    }
 
-    action_queue: Vec<Action>,
+    /// Twin of an entity about to be joined, with blocks and shape reflected across
+    /// the longitudinal axis of this entity.
+    fn mirror_entity(&self, entity: &Entity) -> Box<Entity> {
+        // rotation of the joined entity relative to this one
+        let relative = entity.angle.state - self.angle;
+        let reflection = Mat3::identity().scaled(Vec2::new(-1.0, 1.0));
+
+        let mut twin = Box::new(entity.clone());
+        twin.position.state = self.mirror_point(entity.position.state);
+        twin.angle.state = self.angle;
+        for block in &mut twin.blocks {
+            let offset = Mat3::rotation(relative) * block.offset().into_homogeneous();
+            block.set_offset((reflection * offset).into_cartesian());
+            block.set_angle(-(block.angle() + relative));
+        }
+        twin.set_shape(mirror_shape(
+            Mat3::rotation(relative) * entity.shape.clone(),
+        ));
+        twin
+    }
+}
+
+/// Reflects a polygon across the vertical axis, keeping its winding.
+fn mirror_polygon(polygon: Polygon) -> Polygon {
+    let mut mirrored = Mat3::identity().scaled(Vec2::new(-1.0, 1.0)) * polygon;
+    mirrored.points.reverse();
+    mirrored
+}
+
+fn mirror_shape(shape: Shape) -> Shape {
+    match shape {
+        Shape::Polygon(polygon) => mirror_polygon(polygon).into(),
+        round => Mat3::identity().scaled(Vec2::new(-1.0, 1.0)) * round,
+    }
+}
+
+/// Vertices of a hull outline placed by clicking, merged into the hull when applied
+/// or removed from it when cut.
+#[derive(Default)]
+struct HullDraft {
+    /// In coordinates of the entity, so the outline moves with it.
+    points: Vec<Vec2<f32>>,
+    /// Screen position of the cursor, where the next vertex would go.
+    cursor: Option<Vec2<f32>>,
+}
+
+impl HullDraft {
+    /// Outline wound the same way as hulls built from rectangles.
+    fn polygon(&self) -> Polygon {
+        Polygon::from(self.points.clone())
+    }
+}
+
+struct BlockDrag {
+    index: usize,
+    block: Box<dyn Block>,
+    /// From cursor to block origin, in screen coordinates.
+    grab_offset: Vec2<f32>,
+    screen_coordinates: Vec2<i32>,
+    /// Angle of the block as it appears on the screen.
+    angle: f32,
+    zoom: f32,
+    done: bool,
 }
 
 impl Hud {
-    pub fn new(view_size: Vec2<f32>) -> Hud {
-        Hud {
-            grid_trackers: Vec::new(),
-            elements: vec![
-                HudElement::new_toolbar_button(
-                    Vec2::new(0, -1),
-                    Entity::new_from_block(Box::from(Thruster::new(20.0, Vec2::default(), 0.0))),
-                    view_size,
-                ),
-                HudElement::new_toolbar_button(
-                    Vec2::new(1, -1),
-                    Entity::new_from_block(Box::from(Thruster::new(30.0, Vec2::default(), 0.0))),
-                    view_size,
-                ),
+    pub fn new(view_size: Vec2<f32>, ui_scale: f32, blocks: &BlockRegistry) -> Hud {
+        let mut elements: Vec<HudElement> = blocks
+            .toolbar()
+            .into_iter()
+            .enumerate()
+            .map(|(index, block)| {
                 HudElement::new_toolbar_button(
-                    Vec2::new(2, -1),
-                    Entity::new_from_block(Box::from(Thruster::new(40.0, Vec2::default(), 0.0))),
-                    view_size,
-                ),
-            ],
-            action_queue: Vec::new(),
-        }
+                    Vec2::new(index as i32, -1),
+                    Entity::new_from_block(block),
+                )
+            })
+            .collect();
+        elements.extend(vec![
+            HudElement::new_virtual_stick(Vec2::new(0, -5)),
+            HudElement::new_rotate_button(Vec2::new(-2, -3), 1.0),
+            HudElement::new_rotate_button(Vec2::new(-1, -3), -1.0),
+        ]);
+
+        let mut hud = Hud {
+            grid_trackers: Vec::new(),
+            elements,
+            view_size,
+            ui_scale,
+            touch_enabled: false,
+            throttle: 1.0,
+            heading_hold: false,
+            velocity_match: false,
+            impact: 0,
+            build_mode: false,
+            build_target: None,
+            block_drag: None,
+            hull_draft: None,
+            mirror: false,
+            name_prompt: None,
+            cargo: None,
+            fuel: None,
+            health: None,
+            resupply: None,
+            oxygen: None,
+            control_lost: false,
+            gravity: Vec2::default(),
+            target: None,
+            objectives: Vec::new(),
+            survival: None,
+            tutorial: None,
+            minimap: Minimap {
+                grids: Vec::new(),
+                entities: Vec::new(),
+                range: DEFAULT_MINIMAP_RANGE,
+            },
+            action_queue: ActionQueue::default(),
+        };
+        hud.layout();
+        hud
+    }
+
+    /// Sets the distance from the controlled entity to the edge of the minimap.
+    pub fn with_minimap_range(mut self, range: f32) -> Hud {
+        self.minimap.range = range;
+        self
     }
 
-    pub fn load_saved_entities(&mut self, view_size: Vec2<f32>) {
+    /// Replaces toolbar buttons of saved entities with the ones currently on disk.
+    pub fn load_saved_entities(&mut self, paths: &Paths) {
+        self.elements.retain(|e| !e.is_saved_entity());
+
         let mut index = 0;
-        for path in Entity::list_saved().unwrap_or_else(|_| vec![]) {
-            if let Ok(mut entity) = Entity::load_from_file(path) {
-                entity.redistribute_weight();
-                entity.position.state = Vec2::default();
-                self.elements.push(HudElement::new_toolbar_button(
-                    Vec2::new(index, -2),
-                    entity,
-                    view_size,
-                ));
+        for path in Entity::list_saved(paths).unwrap_or_else(|_| vec![]) {
+            match Entity::load_from_file(path.clone()) {
+                Ok(mut entity) => {
+                    entity.redistribute_weight();
+                    entity.position.state = Vec2::default();
+                    self.elements
+                        .push(HudElement::new_toolbar_button(Vec2::new(index, -2), entity));
+
+                    index += 1;
+                }
+                Err(e) => eprintln!("cannot load blueprint {:?}: {}", path, e),
+            }
+        }
+        self.layout();
+    }
+
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.ui_scale = ui_scale;
+        self.layout();
+    }
+
+    /// Lays elements out again, keeping them at their corners and edges of the screen.
+    pub fn set_view_size(&mut self, view_size: Vec2<f32>) {
+        self.view_size = view_size;
+        self.layout();
+    }
+
+    fn layout(&mut self) {
+        for element in &mut self.elements {
+            element.layout(self.view_size, self.ui_scale);
+        }
+    }
+
+    pub fn set_throttle(&mut self, throttle: f32) {
+        self.throttle = throttle;
+    }
+
+    pub fn set_assists(&mut self, heading_hold: bool, velocity_match: bool) {
+        self.heading_hold = heading_hold;
+        self.velocity_match = velocity_match;
+    }
 
-                index += 1;
+    /// Drops interactions bound to the previously controlled entity.
+    pub fn reset_focus(&mut self) {
+        self.build_target = None;
+        self.block_drag = None;
+        self.hull_draft = None;
+    }
+
+    pub fn handle_world_event(&mut self, event: &WorldEvent, focus: EntityId) {
+        if let WorldEvent::Collision { grid_id, entities } = event {
+            if *grid_id == focus.grid_id && entities.contains(&focus.entity_id) {
+                self.impact = IMPACT_FLASH_TICKS;
             }
         }
     }
 
-    pub fn poll_actions(&mut self) -> std::vec::Drain<'_, Action> {
-        self.action_queue.drain(..)
+    pub fn poll_actions(&mut self) -> Vec<QueuedAction> {
+        self.action_queue.poll()
+    }
+
+    pub fn set_tick(&mut self, tick: u64) {
+        self.action_queue.set_tick(tick);
+    }
+
+    pub fn is_typing(&self) -> bool {
+        self.name_prompt.is_some()
     }
 
     pub fn handle_event(&mut self, event: &Event) -> bool {
+        if self.handle_prompt_event(event) {
+            return true;
+        }
+
+        if let Some(touch) = Touch::from_event(event, self.view_size) {
+            self.touch_enabled = true;
+            return self
+                .elements
+                .iter_mut()
+                .any(|element| element.handle_touch(&touch));
+        }
+
+        if self.handle_build_event(event) {
+            return true;
+        }
         for element in &mut self.elements {
             if element.handle_event(event) {
                 return true;
@@ -76,25 +366,372 @@ impl Hud {
         false
     }
 
+    fn handle_prompt_event(&mut self, event: &Event) -> bool {
+        let name = match &mut self.name_prompt {
+            Some(name) => name,
+            None => {
+                if let Event::KeyDown {
+                    keycode: Some(Keycode::S),
+                    keymod,
+                    ..
+                } = event
+                {
+                    if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+                        self.name_prompt = Some(String::new());
+                        return true;
+                    }
+                }
+                return false;
+            }
+        };
+
+        match event {
+            Event::TextInput { text, .. } => {
+                if name.chars().count() < MAX_NAME_LEN {
+                    name.push_str(text);
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::Backspace => {
+                    name.pop();
+                }
+                Keycode::Escape => self.name_prompt = None,
+                Keycode::Return | Keycode::KpEnter => {
+                    let name = name.trim().to_owned();
+                    if !name.is_empty() {
+                        self.action_queue.push(Action::SaveEntityAs { name });
+                    }
+                    self.name_prompt = None;
+                }
+                _ => {}
+            },
+            Event::KeyUp { .. } => {}
+            _ => return false,
+        }
+        true
+    }
+
+    fn handle_build_event(&mut self, event: &Event) -> bool {
+        if let Event::KeyDown {
+            keycode: Some(Keycode::B),
+            repeat: false,
+            ..
+        } = event
+        {
+            self.build_mode = !self.build_mode;
+            self.block_drag = None;
+            self.hull_draft = None;
+            return true;
+        }
+        if !self.build_mode {
+            return false;
+        }
+
+        if let Event::KeyDown {
+            keycode: Some(Keycode::X),
+            repeat: false,
+            ..
+        } = event
+        {
+            self.mirror = !self.mirror;
+            return true;
+        }
+
+        // shrink-wraps the hull, while no outline is being drawn
+        if let Event::KeyDown {
+            keycode: Some(Keycode::K),
+            repeat: false,
+            ..
+        } = event
+        {
+            if self.hull_draft.is_none() {
+                self.action_queue.push(Action::ShrinkWrapHull);
+                return true;
+            }
+        }
+
+        if let Event::KeyDown {
+            keycode: Some(Keycode::O),
+            repeat: false,
+            ..
+        } = event
+        {
+            self.hull_draft = match self.hull_draft {
+                Some(_) => None,
+                None => Some(HullDraft::default()),
+            };
+            self.block_drag = None;
+            return true;
+        }
+        if self.hull_draft.is_some() {
+            return self.handle_hull_event(event);
+        }
+
+        if let (
+            Some(drag),
+            Event::KeyDown {
+                keycode: Some(Keycode::Delete),
+                ..
+            },
+        ) = (&self.block_drag, event)
+        {
+            self.action_queue
+                .push(Action::RemoveBlock { index: drag.index });
+            self.block_drag = None;
+            return true;
+        }
+
+        if let Some(drag) = &mut self.block_drag {
+            match event {
+                Event::MouseMotion { x, y, .. } => {
+                    drag.screen_coordinates = Vec2::new(*x, *y);
+                    true
+                }
+                Event::MouseWheel { y, .. } => {
+                    drag.angle += *y as f32 * BLOCK_ROTATION_STEP;
+                    true
+                }
+                Event::MouseButtonUp { x, y, .. } => {
+                    drag.screen_coordinates = Vec2::new(*x, *y);
+                    drag.done = true;
+                    true
+                }
+                _ => false,
+            }
+        } else if let Event::MouseButtonDown { x, y, .. } = event {
+            self.pick_block(Vec2::new(*x, *y))
+        } else {
+            false
+        }
+    }
+
+    fn handle_hull_event(&mut self, event: &Event) -> bool {
+        let draft = match &mut self.hull_draft {
+            Some(draft) => draft,
+            None => return false,
+        };
+        match event {
+            Event::MouseMotion { x, y, .. } => {
+                draft.cursor = Some(from_int(Vec2::new(*x, *y)));
+            }
+            Event::MouseButtonDown { x, y, .. } => {
+                if let Some(target) = &self.build_target {
+                    let cursor = from_int(Vec2::new(*x, *y));
+                    let point = target.screen_to_entity * cursor.into_homogeneous();
+                    draft.points.push(point.into_cartesian());
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(keycode),
+                keymod,
+                ..
+            } => match keycode {
+                Keycode::Backspace => {
+                    draft.points.pop();
+                }
+                Keycode::Escape => self.hull_draft = None,
+                Keycode::Return | Keycode::KpEnter => {
+                    let polygon = draft.polygon();
+                    let cut = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                    if let (true, Some(target)) = (polygon.is_simple(), &self.build_target) {
+                        // sent in screen coordinates, like moved blocks
+                        let action = |shape: Polygon| {
+                            let shape = Box::new(target.entity_to_screen * shape);
+                            if cut {
+                                Action::CutShape { cut: shape }
+                            } else {
+                                Action::UpdateShape { new_shape: shape }
+                            }
+                        };
+                        if self.mirror {
+                            let twin = mirror_polygon(polygon.clone());
+                            self.action_queue.push(action(twin));
+                        }
+                        self.action_queue.push(action(polygon));
+                        self.hull_draft = None;
+                    }
+                }
+                _ => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    fn pick_block(&mut self, coordinates: Vec2<i32>) -> bool {
+        let target = match &self.build_target {
+            Some(target) => target,
+            None => return false,
+        };
+        let cursor = from_int(coordinates);
+
+        for (index, block) in target.blocks.iter().enumerate() {
+            let shape = target.entity_to_screen * block.transform() * block.shape().clone();
+            if shape.contains_point(cursor) {
+                let origin =
+                    (target.entity_to_screen * block.offset().into_homogeneous()).into_cartesian();
+
+                self.block_drag = Some(BlockDrag {
+                    index,
+                    block: block.clone(),
+                    grab_offset: origin - cursor,
+                    screen_coordinates: coordinates,
+                    angle: target.angle + block.angle(),
+                    zoom: target.zoom,
+                    done: false,
+                });
+                return true;
+            }
+        }
+        false
+    }
+
     /// Pull data from & push actions to grids
-    pub fn tick(&mut self, world: &mut World, focus: EntityId) {
-        self.update_trackers(world, focus);
+    pub fn tick(&mut self, world: &mut World, view: &View, focus: EntityId) {
+        self.impact = self.impact.saturating_sub(1);
+        self.update_trackers(world, view, focus);
+        self.update_minimap(world, view, focus);
+        self.update_build_target(world, view, focus);
+        self.cargo = world.components.cargo.get(focus.entity_id).copied();
+        self.fuel = world.components.fuel.get(focus.entity_id).copied();
+        self.health = world.components.health.get(focus.entity_id).copied();
+        self.resupply = world.components.resupply.get(focus.entity_id).copied();
+        self.oxygen = world.components.oxygen.get(focus.entity_id).copied();
+        self.control_lost = world
+            .get_entity(&focus)
+            .is_some_and(|entity| !entity.blocks.is_empty())
+            && !life_support::can_control(world, &focus);
+        self.gravity = view.grid_to_screen_direction(gravity::acceleration_on(world, &focus));
+        self.update_objectives(world, view, focus);
+        self.survival = world.survival.clone();
+        self.tutorial = world.tutorial.as_ref().map(|tutorial| tutorial.step);
+
+        if let Some(BlockDrag { done: true, .. }) = self.block_drag {
+            let drag = self.block_drag.take().unwrap();
+
+            self.action_queue.push(Action::MoveBlock {
+                index: drag.index,
+                offset: from_int(drag.screen_coordinates) + drag.grab_offset,
+                angle: drag.angle,
+            });
+        }
 
         for element in &mut self.elements {
-            let mut actions = element.tick();
+            for action in element.tick() {
+                let twin = match (&action, &self.build_target) {
+                    (Action::JoinEntity { entity }, Some(target)) if self.mirror => {
+                        Some(target.mirror_entity(entity))
+                    }
+                    _ => None,
+                };
+                self.action_queue.push(action);
+                if let Some(entity) = twin {
+                    self.action_queue.push(Action::JoinEntity { entity });
+                }
+            }
+        }
+    }
 
-            self.action_queue.extend(actions.drain(..));
+    fn update_build_target(&mut self, world: &World, view: &View, focus: EntityId) {
+        if !self.build_mode {
+            self.build_target = None;
+            return;
         }
+
+        self.build_target = world
+            .grids
+            .get(&focus.grid_id)
+            .and_then(|grid| grid.get_entity(focus.entity_id))
+            .map(|entity| {
+                let entity_to_screen = view.last_grid_to_screen * entity.projection_to_grid();
+                BuildTarget {
+                    entity_to_screen,
+                    screen_to_entity: entity_to_screen.invert().unwrap_or_default(),
+                    angle: entity.angle.state - view.rotation,
+                    zoom: view.zoom,
+                    blocks: entity.blocks.clone(),
+                    imbalances: balance::analyze(entity),
+                    envelope: balance::thrust_envelope(&entity.blocks),
+                }
+            });
+    }
+
+    fn update_objectives(&mut self, world: &World, view: &View, focus: EntityId) {
+        let contracts = world.components.contracts.get(focus.entity_id);
+        self.objectives = contracts
+            .into_iter()
+            .flatten()
+            .filter_map(|contract| missions::position_in(world, focus.grid_id, contract.target()))
+            .map(|position| view.grid_to_screen(position))
+            .collect();
     }
 
-    pub fn update_trackers(&mut self, world: &World, focus: EntityId) {
+    pub fn update_target(
+        &mut self,
+        world: &World,
+        view: &View,
+        focus: EntityId,
+        target: Option<EntityId>,
+    ) {
+        self.target = target.and_then(|target| {
+            let relative = world.relative_insist(&focus, &target)?;
+            let position = world.get_entity(&focus)?.position.state;
+            let to_screen = |offset: Vec2<f32>| view.grid_to_screen(position + offset);
+
+            let distance = relative.state.length();
+            let closing = if distance > 0.0 {
+                -relative.state.dot(relative.velocity) / distance
+            } else {
+                0.0
+            };
+            let lead = intercept_time(relative.state, relative.velocity, PROJECTILE_SPEED)
+                .map(|time| to_screen(relative.state + relative.velocity * time));
+            Some(TargetReadout {
+                screen: to_screen(relative.state),
+                lead,
+                distance,
+                closing,
+            })
+        });
+    }
+
+    pub fn update_trackers(&mut self, world: &World, view: &View, focus: EntityId) {
         self.grid_trackers = world.get_relations(focus.grid_id, Insist::default());
+        for tracker in &mut self.grid_trackers {
+            tracker.position.state = view.grid_to_screen_direction(tracker.position.state);
+            tracker.position.velocity = view.grid_to_screen_direction(tracker.position.velocity);
+        }
     }
 
-    pub fn render<T: RenderTarget>(&self, canvas: &mut Canvas<T>) {
-        canvas.set_draw_color(Color::RGB(128, 128, 172));
-        let center = into_vec(canvas.viewport().center());
-        let padding = TRACKER_PADDING as f32 * 2.0;
+    fn update_minimap(&mut self, world: &World, view: &View, focus: EntityId) {
+        self.minimap.grids.clear();
+        self.minimap.entities.clear();
+        let center = match world.get_entity(&focus) {
+            Some(entity) => entity.position.state,
+            None => return,
+        };
+        let to_map = |position: Vec2<f32>| view.grid_to_screen_direction(position - center);
+        for relation in world.get_relations(focus.grid_id, Insist::default()) {
+            if relation.id != focus.grid_id {
+                self.minimap.grids.push(to_map(relation.position.state));
+            }
+        }
+        if let Some(grid) = world.grids.get(&focus.grid_id) {
+            for entity in &grid.entities {
+                if entity.get_id() != focus.entity_id {
+                    self.minimap.entities.push(to_map(entity.position.state));
+                }
+            }
+        }
+    }
+
+    pub fn render(&self, canvas: &mut dyn DrawTarget) {
+        canvas.set_draw_color(Color::rgb(128, 128, 172));
+        let center = canvas.center();
+        let padding = TRACKER_PADDING as f32 * self.ui_scale * 2.0;
 
         let poly = construct_rect_poly_centered(2.0 * center.x - padding, 2.0 * center.y - padding);
 
@@ -107,6 +744,7 @@ impl Hud {
                 let size = (20.0 + tracker.position.state.length() / -1000.0)
                     .min(15.0)
                     .max(2.0);
+                let size = size * self.ui_scale;
                 let rect = construct_rect_poly_centered(size, size);
                 rect.render(position, canvas);
 
@@ -115,12 +753,432 @@ impl Hud {
         }
 
         for element in &self.elements {
+            if element.is_touch_control() && !self.touch_enabled {
+                continue;
+            }
             element.draw(canvas);
         }
+
+        self.render_throttle_gauge(canvas);
+        self.render_build_mode(canvas);
+        self.render_name_prompt(canvas);
+        self.render_cargo(canvas);
+        self.render_supply(canvas);
+        self.render_gravity(canvas);
+        self.render_objectives(canvas);
+        self.render_target(canvas);
+        self.render_survival(canvas);
+        self.render_tutorial(canvas);
+        self.render_minimap(canvas);
+    }
+
+    /// Round inset in the bottom right corner, above the touch controls, with the
+    /// controlled entity in the middle. Grids out of range stay on the rim.
+    fn render_minimap(&self, canvas: &mut dyn DrawTarget) {
+        let radius = MINIMAP_RADIUS * self.ui_scale;
+        let rows = 3.0 * (HUD_ELEMENT_SIZE + HUD_ELEMENT_SPACING) as f32 * self.ui_scale;
+        let padding = TRACKER_PADDING as f32 * self.ui_scale;
+        let center = translation(self.view_size - Vec2::new(padding, rows) - Vec2::from(radius));
+        let scale = radius / self.minimap.range;
+        let dot = construct_rect_poly_centered(MINIMAP_DOT_SIZE, MINIMAP_DOT_SIZE);
+        let dot = Mat3::identity().scaled(self.ui_scale.into()) * dot;
+
+        canvas.set_draw_color(Color::rgb(60, 60, 90));
+        Circle::new(Vec2::default(), radius).render(center, canvas);
+
+        canvas.set_draw_color(Color::rgb(128, 128, 172));
+        for offset in &self.minimap.grids {
+            let mut on_map = *offset * scale;
+            if on_map.length() > radius {
+                on_map = on_map.normalized() * radius;
+            }
+            dot.render(center * translation(on_map), canvas);
+        }
+
+        canvas.set_draw_color(Color::rgb(200, 200, 200));
+        for offset in &self.minimap.entities {
+            let on_map = *offset * scale;
+            if on_map.length() <= radius {
+                dot.render(center * translation(on_map), canvas);
+            }
+        }
+
+        canvas.set_draw_color(Color::rgb(128, 200, 128));
+        dot.render(center, canvas);
+    }
+
+    /// Prompt of the current tutorial step, at the bottom of the screen.
+    fn render_tutorial(&self, canvas: &mut dyn DrawTarget) {
+        let step = match &self.tutorial {
+            Some(step) => step,
+            None => return,
+        };
+        let height = 2.0 * PROMPT_HEIGHT * self.ui_scale;
+        let prompt = step.prompt();
+        let left = (self.view_size.x - prompt.len() as f32 * Text::advance(height)) * 0.5;
+        let top = self.view_size.y * 0.75;
+        canvas.set_draw_color(Color::rgb(200, 200, 60));
+        Text::new(prompt, height).render(translation(Vec2::new(left, top)), canvas);
+    }
+
+    /// Wave and score during a survival run, and a summary once it is over.
+    fn render_survival(&self, canvas: &mut dyn DrawTarget) {
+        let survival = match &self.survival {
+            Some(survival) => survival,
+            None => return,
+        };
+        let padding = TRACKER_PADDING as f32 * self.ui_scale;
+        let height = PROMPT_HEIGHT * self.ui_scale;
+
+        if !survival.over {
+            canvas.set_draw_color(Color::rgb(220, 60, 60));
+            Text::new(
+                &format!("wave {}  score {}", survival.wave, survival.score),
+                height,
+            )
+            .render(
+                translation(Vec2::new(padding, padding + 4.0 * height)),
+                canvas,
+            );
+            return;
+        }
+
+        let lines = [
+            "all ships destroyed".to_owned(),
+            format!("reached wave {}", survival.wave),
+            format!("destroyed {} raiders", survival.destroyed),
+            format!("survived {} seconds", survival.ticks / 60),
+            format!("score {}", survival.score),
+        ];
+        let height = 2.0 * height;
+        let mut top = self.view_size.y * 0.5 - lines.len() as f32 * height;
+        canvas.set_draw_color(Color::rgb(220, 60, 60));
+        for line in &lines {
+            Text::new(line, height).render(
+                translation(Vec2::new(self.view_size.x * 0.5 - 10.0 * height, top)),
+                canvas,
+            );
+            top += 2.0 * height;
+        }
+    }
+
+    /// Brackets around the locked target with its distance and closing speed, and
+    /// a circle where to aim so projectiles hit it.
+    fn render_target(&self, canvas: &mut dyn DrawTarget) {
+        let target = match &self.target {
+            Some(target) => target,
+            None => return,
+        };
+        let size = TARGET_MARKER_SIZE * self.ui_scale;
+        let corner = size * 0.3;
+        canvas.set_draw_color(Color::rgb(220, 60, 60));
+        for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let tip = target.screen + Vec2::new(x, y) * size * 0.5;
+            canvas.draw_line(tip, tip - Vec2::new(x * corner, 0.0));
+            canvas.draw_line(tip, tip - Vec2::new(0.0, y * corner));
+        }
+
+        let height = PROMPT_HEIGHT * self.ui_scale;
+        // speeds are per tick, shown per second
+        Text::new(
+            &format!(
+                "{:.0}  closing {:.0}/s",
+                target.distance,
+                target.closing * 60.0
+            ),
+            height,
+        )
+        .render(
+            translation(target.screen + Vec2::new(-size * 0.5, size * 0.5 + height)),
+            canvas,
+        );
+
+        if let Some(lead) = target.lead {
+            let size = LEAD_MARKER_SIZE * self.ui_scale;
+            let points: Vec<Vec2<f32>> = (0..8)
+                .map(|i| {
+                    let angle = i as f32 / 8.0 * std::f32::consts::PI * 2.0;
+                    lead + Vec2::new(angle.cos(), angle.sin()) * size * 0.5
+                })
+                .collect();
+            for i in 0..points.len() {
+                canvas.draw_line(points[i], points[(i + 1) % points.len()]);
+            }
+        }
+    }
+
+    /// Diamonds over contract targets, kept at the edge of the screen when off it.
+    fn render_objectives(&self, canvas: &mut dyn DrawTarget) {
+        let padding = TRACKER_PADDING as f32 * self.ui_scale;
+        let size = OBJECTIVE_MARKER_SIZE * self.ui_scale;
+        let diamond = construct_rect_poly_centered(size, size);
+
+        canvas.set_draw_color(Color::rgb(200, 200, 60));
+        for objective in &self.objectives {
+            let position = Vec2::new(
+                objective.x.clamp(padding, self.view_size.x - padding),
+                objective.y.clamp(padding, self.view_size.y - padding),
+            );
+            diamond.render(
+                translation(position) * Mat3::rotation(std::f32::consts::FRAC_PI_4),
+                canvas,
+            );
+        }
+    }
+
+    fn render_name_prompt(&self, canvas: &mut dyn DrawTarget) {
+        if let Some(name) = &self.name_prompt {
+            let padding = TRACKER_PADDING as f32 * self.ui_scale;
+            canvas.set_draw_color(Color::rgb(200, 200, 60));
+            Text::new(
+                &format!("save blueprint as: {}_", name),
+                PROMPT_HEIGHT * self.ui_scale,
+            )
+            .render(translation(Vec2::new(padding, padding)), canvas);
+        }
+    }
+
+    fn render_cargo(&self, canvas: &mut dyn DrawTarget) {
+        if let Some(cargo) = &self.cargo {
+            let padding = TRACKER_PADDING as f32 * self.ui_scale;
+            let height = PROMPT_HEIGHT * self.ui_scale;
+            canvas.set_draw_color(Color::rgb(128, 200, 128));
+            Text::new(
+                &format!("ore {:.0}  material {:.0}", cargo.ore, cargo.material),
+                height,
+            )
+            .render(
+                translation(Vec2::new(padding, padding + 2.0 * height)),
+                canvas,
+            );
+        }
+    }
+
+    /// Fuel and health of the controlled entity, and what it receives while docked.
+    fn render_supply(&self, canvas: &mut dyn DrawTarget) {
+        let mut status = Vec::new();
+        if let Some(tank) = &self.fuel {
+            status.push(format!("fuel {:.0}/{:.0}", tank.fuel, tank.capacity));
+        }
+        if let Some(health) = &self.health {
+            status.push(format!(
+                "health {:.0}/{:.0}",
+                health.points, health.max_points
+            ));
+        }
+        if let Some(oxygen) = &self.oxygen {
+            status.push(format!(
+                "oxygen {:.0}%",
+                oxygen.amount / oxygen.capacity * 100.0
+            ));
+        }
+        let mut lines = vec![status.join("  ")];
+        if self.control_lost {
+            lines.push("no crew at the controls".to_owned());
+        }
+        if let Some(resupply) = &self.resupply {
+            // rates are per tick, shown per second
+            let mut rates = Vec::new();
+            if resupply.fuel > 0.0 {
+                rates.push(format!("refueling +{:.0}/s", resupply.fuel * 60.0));
+            }
+            if resupply.repair > 0.0 {
+                rates.push(format!("repairing +{:.0}/s", resupply.repair * 60.0));
+            }
+            lines.push(rates.join("  "));
+        }
+
+        let padding = TRACKER_PADDING as f32 * self.ui_scale;
+        let height = PROMPT_HEIGHT * self.ui_scale;
+        let mut top = padding + 6.0 * height;
+        canvas.set_draw_color(Color::rgb(128, 200, 128));
+        for line in lines.iter().filter(|line| !line.is_empty()) {
+            Text::new(line, height).render(translation(Vec2::new(padding, top)), canvas);
+            top += 2.0 * height;
+        }
+    }
+
+    /// Arrow under the throttle gauge pointing where gravity pulls the controlled entity,
+    /// with the acceleration per second.
+    fn render_gravity(&self, canvas: &mut dyn DrawTarget) {
+        let strength = self.gravity.length();
+        if strength <= f32::EPSILON {
+            return;
+        }
+        let height = PROMPT_HEIGHT * self.ui_scale;
+        let length = GRAVITY_ARROW_LENGTH * self.ui_scale;
+        let center = Vec2::new(
+            self.view_size.x - TRACKER_PADDING as f32 * self.ui_scale - length,
+            (self.view_size.y + THROTTLE_GAUGE_SIZE.y * self.ui_scale) * 0.5 + 8.0 * height,
+        );
+
+        canvas.set_draw_color(Color::rgb(160, 110, 220));
+        (self.gravity * (length / strength)).render(translation(center), canvas);
+        // acceleration is per tick, shown per second
+        Text::new(&format!("g {:.1}", strength * 60.0), height).render(
+            translation(center + Vec2::new(-length, length + height)),
+            canvas,
+        );
+    }
+
+    fn render_throttle_gauge(&self, canvas: &mut dyn DrawTarget) {
+        let size = THROTTLE_GAUGE_SIZE * self.ui_scale;
+        let margin = 2.0 * self.ui_scale;
+        let position = translation(Vec2::new(
+            self.view_size.x - TRACKER_PADDING as f32 * self.ui_scale - size.x,
+            (self.view_size.y - size.y) * 0.5,
+        ));
+
+        if self.impact > 0 {
+            canvas.set_draw_color(Color::rgb(220, 60, 60));
+        } else {
+            canvas.set_draw_color(Color::rgb(128, 128, 172));
+        }
+        construct_rect_poly(0.0, size.x, 0.0, size.y).render(position, canvas);
+
+        canvas.set_draw_color(Color::rgb(200, 120, 60));
+        let level = size.y * (1.0 - self.throttle);
+        construct_rect_poly(margin, size.x - margin, level + margin, size.y - margin)
+            .render(position, canvas);
+
+        let height = PROMPT_HEIGHT * self.ui_scale;
+        let assists = [(self.heading_hold, "hold"), (self.velocity_match, "match")];
+        let mut top = size.y + height;
+        canvas.set_draw_color(Color::rgb(128, 200, 128));
+        for (_, label) in assists.iter().filter(|(on, _)| *on) {
+            Text::new(label, height).render(position * translation(Vec2::new(0.0, top)), canvas);
+            top += 2.0 * height;
+        }
+    }
+
+    /// Center of mass, thrust of each thruster and the combined thrust envelope.
+    fn render_thrust_overlay(&self, target: &BuildTarget, canvas: &mut dyn DrawTarget) {
+        let scale = Mat3::identity().scaled(Vec2::new(THRUST_OVERLAY_SCALE, THRUST_OVERLAY_SCALE));
+
+        canvas.set_draw_color(Color::rgb(200, 120, 60));
+        for block in &target.blocks {
+            let thrust = Mat2::rotation(block.angle()) * block.thrust();
+            if thrust.length() > 0.0 {
+                thrust.render(
+                    target.entity_to_screen * translation(block.offset()) * scale,
+                    canvas,
+                );
+            }
+        }
+
+        if target.envelope.iter().any(|force| force.length() > 0.0) {
+            canvas.set_draw_color(Color::rgb(60, 160, 200));
+            // without throttle there is no thrust, so the envelope always holds the origin
+            let mut points = target.envelope.clone();
+            points.push(Vec2::default());
+            construct_convex_hull(&points).render(target.entity_to_screen * scale, canvas);
+        }
+
+        // entities keep their origin at the center of mass
+        canvas.set_draw_color(Color::rgb(220, 220, 220));
+        let size = 2.0 * CENTER_OF_MASS_SIZE / target.zoom;
+        construct_rect_poly_centered(size, size).render(target.entity_to_screen, canvas);
+    }
+
+    fn render_mirror_axis(&self, canvas: &mut dyn DrawTarget) {
+        let target = match &self.build_target {
+            Some(target) if self.mirror => target,
+            _ => return,
+        };
+        let to_screen = |y: f32| {
+            (target.entity_to_screen * Vec2::new(0.0, y).into_homogeneous()).into_cartesian()
+        };
+        canvas.set_draw_color(Color::rgb(60, 160, 200));
+        canvas.draw_line(
+            to_screen(-MIRROR_AXIS_LENGTH),
+            to_screen(MIRROR_AXIS_LENGTH),
+        );
+    }
+
+    /// Outline being drawn, red while it cannot be applied, and how to continue.
+    fn render_hull_draft(&self, canvas: &mut dyn DrawTarget) {
+        let (draft, target) = match (&self.hull_draft, &self.build_target) {
+            (Some(draft), Some(target)) => (draft, target),
+            _ => return,
+        };
+        let polygon = draft.polygon();
+        let valid = polygon.is_simple();
+        let points: Vec<Vec2<f32>> = draft
+            .points
+            .iter()
+            .map(|p| (target.entity_to_screen * p.into_homogeneous()).into_cartesian())
+            .collect();
+
+        if valid {
+            canvas.set_draw_color(Color::rgb(60, 200, 60));
+        } else {
+            canvas.set_draw_color(Color::rgb(220, 60, 60));
+        }
+        for pair in points.windows(2) {
+            canvas.draw_line(pair[0], pair[1]);
+        }
+        if let (Some(first), Some(last)) = (points.first(), points.last()) {
+            canvas.draw_line(*last, *first);
+            if let Some(cursor) = draft.cursor {
+                canvas.set_draw_color(Color::rgb(128, 128, 172));
+                canvas.draw_line(*last, cursor);
+            }
+        }
+
+        let prompt = if valid {
+            "click to add vertices, enter to apply, shift enter to cut"
+        } else if draft.points.len() < 3 {
+            "click to place hull vertices"
+        } else {
+            "hull outline crosses itself"
+        };
+        let padding = TRACKER_PADDING as f32 * self.ui_scale;
+        let height = PROMPT_HEIGHT * self.ui_scale;
+        canvas.set_draw_color(Color::rgb(128, 200, 128));
+        Text::new(prompt, height).render(
+            translation(Vec2::new(padding, self.view_size.y * 0.5 - 2.0 * height)),
+            canvas,
+        );
+    }
+
+    fn render_build_mode(&self, canvas: &mut dyn DrawTarget) {
+        if let Some(target) = &self.build_target {
+            canvas.set_draw_color(Color::rgb(200, 200, 60));
+            for block in &target.blocks {
+                block
+                    .shape()
+                    .render(target.entity_to_screen * block.transform(), canvas);
+            }
+
+            self.render_thrust_overlay(target, canvas);
+
+            let padding = TRACKER_PADDING as f32 * self.ui_scale;
+            let height = PROMPT_HEIGHT * self.ui_scale;
+            let mut top = self.view_size.y * 0.5;
+            canvas.set_draw_color(Color::rgb(220, 60, 60));
+            for imbalance in &target.imbalances {
+                Text::new(&imbalance.warning(), height)
+                    .render(translation(Vec2::new(padding, top)), canvas);
+                top += 2.0 * height;
+            }
+        }
+
+        self.render_hull_draft(canvas);
+        self.render_mirror_axis(canvas);
+
+        if let Some(drag) = &self.block_drag {
+            canvas.set_draw_color(Color::rgb(60, 200, 60));
+            let position = from_int(drag.screen_coordinates) + drag.grab_offset;
+            let zoom = Mat3::identity().scaled(drag.zoom.into());
+            drag.block.shape().render(
+                translation(position) * Mat3::rotation(drag.angle) * zoom,
+                canvas,
+            );
+        }
     }
 }
 
-trait UIElement<T: RenderTarget>: Render<T> {
+trait UIElement: Render {
     // fn click(location: Vec<i32>, controls: UserControls);
 
     // fn tick(controls: EventHandler) {
@@ -134,13 +1192,61 @@ trait UIElement<T: RenderTarget>: Render<T> {
 }
 
 struct HudElement {
+    slot: Vec2<i32>,
+    /// Number of slots taken in each direction
+    span: i32,
     position: Vec2<i32>,
     shape: Polygon,
     variant: HudElementVariant,
     dragging: bool,
 }
 
+/// Positions shown in the minimap, relative to the controlled entity and turned as they
+/// appear on the screen.
+struct Minimap {
+    grids: Vec<Vec2<f32>>,
+    /// Of the grid of the controlled entity.
+    entities: Vec<Vec2<f32>>,
+    /// Distance from the controlled entity to the edge of the map.
+    range: f32,
+}
+
+/// What the HUD shows about the locked target.
+struct TargetReadout {
+    screen: Vec2<f32>,
+    /// Screen position to aim at, if projectiles can reach the target.
+    lead: Option<Vec2<f32>>,
+    distance: f32,
+    /// Speed at which the target approaches, negative when it moves away.
+    closing: f32,
+}
+
+/// Earliest time at which a projectile of `speed` meets a target at `position` moving
+/// with `velocity`, both relative to the shooter.
+fn intercept_time(position: Vec2<f32>, velocity: Vec2<f32>, speed: f32) -> Option<f32> {
+    // |position + velocity * t| = speed * t
+    let a = velocity.dot(velocity) - speed * speed;
+    let b = 2.0 * position.dot(velocity);
+    let c = position.dot(position);
+    if a.abs() < f32::EPSILON {
+        return Some(-c / b).filter(|t| *t > 0.0);
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+        .iter()
+        .copied()
+        .filter(|t| *t > 0.0)
+        .fold(None, |earliest: Option<f32>, t| {
+            Some(earliest.map_or(t, |e| e.min(t)))
+        })
+}
+
 const HUD_ELEMENT_SIZE: i32 = 40;
+const HUD_ELEMENT_SPACING: i32 = 10;
 
 enum HudElementVariant {
     ToolbarButton {
@@ -148,6 +1254,51 @@ enum HudElementVariant {
         scale: f32,
         ghost: Option<Ghost>,
     },
+    VirtualStick {
+        finger: Option<i64>,
+        direction: Vec2<f32>,
+        changed: bool,
+    },
+    RotateButton {
+        direction: f32,
+        finger: Option<i64>,
+        changed: bool,
+    },
+}
+
+enum TouchPhase {
+    Down,
+    Move,
+    Up,
+}
+
+struct Touch {
+    finger: i64,
+    phase: TouchPhase,
+    /// Screen coordinates
+    position: Vec2<f32>,
+}
+
+impl Touch {
+    fn from_event(event: &Event, view_size: Vec2<f32>) -> Option<Touch> {
+        let (finger, phase, x, y) = match *event {
+            Event::FingerDown {
+                finger_id, x, y, ..
+            } => (finger_id, TouchPhase::Down, x, y),
+            Event::FingerMotion {
+                finger_id, x, y, ..
+            } => (finger_id, TouchPhase::Move, x, y),
+            Event::FingerUp {
+                finger_id, x, y, ..
+            } => (finger_id, TouchPhase::Up, x, y),
+            _ => return None,
+        };
+        Some(Touch {
+            finger,
+            phase,
+            position: Vec2::new(x * view_size.x, y * view_size.y),
+        })
+    }
 }
 
 struct Ghost {
@@ -165,32 +1316,100 @@ impl Ghost {
 }
 
 impl HudElement {
-    fn new_toolbar_button(slot: Vec2<i32>, entity: Entity, view_size: Vec2<f32>) -> HudElement {
-        let position = from_float(modulo(
-            &from_int(Vec2::new(5, 5) + slot * (HUD_ELEMENT_SIZE + 10)),
-            &view_size,
-        ));
-        let shape = construct_rect_poly(0.0, HUD_ELEMENT_SIZE as f32, 0.0, HUD_ELEMENT_SIZE as f32);
+    fn new(slot: Vec2<i32>, span: i32, variant: HudElementVariant) -> HudElement {
+        HudElement {
+            slot,
+            span,
+            position: Vec2::default(),
+            shape: Polygon { points: Vec::new() },
+            variant,
+            dragging: false,
+        }
+    }
 
-        let bb = entity.shape.bounding_box();
-        let diagonal = bb.bottom_right - bb.top_left;
-        let max_dimen = diagonal.x.max(diagonal.y);
-        let scale_factor = (0.01 * max_dimen + 0.2).min(0.9).max(0.6);
-        let scale = scale_factor * (HUD_ELEMENT_SIZE as f32) / max_dimen;
+    /// Saved entities are in the second row of the toolbar.
+    fn is_saved_entity(&self) -> bool {
+        matches!(self.variant, HudElementVariant::ToolbarButton { .. }) && self.slot.y == -2
+    }
 
-        HudElement {
-            position,
-            shape,
-            variant: HudElementVariant::ToolbarButton {
+    fn new_toolbar_button(slot: Vec2<i32>, entity: Entity) -> HudElement {
+        HudElement::new(
+            slot,
+            1,
+            HudElementVariant::ToolbarButton {
                 entity: Box::from(entity),
-                scale,
+                scale: 1.0,
                 ghost: None,
             },
-            dragging: false,
+        )
+    }
+
+    /// Stick spans three toolbar slots in each direction.
+    fn new_virtual_stick(slot: Vec2<i32>) -> HudElement {
+        HudElement::new(
+            slot,
+            3,
+            HudElementVariant::VirtualStick {
+                finger: None,
+                direction: Vec2::default(),
+                changed: false,
+            },
+        )
+    }
+
+    fn new_rotate_button(slot: Vec2<i32>, direction: f32) -> HudElement {
+        HudElement::new(
+            slot,
+            1,
+            HudElementVariant::RotateButton {
+                direction,
+                finger: None,
+                changed: false,
+            },
+        )
+    }
+
+    /// Positions the element according to its slot. Negative slots are counted from
+    /// the right or bottom edge of the view.
+    fn layout(&mut self, view_size: Vec2<f32>, ui_scale: f32) {
+        let cell = HUD_ELEMENT_SIZE as f32 * ui_scale;
+        let spacing = HUD_ELEMENT_SPACING as f32 * ui_scale;
+
+        self.position = from_float(modulo(
+            &(Vec2::from(spacing * 0.5) + from_int(self.slot) * (cell + spacing)),
+            &view_size,
+        ));
+
+        let size = cell * self.span as f32 + spacing * (self.span - 1) as f32;
+        self.shape = construct_rect_poly(0.0, size, 0.0, size);
+
+        if let HudElementVariant::ToolbarButton { entity, scale, .. } = &mut self.variant {
+            let bb = entity.shape.bounding_box();
+            let diagonal = bb.bottom_right - bb.top_left;
+            let max_dimen = diagonal.x.max(diagonal.y);
+            let scale_factor = (0.01 * max_dimen + 0.2).min(0.9).max(0.6);
+            *scale = scale_factor * size / max_dimen;
+        }
+    }
+
+    fn size(&self) -> f32 {
+        let bb = self.shape.bounding_box();
+        bb.bottom_right.x - bb.top_left.x
+    }
+
+    fn is_touch_control(&self) -> bool {
+        match self.variant {
+            HudElementVariant::ToolbarButton { .. } => false,
+            HudElementVariant::VirtualStick { .. } | HudElementVariant::RotateButton { .. } => true,
         }
     }
 
-    fn draw<T: RenderTarget>(&self, canvas: &mut Canvas<T>) {
+    fn center(&self) -> Vec2<f32> {
+        let bb = self.shape.bounding_box();
+        from_int(self.position) + (bb.top_left + bb.bottom_right) * 0.5
+    }
+
+    fn draw(&self, canvas: &mut dyn DrawTarget) {
         canvas.set_draw_color(Color::RED);
         let position = translation(from_int(self.position));
         self.shape.render(position, canvas);
@@ -201,7 +1420,7 @@ impl HudElement {
                 ghost,
                 scale,
             } => {
-                let center = Vec2::from(HUD_ELEMENT_SIZE as f32) * 0.5;
+                let center = Vec2::from(self.size() * 0.5);
 
                 entity.render(
                     position * translation(center) * Mat3::identity().scaled((*scale).into()),
@@ -211,6 +1430,24 @@ impl HudElement {
                 if let Some(ghost) = ghost {
                     entity.render(translation(from_int(ghost.screen_coordinates)), canvas);
                 }
+
+                if let Some(name) = &entity.name {
+                    let height = self.size() * 0.18;
+                    let max_chars = (self.size() / Text::advance(height)) as usize + 1;
+                    let label: String = name.chars().take(max_chars).collect();
+                    Text::new(&label, height).render(
+                        position * translation(Vec2::new(0.0, -1.5 * height)),
+                        canvas,
+                    );
+                }
+            }
+            HudElementVariant::VirtualStick { direction, .. } => {
+                let radius = self.size() * 0.5;
+                ().render(translation(self.center() + *direction * radius), canvas);
+            }
+            HudElementVariant::RotateButton { direction, .. } => {
+                let arrow = Vec2::new(*direction * self.size() * 0.3, 0.0);
+                arrow.render(translation(self.center() - arrow * 0.5), canvas);
             }
         }
     }
@@ -238,10 +1475,92 @@ impl HudElement {
                     *ghost = None;
                 }
             }
+            HudElementVariant::VirtualStick {
+                direction, changed, ..
+            } => {
+                if *changed {
+                    actions.push(Action::Accelerate {
+                        direction: *direction,
+                        throttle: direction.length(),
+                    });
+                    *changed = false;
+                }
+            }
+            HudElementVariant::RotateButton {
+                direction,
+                finger,
+                changed,
+            } => {
+                if *changed {
+                    let throttle = if finger.is_some() { 1.0 } else { 0.0 };
+                    actions.push(Action::Rotate {
+                        direction: *direction * throttle,
+                        throttle,
+                    });
+                    *changed = false;
+                }
+            }
         }
         actions
     }
 
+    fn handle_touch(&mut self, touch: &Touch) -> bool {
+        let center = self.center();
+        let inside = self
+            .shape
+            .contains_point(touch.position - from_int(self.position));
+        let radius = self.size() * 0.5;
+
+        match &mut self.variant {
+            HudElementVariant::ToolbarButton { .. } => false,
+            HudElementVariant::VirtualStick {
+                finger,
+                direction,
+                changed,
+            } => {
+                match touch.phase {
+                    TouchPhase::Down if inside && finger.is_none() => *finger = Some(touch.finger),
+                    TouchPhase::Down | TouchPhase::Move | TouchPhase::Up
+                        if *finger != Some(touch.finger) =>
+                    {
+                        return false
+                    }
+                    TouchPhase::Up => *finger = None,
+                    _ => {}
+                }
+
+                *direction = if finger.is_some() {
+                    let d = (touch.position - center) * (1.0 / radius);
+                    if d.length() > 1.0 {
+                        d.normalized()
+                    } else {
+                        d
+                    }
+                } else {
+                    Vec2::default()
+                };
+                *changed = true;
+                true
+            }
+            HudElementVariant::RotateButton {
+                finger, changed, ..
+            } => match touch.phase {
+                TouchPhase::Down if inside && finger.is_none() => {
+                    *finger = Some(touch.finger);
+                    *changed = true;
+                    true
+                }
+                TouchPhase::Up if *finger == Some(touch.finger) => {
+                    *finger = None;
+                    *changed = true;
+                    true
+                }
+                TouchPhase::Move => *finger == Some(touch.finger),
+                _ => false,
+            },
+        }
+    }
+
     fn handle_event(&mut self, event: &Event) -> bool {
         if self.dragging {
             match event {
@@ -278,27 +1597,22 @@ impl HudElement {
                 self.dragging = true;
                 true
             }
+            _ => false,
         }
     }
     fn drag_end(&mut self, coordinates: Vec2<i32>) -> bool {
-        match &mut self.variant {
-            HudElementVariant::ToolbarButton { ghost, .. } => {
-                if let Some(g) = ghost {
-                    g.screen_coordinates = coordinates;
-                    g.done = true;
-                    return true;
-                }
-            }
+        if let HudElementVariant::ToolbarButton { ghost: Some(g), .. } = &mut self.variant {
+            g.screen_coordinates = coordinates;
+            g.done = true;
+            return true;
         }
         false
     }
 
     fn drag_move(&mut self, coordinates: Vec2<i32>) {
-        match &mut self.variant {
-            HudElementVariant::ToolbarButton { ghost, .. } => {
-                if ghost.is_some() {
-                    *ghost = Some(Ghost::new(coordinates));
-                }
+        if let HudElementVariant::ToolbarButton { ghost, .. } = &mut self.variant {
+            if ghost.is_some() {
+                *ghost = Some(Ghost::new(coordinates));
             }
         }
     }
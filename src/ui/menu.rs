@@ -0,0 +1,211 @@
+use super::text::Text;
+use crate::math::vec::*;
+use crate::net::discovery::Discovery;
+use crate::net::DEFAULT_PORT;
+use crate::platform::{Color, DrawTarget, Event, Keycode};
+use crate::render::Render;
+use crate::world::difficulty::DifficultyLevel;
+use gamemath::Vec2;
+
+const TITLE_HEIGHT: f32 = 48.0;
+const LINE_HEIGHT: f32 = 16.0;
+const LINE_SPACING: f32 = 14.0;
+const MENU_PADDING: f32 = 120.0;
+
+const MAX_ADDRESS_LEN: usize = 64;
+
+/// How the game should be started.
+pub enum MenuChoice {
+    SinglePlayer,
+    /// Single player, fighting off waves of raiders.
+    Survival,
+    /// Single player, walking through the controls step by step.
+    Tutorial,
+    Host,
+    Connect { address: String },
+}
+
+const SINGLE_PLAYER_SCENARIO: &str = "single player";
+const SURVIVAL_SCENARIO: &str = "survival";
+const TUTORIAL_SCENARIO: &str = "tutorial";
+const HOST_SCENARIO: &str = "host";
+
+impl MenuChoice {
+    /// Name stored in settings as the last used scenario.
+    pub fn scenario(&self) -> String {
+        match self {
+            MenuChoice::SinglePlayer => SINGLE_PLAYER_SCENARIO.to_owned(),
+            MenuChoice::Survival => SURVIVAL_SCENARIO.to_owned(),
+            MenuChoice::Tutorial => TUTORIAL_SCENARIO.to_owned(),
+            MenuChoice::Host => HOST_SCENARIO.to_owned(),
+            MenuChoice::Connect { address } => address.clone(),
+        }
+    }
+}
+
+/// Main menu, shown when the game is started without arguments.
+pub struct Menu {
+    /// Address typed into the connect line.
+    address: String,
+    selected: usize,
+    difficulty: DifficultyLevel,
+    discovery: Option<Discovery>,
+    ui_scale: f32,
+
+    choice: Option<MenuChoice>,
+}
+
+/// Items before the discovered servers.
+const SINGLE_PLAYER_ITEM: usize = 0;
+const SURVIVAL_ITEM: usize = 1;
+const TUTORIAL_ITEM: usize = 2;
+const DIFFICULTY_ITEM: usize = 3;
+const HOST_ITEM: usize = 4;
+const ADDRESS_ITEM: usize = 5;
+const FIXED_ITEMS: usize = 6;
+
+impl Menu {
+    /// Preselects the last used scenario and difficulty.
+    pub fn new(ui_scale: f32, last_scenario: Option<&str>, difficulty: DifficultyLevel) -> Self {
+        let discovery = match Discovery::new() {
+            Ok(discovery) => Some(discovery),
+            Err(e) => {
+                eprintln!("LAN discovery disabled: {}", e);
+                None
+            }
+        };
+        let (selected, address) = match last_scenario {
+            None | Some(SINGLE_PLAYER_SCENARIO) => (SINGLE_PLAYER_ITEM, String::new()),
+            Some(SURVIVAL_SCENARIO) => (SURVIVAL_ITEM, String::new()),
+            Some(TUTORIAL_SCENARIO) => (TUTORIAL_ITEM, String::new()),
+            Some(HOST_SCENARIO) => (HOST_ITEM, String::new()),
+            Some(address) => (ADDRESS_ITEM, address.to_owned()),
+        };
+        Menu {
+            address,
+            selected,
+            difficulty,
+            discovery,
+            ui_scale,
+            choice: None,
+        }
+    }
+
+    pub fn take_choice(&mut self) -> Option<MenuChoice> {
+        self.choice.take()
+    }
+
+    /// Difficulty of the game to be started, in single player or when hosting.
+    pub fn difficulty(&self) -> DifficultyLevel {
+        self.difficulty
+    }
+
+    pub fn tick(&mut self) {
+        if let Some(discovery) = &mut self.discovery {
+            discovery.tick();
+        }
+    }
+
+    fn item_count(&self) -> usize {
+        FIXED_ITEMS + self.discovery.as_ref().map_or(0, |d| d.servers().len())
+    }
+
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::Up => {
+                    self.selected = (self.selected + self.item_count() - 1) % self.item_count()
+                }
+                Keycode::Down => self.selected = (self.selected + 1) % self.item_count(),
+                Keycode::Backspace if self.selected == ADDRESS_ITEM => {
+                    self.address.pop();
+                }
+                Keycode::Return | Keycode::KpEnter => self.activate(),
+                _ => return false,
+            },
+            Event::TextInput { text, .. } if self.selected == ADDRESS_ITEM => {
+                if self.address.len() + text.len() <= MAX_ADDRESS_LEN {
+                    self.address.push_str(text);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn activate(&mut self) {
+        self.choice = match self.selected {
+            SINGLE_PLAYER_ITEM => Some(MenuChoice::SinglePlayer),
+            SURVIVAL_ITEM => Some(MenuChoice::Survival),
+            TUTORIAL_ITEM => Some(MenuChoice::Tutorial),
+            DIFFICULTY_ITEM => {
+                self.difficulty = self.difficulty.next();
+                None
+            }
+            HOST_ITEM => Some(MenuChoice::Host),
+            ADDRESS_ITEM if self.address.trim().is_empty() => None,
+            ADDRESS_ITEM => {
+                let address = self.address.trim();
+                let address = if address.contains(':') {
+                    address.to_owned()
+                } else {
+                    format!("{}:{}", address, DEFAULT_PORT)
+                };
+                Some(MenuChoice::Connect { address })
+            }
+            server => self
+                .discovery
+                .as_ref()
+                .and_then(|d| d.servers().get(server - FIXED_ITEMS))
+                .map(|address| MenuChoice::Connect {
+                    address: address.to_string(),
+                }),
+        };
+    }
+
+    pub fn render(&self, canvas: &mut dyn DrawTarget) {
+        let left = MENU_PADDING * self.ui_scale;
+        let mut top = MENU_PADDING * self.ui_scale;
+
+        canvas.set_draw_color(Color::rgb(255, 255, 255));
+        let title_height = TITLE_HEIGHT * self.ui_scale;
+        Text::new("dgame", title_height).render(translation(Vec2::new(left, top)), canvas);
+        top += title_height + 2.0 * LINE_SPACING * self.ui_scale;
+
+        let servers = self.discovery.as_ref().map_or(&[][..], |d| d.servers());
+        let mut items = vec![
+            "single player".to_owned(),
+            "survival".to_owned(),
+            "tutorial".to_owned(),
+            format!("difficulty: {}", self.difficulty.name()),
+            "host game".to_owned(),
+            format!("connect to: {}", self.address),
+        ];
+        items.extend(servers.iter().map(|s| format!("LAN server {}", s)));
+
+        let height = LINE_HEIGHT * self.ui_scale;
+        for (index, item) in items.iter().enumerate() {
+            if index == self.selected {
+                canvas.set_draw_color(Color::rgb(200, 200, 60));
+            } else {
+                canvas.set_draw_color(Color::rgb(128, 128, 172));
+            }
+            let item = if index == ADDRESS_ITEM && index == self.selected {
+                format!("{}_", item)
+            } else {
+                item.clone()
+            };
+            Text::new(&item, height).render(translation(Vec2::new(left, top)), canvas);
+            top += height + LINE_SPACING * self.ui_scale;
+
+            if index == ADDRESS_ITEM && servers.is_empty() && self.discovery.is_some() {
+                canvas.set_draw_color(Color::rgb(80, 80, 110));
+                Text::new("searching for LAN servers...", height)
+                    .render(translation(Vec2::new(left, top)), canvas);
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::text::Text;
+use crate::client::EntityId;
+use crate::math::vec::*;
+use crate::platform::{Color, DrawTarget, Event, Keycode};
+use crate::render::Render;
+use crate::world::missions::Contract;
+use crate::world::World;
+use gamemath::Vec2;
+
+const LINE_HEIGHT: f32 = 12.0;
+const LINE_SPACING: f32 = 6.0;
+const PANEL_PADDING: f32 = 30.0;
+
+/// List of contracts of the controlled entity, toggled with J.
+pub struct MissionsPanel {
+    open: bool,
+    contracts: Vec<Contract>,
+    view_size: Vec2<f32>,
+    ui_scale: f32,
+}
+
+impl MissionsPanel {
+    pub fn new(view_size: Vec2<f32>, ui_scale: f32) -> Self {
+        MissionsPanel {
+            open: false,
+            contracts: Vec::new(),
+            view_size,
+            ui_scale,
+        }
+    }
+
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.ui_scale = ui_scale;
+    }
+
+    pub fn set_view_size(&mut self, view_size: Vec2<f32>) {
+        self.view_size = view_size;
+    }
+
+    pub fn tick(&mut self, world: &World, focus: &EntityId) {
+        self.contracts = world
+            .components
+            .contracts
+            .get(focus.entity_id)
+            .cloned()
+            .unwrap_or_default();
+    }
+
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::KeyDown {
+                keycode: Some(Keycode::J),
+                repeat: false,
+                ..
+            } => {
+                self.open = !self.open;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn render(&self, canvas: &mut dyn DrawTarget) {
+        if !self.open {
+            return;
+        }
+        let height = LINE_HEIGHT * self.ui_scale;
+        let step = height + LINE_SPACING * self.ui_scale;
+        let left = self.view_size.x * 0.5;
+        let mut top = PANEL_PADDING * self.ui_scale;
+
+        let mut lines = vec!["contracts".to_owned()];
+        if self.contracts.is_empty() {
+            lines.push("none offered".to_owned());
+        }
+        lines.extend(self.contracts.iter().map(Contract::describe));
+
+        canvas.set_draw_color(Color::rgb(200, 200, 60));
+        for line in &lines {
+            Text::new(line, height).render(translation(Vec2::new(left, top)), canvas);
+            top += step;
+        }
+    }
+}
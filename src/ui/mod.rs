@@ -1,2 +1,13 @@
+pub mod action_queue;
+pub mod blueprints;
+pub mod chat;
 pub mod hud;
+pub mod menu;
+pub mod missions;
+pub mod power;
+pub mod statistics;
+pub mod stats;
+pub mod text;
+pub mod touch_controls;
+pub mod trade;
 pub mod user_controls;
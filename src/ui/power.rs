@@ -0,0 +1,132 @@
+use super::text::Text;
+use super::user_controls::Action;
+use crate::client::EntityId;
+use crate::math::vec::*;
+use crate::platform::{Color, DrawTarget, Event, Keycode};
+use crate::render::Render;
+use crate::world::power::{self, PowerGroup, PowerStatus};
+use crate::world::World;
+use gamemath::Vec2;
+
+const LINE_HEIGHT: f32 = 12.0;
+const LINE_SPACING: f32 = 6.0;
+const PANEL_PADDING: f32 = 30.0;
+
+/// Power budget of the controlled entity, opened with P.
+/// Number keys move a group of blocks up in the order in which they get power.
+pub struct PowerPanel {
+    open: bool,
+    status: Option<PowerStatus>,
+    view_size: Vec2<f32>,
+    ui_scale: f32,
+
+    action_queue: Vec<Action>,
+}
+
+impl PowerPanel {
+    pub fn new(view_size: Vec2<f32>, ui_scale: f32) -> Self {
+        PowerPanel {
+            open: false,
+            status: None,
+            view_size,
+            ui_scale,
+            action_queue: Vec::new(),
+        }
+    }
+
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.ui_scale = ui_scale;
+    }
+
+    pub fn set_view_size(&mut self, view_size: Vec2<f32>) {
+        self.view_size = view_size;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn poll_actions(&mut self) -> std::vec::Drain<'_, Action> {
+        self.action_queue.drain(..)
+    }
+
+    pub fn tick(&mut self, world: &World, focus: &EntityId) {
+        self.status = world.components.power.get(focus.entity_id).cloned();
+    }
+
+    fn priorities(&self) -> Vec<PowerGroup> {
+        match &self.status {
+            Some(status) => status.groups.iter().map(|(group, _)| *group).collect(),
+            None => power::DEFAULT_PRIORITIES.to_vec(),
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        let keycode = match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                repeat: false,
+                ..
+            } => *keycode,
+            _ => return false,
+        };
+        if keycode == Keycode::P {
+            self.open = !self.open;
+            return true;
+        }
+        if !self.open {
+            return false;
+        }
+
+        let mut priorities = self.priorities();
+        let index = match keycode {
+            Keycode::Num1 => 0,
+            Keycode::Num2 => 1,
+            Keycode::Num3 => 2,
+            Keycode::Num4 => 3,
+            Keycode::Escape => {
+                self.open = false;
+                return true;
+            }
+            _ => return false,
+        };
+        if let Some(group) = priorities.get(index).copied() {
+            power::raise_priority(&mut priorities, group);
+            self.action_queue
+                .push(Action::SetPowerPriorities { priorities });
+        }
+        true
+    }
+
+    pub fn render(&self, canvas: &mut dyn DrawTarget) {
+        if !self.open {
+            return;
+        }
+        let height = LINE_HEIGHT * self.ui_scale;
+        let step = height + LINE_SPACING * self.ui_scale;
+        let left = PANEL_PADDING * self.ui_scale;
+        let mut top = self.view_size.y * 0.5;
+
+        let mut lines = Vec::new();
+        match &self.status {
+            Some(status) => {
+                lines.push(format!(
+                    "power supply {:.1}  demand {:.1}",
+                    status.supply, status.demand
+                ));
+                for (index, (group, share)) in status.groups.iter().enumerate() {
+                    lines.push(format!("{} {:?} {:.0}%", index + 1, group, share * 100.0));
+                }
+                lines.push("press a number to raise priority".to_owned());
+            }
+            None => lines.push("no generators, blocks run at full power".to_owned()),
+        }
+
+        canvas.set_draw_color(Color::rgb(200, 200, 60));
+        for line in &lines {
+            let line = line.to_lowercase();
+            Text::new(&line, height).render(translation(Vec2::new(left, top)), canvas);
+            top += step;
+        }
+    }
+}
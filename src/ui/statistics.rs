@@ -0,0 +1,100 @@
+use super::text::Text;
+use crate::math::vec::*;
+use crate::platform::{Color, DrawTarget};
+use crate::render::Render;
+use crate::statistics::{Counters, Statistics};
+use gamemath::Vec2;
+
+const TITLE_HEIGHT: f32 = 24.0;
+const LINE_HEIGHT: f32 = 14.0;
+const LINE_SPACING: f32 = 10.0;
+
+/// Width of the name column, in characters.
+const NAME_WIDTH: usize = 22;
+
+/// Statistics of this session and of all sessions, toggled with F4.
+pub struct StatisticsScreen {
+    visible: bool,
+    view_size: Vec2<f32>,
+    ui_scale: f32,
+}
+
+impl StatisticsScreen {
+    pub fn new(view_size: Vec2<f32>, ui_scale: f32) -> Self {
+        StatisticsScreen {
+            visible: false,
+            view_size,
+            ui_scale,
+        }
+    }
+
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.ui_scale = ui_scale;
+    }
+
+    pub fn set_view_size(&mut self, view_size: Vec2<f32>) {
+        self.view_size = view_size;
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn render(&self, statistics: &Statistics, canvas: &mut dyn DrawTarget) {
+        if !self.visible {
+            return;
+        }
+        let title_height = TITLE_HEIGHT * self.ui_scale;
+        let height = LINE_HEIGHT * self.ui_scale;
+        let step = height + LINE_SPACING * self.ui_scale;
+
+        let mut lines = vec![format!(
+            "{:<width$}{:>12}{:>12}",
+            "",
+            "session",
+            "total",
+            width = NAME_WIDTH
+        )];
+        let rows = |counters: &Counters| {
+            [
+                format!("{:.0}", counters.distance),
+                counters.blocks_placed.to_string(),
+                counters.asteroids_mined.to_string(),
+                counters.collisions_survived.to_string(),
+                counters.enemies_destroyed.to_string(),
+            ]
+        };
+        let names = [
+            "distance travelled",
+            "blocks placed",
+            "asteroids mined",
+            "collisions survived",
+            "enemies destroyed",
+        ];
+        let session = rows(&statistics.session);
+        let total = rows(&statistics.total);
+        for (index, name) in names.iter().enumerate() {
+            lines.push(format!(
+                "{:<width$}{:>12}{:>12}",
+                name,
+                session[index],
+                total[index],
+                width = NAME_WIDTH
+            ));
+        }
+
+        let width = lines[0].len() as f32 * Text::advance(height);
+        let left = (self.view_size.x - width) * 0.5;
+        let mut top = (self.view_size.y - title_height - lines.len() as f32 * step) * 0.5;
+
+        canvas.set_draw_color(Color::rgb(255, 255, 255));
+        Text::new("statistics", title_height).render(translation(Vec2::new(left, top)), canvas);
+        top += title_height + LINE_SPACING * self.ui_scale;
+
+        canvas.set_draw_color(Color::rgb(128, 200, 128));
+        for line in &lines {
+            Text::new(line, height).render(translation(Vec2::new(left, top)), canvas);
+            top += step;
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use super::text::Text;
+use crate::math::vec::*;
+use crate::engine::TickReport;
+use crate::memory;
+use crate::platform::{Color, DrawTarget};
+use crate::profiling;
+use crate::render::{Render, View};
+use crate::world::World;
+use gamemath::Vec2;
+use std::time::Instant;
+
+const LINE_HEIGHT: f32 = 10.0;
+const LINE_SPACING: f32 = 5.0;
+const STATS_PADDING: f32 = 30.0;
+
+/// Weight of the latest frame in displayed averages.
+const SMOOTHING: f32 = 0.05;
+
+/// Grids listed with the number of their entities, largest first.
+const LISTED_GRIDS: usize = 5;
+
+/// Overlay with frame rate, size of the simulation and average duration of profiled
+/// spans, toggled with F3.
+pub struct Stats {
+    visible: bool,
+    last_frame: Option<Instant>,
+    frames_per_second: f32,
+    /// Milliseconds per engine tick.
+    tick_millis: f32,
+    /// Milliseconds per frame, by span name.
+    averages: Vec<(&'static str, f32)>,
+    /// Grid maintenance was deferred in the last engine tick.
+    deferred_maintenance: bool,
+    /// Colliding pairs of entities in the last engine tick.
+    collisions: usize,
+    grid_count: usize,
+    /// Ids of grids with most entities, with the number of their entities.
+    largest_grids: Vec<(u64, usize)>,
+    /// Bytes held, by part of the game.
+    memory: Vec<(&'static str, usize)>,
+    live_allocations: Option<(usize, usize)>,
+    view_size: Vec2<f32>,
+    ui_scale: f32,
+}
+
+impl Stats {
+    pub fn new(view_size: Vec2<f32>, ui_scale: f32) -> Self {
+        Stats {
+            visible: false,
+            last_frame: None,
+            frames_per_second: 0.0,
+            tick_millis: 0.0,
+            averages: Vec::new(),
+            deferred_maintenance: false,
+            collisions: 0,
+            grid_count: 0,
+            largest_grids: Vec::new(),
+            memory: Vec::new(),
+            live_allocations: None,
+            view_size,
+            ui_scale,
+        }
+    }
+
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.ui_scale = ui_scale;
+    }
+
+    pub fn set_view_size(&mut self, view_size: Vec2<f32>) {
+        self.view_size = view_size;
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn report_tick(&mut self, report: &TickReport) {
+        self.deferred_maintenance = report.deferred_maintenance;
+        self.collisions = report.collisions;
+        let millis = report.duration.as_secs_f32() * 1000.0;
+        self.tick_millis += (millis - self.tick_millis) * SMOOTHING;
+    }
+
+    /// Collects spans recorded since the previous frame, times the frame and measures
+    /// the world and memory.
+    pub fn tick(&mut self, world: &World, view: &View) {
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            let seconds = (now - last).as_secs_f32();
+            if seconds > 0.0 {
+                self.frames_per_second += (1.0 / seconds - self.frames_per_second) * SMOOTHING;
+            }
+        }
+
+        if self.visible {
+            self.memory = memory::usage(world, &view.stars);
+            self.live_allocations = memory::live_allocations();
+
+            self.grid_count = world.grids.len();
+            let mut grids: Vec<(u64, usize)> = world
+                .grids
+                .iter()
+                .map(|(id, grid)| (*id, grid.entities.len()))
+                .collect();
+            grids.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            grids.truncate(LISTED_GRIDS);
+            self.largest_grids = grids;
+        }
+
+        for (name, duration) in profiling::take() {
+            let millis = duration.as_secs_f32() * 1000.0;
+            match self.averages.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, average)) => *average += (millis - *average) * SMOOTHING,
+                None => self.averages.push((name, millis)),
+            }
+        }
+    }
+
+    pub fn render(&self, canvas: &mut dyn DrawTarget) {
+        if !self.visible {
+            return;
+        }
+        let height = LINE_HEIGHT * self.ui_scale;
+        let step = height + LINE_SPACING * self.ui_scale;
+        let padding = STATS_PADDING * self.ui_scale;
+
+        let mut lines = vec![
+            format!("{:>10} {:6.1}", "fps", self.frames_per_second),
+            format!("{:>10} {:6.2} ms", "tick", self.tick_millis),
+            format!("{:>10} {:6}", "grids", self.grid_count),
+            format!("{:>10} {:6}", "collisions", self.collisions),
+        ];
+        for (id, entities) in &self.largest_grids {
+            // ids are random, so their last digits tell grids apart
+            lines.push(format!("grid {:>5} {:6} entities", id % 100_000, entities));
+        }
+        lines.extend(
+            self.averages
+                .iter()
+                .map(|(name, average)| format!("{:>10} {:6.2} ms", name, average)),
+        );
+        if self.deferred_maintenance {
+            lines.push("grid maintenance deferred".to_owned());
+        }
+        for (name, bytes) in &self.memory {
+            lines.push(format!("{:>10} {:6} kb", name, bytes / 1024));
+        }
+        if let Some((count, bytes)) = self.live_allocations {
+            lines.push(format!("{} allocations {} kb", count, bytes / 1024));
+        }
+
+        canvas.set_draw_color(Color::rgb(128, 200, 128));
+        for (index, line) in lines.iter().enumerate() {
+            let left = self.view_size.x - padding - line.len() as f32 * Text::advance(height);
+            let top = padding + index as f32 * step;
+            Text::new(line, height).render(translation(Vec2::new(left, top)), canvas);
+        }
+    }
+}
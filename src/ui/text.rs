@@ -0,0 +1,118 @@
+use crate::math::vec::*;
+use crate::platform::DrawTarget;
+use crate::render::Render;
+use gamemath::{Mat3, Vec2};
+
+/// Glyphs are drawn on a grid 4 units wide and 6 units high.
+const GLYPH_WIDTH: f32 = 4.0;
+const GLYPH_HEIGHT: f32 = 6.0;
+const GLYPH_SPACING: f32 = 1.5;
+
+/// Line of text, rendered with a stroke font.
+pub struct Text<'a> {
+    content: &'a str,
+    height: f32,
+}
+
+impl<'a> Text<'a> {
+    pub fn new(content: &'a str, height: f32) -> Self {
+        Text { content, height }
+    }
+
+    /// Horizontal distance between characters of given height.
+    pub fn advance(height: f32) -> f32 {
+        (GLYPH_WIDTH + GLYPH_SPACING) * height / GLYPH_HEIGHT
+    }
+}
+
+impl<'a> Render for Text<'a> {
+    fn render(&self, position: Mat3, canvas: &mut dyn DrawTarget) {
+        let scale = self.height / GLYPH_HEIGHT;
+        let advance = Text::advance(self.height);
+
+        for (index, c) in self.content.chars().enumerate() {
+            let origin = Vec2::new(index as f32 * advance, 0.0);
+
+            for stroke in glyph(c).split(' ') {
+                let points: Vec<Vec2<f32>> = stroke
+                    .as_bytes()
+                    .chunks(2)
+                    .map(|p| Vec2::new((p[0] - b'0') as f32, (p[1] - b'0') as f32))
+                    .map(|p| (position * (origin + p * scale).into_homogeneous()).into_cartesian())
+                    .collect();
+
+                for pair in points.windows(2) {
+                    canvas.draw_line(pair[0], pair[1]);
+                }
+            }
+        }
+    }
+}
+
+/// Strokes of a glyph: polylines separated by spaces, with each point written as two digits.
+fn glyph(c: char) -> &'static str {
+    match c.to_ascii_uppercase() {
+        ' ' => "",
+        'A' => "0602204246 0444",
+        'B' => "06003041423303 3344453606",
+        'C' => "4130100105163645",
+        'D' => "06003041453606",
+        'E' => "40000646 0333",
+        'F' => "400006 0333",
+        'G' => "41301001051636454323",
+        'H' => "0006 4046 0343",
+        'I' => "1030 2026 1636",
+        'J' => "4045361605",
+        'K' => "0006 400346",
+        'L' => "000646",
+        'M' => "0600224046",
+        'N' => "06004640",
+        'O' => "103041453616050110",
+        'P' => "06003041423303",
+        'Q' => "103041453616050110 2446",
+        'R' => "06003041423303 3346",
+        'S' => "413010010213334445361605",
+        'T' => "0040 2026",
+        'U' => "000516364540",
+        'V' => "002640",
+        'W' => "0016233640",
+        'X' => "0046 4006",
+        'Y' => "002340 2326",
+        'Z' => "00400646",
+        '0' => "103041453616050110 4105",
+        '1' => "112026 1636",
+        '2' => "01103041420646",
+        '3' => "0110304142334445361605 2333",
+        '4' => "300444 3036",
+        '5' => "400002324345361605",
+        '6' => "30100105163645443303",
+        '7' => "004016",
+        '8' => "103041423313020110 1304051636454433",
+        '9' => "16364541301001021343",
+        '.' => "2526",
+        ',' => "2516",
+        ':' => "2122 2526",
+        ';' => "2122 2516",
+        '!' => "2024 2526",
+        '?' => "01103041422324 2526",
+        '\'' => "2021",
+        '"' => "1011 3031",
+        '-' => "0343",
+        '+' => "0343 2125",
+        '=' => "0242 0444",
+        '*' => "2125 0343 1234 3214",
+        '/' => "4006",
+        '\\' => "0046",
+        '|' => "2026",
+        '(' => "30212536",
+        ')' => "10313516",
+        '[' => "30101636",
+        ']' => "10303616",
+        '<' => "410345",
+        '>' => "014305",
+        '_' => "0646",
+        '#' => "1115 3135 0242 0444",
+        '%' => "4006 0011 3546",
+        _ => "0040460600",
+    }
+}
@@ -0,0 +1,46 @@
+use crate::platform::Event;
+use crate::render::View;
+use gamemath::Vec2;
+
+/// Zoom change per unit of normalized pinch distance.
+const PINCH_ZOOM_SPEED: f32 = 4.0;
+
+/// Camera pan & zoom from touch screen gestures.
+#[derive(Default)]
+pub struct TouchControls {
+    panning_finger: Option<i64>,
+}
+
+impl TouchControls {
+    /// Handles touches that were not claimed by the HUD.
+    pub fn handle_event(&mut self, event: &Event, view: &mut View) -> bool {
+        match *event {
+            Event::FingerDown { finger_id, .. } => {
+                if self.panning_finger.is_none() {
+                    self.panning_finger = Some(finger_id);
+                }
+            }
+            Event::FingerMotion {
+                finger_id, dx, dy, ..
+            } => {
+                if self.panning_finger != Some(finger_id) {
+                    return false;
+                }
+                view.pan_by(Vec2::new(dx * view.size.x, dy * view.size.y));
+            }
+            Event::FingerUp { finger_id, .. } => {
+                if self.panning_finger != Some(finger_id) {
+                    return false;
+                }
+                self.panning_finger = None;
+            }
+            Event::MultiGesture { d_dist, .. } => {
+                // pinching takes over from panning
+                self.panning_finger = None;
+                view.zoom_by(1.0 + d_dist * PINCH_ZOOM_SPEED);
+            }
+            _ => return false,
+        }
+        true
+    }
+}
@@ -0,0 +1,197 @@
+use super::text::Text;
+use super::user_controls::Action;
+use crate::client::EntityId;
+use crate::math::vec::*;
+use crate::platform::{Color, DrawTarget, Event, Keycode};
+use crate::render::Render;
+use crate::world::industry::Cargo;
+use crate::world::trade::{self, Resource, Station, Trade};
+use crate::world::{BlockRegistry, Entity, World};
+use gamemath::Vec2;
+
+/// Resources are traded in lots of this size.
+const LOT: f32 = 10.0;
+
+/// Number of station blueprints that can be bought, with keys 5 to 9.
+const MAX_BLUEPRINTS: usize = 5;
+
+const LINE_HEIGHT: f32 = 12.0;
+const LINE_SPACING: f32 = 6.0;
+const WINDOW_PADDING: f32 = 30.0;
+
+/// What the docked station offers, copied from the world each tick.
+struct Offer {
+    station_id: u64,
+    station: Station,
+    cargo: Cargo,
+    /// The controlled entity, which can be sold as a blueprint, and its price.
+    design: (Entity, f32),
+}
+
+/// Trade window, opened with T while docked to a station.
+/// Trades are picked with number keys.
+pub struct TradeWindow {
+    open: bool,
+    offer: Option<Offer>,
+    view_size: Vec2<f32>,
+    ui_scale: f32,
+
+    action_queue: Vec<Action>,
+}
+
+impl TradeWindow {
+    pub fn new(view_size: Vec2<f32>, ui_scale: f32) -> Self {
+        TradeWindow {
+            open: false,
+            offer: None,
+            view_size,
+            ui_scale,
+            action_queue: Vec::new(),
+        }
+    }
+
+    pub fn set_ui_scale(&mut self, ui_scale: f32) {
+        self.ui_scale = ui_scale;
+    }
+
+    pub fn set_view_size(&mut self, view_size: Vec2<f32>) {
+        self.view_size = view_size;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn poll_actions(&mut self) -> std::vec::Drain<'_, Action> {
+        self.action_queue.drain(..)
+    }
+
+    /// Closes the window when the entity undocks.
+    pub fn tick(&mut self, world: &World, focus: &EntityId, blocks: &BlockRegistry) {
+        self.offer = trade::docked_station(world, focus).and_then(|station_id| {
+            let design = trade::design_of(world.get_entity(focus)?);
+            let price = Station::blueprint_offer(&design, blocks);
+
+            Some(Offer {
+                station_id,
+                station: world.components.stations.get(station_id)?.clone(),
+                cargo: world.components.cargo.get(focus.entity_id).copied()?,
+                design: (design, price),
+            })
+        });
+        if self.offer.is_none() {
+            self.open = false;
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        let offer = match &self.offer {
+            Some(offer) => offer,
+            None => return false,
+        };
+        let keycode = match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                repeat: false,
+                ..
+            } => *keycode,
+            _ => return false,
+        };
+        if keycode == Keycode::T {
+            self.open = !self.open;
+            return true;
+        }
+        if !self.open {
+            return false;
+        }
+
+        let trade = match keycode {
+            Keycode::Num1 => Trade::Buy {
+                resource: Resource::Ore,
+                amount: LOT,
+            },
+            Keycode::Num2 => Trade::Sell {
+                resource: Resource::Ore,
+                amount: LOT,
+            },
+            Keycode::Num3 => Trade::Buy {
+                resource: Resource::Material,
+                amount: LOT,
+            },
+            Keycode::Num4 => Trade::Sell {
+                resource: Resource::Material,
+                amount: LOT,
+            },
+            Keycode::Num5 => Trade::BuyBlueprint { index: 0 },
+            Keycode::Num6 => Trade::BuyBlueprint { index: 1 },
+            Keycode::Num7 => Trade::BuyBlueprint { index: 2 },
+            Keycode::Num8 => Trade::BuyBlueprint { index: 3 },
+            Keycode::Num9 => Trade::BuyBlueprint { index: 4 },
+            Keycode::Num0 => Trade::SellBlueprint,
+            Keycode::Escape => {
+                self.open = false;
+                return true;
+            }
+            _ => return false,
+        };
+        self.action_queue.push(Action::Trade {
+            station: offer.station_id,
+            trade,
+        });
+        true
+    }
+
+    pub fn render(&self, canvas: &mut dyn DrawTarget) {
+        let offer = match &self.offer {
+            Some(offer) => offer,
+            None => return,
+        };
+        let height = LINE_HEIGHT * self.ui_scale;
+        let step = height + LINE_SPACING * self.ui_scale;
+        let left = WINDOW_PADDING * self.ui_scale;
+        let mut top = self.view_size.y * 0.5 - 4.0 * step;
+
+        if !self.open {
+            canvas.set_draw_color(Color::rgb(128, 128, 172));
+            Text::new("docked, press t to trade", height)
+                .render(translation(Vec2::new(left, top)), canvas);
+            return;
+        }
+
+        let station = &offer.station;
+        let mut lines = vec![
+            format!(
+                "station has ore {:.0} at {:.1}, material {:.0} at {:.1}",
+                station.stock.ore,
+                station.ore_price,
+                station.stock.material,
+                station.material_price
+            ),
+            format!(
+                "you have {:.0} credits, ore {:.0}, material {:.0}",
+                offer.cargo.credits, offer.cargo.ore, offer.cargo.material
+            ),
+            format!("1 buy {} ore  2 sell {} ore", LOT, LOT),
+            format!("3 buy {} material  4 sell {} material", LOT, LOT),
+        ];
+        for (index, blueprint) in station.blueprints.iter().take(MAX_BLUEPRINTS).enumerate() {
+            let name = blueprint.entity.file_name();
+            lines.push(format!(
+                "{} buy blueprint {} for {:.0}",
+                index + 5,
+                name,
+                blueprint.price
+            ));
+        }
+        lines.push(format!(
+            "0 sell blueprint of this ship for {:.0}",
+            offer.design.1
+        ));
+
+        canvas.set_draw_color(Color::rgb(200, 200, 60));
+        for line in &lines {
+            Text::new(line, height).render(translation(Vec2::new(left, top)), canvas);
+            top += step;
+        }
+    }
+}
@@ -1,11 +1,38 @@
-use crate::world::{Entity};
-use crate::math::polygon::Polygon;
+use crate::client::EntityId;
+use crate::world::{power::PowerGroup, trade::Trade, Entity, World};
+use crate::math::{polygon::Polygon, vec::*};
+use crate::platform::{Event, Keycode, Mod};
 use crate::render::View;
-use gamemath::{Vec2};
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use crate::settings::KeyBindings;
+use crate::ui::action_queue::{ActionQueue, QueuedAction};
+use gamemath::{Mat2, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+const THROTTLE_KEY_STEP: f32 = 0.1;
+const THROTTLE_WHEEL_STEP: f32 = 0.05;
+
+const UI_SCALE_STEP: f32 = 1.25;
+
+/// Number of blocks that can be fired directly with number keys.
+const MANUAL_THRUSTER_KEYS: usize = 10;
+
+/// Gains of the heading hold controller, for error in radians and rates per tick.
+const HOLD_P: f32 = 2.0;
+const HOLD_I: f32 = 0.01;
+const HOLD_D: f32 = 40.0;
+/// Limit of the integrated error, so it does not wind up while rotation is saturated.
+const HOLD_MAX_INTEGRAL: f32 = 10.0;
+/// Change of rotation throttle below which no new action is emitted.
+const HOLD_THROTTLE_STEP: f32 = 0.01;
+
+/// Relative speed, per tick, at which the velocity of the target is matched.
+const MATCH_TOLERANCE: f32 = 0.01;
+/// Throttle for each unit of relative speed per tick, up to the throttle setting.
+const MATCH_GAIN: f32 = 2.0;
+/// Change of thrust below which no new action is emitted.
+const MATCH_THRUST_STEP: f32 = 0.01;
 
-#[derive(Default)]
 pub struct UserControls {
     up: bool,
     down: bool,
@@ -14,16 +41,147 @@ pub struct UserControls {
     rotate_right: bool,
     rotate_left: bool,
 
-    action_queue: Vec<Action>,
+    /// Persistent throttle setting, applied to all acceleration, from 0 to 1.
+    throttle: f32,
+
+    /// In manual mode, number keys fire individual blocks instead of directional thrust.
+    manual_thrusters: bool,
+    fired: [bool; MANUAL_THRUSTER_KEYS],
+
+    /// Keeps the heading by rotating, while thrusters stay under manual control.
+    heading_hold: Option<HeadingHold>,
+    /// Thrusts until the velocity of a target entity is matched.
+    velocity_match: Option<VelocityMatch>,
+
+    bindings: KeyBindings,
+
+    action_queue: ActionQueue,
+}
+
+/// State of the heading hold controller.
+struct HeadingHold {
+    /// Angle to hold, set to the current angle when the hold is engaged and
+    /// after each manual rotation.
+    target: Option<f32>,
+    integral: f32,
+    /// Rotation last emitted, as direction times throttle.
+    rotation: f32,
+}
+
+/// State of the velocity-match autopilot.
+struct VelocityMatch {
+    /// Entity to match, chosen when the autopilot starts if not given.
+    target: Option<EntityId>,
+    /// Thrust last emitted, as direction relative to the entity times throttle.
+    thrust: Vec2<f32>,
+}
+
+impl Default for UserControls {
+    fn default() -> Self {
+        UserControls {
+            up: false,
+            down: false,
+            left: false,
+            right: false,
+            rotate_right: false,
+            rotate_left: false,
+
+            throttle: 1.0,
+
+            manual_thrusters: false,
+            fired: [false; MANUAL_THRUSTER_KEYS],
+
+            heading_hold: None,
+            velocity_match: None,
+
+            bindings: KeyBindings::default(),
+
+            action_queue: ActionQueue::default(),
+        }
+    }
 }
 
 impl UserControls {
-    pub fn poll_actions(&mut self) -> std::vec::Drain<'_, Action> {
-        self.action_queue.drain(..)
+    pub fn new(bindings: KeyBindings) -> Self {
+        UserControls {
+            bindings,
+            ..UserControls::default()
+        }
+    }
+
+    pub fn throttle(&self) -> f32 {
+        self.throttle
+    }
+
+    pub fn poll_actions(&mut self) -> Vec<QueuedAction> {
+        self.action_queue.poll()
+    }
+
+    pub fn set_tick(&mut self, tick: u64) {
+        self.action_queue.set_tick(tick);
+    }
+
+    pub fn is_holding_heading(&self) -> bool {
+        self.heading_hold.is_some()
+    }
+
+    pub fn is_matching_velocity(&self) -> bool {
+        self.velocity_match.is_some()
+    }
+
+    /// Runs flight assists. The velocity-match autopilot starts on the locked target,
+    /// or on the nearest entity without one.
+    pub fn tick(&mut self, world: &World, controlled: &EntityId, target: Option<EntityId>) {
+        self.tick_heading_hold(world, controlled);
+        self.tick_velocity_match(world, controlled, target);
+    }
+
+    /// Emits rotation that holds the heading of the controlled entity, if the hold is on
+    /// and the entity is not being rotated by hand.
+    fn tick_heading_hold(&mut self, world: &World, controlled: &EntityId) {
+        let manual = self.rotate_left || self.rotate_right;
+        let hold = match &mut self.heading_hold {
+            Some(hold) => hold,
+            None => return,
+        };
+        let entity = match world.get_entity(controlled) {
+            Some(entity) => entity,
+            None => return,
+        };
+        if manual {
+            hold.target = None;
+            hold.integral = 0.0;
+            hold.rotation = 0.0;
+            return;
+        }
+        let target = *hold.target.get_or_insert(entity.angle.state);
+
+        // shortest way around, from -PI to PI
+        let error = (target - entity.angle.state + std::f32::consts::PI)
+            .rem_euclid(2.0 * std::f32::consts::PI)
+            - std::f32::consts::PI;
+        hold.integral = (hold.integral + error).clamp(-HOLD_MAX_INTEGRAL, HOLD_MAX_INTEGRAL);
+        let rotation = (HOLD_P * error + HOLD_I * hold.integral - HOLD_D * entity.angle.velocity)
+            .clamp(-1.0, 1.0);
+
+        if (rotation - hold.rotation).abs() > HOLD_THROTTLE_STEP {
+            hold.rotation = rotation;
+            self.action_queue.push(Action::Rotate {
+                direction: rotation.signum(),
+                throttle: rotation.abs(),
+            });
+        }
     }
 
     pub fn handle_event(&mut self, event: &Event, _view: &View) -> bool {
         match *event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                keymod,
+                ..
+            } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                self.handle_ctrl_key_event(keycode);
+            }
             Event::KeyDown {
                 keycode: Some(keycode),
                 ..
@@ -36,16 +194,14 @@ impl UserControls {
             } => {
                 self.handle_key_event(keycode, false);
             }
-            Event::MouseButtonUp { .. } => {
-                // let screen_coordinates = Vec3 {
-                //     x: x as f32,
-                //     y: y as f32,
-                //     z: 1.0,
-                // };
-
-                // let grid_coordinates =
-                //     crate::math::lu::solve_lu(&view.last_grid_to_screen, screen_coordinates)
-                //         .into_cartesian();
+            Event::MouseWheel { y, .. } => {
+                self.adjust_throttle(y as f32 * THROTTLE_WHEEL_STEP);
+            }
+            Event::MouseButtonUp { x, y, .. } => {
+                self.action_queue.push(Action::SelectTarget {
+                    position: Vec2::new(x as f32, y as f32),
+                });
+                // let grid_coordinates = view.screen_to_grid(Vec2::new(x as f32, y as f32));
 
                 // self.clicked = Some(grid_coordinates);
             }
@@ -55,62 +211,212 @@ impl UserControls {
     }
 
     fn handle_key_event(&mut self, keycode: Keycode, pressed: bool) {
-        match keycode {
-            Keycode::Left => {
-                self.left = pressed;
-                self.emit_acceleration_action();
-            }
-            Keycode::A => {
-                self.left = pressed;
-                self.emit_acceleration_action();
-            }
-            Keycode::Right => {
-                self.right = pressed;
-                self.emit_acceleration_action();
+        if self.manual_thrusters {
+            if let Some(index) = manual_thruster_index(keycode) {
+                self.fire_thruster(index, pressed);
+                return;
             }
-            Keycode::D => {
-                self.right = pressed;
-                self.emit_acceleration_action();
-            }
-            Keycode::Up => {
-                self.up = pressed;
-                self.emit_acceleration_action();
-            }
-            Keycode::W => {
-                self.up = pressed;
-                self.emit_acceleration_action();
-            }
-            Keycode::Down => {
-                self.down = pressed;
-                self.emit_acceleration_action();
+        }
+
+        let bindings = self.bindings.clone();
+        let movement = [Keycode::Left, Keycode::Right, Keycode::Up, Keycode::Down];
+        let bound = [bindings.left, bindings.right, bindings.up, bindings.down];
+        if pressed && (movement.contains(&keycode) || bound.contains(&keycode)) {
+            // flying by hand takes over from the autopilot
+            self.velocity_match = None;
+        }
+        if keycode == Keycode::Left || keycode == bindings.left {
+            self.left = pressed;
+            self.emit_acceleration_action();
+        } else if keycode == Keycode::Right || keycode == bindings.right {
+            self.right = pressed;
+            self.emit_acceleration_action();
+        } else if keycode == Keycode::Up || keycode == bindings.up {
+            self.up = pressed;
+            self.emit_acceleration_action();
+        } else if keycode == Keycode::Down || keycode == bindings.down {
+            self.down = pressed;
+            self.emit_acceleration_action();
+        } else if keycode == bindings.rotate_left {
+            self.rotate_left = pressed;
+            self.emit_rotate_action();
+        } else if keycode == bindings.rotate_right {
+            self.rotate_right = pressed;
+            self.emit_rotate_action();
+        } else if pressed {
+            self.handle_key_press(keycode);
+        }
+    }
+
+    /// Emits acceleration towards the velocity of the target, until it is matched
+    /// or the target is gone.
+    fn tick_velocity_match(
+        &mut self,
+        world: &World,
+        controlled: &EntityId,
+        locked: Option<EntityId>,
+    ) {
+        let autopilot = match &mut self.velocity_match {
+            Some(autopilot) => autopilot,
+            None => return,
+        };
+        let target = autopilot
+            .target
+            .or(locked)
+            .or_else(|| world.nearest_entity(controlled, |_| true))
+            .and_then(|target| world.find_entity(&target));
+        autopilot.target = target;
+
+        let relative = target.and_then(|target| world.relative_insist(controlled, &target));
+        let entity = world.get_entity(controlled);
+        let (relative, entity) = match (relative, entity) {
+            (Some(relative), Some(entity)) if relative.velocity.length() > MATCH_TOLERANCE => {
+                (relative, entity)
             }
-            Keycode::S => {
-                self.down = pressed;
+            _ => {
+                self.velocity_match = None;
                 self.emit_acceleration_action();
+                return;
             }
-            Keycode::E => {
-                self.rotate_left = pressed;
-                self.emit_rotate_action();
-            }
-            Keycode::Q => {
-                self.rotate_right = pressed;
-                self.emit_rotate_action();
-            }
-            Keycode::F5 => {
-                if pressed {
-                    self.action_queue.push(Action::SaveEntity)
+        };
+
+        // thrusters take directions relative to the entity
+        let direction = Mat2::rotation(-entity.angle.state) * relative.velocity.normalized();
+        let throttle = (relative.velocity.length() * MATCH_GAIN).min(self.throttle);
+        let thrust = direction * throttle;
+        if (thrust - autopilot.thrust).length() > MATCH_THRUST_STEP {
+            autopilot.thrust = thrust;
+            self.action_queue.push(Action::Accelerate {
+                direction,
+                throttle,
+            });
+        }
+    }
+
+    /// Controls that act on key press only.
+    fn handle_key_press(&mut self, keycode: Keycode) {
+        let bindings = self.bindings.clone();
+        if keycode == bindings.throttle_up || keycode == Keycode::KpPlus {
+            self.adjust_throttle(THROTTLE_KEY_STEP);
+        } else if keycode == bindings.throttle_down || keycode == Keycode::KpMinus {
+            self.adjust_throttle(-THROTTLE_KEY_STEP);
+        } else if keycode == bindings.manual_thrusters {
+            self.toggle_manual_thrusters();
+        } else if keycode == bindings.cycle_control {
+            self.action_queue.push(Action::CycleControl);
+        } else if keycode == bindings.save_entity {
+            self.action_queue.push(Action::SaveEntity);
+        } else if keycode == bindings.export_entity {
+            self.action_queue.push(Action::ExportEntity);
+        } else if keycode == bindings.export_text {
+            self.action_queue.push(Action::ExportText);
+        } else if keycode == bindings.toggle_stats {
+            self.action_queue.push(Action::ToggleStats);
+        } else if keycode == bindings.toggle_statistics {
+            self.action_queue.push(Action::ToggleStatistics);
+        } else if keycode == bindings.hold_heading {
+            self.toggle_heading_hold();
+        } else if keycode == bindings.match_velocity {
+            self.toggle_velocity_match();
+        } else if keycode == bindings.target_hostile {
+            self.action_queue.push(Action::TargetNearestHostile);
+        } else if keycode == bindings.toggle_rotation_lock {
+            self.action_queue.push(Action::ToggleRotationLock);
+        } else if keycode == bindings.toggle_debug_draw {
+            self.action_queue.push(Action::ToggleDebugDraw);
+        } else if keycode == bindings.screenshot {
+            self.action_queue.push(Action::Screenshot);
+        } else if keycode == bindings.load_entity {
+            self.action_queue.push(Action::LoadEntity {
+                filename: "12094447930535717060".to_owned(),
+            });
+        }
+    }
+
+    fn handle_ctrl_key_event(&mut self, keycode: Keycode) {
+        match keycode {
+            Keycode::Equals | Keycode::KpPlus => self.action_queue.push(Action::ScaleUi {
+                factor: UI_SCALE_STEP,
+            }),
+            Keycode::Minus | Keycode::KpMinus => self.action_queue.push(Action::ScaleUi {
+                factor: 1.0 / UI_SCALE_STEP,
+            }),
+            _ => {}
+        }
+    }
+
+    fn adjust_throttle(&mut self, change: f32) {
+        self.throttle = (self.throttle + change).clamp(0.0, 1.0);
+        self.emit_acceleration_action();
+
+        if self.manual_thrusters {
+            for index in 0..MANUAL_THRUSTER_KEYS {
+                if self.fired[index] {
+                    self.action_queue.push(Action::FireBlock {
+                        index,
+                        throttle: self.throttle,
+                    });
                 }
             }
-            Keycode::F6 => {
-                if pressed {
-                    self.action_queue.push(Action::LoadEntity { filename: "./data/entities/12094447930535717060".to_owned() })
-                }
+        }
+    }
+
+    fn toggle_manual_thrusters(&mut self) {
+        self.velocity_match = None;
+        if self.manual_thrusters {
+            for index in 0..MANUAL_THRUSTER_KEYS {
+                self.fire_thruster(index, false);
             }
-            _ => {}
+            self.manual_thrusters = false;
+            self.emit_acceleration_action();
+        } else {
+            self.action_queue.push(Action::Accelerate {
+                direction: Vec2::default(),
+                throttle: 0.0,
+            });
+            self.manual_thrusters = true;
+        }
+    }
+
+    fn toggle_heading_hold(&mut self) {
+        if self.heading_hold.take().is_some() {
+            self.emit_rotate_action();
+        } else {
+            self.heading_hold = Some(HeadingHold {
+                target: None,
+                integral: 0.0,
+                rotation: 0.0,
+            });
         }
     }
 
+    fn toggle_velocity_match(&mut self) {
+        if self.velocity_match.take().is_some() || self.manual_thrusters {
+            self.emit_acceleration_action();
+        } else {
+            self.velocity_match = Some(VelocityMatch {
+                target: None,
+                thrust: Vec2::default(),
+            });
+        }
+    }
+
+    fn fire_thruster(&mut self, index: usize, pressed: bool) {
+        if self.fired[index] == pressed {
+            return;
+        }
+        self.fired[index] = pressed;
+        self.action_queue.push(Action::FireBlock {
+            index,
+            throttle: if pressed { self.throttle } else { 0.0 },
+        });
+    }
+
     fn emit_acceleration_action(&mut self) {
+        if self.manual_thrusters {
+            return;
+        }
+
         let mut direction = Vec2::default();
         if self.left {
             direction += Vec2 { x: -1.0, y: 0.0 };
@@ -126,7 +432,11 @@ impl UserControls {
         }
         self.action_queue.push(Action::Accelerate {
             direction,
-            throttle: if direction.length() > 0.0 { 1.0 } else { 0.0 },
+            throttle: if direction.length() > 0.0 {
+                self.throttle
+            } else {
+                0.0
+            },
         });
     }
 
@@ -145,14 +455,84 @@ impl UserControls {
     }
 }
 
+/// Number keys 1 to 9 and then 0 map to block indices.
+fn manual_thruster_index(keycode: Keycode) -> Option<usize> {
+    let index = match keycode {
+        Keycode::Num1 => 0,
+        Keycode::Num2 => 1,
+        Keycode::Num3 => 2,
+        Keycode::Num4 => 3,
+        Keycode::Num5 => 4,
+        Keycode::Num6 => 5,
+        Keycode::Num7 => 6,
+        Keycode::Num8 => 7,
+        Keycode::Num9 => 8,
+        Keycode::Num0 => 9,
+        _ => return None,
+    };
+    Some(index)
+}
+
 #[allow(dead_code)]
+#[serde_as]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Action {
-    Accelerate { direction: Vec2<f32>, throttle: f32 },
+    Accelerate {
+        #[serde_as(as = "Vec2Serde<f32>")]
+        direction: Vec2<f32>,
+        throttle: f32,
+    },
     Rotate { direction: f32, throttle: f32 },
+    /// Fires a single block directly, bypassing directional control.
+    FireBlock { index: usize, throttle: f32 },
 
     UpdateShape { new_shape: Box<Polygon> },
+    /// Removes a part of the hull, given like `UpdateShape`.
+    CutShape { cut: Box<Polygon> },
+    /// Replaces the hull with its convex hull, which cleans up messy outlines.
+    ShrinkWrapHull,
     JoinEntity { entity: Box<Entity> },
+    MoveBlock {
+        index: usize,
+        #[serde_as(as = "Vec2Serde<f32>")]
+        offset: Vec2<f32>,
+        angle: f32,
+    },
+    RemoveBlock { index: usize },
+    Trade { station: u64, trade: Trade },
+    /// Order in which groups of blocks get power when there is not enough.
+    SetPowerPriorities { priorities: Vec<PowerGroup> },
 
     SaveEntity,
+    /// Saves the controlled entity as a blueprint with a display name.
+    SaveEntityAs { name: String },
+    /// Exports an image of the controlled entity.
+    ExportEntity,
+    /// Saves the controlled entity as text, which can be edited and loaded again.
+    ExportText,
+    /// Loads a saved entity, with file name relative to the entities directory.
     LoadEntity { filename: String },
+    /// Removes a saved entity, with file name relative to the entities directory.
+    DeleteBlueprint { filename: String },
+    /// Saves a saved entity under a new display name, replacing the old file.
+    RenameBlueprint { filename: String, name: String },
+
+    CycleControl,
+    /// Locks the entity under a point on the screen as the target, or else the first entity
+    /// between the controlled one and the point, or unlocks the target.
+    SelectTarget {
+        #[serde_as(as = "Vec2Serde<f32>")]
+        position: Vec2<f32>,
+    },
+    TargetNearestHostile,
+    ScaleUi { factor: f32 },
+    ToggleStats,
+    ToggleStatistics,
+    /// Turns the view with the controlled entity, or back to the grid.
+    ToggleRotationLock,
+    /// Draws velocities, forces, centers of mass and contacts of entities.
+    ToggleDebugDraw,
+    /// Saves the next drawn frame as an image.
+    Screenshot,
+    SendChat { text: String },
 }
@@ -0,0 +1,98 @@
+use super::{entity, Block, Entity};
+use crate::ui::user_controls::Action;
+use gamemath::{Mat2, Vec2};
+
+/// Ticks for throttles of thrusters to reach their target from zero.
+const SETTLE_TICKS: usize = 100;
+
+/// Directions sampled for the thrust envelope.
+const ENVELOPE_DIRECTIONS: usize = 16;
+
+/// Imbalances are reported when the thrust line misses the center of mass by more than
+/// this fraction of the radius of gyration of the entity.
+const TOLERANCE: f32 = 0.1;
+
+/// Thrust in a commanded direction that also turns the entity.
+#[derive(Clone, Debug)]
+pub struct Imbalance {
+    pub direction: &'static str,
+    /// Distance by which the combined thrust misses the center of mass.
+    pub lever_arm: f32,
+}
+
+impl Imbalance {
+    pub fn warning(&self) -> String {
+        format!(
+            "{} thrust induces spin, {:.0} off center",
+            self.direction, self.lever_arm
+        )
+    }
+}
+
+/// Directions commanded by the crew, relative to the entity.
+fn directions() -> [(&'static str, Vec2<f32>); 4] {
+    [
+        ("forward", Vec2::new(0.0, -1.0)),
+        ("reverse", Vec2::new(0.0, 1.0)),
+        ("left", Vec2::new(-1.0, 0.0)),
+        ("right", Vec2::new(1.0, 0.0)),
+    ]
+}
+
+/// Commanded directions in which full, fully powered thrust would spin the entity.
+pub fn analyze(entity: &Entity) -> Vec<Imbalance> {
+    if entity.mass <= 0.0 || entity.mass_angular <= 0.0 {
+        return Vec::new();
+    }
+    let radius_of_gyration = (entity.mass_angular / entity.mass).sqrt();
+
+    directions()
+        .iter()
+        .filter_map(|(name, direction)| {
+            let force = full_thrust(&entity.blocks, *direction);
+            let thrust = force.force.length();
+            if thrust <= 0.0 {
+                return None;
+            }
+            let lever_arm = force.torque.abs() / thrust;
+            if lever_arm <= radius_of_gyration * TOLERANCE {
+                return None;
+            }
+            Some(Imbalance {
+                direction: name,
+                lever_arm,
+            })
+        })
+        .collect()
+}
+
+/// Combined force of the blocks when accelerating in directions all around the entity,
+/// relative to the entity.
+pub fn thrust_envelope(blocks: &[Box<dyn Block>]) -> Vec<Vec2<f32>> {
+    (0..ENVELOPE_DIRECTIONS)
+        .map(|i| {
+            let angle = i as f32 / ENVELOPE_DIRECTIONS as f32 * 2.0 * std::f32::consts::PI;
+            let direction = Mat2::rotation(angle) * Vec2::new(0.0, -1.0);
+            full_thrust(blocks, direction).force
+        })
+        .collect()
+}
+
+/// Force the blocks settle to when accelerating in `direction` at full throttle.
+pub fn full_thrust(blocks: &[Box<dyn Block>], direction: Vec2<f32>) -> entity::ForcePoint {
+    let mut blocks = blocks.to_vec();
+    let action = Action::Accelerate {
+        direction,
+        throttle: 1.0,
+    };
+    for block in &mut blocks {
+        block.set_power(1.0);
+        block.apply_action(&action);
+    }
+    for _ in 0..SETTLE_TICKS {
+        for block in &mut blocks {
+            block.tick();
+        }
+    }
+    entity::blocks_force(&blocks)
+}
@@ -1,11 +1,11 @@
-use super::{ForcePoint};
-use crate::math::{polygon::Polygon, vec::*};
+use super::{power::PowerGroup, thruster::Exhaust, ForcePoint, Material};
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
 use crate::ui::user_controls::Action;
 use gamemath::{Mat3, Vec2};
 
 #[typetag::serde(tag = "type")]
 pub trait Block: std::fmt::Debug + CloneBlock {
-    fn shape(&self) -> &Polygon;
+    fn shape(&self) -> &Shape;
     fn offset(&self) -> Vec2<f32>;
     fn set_offset(&mut self, offset: Vec2<f32>);
     fn angle(&self) -> f32;
@@ -17,15 +17,103 @@ pub trait Block: std::fmt::Debug + CloneBlock {
 
     fn tick(&mut self) {}
 
+    /// Force at full throttle, relative to the block.
+    fn thrust(&self) -> Vec2<f32> {
+        Vec2::default()
+    }
+
     fn mass(&self) -> f32 {
         0.0
     }
 
+    /// Ore and material this block can hold.
+    fn cargo_capacity(&self) -> f32 {
+        0.0
+    }
+
+    /// Ore mined per tick while the entity pushes against an asteroid.
+    fn drill_rate(&self) -> f32 {
+        0.0
+    }
+
+    /// Ore in cargo refined per tick.
+    fn refining_rate(&self) -> f32 {
+        0.0
+    }
+
+    /// Fuel burned per tick, at the current throttle.
+    fn fuel_use(&self) -> f32 {
+        0.0
+    }
+
+    /// Fuel per tick given to entities docked to the entity of this block.
+    fn pump_rate(&self) -> f32 {
+        0.0
+    }
+
+    /// Power produced per tick.
+    fn power_output(&self) -> f32 {
+        0.0
+    }
+
+    /// Group of loads this block belongs to, if it uses power.
+    fn power_group(&self) -> Option<PowerGroup> {
+        None
+    }
+
+    /// Power wanted per tick, at the current throttle.
+    fn power_demand(&self) -> f32 {
+        0.0
+    }
+
+    /// Share of demanded power the block gets, from 0 to 1.
+    fn set_power(&mut self, _share: f32) {}
+
+    /// Whether the crew controls the entity from this block.
+    fn is_cockpit(&self) -> bool {
+        false
+    }
+
+    /// Oxygen this block can hold for the crew.
+    fn oxygen_capacity(&self) -> f32 {
+        0.0
+    }
+
+    /// Oxygen produced per tick.
+    fn oxygen_rate(&self) -> f32 {
+        0.0
+    }
+
+    /// Health of damaged blocks within reach restored per tick.
+    fn repair_rate(&self) -> f32 {
+        0.0
+    }
+
+    /// Surface of this block in collisions, when it differs from the entity it is on.
+    fn material(&self) -> Option<Material> {
+        None
+    }
+
+    /// Where the block blows exhaust out, while it does.
+    fn exhaust(&self) -> Option<Exhaust> {
+        None
+    }
+
+    /// Name of the image drawn in place of the outline, from the textures directory.
+    fn texture(&self) -> Option<&'static str> {
+        None
+    }
+
     fn apply_action(&mut self, action: &Action);
 
     fn transform(&self) -> Mat3 {
         translation(self.offset()) * Mat3::rotation(self.angle())
     }
+
+    /// Shape in coordinates of the entity, round shapes approximated by a polygon.
+    fn outline(&self) -> Polygon {
+        self.transform() * self.shape().to_polygon().into_owned()
+    }
 }
 
 pub trait CloneBlock {
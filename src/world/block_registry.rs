@@ -0,0 +1,123 @@
+use super::{
+    Block, CargoBay, Cockpit, Drill, FuelPump, Generator, Gyroscope, LifeSupport, Material,
+    Plating, Refinery, RepairArm, Thruster,
+};
+use gamemath::Vec2;
+
+/// Kind of block that can be built.
+pub struct BlockType {
+    /// Name of the block type, as written in saves.
+    pub name: &'static str,
+    /// Building material used to place a block of this type, refunded less its damage when
+    /// it is removed.
+    pub cost: f32,
+    /// Constructs the blocks offered on the toolbar.
+    pub toolbar: fn() -> Vec<Box<dyn Block>>,
+}
+
+/// Block types available in the game, registered at startup.
+///
+/// Serialization of a block type is registered by `#[typetag::serde]` on its `Block`
+/// impl and its behavior each tick by `Block::tick`.
+#[derive(Default)]
+pub struct BlockRegistry {
+    types: Vec<BlockType>,
+}
+
+impl BlockRegistry {
+    pub fn with_builtin() -> Self {
+        let mut registry = BlockRegistry::default();
+        registry.register(BlockType {
+            name: "Thruster",
+            cost: 10.0,
+            toolbar: || {
+                [20.0, 30.0, 40.0]
+                    .iter()
+                    .map(|width| {
+                        Box::new(Thruster::new(*width, Vec2::default(), 0.0)) as Box<dyn Block>
+                    })
+                    .collect()
+            },
+        });
+        registry.register(BlockType {
+            name: "Gyroscope",
+            cost: 15.0,
+            toolbar: || vec![Box::new(Gyroscope::new(20.0, Vec2::default(), 0.0))],
+        });
+        registry.register(BlockType {
+            name: "Cockpit",
+            cost: 20.0,
+            toolbar: || vec![Box::new(Cockpit::new(12.0, Vec2::default(), 0.0))],
+        });
+        registry.register(BlockType {
+            name: "LifeSupport",
+            cost: 25.0,
+            toolbar: || vec![Box::new(LifeSupport::new(12.0, Vec2::default(), 0.0))],
+        });
+        registry.register(BlockType {
+            name: "Generator",
+            cost: 30.0,
+            toolbar: || vec![Box::new(Generator::new(20.0, Vec2::default(), 0.0))],
+        });
+        registry.register(BlockType {
+            name: "Drill",
+            cost: 20.0,
+            toolbar: || vec![Box::new(Drill::new(20.0, Vec2::default(), 0.0))],
+        });
+        registry.register(BlockType {
+            name: "CargoBay",
+            cost: 15.0,
+            toolbar: || vec![Box::new(CargoBay::new(30.0, Vec2::default(), 0.0))],
+        });
+        registry.register(BlockType {
+            name: "Refinery",
+            cost: 40.0,
+            toolbar: || vec![Box::new(Refinery::new(30.0, Vec2::default(), 0.0))],
+        });
+        registry.register(BlockType {
+            name: "FuelPump",
+            cost: 25.0,
+            toolbar: || vec![Box::new(FuelPump::new(20.0, Vec2::default(), 0.0))],
+        });
+        registry.register(BlockType {
+            name: "RepairArm",
+            cost: 30.0,
+            toolbar: || vec![Box::new(RepairArm::new(20.0, Vec2::default(), 0.0))],
+        });
+        registry.register(BlockType {
+            name: "Plating",
+            cost: 5.0,
+            toolbar: || {
+                [Material::BUMPER, Material::ARMOR]
+                    .iter()
+                    .map(|material| {
+                        Box::new(Plating::new(30.0, *material, Vec2::default(), 0.0))
+                            as Box<dyn Block>
+                    })
+                    .collect()
+            },
+        });
+        registry
+    }
+
+    pub fn register(&mut self, block_type: BlockType) {
+        if self.types.iter().any(|t| t.name == block_type.name) {
+            eprintln!("block type {} is already registered", block_type.name);
+            return;
+        }
+        self.types.push(block_type);
+    }
+
+    /// Building material used to place `block`. Blocks of unregistered types are free.
+    pub fn cost(&self, block: &dyn Block) -> f32 {
+        self.types
+            .iter()
+            .find(|t| t.name == block.typetag_name())
+            .map_or(0.0, |t| t.cost)
+    }
+
+    /// Blocks of all types offered on the toolbar, in order of registration.
+    pub fn toolbar(&self) -> Vec<Box<dyn Block>> {
+        self.types.iter().flat_map(|t| (t.toolbar)()).collect()
+    }
+}
@@ -0,0 +1,63 @@
+use super::Block;
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
+use crate::ui::user_controls::Action;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Cargo held, for each square unit of area.
+const CARGO_CAPACITY: f32 = 0.2;
+
+/// Holds ore and building material.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CargoBay {
+    shape: Shape,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    offset: Vec2<f32>,
+    angle: f32,
+    cargo_capacity: f32,
+}
+
+impl CargoBay {
+    pub fn new(width: f32, offset: Vec2<f32>, angle: f32) -> Self {
+        CargoBay {
+            shape: CargoBay::shape(width).into(),
+            offset,
+            angle,
+            cargo_capacity: width * width * CARGO_CAPACITY,
+        }
+    }
+
+    pub fn shape(width: f32) -> Polygon {
+        let p = Polygon::from(&[[-0.5, -0.5], [-0.5, 0.5], [0.5, 0.5], [0.5, -0.5]][..]);
+        Mat3::identity().scaled(Vec2::new(width, width)) * p
+    }
+}
+
+#[typetag::serde]
+impl Block for CargoBay {
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+    fn set_offset(&mut self, offset: Vec2<f32>) {
+        self.offset = offset;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn cargo_capacity(&self) -> f32 {
+        self.cargo_capacity
+    }
+
+    fn apply_action(&mut self, _action: &Action) {}
+}
@@ -0,0 +1,36 @@
+/// Floats are rounded to this precision, so tiny differences do not change the checksum.
+const QUANTUM: f32 = 1.0 / 1024.0;
+
+/// FNV-1a hash, which unlike std hashers is stable across builds and platforms.
+pub struct Checksum {
+    hash: u64,
+}
+
+impl Checksum {
+    pub fn new() -> Self {
+        Checksum {
+            hash: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        for byte in value.to_le_bytes() {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_u64((value / QUANTUM).round() as i64 as u64);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl Default for Checksum {
+    fn default() -> Self {
+        Checksum::new()
+    }
+}
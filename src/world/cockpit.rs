@@ -0,0 +1,76 @@
+use super::Block;
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
+use crate::ui::user_controls::Action;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Oxygen held, for each width unit. Lasts two minutes for a cockpit of width 12.
+const OXYGEN_CAPACITY: f32 = 600.0;
+
+/// Seat of the crew. Entities are controlled only while they have one.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cockpit {
+    shape: Shape,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    offset: Vec2<f32>,
+    angle: f32,
+    oxygen_capacity: f32,
+}
+
+impl Cockpit {
+    pub fn new(width: f32, offset: Vec2<f32>, angle: f32) -> Self {
+        Cockpit {
+            shape: Cockpit::shape(width).into(),
+            offset,
+            angle,
+            oxygen_capacity: width * OXYGEN_CAPACITY,
+        }
+    }
+
+    pub fn shape(width: f32) -> Polygon {
+        let p = Polygon::from(
+            &[
+                [-0.5, 0.5],
+                [-0.5, -0.1],
+                [-0.2, -0.5],
+                [0.2, -0.5],
+                [0.5, -0.1],
+                [0.5, 0.5],
+            ][..],
+        );
+        Mat3::identity().scaled(Vec2::new(width, width)) * p
+    }
+}
+
+#[typetag::serde]
+impl Block for Cockpit {
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+    fn set_offset(&mut self, offset: Vec2<f32>) {
+        self.offset = offset;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn is_cockpit(&self) -> bool {
+        true
+    }
+
+    fn oxygen_capacity(&self) -> f32 {
+        self.oxygen_capacity
+    }
+
+    fn apply_action(&mut self, _action: &Action) {}
+}
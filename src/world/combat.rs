@@ -0,0 +1,110 @@
+use super::{missions, Faction, World};
+use crate::client::EntityId;
+use crate::events::{Events, WorldEvent};
+use gamemath::Mat2;
+use serde::{Deserialize, Serialize};
+
+/// Health taken from both entities when they collide.
+const COLLISION_DAMAGE: f32 = 10.0;
+
+/// Entities touch for many ticks in a row, so each hit makes the entity immune for a while.
+const HIT_COOLDOWN: u32 = 30;
+
+/// Health taken from an entity that wrecks one of its blocks.
+pub const BLOCK_POINTS: f32 = 20.0;
+
+/// Structural health of an entity that can be destroyed.
+/// Entities without health are indestructible.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Health {
+    pub points: f32,
+    pub max_points: f32,
+    /// Ticks until the entity can be damaged again.
+    cooldown: u32,
+}
+
+impl Health {
+    pub fn new(points: f32) -> Self {
+        Health {
+            points,
+            max_points: points,
+            cooldown: 0,
+        }
+    }
+
+    /// Returns whether the entity was hit, which it is not while it is immune.
+    fn damage(&mut self, amount: f32) -> bool {
+        if self.cooldown > 0 {
+            return false;
+        }
+        self.points -= amount;
+        self.cooldown = HIT_COOLDOWN;
+        true
+    }
+}
+
+/// Damages colliding entities, and the block of each that is closest to the other,
+/// and removes entities with no health left.
+pub fn tick(world: &mut World, events: &Events) {
+    for (_, health) in world.components.health.iter_mut() {
+        health.cooldown = health.cooldown.saturating_sub(1);
+    }
+
+    for event in events.iter() {
+        if let WorldEvent::Collision { grid_id, entities } = event {
+            for (entity, other) in [(entities[0], entities[1]), (entities[1], entities[0])] {
+                let mut amount = COLLISION_DAMAGE * world.difficulty.collision_damage;
+                if world.components.faction(entity) == Faction::Player {
+                    amount *= world.difficulty.damage_taken;
+                }
+                let hit = match world.components.health.get_mut(entity) {
+                    Some(health) => health.damage(amount),
+                    None => false,
+                };
+                if hit {
+                    damage_block(world, *grid_id, entity, other, amount);
+                }
+            }
+        }
+    }
+
+    let destroyed: Vec<u64> = world
+        .components
+        .health
+        .iter()
+        .filter(|(_, health)| health.points <= 0.0)
+        .map(|(id, _)| *id)
+        .collect();
+    for entity in destroyed {
+        if let Some(id) = world.find_entity(&EntityId::new(0, entity)) {
+            world.remove_entity(&id);
+        }
+    }
+}
+
+/// Damages the block of the entity that faces the entity it was hit by.
+fn damage_block(world: &mut World, grid_id: u64, entity: u64, other: u64, amount: f32) {
+    let other = match missions::position_in(world, grid_id, other) {
+        Some(position) => position,
+        None => return,
+    };
+    let entity = match world
+        .grids
+        .get_mut(&grid_id)
+        .and_then(|grid| grid.get_entity_mut(entity))
+    {
+        Some(entity) => entity,
+        None => return,
+    };
+
+    // blocks are placed relative to the entity
+    let towards = Mat2::rotation(-entity.angle.state) * (other - entity.position.state);
+    let closest = (0..entity.blocks.len()).min_by(|a, b| {
+        let a = (entity.blocks[*a].offset() - towards).length();
+        let b = (entity.blocks[*b].offset() - towards).length();
+        a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    if let Some(index) = closest {
+        entity.damage_block(index, amount / BLOCK_POINTS);
+    }
+}
@@ -0,0 +1,139 @@
+use super::{
+    combat::Health,
+    gravity::GravityWell,
+    industry::Cargo,
+    life_support::Oxygen,
+    missions::Contract,
+    power::{PowerGroup, PowerStatus},
+    supply::{Resupply, Tank},
+    trade::Station,
+    Faction,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Values of one component type, keyed by entity id.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComponentStore<T> {
+    items: HashMap<u64, T>,
+}
+
+impl<T> Default for ComponentStore<T> {
+    fn default() -> Self {
+        ComponentStore {
+            items: HashMap::new(),
+        }
+    }
+}
+
+impl<T> ComponentStore<T> {
+    pub fn get(&self, entity: u64) -> Option<&T> {
+        self.items.get(&entity)
+    }
+
+    pub fn get_mut(&mut self, entity: u64) -> Option<&mut T> {
+        self.items.get_mut(&entity)
+    }
+
+    pub fn insert(&mut self, entity: u64, value: T) {
+        self.items.insert(entity, value);
+    }
+
+    pub fn remove(&mut self, entity: u64) -> Option<T> {
+        self.items.remove(&entity)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&u64, &T)> {
+        self.items.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&u64, &mut T)> {
+        self.items.iter_mut()
+    }
+}
+
+/// Gameplay state of entities that is not needed for physics.
+/// Kept apart from `Entity`, so systems can add components without changing
+/// entity serialization or collision code. Placement, shape and blocks stay on `Entity`,
+/// which collision and building code work on directly.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Components {
+    pub factions: ComponentStore<Faction>,
+    /// Names of scripts controlling entities.
+    #[serde(default)]
+    pub scripts: ComponentStore<String>,
+    /// Ore left in asteroids.
+    #[serde(default)]
+    pub ore: ComponentStore<f32>,
+    #[serde(default)]
+    pub cargo: ComponentStore<Cargo>,
+    /// Building material paid for blocks of entities, the most that removing them refunds.
+    #[serde(default)]
+    pub invested: ComponentStore<f32>,
+    #[serde(default)]
+    pub stations: ComponentStore<Station>,
+    /// Contracts taken by player entities.
+    #[serde(default)]
+    pub contracts: ComponentStore<Vec<Contract>>,
+    #[serde(default)]
+    pub health: ComponentStore<Health>,
+    #[serde(default)]
+    pub fuel: ComponentStore<Tank>,
+    /// Received by docked entities in the last tick.
+    #[serde(default)]
+    pub resupply: ComponentStore<Resupply>,
+    /// Order in which groups of blocks get power when there is not enough.
+    #[serde(default)]
+    pub power_priorities: ComponentStore<Vec<PowerGroup>>,
+    /// Power budget of entities with generators in the last tick.
+    #[serde(default)]
+    pub power: ComponentStore<PowerStatus>,
+    /// Air of entities with cockpits.
+    #[serde(default)]
+    pub oxygen: ComponentStore<Oxygen>,
+    /// Planets and other bodies that pull entities towards them.
+    #[serde(default)]
+    pub gravity: ComponentStore<GravityWell>,
+}
+
+impl Components {
+    pub fn faction(&self, entity: u64) -> Faction {
+        self.factions.get(entity).copied().unwrap_or_default()
+    }
+
+    /// Whether the entity has no gameplay state, so it is only a piece of matter.
+    pub fn is_bare(&self, entity: u64) -> bool {
+        self.factions.get(entity).is_none()
+            && self.scripts.get(entity).is_none()
+            && self.ore.get(entity).is_none()
+            && self.cargo.get(entity).is_none()
+            && self.invested.get(entity).is_none()
+            && self.stations.get(entity).is_none()
+            && self.contracts.get(entity).is_none()
+            && self.health.get(entity).is_none()
+            && self.fuel.get(entity).is_none()
+            && self.resupply.get(entity).is_none()
+            && self.power_priorities.get(entity).is_none()
+            && self.power.get(entity).is_none()
+            && self.oxygen.get(entity).is_none()
+            && self.gravity.get(entity).is_none()
+    }
+
+    /// Drops all components of an entity that was removed from the world.
+    pub fn remove(&mut self, entity: u64) {
+        self.factions.remove(entity);
+        self.scripts.remove(entity);
+        self.ore.remove(entity);
+        self.cargo.remove(entity);
+        self.invested.remove(entity);
+        self.stations.remove(entity);
+        self.contracts.remove(entity);
+        self.health.remove(entity);
+        self.fuel.remove(entity);
+        self.resupply.remove(entity);
+        self.power_priorities.remove(entity);
+        self.power.remove(entity);
+        self.oxygen.remove(entity);
+        self.gravity.remove(entity);
+    }
+}
@@ -0,0 +1,138 @@
+use super::{Components, Entity, Grid, Material, World};
+use crate::math::bounding_box::BoundingBox;
+use crate::math::polygon::{construct_convex_hull, Polygon};
+use crate::math::shape::Shape;
+use crate::math::vec::*;
+use gamemath::Vec2;
+
+/// Entities a grid may hold before its debris is consolidated.
+const GRID_ENTITY_BUDGET: usize = 150;
+
+/// Entities without blocks or gameplay state and with a smaller area are debris.
+const DEBRIS_AREA: f32 = 400.0;
+
+/// Debris is merged with fragments at most this far from it.
+const MERGE_RADIUS: f32 = 150.0;
+
+/// Most fragments merged into one compound entity at once.
+const MERGE_BATCH: usize = 16;
+
+/// Merges clusters of small debris fragments in grids over their entity budget into
+/// compound debris entities, so collision cost stays bounded after big battles.
+pub fn consolidate(world: &mut World) {
+    let components = &world.components;
+    for grid in world.grids.values_mut() {
+        while grid.entities.len() > GRID_ENTITY_BUDGET {
+            if !merge_cluster(grid, components) {
+                break;
+            }
+        }
+    }
+}
+
+fn is_debris(entity: &Entity, components: &Components) -> bool {
+    entity.blocks.is_empty()
+        && entity.shape.area_and_centroid().0.abs() < DEBRIS_AREA
+        && components.is_bare(entity.get_id())
+}
+
+/// Merges the smallest fragment that has neighbours with up to a batch of the closest
+/// of them. Returns false when no fragments can be merged.
+fn merge_cluster(grid: &mut Grid, components: &Components) -> bool {
+    let mut debris: Vec<(usize, f32)> = grid
+        .entities
+        .iter()
+        .enumerate()
+        .filter(|(_, entity)| is_debris(entity, components))
+        .map(|(index, entity)| (index, entity.shape.area_and_centroid().0.abs()))
+        .collect();
+    debris.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    for (seed, _) in &debris {
+        let center = grid.entities[*seed].position.state;
+        let mut cluster: Vec<(usize, f32)> = debris
+            .iter()
+            .map(|(index, _)| {
+                let distance = (grid.entities[*index].position.state - center).length();
+                (*index, distance)
+            })
+            .filter(|(_, distance)| *distance <= MERGE_RADIUS)
+            .collect();
+        if cluster.len() < 2 {
+            continue;
+        }
+        cluster.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        cluster.truncate(MERGE_BATCH);
+
+        let mut indices: Vec<usize> = cluster.into_iter().map(|(index, _)| index).collect();
+        let hull = hull_of(indices.iter().map(|index| &grid.entities[*index]));
+        if overlaps_others(grid, &indices, &hull) {
+            continue;
+        }
+
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        let fragments: Vec<Entity> = indices
+            .into_iter()
+            .map(|index| grid.entities.remove(index))
+            .collect();
+        grid.entities.push(compound(&fragments, hull));
+        return true;
+    }
+    false
+}
+
+/// Convex hull of the fragments, in grid coordinates.
+fn hull_of<'a>(fragments: impl Iterator<Item = &'a Entity>) -> Polygon {
+    let points: Vec<Vec2<f32>> = fragments
+        .flat_map(|fragment| {
+            let shape: Polygon =
+                fragment.projection_to_grid() * fragment.shape.to_polygon().into_owned();
+            shape
+                .points
+                .into_iter()
+                .map(|point| point.into_cartesian())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    construct_convex_hull(&points)
+}
+
+/// Whether the hull would cover an entity that is not one of the fragments at `members`,
+/// which collisions would then push out of it.
+fn overlaps_others(grid: &Grid, members: &[usize], hull: &Polygon) -> bool {
+    let bounds = hull.bounding_box();
+    let hull = Shape::from(hull.clone());
+    grid.entities
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !members.contains(index))
+        .any(|(_, entity)| {
+            let shape: Polygon =
+                entity.projection_to_grid() * entity.shape.to_polygon().into_owned();
+            shape.bounding_box().intersects(&bounds)
+                && hull.penetration(&Shape::from(shape)).is_some()
+        })
+}
+
+/// Entity with the convex hull of the fragments as its shape, moving with their momentum.
+/// The hull covers gaps between the fragments, so it is made lighter to weigh as much as
+/// they did.
+fn compound(fragments: &[Entity], hull: Polygon) -> Entity {
+    let mass: f32 = fragments.iter().map(|f| f.mass).sum();
+    let momentum = fragments
+        .iter()
+        .fold(Vec2::default(), |sum, f| sum + f.position.velocity * f.mass);
+
+    // shape is in grid coordinates, so the entity is placed at its center of mass
+    let area = hull.area_and_centroid().0.abs();
+    let mut entity = Entity::new(hull, Vec::new());
+    if mass > 0.0 && area > 0.0 {
+        entity.material = Material {
+            density: Some(mass / area),
+            ..fragments[0].material
+        };
+        entity.redistribute_weight();
+        entity.position.velocity = momentum * (1.0 / mass);
+    }
+    entity
+}
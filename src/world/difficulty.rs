@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+/// Preset chosen on the new-game screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DifficultyLevel {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl DifficultyLevel {
+    const ALL: [DifficultyLevel; 3] = [
+        DifficultyLevel::Easy,
+        DifficultyLevel::Normal,
+        DifficultyLevel::Hard,
+    ];
+
+    /// Name shown in the menu and stored in settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DifficultyLevel::Easy => "easy",
+            DifficultyLevel::Normal => "normal",
+            DifficultyLevel::Hard => "hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DifficultyLevel> {
+        DifficultyLevel::ALL
+            .iter()
+            .find(|level| level.name() == name)
+            .copied()
+    }
+
+    /// Next harder preset, wrapping around to the easiest.
+    pub fn next(&self) -> DifficultyLevel {
+        let index = DifficultyLevel::ALL
+            .iter()
+            .position(|l| l == self)
+            .unwrap_or(0);
+        DifficultyLevel::ALL[(index + 1) % DifficultyLevel::ALL.len()]
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        match self {
+            DifficultyLevel::Easy => Difficulty {
+                damage_taken: 0.5,
+                fuel_use: 0.5,
+                enemy_spawn_rate: 0.7,
+                collision_damage: 0.5,
+            },
+            DifficultyLevel::Normal => Difficulty::default(),
+            DifficultyLevel::Hard => Difficulty {
+                damage_taken: 1.5,
+                fuel_use: 1.5,
+                enemy_spawn_rate: 1.5,
+                collision_damage: 2.0,
+            },
+        }
+    }
+}
+
+/// Multipliers applied by world systems, set when the scenario is started.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Difficulty {
+    /// Damage taken by player entities, from any source.
+    pub damage_taken: f32,
+    /// Fuel burned by thrusters.
+    pub fuel_use: f32,
+    /// Raiders in each survival wave. Pauses between waves are shorter by as much.
+    pub enemy_spawn_rate: f32,
+    /// Damage dealt by collisions, to all entities.
+    pub collision_damage: f32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty {
+            damage_taken: 1.0,
+            fuel_use: 1.0,
+            enemy_spawn_rate: 1.0,
+            collision_damage: 1.0,
+        }
+    }
+}
@@ -1,18 +1,38 @@
-use super::{Insist, Block};
-use crate::math::{polygon::Polygon, vec::*};
+use super::{Insist, Block, Material};
+use crate::math::{
+    bounding_box::{BoundingBox, RectBounds},
+    bvh::Bvh,
+    polygon::Polygon,
+    shape::Shape,
+    vec::*,
+};
+use crate::error::Error;
+use crate::paths::Paths;
+use crate::persistence::{self, blueprint::BlueprintMeta, LoadError};
 use crate::ui::user_controls::Action;
 use gamemath::{Mat2, Mat3, Vec2, Vec3};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use std::io::Write;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
 
 const ENTITY_SHAPE_DENSITY: f32 = 0.02;
 
+/// Ticks an entity has to be still before it falls asleep.
+const SLEEP_TICKS: u32 = 60;
+
+/// Entities slower than this, and spinning slower than `SLEEP_SPIN`, are still.
+const SLEEP_SPEED: f32 = 0.01;
+const SLEEP_SPIN: f32 = 0.0005;
+
+/// Rigid body made of a hull and blocks, with what physics and building work on:
+/// placement, shape, blocks and their damage. Gameplay state, such as faction, health,
+/// cargo and scripts, is kept in `Components` by the id of the entity.
 #[serde_as]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Entity {
     id: u64,
-    pub shape: Polygon,
+    pub shape: Shape,
 
     #[serde_as(as = "Insist<Vec2Serde<f32>>")]
     pub position: Insist<Vec2<f32>>,
@@ -23,17 +43,65 @@ pub struct Entity {
     // calculated values
     pub mass: f32,
     pub mass_angular: f32,
+
+    /// Name given by the player when saving as a blueprint.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Damage of each block, from 0 when intact to 1 when wrecked.
+    /// Blocks past the end of the list are intact.
+    #[serde(default)]
+    damage: Vec<f32>,
+
+    /// Surface of the hull, which blocks with a material of their own cover.
+    #[serde(default)]
+    pub material: Material,
+
+    /// Red, green and blue of the outline, only set by editing a blueprint exported as text.
+    /// Without one, the outline is tinted by the faction of the entity.
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
+
+    #[serde(skip)]
+    grid_shape: Option<GridShape>,
+
+    /// Convex pieces of the shape, decomposed when first needed.
+    #[serde(skip)]
+    convex_pieces: Option<Vec<Shape>>,
+
+    /// Consecutive ticks the entity was still, up to `SLEEP_TICKS`.
+    #[serde(skip)]
+    still_ticks: u32,
+
+    /// Bounds of the blocks, rebuilt when they are moved by `redistribute_weight`.
+    #[serde(skip)]
+    block_tree: Bvh,
+
+    /// Change of position and angle in the last tick, to draw the entity between ticks.
+    /// Kept as a change, so it holds when the grid's frame of reference shifts.
+    #[serde(skip)]
+    last_motion: (Vec2<f32>, f32),
+}
+
+/// Shape projected to grid coordinates, with the placement it was projected for.
+#[derive(Debug, Clone)]
+struct GridShape {
+    position: Vec2<f32>,
+    angle: f32,
+    shape: Shape,
+    /// Convex pieces of the shape, projected along with it.
+    pieces: Vec<Shape>,
 }
 
 impl Entity {
-    pub fn new(poly: Polygon, blocks: Vec<Box<dyn Block>>) -> Entity {
+    pub fn new(shape: impl Into<Shape>, blocks: Vec<Box<dyn Block>>) -> Entity {
         use rand::RngCore;
         let mut rng = rand::thread_rng();
 
         let mut result = Entity {
             id: rng.next_u64(),
 
-            shape: poly,
+            shape: shape.into(),
             position: Insist::default(),
             angle: Insist::default(),
 
@@ -41,11 +109,27 @@ impl Entity {
 
             mass: 0.0,
             mass_angular: 0.0,
+
+            name: None,
+            damage: Vec::new(),
+            material: Material::default(),
+            color: None,
+            grid_shape: None,
+            convex_pieces: None,
+            still_ticks: 0,
+            block_tree: Bvh::default(),
+            last_motion: (Vec2::default(), 0.0),
         };
         result.redistribute_weight();
         result
     }
 
+    /// Keeps the identity of an entity that was saved before.
+    pub fn with_id(mut self, id: u64) -> Entity {
+        self.id = id;
+        self
+    }
+
     pub fn new_from_block(mut block: Box<dyn Block>) -> Entity {
         block.set_offset(Vec2::default());
         block.set_angle(0.0);
@@ -60,6 +144,16 @@ impl Entity {
     }
 
     pub fn apply_action(&mut self, action: Action) {
+        // blocks shut down every tick on entities nobody controls, which must still sleep
+        let shuts_down = matches!(
+            action,
+            Action::Accelerate { throttle, .. }
+                | Action::Rotate { throttle, .. }
+                | Action::FireBlock { throttle, .. } if throttle == 0.0
+        );
+        if !shuts_down {
+            self.wake();
+        }
         match action {
             Action::Accelerate { .. } => {
                 // let rotation = Mat2::rotation(-self.angle.state);
@@ -73,30 +167,69 @@ impl Entity {
                     block.apply_action(&action);
                 }
             }
+            Action::FireBlock { index, .. } => {
+                if let Some(block) = self.blocks.get_mut(index) {
+                    block.apply_action(&action);
+                }
+            }
             Action::UpdateShape { new_shape } => {
                 let transform =
                     Mat3::rotation(-self.angle.state) * translation(-self.position.state);
                 self.expand_shape(transform * *new_shape);
                 self.redistribute_weight();
             }
+            Action::CutShape { cut } => {
+                let transform =
+                    Mat3::rotation(-self.angle.state) * translation(-self.position.state);
+                self.cut_shape(transform * *cut);
+                self.redistribute_weight();
+            }
+            Action::ShrinkWrapHull => {
+                self.set_shape(self.shape.to_polygon().convex_hull());
+                self.redistribute_weight();
+            }
             Action::JoinEntity { mut entity } => {
+                let damage: Vec<f32> = (0..entity.blocks.len())
+                    .map(|index| entity.block_damage(index))
+                    .collect();
                 let transform = Mat3::rotation(-self.angle.state)
                     * translation(entity.position.state - self.position.state)
                     * Mat3::rotation(entity.angle.state);
 
-                self.expand_shape(transform * entity.shape);
+                self.expand_shape(transform * entity.shape.to_polygon().into_owned());
 
-                for mut block in entity.blocks.drain(..) {
+                for (mut block, damage) in entity.blocks.drain(..).zip(damage) {
                     block.set_offset(
                         (transform * block.offset().into_homogeneous()).into_cartesian(),
                     );
                     block.set_angle(block.angle() + entity.angle.state - self.angle.state);
+                    let index = self.blocks.len();
                     self.add_block(block);
+                    if index < self.blocks.len() {
+                        self.damage_block(index, damage);
+                    }
                 }
                 self.redistribute_weight();
             }
-            Action::SaveEntity => {
-                self.save_to_file().ok();
+            Action::MoveBlock {
+                index,
+                offset,
+                angle,
+            } => {
+                let transform =
+                    Mat3::rotation(-self.angle.state) * translation(-self.position.state);
+                let offset = (transform * offset.into_homogeneous()).into_cartesian();
+
+                if self.move_block(index, offset, angle - self.angle.state) {
+                    self.redistribute_weight();
+                }
+            }
+            Action::RemoveBlock { index } if index < self.blocks.len() => {
+                self.blocks.remove(index);
+                if index < self.damage.len() {
+                    self.damage.remove(index);
+                }
+                self.redistribute_weight();
             }
             _ => {}
         }
@@ -111,23 +244,90 @@ impl Entity {
         // }
     }
 
+    pub fn block_damage(&self, index: usize) -> f32 {
+        self.damage.get(index).copied().unwrap_or(0.0)
+    }
+
+    /// Adds damage to the block at `index`, or repairs it when negative.
+    pub fn damage_block(&mut self, index: usize, amount: f32) {
+        if index >= self.blocks.len() || amount == 0.0 {
+            return;
+        }
+        if self.damage.len() <= index {
+            self.damage.resize(index + 1, 0.0);
+        }
+        self.damage[index] = (self.damage[index] + amount).clamp(0.0, 1.0);
+    }
+
     pub fn add_block(&mut self, block: Box<dyn Block>) {
-        let block_shape = block.transform() * Mat3::identity().scaled(Vec2::new(0.999, 0.999)) * block.shape().clone();
+        if self.can_place_block(block.as_ref(), None) {
+            self.blocks.push(block);
+        }
+    }
+
+    /// Moves block at `index` to a new offset and angle (in entity coordinates).
+    /// Returns false and leaves the block in place if the new placement is invalid.
+    pub fn move_block(&mut self, index: usize, offset: Vec2<f32>, angle: f32) -> bool {
+        if index >= self.blocks.len() {
+            return false;
+        }
+        let block = &mut self.blocks[index];
+        let (old_offset, old_angle) = (block.offset(), block.angle());
+
+        block.set_offset(offset);
+        block.set_angle(angle);
+
+        let valid = self.can_place_block(self.blocks[index].as_ref(), Some(index));
+        if !valid {
+            let block = &mut self.blocks[index];
+            block.set_offset(old_offset);
+            block.set_angle(old_angle);
+        }
+        valid
+    }
+
+    /// Block must be within the hull and must not overlap other blocks, except the block
+    /// at `skip`, which is the one being moved.
+    fn can_place_block(&self, block: &dyn Block, skip: Option<usize>) -> bool {
+        let block_shape = block.transform()
+            * Mat3::identity().scaled(Vec2::new(0.999, 0.999))
+            * block.shape().to_polygon().into_owned();
 
         if !self.shape.contains_polygon(&block_shape) {
-            return;
+            return false;
         }
 
-        for b in &self.blocks {
-            let s = b.transform() * b.shape().clone();
+        for index in self.blocks_near(&block_shape.bounding_box()) {
+            if Some(index) == skip {
+                continue;
+            }
+            let s = self.blocks[index].outline();
             for p in &s.points {
                 if block_shape.contains_point(p.into_cartesian()) {
-                    return;
+                    return false;
                 }
             }
         }
+        true
+    }
+
+    /// Indices of blocks whose bounds overlap `bounds`, in coordinates of the entity,
+    /// in order. Blocks added since the tree of blocks was rebuilt are all included.
+    pub fn blocks_near(&self, bounds: &RectBounds) -> Vec<usize> {
+        let mut near = self.block_tree.query(bounds);
+        near.retain(|index| *index < self.blocks.len());
+        near.extend(self.block_tree.leaves().min(self.blocks.len())..self.blocks.len());
+        near.sort_unstable();
+        near
+    }
 
-        self.blocks.push(block);
+    fn update_block_tree(&mut self) {
+        let bounds = self
+            .blocks
+            .iter()
+            .map(|block| block.outline().bounding_box())
+            .collect();
+        self.block_tree = Bvh::new(bounds);
     }
 
     pub fn tick(&mut self) {
@@ -136,30 +336,173 @@ impl Entity {
         }
     }
 
+    /// Whether the entity has been still for a while. The engine does not move sleeping
+    /// entities nor check them for collisions with each other.
+    pub fn is_asleep(&self) -> bool {
+        self.still_ticks >= SLEEP_TICKS
+    }
+
+    pub fn wake(&mut self) {
+        self.still_ticks = 0;
+    }
+
+    /// Whether the entity barely moves.
+    pub fn is_still(&self) -> bool {
+        self.position.velocity.length() < SLEEP_SPEED && self.angle.velocity.abs() < SLEEP_SPIN
+    }
+
+    /// Remembers how the entity moved in this tick, from where it was at its start.
+    pub fn record_motion(&mut self, position: Vec2<f32>, angle: f32) {
+        self.last_motion = (self.position.state - position, self.angle.state - angle);
+    }
+
+    /// Position and angle between the previous tick, at `alpha` 0, and this one, at 1.
+    pub fn interpolated(&self, alpha: f32) -> (Vec2<f32>, f32) {
+        let (motion, turn) = self.last_motion;
+        (
+            self.position.state - motion * (1.0 - alpha),
+            self.angle.state - turn * (1.0 - alpha),
+        )
+    }
+
+    /// Counts ticks in which the entity was still and stops it completely once it falls
+    /// asleep.
+    pub fn update_sleep(&mut self) {
+        if !self.is_still() {
+            self.still_ticks = 0;
+            return;
+        }
+        self.still_ticks = (self.still_ticks + 1).min(SLEEP_TICKS);
+        if self.is_asleep() {
+            self.position.velocity = Vec2::default();
+            self.angle.velocity = 0.0;
+        }
+    }
+
+    /// Replaces the shape, dropping what was cached for the old one.
+    pub fn set_shape(&mut self, shape: impl Into<Shape>) {
+        self.shape = shape.into();
+        self.shape_changed();
+    }
+
+    fn shape_changed(&mut self) {
+        self.grid_shape = None;
+        self.convex_pieces = None;
+    }
+
+    /// Convex pieces covering the shape, in coordinates of the entity.
+    pub fn convex_pieces(&mut self) -> &[Shape] {
+        let shape = &self.shape;
+        self.convex_pieces
+            .get_or_insert_with(|| shape.decompose_convex())
+    }
+
+    /// Shape in grid coordinates, if it has been projected since the entity last moved.
+    pub fn grid_shape(&self) -> Option<&Shape> {
+        self.grid_shape
+            .as_ref()
+            .filter(|cached| {
+                cached.position == self.position.state && cached.angle == self.angle.state
+            })
+            .map(|cached| &cached.shape)
+    }
+
+    /// Convex pieces of the shape in grid coordinates, projected along with `grid_shape`.
+    pub fn grid_pieces(&self) -> Option<&[Shape]> {
+        self.grid_shape()?;
+        self.grid_shape.as_ref().map(|cached| &cached.pieces[..])
+    }
+
+    /// Projects the shape to grid coordinates, unless the projection is up to date.
+    pub fn update_grid_shape(&mut self) {
+        if self.grid_shape().is_some() {
+            return;
+        }
+        let mut cached = self.grid_shape.take().unwrap_or(GridShape {
+            position: Vec2::default(),
+            angle: 0.0,
+            shape: Shape::default(),
+            pieces: Vec::new(),
+        });
+        cached.position = self.position.state;
+        cached.angle = self.angle.state;
+        let projection = self.projection_to_grid();
+        self.shape.transform_into(projection, &mut cached.shape);
+
+        let pieces = self.convex_pieces();
+        cached.pieces.resize_with(pieces.len(), Shape::default);
+        for (piece, out) in pieces.iter().zip(&mut cached.pieces) {
+            piece.transform_into(projection, out);
+        }
+        self.grid_shape = Some(cached);
+    }
+
     pub fn expand_shape(&mut self, new_shape: Polygon) {
-        let mut polygons = self.shape.clone().intersection(new_shape);
+        let mut polygons = self.shape.to_polygon().into_owned().intersection(new_shape);
 
-        for poly in polygons.drain(..) {
+        for mut poly in polygons.drain(..) {
+            // merging can leave an outline that crosses itself
+            if !poly.repair() {
+                continue;
+            }
             if poly.contains_point(Vec2::new(0.0, 0.0)) {
                 // let (old_area, _) = self.shape.area_and_centroid();
-                self.shape = poly;
+                self.set_shape(poly);
 
                 // let (new_area, _) = self.shape.area_and_centroid();
             }
         }
     }
 
-    pub fn force(&self) -> ForcePoint {
-        let mut result = ForcePoint::default();
+    /// Drops vertices of the shape that barely change it, unless that would uncover a block.
+    pub fn simplify_shape(&mut self, tolerance: f32) {
+        if tolerance <= 0.0 {
+            return;
+        }
+        // round shapes have no vertices to drop
+        let mut shape = match self.shape.polygon() {
+            Some(polygon) => polygon.clone(),
+            None => return,
+        };
+        let vertices = shape.points.len();
+        shape.simplify(tolerance);
+        if shape.points.len() == vertices {
+            return;
+        }
+        let covers_blocks = self
+            .blocks
+            .iter()
+            .all(|block| shape.contains_polygon(&block.outline()));
+        if covers_blocks {
+            self.set_shape(shape);
+            self.redistribute_weight();
+        }
+    }
 
-        for block in &self.blocks {
-            let mut force_point = block.force();
-            force_point.force = Mat2::rotation(block.angle()) * force_point.force;
-            force_point.add_force_torque(block.offset());
+    /// Removes `cut` from the shape. The piece that remains must hold all blocks,
+    /// otherwise the shape is kept. Without blocks, the largest piece remains.
+    pub fn cut_shape(&mut self, cut: Polygon) {
+        let pieces = self.shape.to_polygon().into_owned().difference(cut);
 
-            result += force_point;
+        let piece = if self.blocks.is_empty() {
+            pieces.into_iter().max_by(|a, b| {
+                let area = |p: &Polygon| p.area_and_centroid().0.abs();
+                area(a).partial_cmp(&area(b)).unwrap_or(Ordering::Equal)
+            })
+        } else {
+            pieces.into_iter().find(|piece| {
+                self.blocks
+                    .iter()
+                    .all(|block| piece.contains_polygon(&block.outline()))
+            })
+        };
+        if let Some(piece) = piece {
+            self.set_shape(piece);
         }
+    }
 
+    pub fn force(&self) -> ForcePoint {
+        let mut result = blocks_force(&self.blocks);
         result.force = Mat2::rotation(self.angle.state) * result.force;
         result
     }
@@ -170,7 +513,9 @@ impl Entity {
         for block in &mut self.blocks {
             block.set_offset(block.offset() - mass_point.point);
         }
-        self.shape = translation(-mass_point.point) * self.shape.clone();
+        self.shape.transform(translation(-mass_point.point));
+        self.shape_changed();
+        self.update_block_tree();
 
         self.position.state += mass_point.point;
 
@@ -183,7 +528,7 @@ impl Entity {
         for block in &self.blocks {
             sum += block.mass() * block.offset().length_squared();
         }
-        let shape_mass = self.shape.area_and_centroid().0.abs() * ENTITY_SHAPE_DENSITY;
+        let shape_mass = self.shape.area_and_centroid().0.abs() * self.shape_density();
         sum += shape_mass * self.shape.radius_of_gyration(Vec2::default());
         sum
     }
@@ -192,7 +537,7 @@ impl Entity {
         let (shape_area, centroid) = self.shape.area_and_centroid();
         let mut result = MassPoint {
             point: centroid,
-            mass: shape_area.abs() * ENTITY_SHAPE_DENSITY,
+            mass: shape_area.abs() * self.shape_density(),
         };
 
         for block in &self.blocks {
@@ -205,44 +550,123 @@ impl Entity {
         result
     }
 
+    fn shape_density(&self) -> f32 {
+        self.material.density.unwrap_or(ENTITY_SHAPE_DENSITY)
+    }
+
+    /// Material of the surface at `point` in grid coordinates: of the block covering it,
+    /// or of the hull.
+    pub fn material_at(&self, point: Vec2<f32>) -> Material {
+        let local = Mat2::rotation(-self.angle.state) * (point - self.position.state);
+        self.blocks_near(&RectBounds::new(local))
+            .into_iter()
+            .map(|index| &self.blocks[index])
+            .find_map(|block| {
+                let in_block = Mat2::rotation(-block.angle()) * (local - block.offset());
+                block
+                    .material()
+                    .filter(|_| block.shape().contains_point(in_block))
+            })
+            .unwrap_or(self.material)
+    }
+
     pub fn projection_to_grid(&self) -> Mat3 {
         translation(self.position.state) * Mat3::rotation(self.angle.state)
     }
 
-    pub fn save_to_file(&self) -> Result<(), std::io::Error> {
-        let bytes = rmp_serde::to_vec(self).unwrap();
+    pub fn save_to_file(&self, paths: &Paths) -> Result<(), Error> {
+        let bytes = persistence::encode(self)?;
+
+        let filename = paths.entities()?.join(self.file_name());
+
+        persistence::write_atomic(&filename, &bytes)?;
+        Ok(BlueprintMeta::of(self).write(&filename)?)
+    }
+
+    /// Named entities are saved under their name, others under their id.
+    pub fn file_name(&self) -> String {
+        let name: Option<String> = self.name.as_ref().map(|name| {
+            name.chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '-' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect()
+        });
+        name.filter(|name| !name.is_empty())
+            .unwrap_or_else(|| self.id.to_string())
+    }
+
+    /// Text files are recognized by their extension.
+    pub fn load_from_file(filename: std::ffi::OsString) -> Result<Entity, LoadError> {
+        if is_text_file(Path::new(&filename)) {
+            return Entity::import_ron(&std::fs::read_to_string(filename)?);
+        }
+        let bytes = std::fs::read(filename)?;
 
-        let filename = "./data/entities/".to_owned() + &self.id.to_string();
+        persistence::decode_entity(&bytes)
+    }
 
-        let mut file = std::fs::File::create(filename)?;
-        file.write_all(&bytes)?;
+    /// Human-readable form, for editing blueprints by hand and sharing them as text.
+    pub fn export_ron(&self) -> Result<String, Error> {
+        Ok(persistence::encode_text(self)?)
+    }
 
-        Ok(())
+    pub fn import_ron(text: &str) -> Result<Entity, LoadError> {
+        persistence::decode_entity_text(text)
     }
 
-    pub fn load_from_file(
-        filename: std::ffi::OsString,
-    ) -> Result<Entity, rmp_serde::decode::Error> {
-        let bytes = std::fs::read(filename).unwrap_or_else(|_| Vec::new());
+    /// Writes the entity as text next to the saved entities, where it is listed as well.
+    pub fn save_ron_to_file(&self, paths: &Paths) -> Result<PathBuf, Error> {
+        let text = self.export_ron()?;
 
-        rmp_serde::from_read_ref(&bytes)
+        let filename = paths
+            .entities()?
+            .join(self.file_name())
+            .with_extension(persistence::TEXT_EXTENSION);
+
+        persistence::write_atomic(&filename, text.as_bytes())?;
+        BlueprintMeta::of(self).write(&filename)?;
+        Ok(filename)
     }
 
-    pub fn list_saved() -> Result<Vec<std::ffi::OsString>, std::io::Error> {
-        let res = std::fs::read_dir("./data/entities")?;
+    /// Saved entities have no extension, which excludes backups and unfinished writes.
+    /// Entities saved as text are the exception.
+    pub fn list_saved(paths: &Paths) -> Result<Vec<std::ffi::OsString>, std::io::Error> {
+        let res = std::fs::read_dir(paths.entities()?)?;
 
         Ok(res
-            .filter(|e| {
-                e.as_ref()
-                    .map(|e| e.file_type().unwrap())
-                    .map(|t| t.is_file())
-                    .unwrap_or(false)
-            })
-            .map(|e| e.unwrap().path().into_os_string())
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+            .map(|e| e.path())
+            .filter(|path| path.extension().is_none() || is_text_file(path))
+            .map(|path| path.into_os_string())
             .collect())
     }
 }
 
+fn is_text_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == persistence::TEXT_EXTENSION)
+}
+
+/// Force and torque of `blocks`, relative to the entity they belong to.
+pub fn blocks_force(blocks: &[Box<dyn Block>]) -> ForcePoint {
+    let mut result = ForcePoint::default();
+
+    for block in blocks {
+        let mut force_point = block.force();
+        force_point.force = Mat2::rotation(block.angle()) * force_point.force;
+        force_point.add_force_torque(block.offset());
+
+        result += force_point;
+    }
+    result
+}
+
 impl PartialEq<u64> for Entity {
     fn eq(&self, right: &u64) -> bool {
         self.id == *right
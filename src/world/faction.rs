@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// Who an entity belongs to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Faction {
+    #[default]
+    Neutral,
+    Player,
+    Hostile,
+    /// Abandoned wrecks, which can be towed for contracts.
+    Derelict,
+}
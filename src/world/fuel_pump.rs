@@ -0,0 +1,74 @@
+use super::Block;
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
+use crate::ui::user_controls::Action;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Fuel pumped per tick, for each width unit.
+const PUMP_RATE: f32 = 0.02;
+
+/// Pumps fuel from the tank of its entity into entities docked to it.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FuelPump {
+    shape: Shape,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    offset: Vec2<f32>,
+    angle: f32,
+    pump_rate: f32,
+}
+
+impl FuelPump {
+    pub fn new(width: f32, offset: Vec2<f32>, angle: f32) -> Self {
+        FuelPump {
+            shape: FuelPump::shape(width).into(),
+            offset,
+            angle,
+            pump_rate: width * PUMP_RATE,
+        }
+    }
+
+    pub fn shape(width: f32) -> Polygon {
+        let p = Polygon::from(
+            &[
+                [-0.2, -0.5],
+                [0.2, -0.5],
+                [0.2, -0.2],
+                [0.5, -0.2],
+                [0.5, 0.5],
+                [-0.5, 0.5],
+                [-0.5, -0.2],
+                [-0.2, -0.2],
+            ][..],
+        );
+        Mat3::identity().scaled(Vec2::new(width, width)) * p
+    }
+}
+
+#[typetag::serde]
+impl Block for FuelPump {
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+    fn set_offset(&mut self, offset: Vec2<f32>) {
+        self.offset = offset;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn pump_rate(&self) -> f32 {
+        self.pump_rate
+    }
+
+    fn apply_action(&mut self, _action: &Action) {}
+}
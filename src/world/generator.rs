@@ -0,0 +1,74 @@
+use super::Block;
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
+use crate::ui::user_controls::Action;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Power produced per tick, for each width unit.
+const POWER_OUTPUT: f32 = 1.0;
+
+/// Powers blocks of its entity.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Generator {
+    shape: Shape,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    offset: Vec2<f32>,
+    angle: f32,
+    power_output: f32,
+}
+
+impl Generator {
+    pub fn new(width: f32, offset: Vec2<f32>, angle: f32) -> Self {
+        Generator {
+            shape: Generator::shape(width).into(),
+            offset,
+            angle,
+            power_output: width * POWER_OUTPUT,
+        }
+    }
+
+    pub fn shape(width: f32) -> Polygon {
+        let p = Polygon::from(
+            &[
+                [-0.5, -0.5],
+                [0.5, -0.5],
+                [0.5, 0.5],
+                [0.1, 0.5],
+                [0.1, 0.2],
+                [-0.1, 0.2],
+                [-0.1, 0.5],
+                [-0.5, 0.5],
+            ][..],
+        );
+        Mat3::identity().scaled(Vec2::new(width, width)) * p
+    }
+}
+
+#[typetag::serde]
+impl Block for Generator {
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+    fn set_offset(&mut self, offset: Vec2<f32>) {
+        self.offset = offset;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn power_output(&self) -> f32 {
+        self.power_output
+    }
+
+    fn apply_action(&mut self, _action: &Action) {}
+}
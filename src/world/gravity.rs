@@ -0,0 +1,186 @@
+use super::{Entity, Material, World};
+use crate::client::EntityId;
+use crate::math::circle::Circle;
+use gamemath::Vec2;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Pull is computed as if entities closer than this were at this distance,
+/// so it stays finite next to the center of a well.
+const MIN_DISTANCE: f32 = 50.0;
+
+/// Body that pulls entities within range towards its center, such as a planet.
+/// Wells themselves are not pulled.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GravityWell {
+    /// Acceleration per tick at a distance of one unit, falling off with the square
+    /// of the distance.
+    pub strength: f32,
+    /// Entities further than this are not pulled.
+    pub range: f32,
+}
+
+impl GravityWell {
+    /// Acceleration towards the well of something at `offset` from its center.
+    pub fn pull(&self, offset: Vec2<f32>) -> Vec2<f32> {
+        let distance = offset.length();
+        if distance > self.range || distance <= f32::EPSILON {
+            return Vec2::default();
+        }
+        let clamped = distance.max(MIN_DISTANCE);
+        offset * (-self.strength / (clamped * clamped) / distance)
+    }
+}
+
+/// Attraction between all entities of a grid, by their mass. Only entities closer than
+/// the cutoff attract each other, so the cost grows with the number of neighbours
+/// rather than with the square of the number of entities.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MutualGravity {
+    /// Acceleration per tick towards an entity of unit mass at a distance of one unit.
+    pub strength: f32,
+    /// Entities further apart than this do not attract each other.
+    pub cutoff: f32,
+}
+
+impl Default for MutualGravity {
+    fn default() -> Self {
+        MutualGravity {
+            strength: 0.05,
+            cutoff: 300.0,
+        }
+    }
+}
+
+impl MutualGravity {
+    /// Velocity each entity gains in one tick. Entities are binned into cells as big as
+    /// the cutoff, so only entities in neighbouring cells are compared.
+    fn accelerations(&self, entities: &[Entity]) -> Vec<Vec2<f32>> {
+        let cell_of = |position: Vec2<f32>| {
+            (
+                (position.x / self.cutoff).floor() as i32,
+                (position.y / self.cutoff).floor() as i32,
+            )
+        };
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (index, entity) in entities.iter().enumerate() {
+            cells
+                .entry(cell_of(entity.position.state))
+                .or_default()
+                .push(index);
+        }
+
+        entities
+            .iter()
+            .enumerate()
+            .map(|(index, entity)| {
+                let (x, y) = cell_of(entity.position.state);
+                let mut acceleration = Vec2::default();
+                for neighbour in (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (x + dx, y + dy))) {
+                    for other in cells.get(&neighbour).into_iter().flatten() {
+                        if *other == index {
+                            continue;
+                        }
+                        let offset = entities[*other].position.state - entity.position.state;
+                        let distance = offset.length();
+                        if distance > self.cutoff || distance <= f32::EPSILON {
+                            continue;
+                        }
+                        let clamped = distance.max(MIN_DISTANCE);
+                        acceleration += offset
+                            * (self.strength * entities[*other].mass
+                                / (clamped * clamped)
+                                / distance);
+                    }
+                }
+                acceleration
+            })
+            .collect()
+    }
+}
+
+/// Well in the frame of one grid.
+struct PlacedWell {
+    entity: u64,
+    position: Vec2<f32>,
+    well: GravityWell,
+}
+
+/// Accelerates entities towards gravity wells within range and, in grids with mutual
+/// gravity, towards each other.
+pub fn tick(world: &mut World) {
+    for grid in world.grids.values_mut() {
+        if let Some(mutual) = grid.mutual_gravity {
+            let accelerations = mutual.accelerations(&grid.entities);
+            for (entity, acceleration) in grid.entities.iter_mut().zip(accelerations) {
+                entity.position.velocity += acceleration;
+            }
+        }
+    }
+
+    if world.components.gravity.iter().next().is_none() {
+        return;
+    }
+    let grid_ids: Vec<u64> = world.grids.keys().copied().collect();
+    for grid_id in grid_ids {
+        let wells = wells_in(world, grid_id);
+        if wells.is_empty() {
+            continue;
+        }
+        let gravity = &world.components.gravity;
+        let grid = world.grids.get_mut(&grid_id).unwrap();
+        for entity in &mut grid.entities {
+            if gravity.get(entity.get_id()).is_none() {
+                entity.position.velocity += acceleration(&wells, entity);
+            }
+        }
+    }
+}
+
+/// Acceleration by gravity on the entity, for the HUD.
+pub fn acceleration_on(world: &World, id: &EntityId) -> Vec2<f32> {
+    match world.get_entity(id) {
+        Some(entity) => acceleration(&wells_in(world, id.grid_id), entity),
+        None => Vec2::default(),
+    }
+}
+
+fn acceleration(wells: &[PlacedWell], entity: &Entity) -> Vec2<f32> {
+    wells
+        .iter()
+        .filter(|placed| placed.entity != entity.get_id())
+        .fold(Vec2::default(), |sum, placed| {
+            sum + placed.well.pull(entity.position.state - placed.position)
+        })
+}
+
+/// Wells of grids related to `grid_id`, with their positions in the frame of that grid.
+fn wells_in(world: &World, grid_id: u64) -> Vec<PlacedWell> {
+    let mut placed = Vec::new();
+    for relation in world.get_relations(grid_id, Default::default()) {
+        let grid = &world.grids[&relation.id];
+        for entity in &grid.entities {
+            if let Some(well) = world.components.gravity.get(entity.get_id()) {
+                placed.push(PlacedWell {
+                    entity: entity.get_id(),
+                    position: relation.position.state + entity.position.state,
+                    well: *well,
+                });
+            }
+        }
+    }
+    placed
+}
+
+/// Round body with a gravity well at its center. Its density makes it too heavy to be
+/// pushed around by collisions.
+pub fn construct_planet(radius: f32) -> Entity {
+    let mut planet = Entity::new(Circle::new(Vec2::default(), radius), vec![]);
+    planet.material = Material {
+        restitution: 0.2,
+        friction: 0.8,
+        density: Some(1000.0),
+    };
+    planet.redistribute_weight();
+    planet
+}
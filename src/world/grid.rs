@@ -1,24 +1,65 @@
-use super::{Entity, Insist, Thruster};
+use super::{
+    checksum::Checksum,
+    combat,
+    difficulty::Difficulty,
+    gravity::{self, MutualGravity},
+    industry, life_support, supply,
+    survival::Survival,
+    trade,
+    tutorial::{self, Tutorial},
+    BlockRegistry, Cockpit, Components, Entity, Faction, FuelPump, Generator, Gyroscope, Insist,
+    LifeSupport, Thruster,
+};
 use crate::client::EntityId;
+use crate::error::Error;
+use crate::events::{Events, WorldEvent};
+use crate::persistence::{self, LoadError};
+use crate::ui::user_controls::Action;
 use crate::math::{
     bounding_box::{BoundingBox, RectBounds},
-    polygon::{construct_rect_poly_centered, Polygon},
+    polygon::{construct_convex_hull, construct_rect_poly_centered, Polygon},
+    segment::Segment,
     vec::*,
 };
-use gamemath::Vec2;
+use gamemath::{Mat2, Vec2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use std::collections::HashMap;
 use std::ops::Add;
+use std::path::Path;
 
 const GRID_SPLIT_DISTANCE: f32 = 500.0;
 const GRID_JOIN_DISTANCE: f32 = GRID_SPLIT_DISTANCE * 0.5;
 
-#[derive(Debug)]
+/// Entities move to another grid when they are closer to its center than to the center
+/// of their own grid by this ratio. Below 1, so entities do not move back and forth.
+const MIGRATION_RATIO: f32 = 0.75;
+
+/// Bounds only grow as entities move, so they are recomputed this often to shrink again.
+const BOUNDS_REFIT_TICKS: u32 = 60;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Grid {
     id: u64,
     parent: Option<GridRelation>,
     children: Vec<u64>,
 
     pub entities: Vec<Entity>,
+
+    /// Makes entities of the grid attract each other, kept by grids split off from it.
+    #[serde(default)]
+    pub mutual_gravity: Option<MutualGravity>,
+
+    #[serde(skip)]
+    bounds: Option<RectBounds>,
+    #[serde(skip)]
+    ticks_since_refit: u32,
+
+    /// Points where entities touched in the last tick, in coordinates of the grid.
+    #[serde(skip)]
+    pub contacts: Vec<Vec2<f32>>,
 }
 
 impl Grid {
@@ -31,9 +72,17 @@ impl Grid {
             parent,
             children: Vec::new(),
             entities,
+            mutual_gravity: None,
+            bounds: None,
+            ticks_since_refit: 0,
+            contacts: Vec::new(),
         }
     }
 
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
     pub fn get_entity_mut(&mut self, entity_id: u64) -> Option<&mut Entity> {
         for entity in &mut self.entities {
             if entity.get_id() == entity_id {
@@ -56,8 +105,7 @@ impl Grid {
         let bounds = self.bounding_box();
         let closest_edge = bounds
             .polygon()
-            .to_segments()
-            .iter()
+            .segments()
             .map(|segment| {
                 let alpha = segment.project_point(position);
                 let perpendicular = ((segment.direction() * alpha) + segment.a) - position;
@@ -82,6 +130,36 @@ impl Grid {
         }
     }
 
+    /// Bounds of entities in grid coordinates. May be larger than needed since the last refit.
+    pub fn bounds(&self) -> RectBounds {
+        self.bounds.clone().unwrap_or_else(|| self.bounding_box())
+    }
+
+    /// Grows bounds to include entities at their current placement.
+    /// Expects grid shapes of entities to be up to date.
+    pub fn update_bounds(&mut self) {
+        self.ticks_since_refit += 1;
+        if self.ticks_since_refit >= BOUNDS_REFIT_TICKS {
+            self.ticks_since_refit = 0;
+            self.bounds = None;
+        }
+
+        let mut bounds = self.bounds.take();
+        for entity in &self.entities {
+            let entity_bounds = match entity.grid_shape() {
+                Some(shape) => shape.bounding_box(),
+                None => entity
+                    .shape
+                    .bounding_box_transformed(&entity.projection_to_grid()),
+            };
+            match &mut bounds {
+                Some(bounds) => *bounds += entity_bounds,
+                None => bounds = Some(entity_bounds),
+            }
+        }
+        self.bounds = bounds;
+    }
+
     fn get_common_insist(&self) -> Insist<Vec2<f32>> {
         Insist::get_common(self.entities.iter().map(|e| &e.position).collect())
     }
@@ -90,6 +168,9 @@ impl Grid {
         for entity in &mut self.entities {
             entity.position += insist;
         }
+        if let Some(bounds) = &mut self.bounds {
+            bounds.translate(insist.state);
+        }
     }
 
     pub fn tick_parent_relation(&mut self) {
@@ -99,11 +180,7 @@ impl Grid {
     }
 
     fn should_split(&self) -> bool {
-        let mut bounding_box = RectBounds::default();
-        for entity in &self.entities {
-            bounding_box += entity.position.state;
-        }
-        bounding_box.size() > GRID_SPLIT_DISTANCE
+        self.bounds().size() > GRID_SPLIT_DISTANCE
     }
 
     fn split_by_position(&mut self) -> Option<Grid> {
@@ -115,8 +192,11 @@ impl Grid {
         let (a, b) = self.get_most_distanced_entities();
         let (parent_entities, child_entities) = Grid::segment_to_closest(&mut self.entities, a, b);
         self.entities = parent_entities;
+        self.bounds = None;
 
-        Some(Grid::new(Some(GridRelation::new(self.id)), child_entities))
+        let mut child = Grid::new(Some(GridRelation::new(self.id)), child_entities);
+        child.mutual_gravity = self.mutual_gravity;
+        Some(child)
     }
 
     fn segment_to_closest(
@@ -252,17 +332,99 @@ impl Grid {
     // }
 }
 
+/// Default of `World::shape_tolerance`, small next to blocks.
+pub const DEFAULT_SHAPE_TOLERANCE: f32 = 0.5;
+
+#[derive(Serialize, Deserialize)]
 pub struct World {
     pub grids: HashMap<u64, Grid>,
+    pub components: Components,
+    /// Set in the wave-survival scenario.
+    #[serde(default)]
+    pub survival: Option<Survival>,
+    /// Set in the tutorial scenario.
+    #[serde(default)]
+    pub tutorial: Option<Tutorial>,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Vertices of merged hulls closer than this to the simplified outline are dropped.
+    /// Zero keeps them all.
+    #[serde(default = "default_shape_tolerance")]
+    pub shape_tolerance: f32,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World {
+            grids: HashMap::new(),
+            components: Components::default(),
+            survival: None,
+            tutorial: None,
+            difficulty: Difficulty::default(),
+            shape_tolerance: DEFAULT_SHAPE_TOLERANCE,
+        }
+    }
 }
 
+fn default_shape_tolerance() -> f32 {
+    DEFAULT_SHAPE_TOLERANCE
+}
+
+/// Motion absorbed into frames of reference of grids, by grid id.
+pub type FrameShifts = HashMap<u64, Insist<Vec2<f32>>>;
+
 impl World {
+    /// Hash of grids and entities in them, equal for worlds in the same state.
+    /// Used to detect when a copy of the world diverges from the original.
+    pub fn checksum(&self) -> u64 {
+        let mut checksum = Checksum::new();
+
+        let mut grids: Vec<&Grid> = self.grids.values().collect();
+        grids.sort_by_key(|g| g.id);
+        for grid in grids {
+            checksum.write_u64(grid.id);
+            if let Some(parent) = &grid.parent {
+                checksum.write_u64(parent.id);
+                checksum.write_f32(parent.position.state.x);
+                checksum.write_f32(parent.position.state.y);
+            }
+
+            let mut entities: Vec<&Entity> = grid.entities.iter().collect();
+            entities.sort_by_key(|e| e.get_id());
+            for entity in entities {
+                checksum.write_u64(entity.get_id());
+                checksum.write_f32(entity.position.state.x);
+                checksum.write_f32(entity.position.state.y);
+                checksum.write_f32(entity.position.velocity.x);
+                checksum.write_f32(entity.position.velocity.y);
+                checksum.write_f32(entity.angle.state);
+                checksum.write_f32(entity.angle.velocity);
+                checksum.write_u64(entity.shape.polygon().map_or(0, |p| p.points.len()) as u64);
+                checksum.write_u64(entity.blocks.len() as u64);
+            }
+        }
+        checksum.finish()
+    }
+
+    /// Reads a world saved by this or an older build.
+    pub fn load_from_file(path: &Path) -> Result<World, LoadError> {
+        let bytes = std::fs::read(path)?;
+
+        persistence::decode_world(&bytes)
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<(), Error> {
+        let bytes = persistence::encode(self)?;
+
+        Ok(persistence::write_atomic(path, &bytes)?)
+    }
+
     pub fn grid_ids(&self) -> Vec<u64> {
         self.grids.keys().copied().collect()
     }
 
-    pub fn absorb_common_insist(&mut self, focused_grid: u64) -> Option<Insist<Vec2<f32>>> {
-        let mut res = None;
+    pub fn absorb_common_insists(&mut self) -> FrameShifts {
+        let mut res = HashMap::new();
         for grid_id in &self.grid_ids() {
             let insist = self.grids[grid_id].get_common_insist();
 
@@ -288,9 +450,7 @@ impl World {
                 }
             }
 
-            if grid_id == &focused_grid {
-                res = Some(insist);
-            }
+            res.insert(*grid_id, insist);
         }
         res
     }
@@ -307,21 +467,87 @@ impl World {
         }
 
         for grid in new_grids {
-            self.add_grid(grid);
+            if let Err(e) = self.add_grid(grid) {
+                eprintln!("split grid was added as a root: {}", e);
+            }
         }
     }
 
-    pub fn add_grid(&mut self, grid: Grid) {
+    /// Moves single entities to a connected grid whose center they are closer to than
+    /// the center of their own grid, rewriting their position into its frame.
+    /// Grids are left with at least one entity, as emptying them is up to joining.
+    pub fn migrate_entities(&mut self) {
+        let mut transfers = Vec::new();
+        for grid in self.grids.values() {
+            if grid.entities.len() < 2 {
+                continue;
+            }
+            let relations = self.get_relations(grid.id, Insist::default());
+            for entity in &grid.entities {
+                // entities are relative to the center of their grid, where its origin is kept
+                let own = entity.position.state.length();
+                let closest = relations
+                    .iter()
+                    .filter(|r| r.id != grid.id)
+                    .map(|r| (r, (entity.position.state - r.position.state).length()))
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+                if let Some((relation, distance)) = closest {
+                    if distance < own * MIGRATION_RATIO {
+                        transfers.push((grid.id, entity.get_id(), relation.id, relation.position));
+                    }
+                }
+            }
+        }
+
+        for (from, entity_id, to, offset) in transfers {
+            if !self.grids.contains_key(&to) {
+                continue;
+            }
+            let mut entity = match self.grids.get_mut(&from) {
+                Some(grid) if grid.entities.len() > 1 => {
+                    let index = match grid.entities.iter().position(|e| e.get_id() == entity_id) {
+                        Some(index) => index,
+                        None => continue,
+                    };
+                    grid.bounds = None;
+                    grid.entities.remove(index)
+                }
+                _ => continue,
+            };
+            entity.position += -offset;
+            if let Some(grid) = self.grids.get_mut(&to) {
+                grid.entities.push(entity);
+            }
+        }
+    }
+
+    /// Adds a grid as a child of its parent.
+    /// If the parent does not exist, the grid is added without one and an error is returned.
+    pub fn add_grid(&mut self, mut grid: Grid) -> Result<(), Error> {
         let own_id = grid.id;
-        let parent_id = grid.parent.as_ref().map(|p| p.id).unwrap();
-        self.grids.insert(own_id, grid);
+        let parent_id = grid.parent.as_ref().map(|p| p.id);
 
-        let parent = self.grids.get_mut(&parent_id).unwrap();
-        parent.children.push(own_id);
+        let parent = parent_id.map(|id| (id, self.grids.get_mut(&id)));
+        let res = match parent {
+            Some((_, Some(parent))) => {
+                parent.children.push(own_id);
+                Ok(())
+            }
+            Some((id, None)) => {
+                grid.parent = None;
+                Err(Error::MissingGrid(id))
+            }
+            None => Ok(()),
+        };
+        self.grids.insert(own_id, grid);
+        res
     }
 
     pub fn join_grids(&mut self) {
-        let first_grid = self.grids.iter().next().map(|g| *g.0).unwrap();
+        let first_grid = match self.grids.keys().next() {
+            Some(id) => *id,
+            None => return,
+        };
         let relations = self.get_relations(first_grid, Insist::default());
 
         for relation in &relations {
@@ -338,11 +564,12 @@ impl World {
 
             if let Some(join_with) = join_with {
                 if let Some(mut grid) = self.remove_grid(relation.id) {
-                    for c in &mut grid.entities {
-                        c.position += -join_with.1;
-                    }
+                    // the relation carries the frame velocity as well, so world-space
+                    // motion of the entities is unchanged by the merge
+                    grid.offset_entities(-join_with.1);
                     let parent = &mut self.grids.get_mut(&join_with.0).unwrap();
                     parent.entities.extend(grid.entities);
+                    parent.mutual_gravity = parent.mutual_gravity.or(grid.mutual_gravity);
                 }
             }
         }
@@ -356,7 +583,9 @@ impl World {
                 }
 
                 let first_child = self.grids.get_mut(&grid.children.pop().unwrap()).unwrap();
-                let position = first_child.parent.as_ref().unwrap().position;
+                // the child stores the origin of this grid in its frame, but the remaining
+                // children need the origin of the child in the frame of this grid
+                let position = -first_child.parent.as_ref().unwrap().position;
                 first_child.parent = None;
 
                 Some(GridRelation {
@@ -388,11 +617,12 @@ impl World {
         }
     }
 
+    /// Relations of all grids connected to `grid_id`, or none if the grid does not exist.
     pub fn get_relations(&self, grid_id: u64, position: Insist<Vec2<f32>>) -> Vec<GridRelation> {
-        if let Some(p) = &self.grids[&grid_id].parent {
-            self.get_relations(p.id, position + p.position)
-        } else {
-            self.get_descendant_relations(grid_id, position)
+        match self.grids.get(&grid_id).map(|g| &g.parent) {
+            Some(Some(p)) => self.get_relations(p.id, position + p.position),
+            Some(None) => self.get_descendant_relations(grid_id, position),
+            None => Vec::new(),
         }
     }
 
@@ -400,37 +630,140 @@ impl World {
         let mut res = Vec::new();
         res.push(GridRelation { position, id });
 
-        for child_id in self.grids[&id].children.clone() {
+        for child_id in &self.grids[&id].children {
+            let child = match self.grids.get(child_id) {
+                Some(child) => child,
+                None => continue,
+            };
             let child_position = position
-                + self.grids[&child_id]
+                + child
                     .parent
                     .as_ref()
                     .map(|p| -p.position)
                     .unwrap_or_default();
 
-            res.extend(self.get_descendant_relations(child_id, child_position));
+            res.extend(self.get_descendant_relations(*child_id, child_position));
         }
 
         res
     }
 
-    #[allow(dead_code)]
+    /// Origin of grid `b` relative to grid `a`, which is at `relation`.
+    /// Grids that are not connected have no relation.
     pub fn get_relation_between(
         &self,
         a: u64,
         b: u64,
         relation: Insist<Vec2<f32>>,
-    ) -> Insist<Vec2<f32>> {
-        if let Some(p) = &self.grids[&a].parent {
-            self.get_relation_between(p.id, b, relation + p.position)
-        } else if let Some(p) = &self.grids[&b].parent {
-            self.get_relation_between(p.id, b, relation + p.position)
-        } else {
-            relation
+    ) -> Option<Insist<Vec2<f32>>> {
+        self.get_relations(a, relation)
+            .into_iter()
+            .find(|r| r.id == b)
+            .map(|r| r.position)
+    }
+
+    /// Position and velocity of entity `to` relative to entity `from`, in the frame of
+    /// the grid of `from`. The entities may be in different grids.
+    pub fn relative_insist(&self, from: &EntityId, to: &EntityId) -> Option<Insist<Vec2<f32>>> {
+        let relation = self.get_relation_between(from.grid_id, to.grid_id, Insist::default())?;
+        let from = self.get_entity(from)?;
+        let to = self.get_entity(to)?;
+        Some(relation + to.position + -from.position)
+    }
+
+    /// Closest entity to `from` accepted by `accept`, in grids related to the grid of `from`.
+    pub fn nearest_entity(
+        &self,
+        from: &EntityId,
+        accept: impl Fn(u64) -> bool,
+    ) -> Option<EntityId> {
+        self.get_relations(from.grid_id, Insist::default())
+            .into_iter()
+            .flat_map(|relation| {
+                self.grids[&relation.id]
+                    .entities
+                    .iter()
+                    .map(move |e| EntityId::new(relation.id, e.get_id()))
+            })
+            .filter(|id| id.entity_id != from.entity_id && accept(id.entity_id))
+            .filter_map(|id| {
+                let distance = self.relative_insist(from, &id)?.state.length();
+                Some((id, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(id, _)| id)
+    }
+
+    /// Entity whose shape contains the point, given in coordinates of the grid.
+    pub fn entity_at(&self, grid_id: u64, point: Vec2<f32>) -> Option<EntityId> {
+        self.get_relations(grid_id, Insist::default())
+            .into_iter()
+            .find_map(|relation| {
+                let point = point - relation.position.state;
+                self.grids[&relation.id]
+                    .entities
+                    .iter()
+                    .find(|entity| {
+                        let local =
+                            Mat2::rotation(-entity.angle.state) * (point - entity.position.state);
+                        entity.shape.contains_point(local)
+                    })
+                    .map(|entity| EntityId::new(relation.id, entity.get_id()))
+            })
+    }
+
+    /// First entity hit by a ray from `origin`, given in coordinates of the grid, along
+    /// `direction` up to `max_len`. Entities of all grids related to the grid are tested,
+    /// except those containing the origin, so rays cast from an entity do not hit it.
+    pub fn raycast(
+        &self,
+        grid_id: u64,
+        origin: Vec2<f32>,
+        direction: Vec2<f32>,
+        max_len: f32,
+    ) -> Option<RayHit> {
+        if direction.length_squared() <= 0.0 || max_len <= 0.0 {
+            return None;
         }
+        let direction = direction.normalized();
+        let end = origin + direction * max_len;
+
+        self.get_relations(grid_id, Insist::default())
+            .into_iter()
+            .flat_map(|relation| {
+                let offset = relation.position.state;
+                self.grids[&relation.id]
+                    .entities
+                    .iter()
+                    .filter_map(move |entity| {
+                        let rotation = Mat2::rotation(-entity.angle.state);
+                        let local = |p: Vec2<f32>| rotation * (p - offset - entity.position.state);
+                        let segment = Segment::new(local(origin), local(end));
+                        if entity.shape.contains_point(segment.a) {
+                            return None;
+                        }
+                        let (alpha, hit) = entity.shape.intersect_line_segment(segment)?;
+
+                        let normal =
+                            Mat2::rotation(entity.angle.state) * entity.shape.normal_at(hit);
+                        let distance = alpha * max_len;
+                        Some(RayHit {
+                            entity: EntityId::new(relation.id, entity.get_id()),
+                            point: origin + direction * distance,
+                            normal,
+                            distance,
+                        })
+                    })
+            })
+            .min_by(|a, b| {
+                a.distance
+                    .partial_cmp(&b.distance)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
     }
 
-    pub fn find_entity(&self, id: &EntityId) -> EntityId {
+    /// Finds where the entity is now, as it may have moved to another grid.
+    pub fn find_entity(&self, id: &EntityId) -> Option<EntityId> {
         if self
             .grids
             .get(&id.grid_id)
@@ -438,35 +771,155 @@ impl World {
             .flatten()
             .is_some()
         {
-            return *id;
+            return Some(*id);
         }
 
         // entity may have changed grid, search all grids
         for grid in self.grids.values() {
             if let Some(entity) = grid.get_entity(id.entity_id) {
-                return EntityId {
+                return Some(EntityId {
                     grid_id: grid.id,
                     entity_id: entity.get_id(),
-                };
+                });
             }
         }
-        panic!("cannot find controlled entity");
+        None
+    }
+
+    /// Finds the entity of given faction that follows `current` in order of entity ids.
+    pub fn next_entity_of(&self, current: &EntityId, faction: Faction) -> Option<EntityId> {
+        let mut candidates: Vec<EntityId> = self
+            .grids
+            .values()
+            .flat_map(|grid| {
+                grid.entities
+                    .iter()
+                    .filter(|e| self.components.faction(e.get_id()) == faction)
+                    .map(move |e| EntityId::new(grid.id, e.get_id()))
+            })
+            .collect();
+        candidates.sort_by_key(|id| id.entity_id);
+
+        let next = candidates
+            .iter()
+            .position(|id| id.entity_id > current.entity_id)
+            .unwrap_or(0);
+        candidates.get(next).copied()
+    }
+
+    /// Spawns an entity next to the grid of entity `near`.
+    pub fn spawn_entity_near(&mut self, near: &EntityId, entity: Entity) {
+        if let Some(grid) = self.grids.get_mut(&near.grid_id) {
+            let position = grid
+                .get_entity(near.entity_id)
+                .map(|e| e.position.state)
+                .unwrap_or_default();
+            grid.spawn_entity(position, entity);
+        }
+    }
+
+    /// Builds a saved entity next to the entity `builder`, which pays for its blocks.
+    /// The copy gets an id of its own, so it is never mistaken for an existing entity.
+    pub fn spawn_blueprint(&mut self, builder: &EntityId, entity: Entity, blocks: &BlockRegistry) {
+        let entity = entity.with_id(rand::random());
+        if industry::pay_for_entity(self, blocks, builder, &entity) {
+            self.spawn_entity_near(builder, entity);
+        }
+    }
+
+    pub fn get_entity(&self, id: &EntityId) -> Option<&Entity> {
+        self.grids.get(&id.grid_id)?.get_entity(id.entity_id)
+    }
+
+    /// Takes the entity out of its grid and drops its components.
+    pub fn remove_entity(&mut self, id: &EntityId) -> Option<Entity> {
+        let grid = self.grids.get_mut(&id.grid_id)?;
+        let index = grid
+            .entities
+            .iter()
+            .position(|e| e.get_id() == id.entity_id)?;
+        self.components.remove(id.entity_id);
+        Some(grid.entities.remove(index))
     }
 
     pub fn get_entity_mut<'a>(&'a mut self, id: &EntityId) -> Option<&'a mut Entity> {
         self.grids
-            .get_mut(&id.grid_id)
-            .unwrap()
+            .get_mut(&id.grid_id)?
             .get_entity_mut(id.entity_id)
     }
+
+    /// Applies an action of a player, if the entity can pay for the blocks it adds.
+    pub fn apply_action(
+        &mut self,
+        id: &EntityId,
+        action: Action,
+        blocks: &BlockRegistry,
+        events: &mut Events,
+    ) {
+        if let Action::Trade { station, trade } = action {
+            return trade::apply(self, blocks, id, station, trade, events);
+        }
+        if let Action::SetPowerPriorities { priorities } = action {
+            self.components
+                .power_priorities
+                .insert(id.entity_id, priorities);
+            return;
+        }
+        if life_support::is_flight_control(&action) && !life_support::can_control(self, id) {
+            return;
+        }
+        if !industry::pay_for(self, blocks, id, &action) {
+            return;
+        }
+        tutorial::observe(self, &action);
+        if life_support::is_flight_control(&action) {
+            events.publish(WorldEvent::Steered {
+                entity: id.entity_id,
+                action: action.clone(),
+            });
+        }
+        let merges_shape = matches!(
+            action,
+            Action::UpdateShape { .. } | Action::JoinEntity { .. }
+        );
+        let joined_from = match action {
+            Action::JoinEntity { .. } => self.get_entity(id).map(|e| e.blocks.len()),
+            _ => None,
+        };
+        let tolerance = self.shape_tolerance;
+        if let Some(entity) = self.get_entity_mut(id) {
+            entity.apply_action(action);
+            if merges_shape {
+                entity.simplify_shape(tolerance);
+            }
+        }
+        if let Some(first) = joined_from {
+            industry::pay_for_joined(self, blocks, id, first);
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GridRelation {
+    #[serde_as(as = "Insist<Vec2Serde<f32>>")]
     pub position: Insist<Vec2<f32>>,
     pub id: u64,
 }
 
+/// Where a ray cast with `World::raycast` hit an entity.
+#[allow(dead_code)]
+#[derive(Clone)]
+pub struct RayHit {
+    pub entity: EntityId,
+    /// In coordinates of the grid the ray was cast in.
+    pub point: Vec2<f32>,
+    /// Outward unit normal of the outline at the hit.
+    pub normal: Vec2<f32>,
+    /// From the origin of the ray.
+    pub distance: f32,
+}
+
 impl GridRelation {
     pub fn new(id: u64) -> Self {
         GridRelation {
@@ -499,9 +952,32 @@ impl PartialEq<u64> for Grid {
     }
 }
 
-#[allow(dead_code)]
-pub fn construct_demo_world() -> World {
+/// Rectangular hull with a thruster on each side and a crewed cockpit.
+pub fn construct_demo_ship() -> Entity {
+    use std::f32::consts::{FRAC_PI_2, PI};
+    Entity::new(
+        construct_rect_poly_centered(50.0, 70.0),
+        vec![
+            Box::from(Thruster::new(20.0, Vec2::new(0.0, 10.0), 0.0)),
+            Box::from(Thruster::new(20.0, Vec2::new(-10.0, 0.0), FRAC_PI_2)),
+            Box::from(Thruster::new(20.0, Vec2::new(10.0, 0.0), -FRAC_PI_2)),
+            Box::from(Thruster::new(20.0, Vec2::new(0.0, -10.0), PI)),
+            Box::from(Gyroscope::new(20.0, Vec2::new(0.0, 25.0), 0.0)),
+            Box::from(Generator::new(20.0, Vec2::new(0.0, -25.0), 0.0)),
+            Box::from(Cockpit::new(12.0, Vec2::new(-17.0, -25.0), 0.0)),
+            Box::from(LifeSupport::new(12.0, Vec2::new(17.0, -25.0), 0.0)),
+        ],
+    )
+}
+
+/// Ore asteroids scattered around the demo world, placed by the world seed.
+const DEMO_ASTEROIDS: usize = 6;
+
+/// Demo ships, a station and some targets, with asteroids scattered according to `seed`.
+pub fn construct_demo_world(seed: u64) -> World {
+    let mut rng = StdRng::seed_from_u64(seed);
     let mut grids = HashMap::new();
+    let mut components = Components::default();
 
     {
         let mut grid = Grid::new(None, Vec::new());
@@ -635,20 +1111,24 @@ pub fn construct_demo_world() -> World {
             Vec2 { x: 142.0, y: 133.0 },
         ]);
 
-        {
-            use std::f32::consts::{FRAC_PI_2, PI};
-            let entity = Entity::new(
-                a.clone(),
-                vec![
-                    Box::from(Thruster::new(20.0, Vec2::new(0.0, 10.0), 0.0)),
-                    Box::from(Thruster::new(20.0, Vec2::new(-10.0, 0.0), FRAC_PI_2)),
-                    Box::from(Thruster::new(20.0, Vec2::new(10.0, 0.0), -FRAC_PI_2)),
-                    Box::from(Thruster::new(20.0, Vec2::new(0.0, -10.0), PI)),
-                ],
-            );
-
-            grid.entities.push(entity);
-        }
+        let ship = construct_demo_ship();
+        components.factions.insert(ship.get_id(), Faction::Player);
+        components.cargo.insert(
+            ship.get_id(),
+            industry::Cargo {
+                ore: 0.0,
+                material: 100.0,
+                credits: 200.0,
+            },
+        );
+        components.fuel.insert(
+            ship.get_id(),
+            supply::Tank {
+                fuel: 300.0,
+                capacity: 500.0,
+            },
+        );
+        grid.entities.push(ship);
 
         {
             // let mut child = Grid::default();
@@ -660,6 +1140,7 @@ pub fn construct_demo_world() -> World {
             let mut entity = Entity::new(a, vec![]);
 
             entity.position.state = Vec2 { x: 100.0, y: 60.0 };
+            components.factions.insert(entity.get_id(), Faction::Player);
 
             grid.entities.push(entity);
 
@@ -675,12 +1156,138 @@ pub fn construct_demo_world() -> World {
                 vec![],
             );
             entity.angle.state = 1.0;
+            components.ore.insert(entity.get_id(), 1000.0);
 
             grid.entities.push(entity);
         }
 
+        {
+            let mut station = Entity::new(construct_rect_poly_centered(160.0, 160.0), vec![]);
+            station.position.state = Vec2 { x: 400.0, y: 250.0 };
+
+            let mut scout = construct_demo_ship();
+            scout.name = Some("scout".to_owned());
+            components.stations.insert(
+                station.get_id(),
+                trade::Station {
+                    stock: industry::Cargo {
+                        ore: 500.0,
+                        material: 500.0,
+                        credits: 0.0,
+                    },
+                    ore_price: 1.0,
+                    material_price: 3.0,
+                    blueprints: vec![trade::Blueprint {
+                        price: 150.0,
+                        entity: scout,
+                    }],
+                },
+            );
+
+            grid.entities.push(station);
+        }
+
+        {
+            let mut drone = construct_demo_ship();
+            drone.position.state = Vec2 { x: -200.0, y: 0.0 };
+            components
+                .scripts
+                .insert(drone.get_id(), "drone".to_owned());
+            components.factions.insert(drone.get_id(), Faction::Hostile);
+            components
+                .health
+                .insert(drone.get_id(), combat::Health::new(20.0));
+
+            grid.entities.push(drone);
+        }
+
+        {
+            let mut tanker = construct_demo_ship();
+            tanker
+                .blocks
+                .push(Box::new(FuelPump::new(20.0, Vec2::default(), 0.0)));
+            tanker.redistribute_weight();
+            tanker.position.state = Vec2 {
+                x: -350.0,
+                y: -250.0,
+            };
+            tanker.name = Some("tanker".to_owned());
+            components.fuel.insert(
+                tanker.get_id(),
+                supply::Tank {
+                    fuel: 2000.0,
+                    capacity: 2000.0,
+                },
+            );
+
+            grid.entities.push(tanker);
+        }
+
+        {
+            let mut derelict = Entity::new(construct_rect_poly_centered(60.0, 40.0), vec![]);
+            derelict.position.state = Vec2 {
+                x: -150.0,
+                y: 350.0,
+            };
+            derelict.angle.state = 0.4;
+            components
+                .factions
+                .insert(derelict.get_id(), Faction::Derelict);
+
+            grid.entities.push(derelict);
+        }
+
+        {
+            let mut planet = gravity::construct_planet(400.0);
+            planet.position.state = Vec2::new(3000.0, -1000.0);
+            components.gravity.insert(
+                planet.get_id(),
+                gravity::GravityWell {
+                    strength: 8000.0,
+                    range: 2000.0,
+                },
+            );
+
+            grid.entities.push(planet);
+        }
+
+        for _ in 0..DEMO_ASTEROIDS {
+            let asteroid = construct_asteroid(&mut rng);
+            components
+                .ore
+                .insert(asteroid.get_id(), rng.gen_range(200.0, 1500.0));
+
+            grid.entities.push(asteroid);
+        }
+
         grids.insert(grid.id, grid);
     }
 
-    World { grids }
+    World {
+        grids,
+        components,
+        survival: None,
+        tutorial: None,
+        difficulty: Difficulty::default(),
+        shape_tolerance: DEFAULT_SHAPE_TOLERANCE,
+    }
+}
+
+/// Irregular rock at a random spot on a ring around the origin.
+fn construct_asteroid(rng: &mut StdRng) -> Entity {
+    use std::f32::consts::PI;
+
+    let radius = rng.gen_range(20.0, 60.0);
+    let points: Vec<Vec2<f32>> = (0..12)
+        .map(|_| {
+            let angle = rng.gen_range(0.0, 2.0 * PI);
+            Vec2::new(angle.cos(), angle.sin()) * radius * rng.gen_range(0.6, 1.0)
+        })
+        .collect();
+
+    let mut asteroid = Entity::new(construct_convex_hull(&points), vec![]);
+    let angle = rng.gen_range(0.0, 2.0 * PI);
+    asteroid.position.state = Vec2::new(angle.cos(), angle.sin()) * rng.gen_range(550.0, 800.0);
+    asteroid.angle.state = rng.gen_range(0.0, 2.0 * PI);
+    asteroid
 }
@@ -0,0 +1,95 @@
+use super::power::{self, PowerGroup};
+use super::{Block, ForcePoint};
+use crate::math::{circle::Circle, shape::Shape, vec::*};
+use crate::ui::user_controls::Action;
+use gamemath::Vec2;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Torque at full throttle, for each width unit.
+const TORQUE: f32 = 15.0;
+
+/// Power used at full throttle, for each width unit.
+const POWER: f32 = 0.5;
+
+/// Turns the entity without thrust, on rotate actions.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Gyroscope {
+    shape: Shape,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    offset: Vec2<f32>,
+    angle: f32,
+    width: f32,
+    /// From -1 to 1, positive is counter-clockwise.
+    throttle: f32,
+    #[serde(skip, default = "power::full_share")]
+    power: f32,
+}
+
+impl Gyroscope {
+    pub fn new(width: f32, offset: Vec2<f32>, angle: f32) -> Self {
+        Gyroscope {
+            shape: Gyroscope::shape(width),
+            offset,
+            angle,
+            width,
+            throttle: 0.0,
+            power: power::full_share(),
+        }
+    }
+
+    pub fn shape(width: f32) -> Shape {
+        Circle::new(Vec2::default(), width * 0.5).into()
+    }
+}
+
+#[typetag::serde]
+impl Block for Gyroscope {
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+    fn set_offset(&mut self, offset: Vec2<f32>) {
+        self.offset = offset;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn force(&self) -> ForcePoint {
+        ForcePoint {
+            torque: self.width * TORQUE * self.throttle * self.power,
+            force: Vec2::default(),
+        }
+    }
+
+    fn power_group(&self) -> Option<PowerGroup> {
+        Some(PowerGroup::Gyros)
+    }
+
+    fn power_demand(&self) -> f32 {
+        self.width * POWER * self.throttle.abs()
+    }
+
+    fn set_power(&mut self, share: f32) {
+        self.power = share;
+    }
+
+    fn apply_action(&mut self, action: &Action) {
+        if let Action::Rotate {
+            direction,
+            throttle,
+        } = action
+        {
+            self.throttle = (direction.signum() * throttle).clamp(-1.0, 1.0);
+        }
+    }
+}
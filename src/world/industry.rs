@@ -0,0 +1,181 @@
+use super::{Block, BlockRegistry, Entity, World};
+use crate::client::EntityId;
+use crate::events::{Events, WorldEvent};
+use crate::ui::user_controls::Action;
+use serde::{Deserialize, Serialize};
+
+/// Material gained from each unit of refined ore.
+const REFINING_YIELD: f32 = 0.5;
+
+/// Ore and building material carried by an entity.
+/// Entities without cargo are not part of the economy and build for free.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Cargo {
+    pub ore: f32,
+    pub material: f32,
+    /// Money for trading with stations.
+    #[serde(default)]
+    pub credits: f32,
+}
+
+/// Moves ore from asteroids into cargo of drilling entities that push against them
+/// and refines ore in cargo of entities with a refinery.
+pub fn tick(world: &mut World, events: &Events) {
+    for event in events.iter() {
+        if let WorldEvent::Collision { grid_id, entities } = event {
+            mine(world, *grid_id, entities[0], entities[1]);
+            mine(world, *grid_id, entities[1], entities[0]);
+        }
+    }
+    refine(world);
+}
+
+/// Refunds building material for blocks that `action` would remove from the entity.
+/// Blocks it would add are paid for by `pay_for_joined`, once it is known which of them fit.
+/// Returns false, if the entity cannot afford the blocks.
+pub fn pay_for(world: &mut World, blocks: &BlockRegistry, id: &EntityId, action: &Action) -> bool {
+    match action {
+        Action::JoinEntity { entity } => can_afford(world, id, cost_of(entity, blocks)),
+        Action::RemoveBlock { index } => {
+            let refund = refund_for(world, blocks, id, *index);
+            pay(world, id, -refund)
+        }
+        _ => true,
+    }
+}
+
+/// Material returned for removing the block at `index`: its cost, less its damage,
+/// out of what was paid for the blocks of the entity. Blocks the entity was given refund
+/// nothing, so they cannot be sold for material.
+fn refund_for(world: &mut World, blocks: &BlockRegistry, id: &EntityId, index: usize) -> f32 {
+    let value = match world.get_entity(id) {
+        Some(entity) if index < entity.blocks.len() => {
+            blocks.cost(entity.blocks[index].as_ref()) * (1.0 - entity.block_damage(index))
+        }
+        _ => return 0.0,
+    };
+    match world.components.invested.get_mut(id.entity_id) {
+        Some(invested) => {
+            let refund = value.min(*invested);
+            *invested -= refund;
+            refund
+        }
+        None => 0.0,
+    }
+}
+
+/// Takes building material for all blocks of `entity`, built by the entity `id`,
+/// and records it as paid for the blocks of `entity`. Returns false, if `id` cannot afford them.
+pub fn pay_for_entity(
+    world: &mut World,
+    blocks: &BlockRegistry,
+    id: &EntityId,
+    entity: &Entity,
+) -> bool {
+    let cost = cost_of(entity, blocks);
+    if !pay(world, id, cost) {
+        return false;
+    }
+    if world.components.cargo.get(id.entity_id).is_some() {
+        invest(world, entity.get_id(), cost);
+    }
+    true
+}
+
+/// Takes building material for blocks of the entity from index `first` on, which joining
+/// an entity added to it. Blocks that did not fit on the hull are not charged.
+pub fn pay_for_joined(world: &mut World, blocks: &BlockRegistry, id: &EntityId, first: usize) {
+    let cost = match world.get_entity(id) {
+        Some(entity) => entity
+            .blocks
+            .get(first..)
+            .unwrap_or_default()
+            .iter()
+            .map(|b| blocks.cost(b.as_ref()))
+            .sum(),
+        None => return,
+    };
+    if world.components.cargo.get(id.entity_id).is_some() {
+        pay(world, id, cost);
+        invest(world, id.entity_id, cost);
+    }
+}
+
+fn cost_of(entity: &Entity, blocks: &BlockRegistry) -> f32 {
+    entity.blocks.iter().map(|b| blocks.cost(b.as_ref())).sum()
+}
+
+fn invest(world: &mut World, entity: u64, material: f32) {
+    match world.components.invested.get_mut(entity) {
+        Some(invested) => *invested += material,
+        None => world.components.invested.insert(entity, material),
+    }
+}
+
+fn can_afford(world: &World, id: &EntityId, cost: f32) -> bool {
+    world
+        .components
+        .cargo
+        .get(id.entity_id)
+        .is_none_or(|cargo| cargo.material >= cost)
+}
+
+fn pay(world: &mut World, id: &EntityId, cost: f32) -> bool {
+    let cargo = match world.components.cargo.get_mut(id.entity_id) {
+        Some(cargo) => cargo,
+        None => return true,
+    };
+
+    if cargo.material < cost {
+        return false;
+    }
+    cargo.material -= cost;
+    true
+}
+
+fn mine(world: &mut World, grid_id: u64, miner: u64, asteroid: u64) {
+    let ore = match world.components.ore.get(asteroid) {
+        Some(ore) => *ore,
+        None => return,
+    };
+    let miner_entity = match world.grids.get(&grid_id).and_then(|g| g.get_entity(miner)) {
+        Some(entity) => entity,
+        None => return,
+    };
+    let rate = sum(miner_entity, |b| b.drill_rate());
+    let capacity = sum(miner_entity, |b| b.cargo_capacity());
+
+    let mut cargo = world
+        .components
+        .cargo
+        .get(miner)
+        .copied()
+        .unwrap_or_default();
+    let mined = rate.min(ore).min(capacity - cargo.ore - cargo.material);
+    if mined <= 0.0 {
+        return;
+    }
+    cargo.ore += mined;
+    world.components.cargo.insert(miner, cargo);
+    world.components.ore.insert(asteroid, ore - mined);
+}
+
+fn refine(world: &mut World) {
+    for grid in world.grids.values() {
+        for entity in &grid.entities {
+            let rate = sum(entity, |b| b.refining_rate());
+            if rate <= 0.0 {
+                continue;
+            }
+            if let Some(cargo) = world.components.cargo.get_mut(entity.get_id()) {
+                let refined = rate.min(cargo.ore);
+                cargo.ore -= refined;
+                cargo.material += refined * REFINING_YIELD;
+            }
+        }
+    }
+}
+
+fn sum(entity: &Entity, value: fn(&dyn Block) -> f32) -> f32 {
+    entity.blocks.iter().map(|b| value(b.as_ref())).sum()
+}
@@ -0,0 +1,196 @@
+use super::power::{self, PowerGroup};
+use super::{Block, World};
+use crate::client::EntityId;
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
+use crate::ui::user_controls::Action;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Oxygen breathed per tick by the crew of each cockpit.
+const OXYGEN_USE: f32 = 1.0;
+
+/// Oxygen produced per tick by life support, for each width unit.
+const OXYGEN_RATE: f32 = 0.1;
+
+/// Power used per tick by life support, for each width unit.
+const POWER: f32 = 0.3;
+
+/// Air of an entity with cockpits.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Oxygen {
+    pub amount: f32,
+    pub capacity: f32,
+}
+
+/// Produces oxygen for the crew of its entity.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LifeSupport {
+    shape: Shape,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    offset: Vec2<f32>,
+    angle: f32,
+    oxygen_rate: f32,
+    #[serde(skip, default = "power::full_share")]
+    power: f32,
+}
+
+impl LifeSupport {
+    pub fn new(width: f32, offset: Vec2<f32>, angle: f32) -> Self {
+        LifeSupport {
+            shape: LifeSupport::shape(width).into(),
+            offset,
+            angle,
+            oxygen_rate: width * OXYGEN_RATE,
+            power: power::full_share(),
+        }
+    }
+
+    pub fn shape(width: f32) -> Polygon {
+        let p = Polygon::from(
+            &[
+                [-0.5, -0.3],
+                [-0.3, -0.5],
+                [0.3, -0.5],
+                [0.5, -0.3],
+                [0.5, 0.3],
+                [0.3, 0.5],
+                [-0.3, 0.5],
+                [-0.5, 0.3],
+            ][..],
+        );
+        Mat3::identity().scaled(Vec2::new(width, width)) * p
+    }
+}
+
+#[typetag::serde]
+impl Block for LifeSupport {
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+    fn set_offset(&mut self, offset: Vec2<f32>) {
+        self.offset = offset;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn oxygen_rate(&self) -> f32 {
+        self.oxygen_rate * self.power
+    }
+
+    fn power_group(&self) -> Option<PowerGroup> {
+        Some(PowerGroup::LifeSupport)
+    }
+
+    fn power_demand(&self) -> f32 {
+        self.oxygen_rate / OXYGEN_RATE * POWER
+    }
+
+    fn set_power(&mut self, share: f32) {
+        self.power = share;
+    }
+
+    fn apply_action(&mut self, _action: &Action) {}
+}
+
+/// Whether the entity accepts flight controls from players and scripts:
+/// it needs a cockpit and air for its crew.
+pub fn can_control(world: &World, id: &EntityId) -> bool {
+    let has_cockpit = world
+        .get_entity(id)
+        .is_some_and(|entity| entity.blocks.iter().any(|b| b.is_cockpit()));
+    let breathing = world
+        .components
+        .oxygen
+        .get(id.entity_id)
+        .is_none_or(|oxygen| oxygen.amount > 0.0);
+    has_cockpit && breathing
+}
+
+/// Actions that fly the entity, as opposed to building or trading.
+pub fn is_flight_control(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::Accelerate { .. } | Action::Rotate { .. } | Action::FireBlock { .. }
+    )
+}
+
+/// Crews breathe and life support refills their air. Entities that cannot be controlled
+/// have their thrusters and gyroscopes shut down.
+pub fn tick(world: &mut World) {
+    let ids: Vec<EntityId> = world
+        .grids
+        .values()
+        .flat_map(|grid| {
+            let grid_id = grid.get_id();
+            grid.entities
+                .iter()
+                .map(move |e| EntityId::new(grid_id, e.get_id()))
+        })
+        .collect();
+
+    for id in ids {
+        breathe(world, &id);
+
+        if can_control(world, &id) {
+            continue;
+        }
+        if let Some(entity) = world.get_entity_mut(&id) {
+            entity.apply_action(Action::Accelerate {
+                direction: Vec2::default(),
+                throttle: 0.0,
+            });
+            entity.apply_action(Action::Rotate {
+                direction: 0.0,
+                throttle: 0.0,
+            });
+            for index in 0..entity.blocks.len() {
+                entity.apply_action(Action::FireBlock {
+                    index,
+                    throttle: 0.0,
+                });
+            }
+        }
+    }
+}
+
+fn breathe(world: &mut World, id: &EntityId) {
+    let (capacity, used, produced) = match world.get_entity(id) {
+        Some(entity) => {
+            let cockpits = entity.blocks.iter().filter(|b| b.is_cockpit()).count();
+            (
+                entity
+                    .blocks
+                    .iter()
+                    .map(|b| b.oxygen_capacity())
+                    .sum::<f32>(),
+                cockpits as f32 * OXYGEN_USE,
+                entity.blocks.iter().map(|b| b.oxygen_rate()).sum::<f32>(),
+            )
+        }
+        None => return,
+    };
+    if capacity == 0.0 {
+        world.components.oxygen.remove(id.entity_id);
+        return;
+    }
+
+    // new cockpits come with full air
+    let oxygen = world.components.oxygen.get(id.entity_id).copied();
+    let amount = oxygen.map_or(capacity, |oxygen| oxygen.amount);
+    let amount = (amount - used + produced).clamp(0.0, capacity);
+    world
+        .components
+        .oxygen
+        .insert(id.entity_id, Oxygen { amount, capacity });
+}
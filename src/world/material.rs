@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// How a surface responds to collisions and, optionally, how heavy it is.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Material {
+    /// Share of the approach speed that is kept after a collision,
+    /// from 0 for a dead stop to 1 for a perfect bounce.
+    pub restitution: f32,
+    /// Sliding resistance, as a ratio to the impulse of the impact.
+    pub friction: f32,
+    /// Mass of each unit of area, instead of the default density.
+    #[serde(default)]
+    pub density: Option<f32>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            restitution: 0.0,
+            friction: 0.5,
+            density: None,
+        }
+    }
+}
+
+impl Material {
+    /// Bounces entities off with little loss of speed.
+    pub const BUMPER: Material = Material {
+        restitution: 0.9,
+        friction: 0.1,
+        density: Some(0.01),
+    };
+
+    /// Stops entities dead and grips them.
+    pub const ARMOR: Material = Material {
+        restitution: 0.0,
+        friction: 1.0,
+        density: Some(0.08),
+    };
+
+    /// Restitution and friction of a contact between two surfaces.
+    /// The bouncier surface decides restitution, while both surfaces add to friction.
+    pub fn contact(&self, other: &Material) -> (f32, f32) {
+        (
+            self.restitution.max(other.restitution),
+            (self.friction * other.friction).sqrt(),
+        )
+    }
+}
@@ -0,0 +1,226 @@
+use super::trade::{self, Resource, DOCKING_DISTANCE};
+use super::{Faction, World};
+use crate::client::EntityId;
+use gamemath::Vec2;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Entities of players get new contracts until they have this many.
+const MAX_CONTRACTS: usize = 3;
+
+/// Reward for delivering resources, as a multiple of their price at the station.
+const DELIVERY_BONUS: f32 = 1.5;
+
+const TOW_REWARD: f32 = 150.0;
+const DESTROY_REWARD: f32 = 300.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Objective {
+    /// Bring resources in cargo to a station and dock there.
+    Deliver {
+        station: u64,
+        resource: Resource,
+        amount: f32,
+    },
+    /// Push a derelict entity close to a station.
+    Tow { entity: u64, station: u64 },
+    /// Done when the entity no longer exists.
+    Destroy { entity: u64 },
+}
+
+/// Task for a player entity, paid in credits when its objective is done.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Contract {
+    pub objective: Objective,
+    pub reward: f32,
+}
+
+impl Contract {
+    pub fn describe(&self) -> String {
+        let objective = match &self.objective {
+            Objective::Deliver {
+                resource, amount, ..
+            } => format!("deliver {:.0} {:?} to station", amount, resource).to_lowercase(),
+            Objective::Tow { .. } => "tow derelict to station".to_owned(),
+            Objective::Destroy { .. } => "destroy pirate".to_owned(),
+        };
+        format!("{} for {:.0}", objective, self.reward)
+    }
+
+    /// Entity the objective is about, for markers on the HUD.
+    pub fn target(&self) -> u64 {
+        match self.objective {
+            Objective::Deliver { station, .. } => station,
+            Objective::Tow { entity, .. } => entity,
+            Objective::Destroy { entity } => entity,
+        }
+    }
+}
+
+/// Offers contracts to player entities with cargo and pays for the completed ones.
+/// Contracts whose station or derelict is gone are dropped.
+pub fn tick(world: &mut World) {
+    let contractors: Vec<u64> = world
+        .components
+        .cargo
+        .iter()
+        .map(|(id, _)| *id)
+        .filter(|id| world.components.faction(*id) == Faction::Player)
+        .collect();
+
+    for contractor in contractors {
+        let mut contracts = world
+            .components
+            .contracts
+            .get(contractor)
+            .cloned()
+            .unwrap_or_default();
+
+        contracts.retain(|contract| match progress(world, contractor, contract) {
+            Progress::Done => {
+                pay(world, contractor, contract);
+                false
+            }
+            Progress::Failed => false,
+            Progress::Pending => true,
+        });
+
+        if contracts.len() < MAX_CONTRACTS {
+            contracts.extend(generate(world));
+        }
+        world.components.contracts.insert(contractor, contracts);
+    }
+}
+
+enum Progress {
+    Pending,
+    Done,
+    Failed,
+}
+
+fn progress(world: &World, contractor: u64, contract: &Contract) -> Progress {
+    match &contract.objective {
+        Objective::Deliver {
+            station,
+            resource,
+            amount,
+        } => {
+            if locate(world, *station).is_none() {
+                return Progress::Failed;
+            }
+            let docked = locate(world, contractor)
+                .and_then(|id| trade::docked_station(world, &id))
+                .is_some_and(|docked| docked == *station);
+            let cargo = world.components.cargo.get(contractor);
+            if docked && cargo.is_some_and(|cargo| cargo.amount(*resource) >= *amount) {
+                Progress::Done
+            } else {
+                Progress::Pending
+            }
+        }
+        Objective::Tow { entity, station } => match distance(world, *entity, *station) {
+            Some(distance) if distance < DOCKING_DISTANCE => Progress::Done,
+            Some(_) => Progress::Pending,
+            None => Progress::Failed,
+        },
+        Objective::Destroy { entity } => match locate(world, *entity) {
+            Some(_) => Progress::Pending,
+            None => Progress::Done,
+        },
+    }
+}
+
+fn pay(world: &mut World, contractor: u64, contract: &Contract) {
+    if let Objective::Deliver {
+        station,
+        resource,
+        amount,
+    } = &contract.objective
+    {
+        if let Some(cargo) = world.components.cargo.get_mut(contractor) {
+            *cargo.amount_mut(*resource) -= amount;
+        }
+        if let Some(station) = world.components.stations.get_mut(*station) {
+            *station.stock.amount_mut(*resource) += amount;
+        }
+    }
+    if let Some(cargo) = world.components.cargo.get_mut(contractor) {
+        cargo.credits += contract.reward;
+    }
+}
+
+/// New contract for a random station, derelict or pirate, if there are any.
+fn generate(world: &World) -> Option<Contract> {
+    let mut rng = rand::thread_rng();
+
+    let stations: Vec<u64> = world
+        .components
+        .stations
+        .iter()
+        .map(|(id, _)| *id)
+        .collect();
+    let of_faction = |faction: Faction| -> Vec<u64> {
+        world
+            .grids
+            .values()
+            .flat_map(|grid| grid.entities.iter().map(|e| e.get_id()))
+            .filter(|id| world.components.faction(*id) == faction)
+            .collect()
+    };
+    let derelicts = of_faction(Faction::Derelict);
+    let pirates = of_faction(Faction::Hostile);
+
+    let station = *stations.choose(&mut rng)?;
+    let objective = match rng.gen_range(0, 3) {
+        0 if !derelicts.is_empty() => Objective::Tow {
+            entity: *derelicts.choose(&mut rng)?,
+            station,
+        },
+        1 if !pirates.is_empty() => Objective::Destroy {
+            entity: *pirates.choose(&mut rng)?,
+        },
+        _ => Objective::Deliver {
+            station,
+            resource: *[Resource::Ore, Resource::Material].choose(&mut rng)?,
+            amount: (rng.gen_range(2, 7) * 10) as f32,
+        },
+    };
+
+    let reward = match &objective {
+        Objective::Deliver {
+            station,
+            resource,
+            amount,
+        } => {
+            let price = world.components.stations.get(*station)?.price(*resource);
+            amount * price * DELIVERY_BONUS
+        }
+        Objective::Tow { .. } => TOW_REWARD,
+        Objective::Destroy { .. } => DESTROY_REWARD,
+    };
+    Some(Contract { objective, reward })
+}
+
+fn locate(world: &World, entity: u64) -> Option<EntityId> {
+    world.find_entity(&EntityId::new(0, entity))
+}
+
+/// Position of the entity relative to the grid, which may be any grid related to the
+/// grid of the entity.
+pub fn position_in(world: &World, grid_id: u64, entity: u64) -> Option<Vec2<f32>> {
+    let id = locate(world, entity)?;
+    let relation = world
+        .get_relations(grid_id, Default::default())
+        .into_iter()
+        .find(|relation| relation.id == id.grid_id)?;
+    Some(relation.position.state + world.get_entity(&id)?.position.state)
+}
+
+/// Distance between entities, which may be in different grids.
+fn distance(world: &World, a: u64, b: u64) -> Option<f32> {
+    let grid_id = locate(world, a)?.grid_id;
+    let a = position_in(world, grid_id, a)?;
+    let b = position_in(world, grid_id, b)?;
+    Some((b - a).length())
+}
@@ -1,12 +1,111 @@
 pub mod grid;
+pub mod balance;
 pub mod block;
+pub mod block_registry;
+pub mod cargo_bay;
+pub mod checksum;
+pub mod cockpit;
+pub mod debris;
+pub mod combat;
 pub mod thruster;
+pub mod drill;
 pub mod entity;
+pub mod components;
+pub mod difficulty;
+pub mod faction;
+pub mod fuel_pump;
+pub mod generator;
+pub mod gravity;
 pub mod gyroscope;
+pub mod industry;
 pub mod insist;
+pub mod life_support;
+pub mod material;
+pub mod missions;
+pub mod plating;
+pub mod power;
+pub mod refinery;
+pub mod repair_arm;
+pub mod stress;
+pub mod supply;
+pub mod survival;
+pub mod trade;
+pub mod tutorial;
 
-pub use grid::{Grid, GridRelation, World};
+use crate::events::Events;
+use crate::scripting::Scripts;
+
+pub use grid::{FrameShifts, Grid, GridRelation, World, DEFAULT_SHAPE_TOLERANCE};
 pub use insist::{Insist};
 pub use entity::{Entity, ForcePoint, MassPoint};
+pub use faction::Faction;
+pub use components::Components;
 pub use block::Block;
-pub use thruster::Thruster;
\ No newline at end of file
+pub use block_registry::BlockRegistry;
+pub use thruster::Thruster;
+pub use drill::Drill;
+pub use cargo_bay::CargoBay;
+pub use refinery::Refinery;
+pub use repair_arm::RepairArm;
+pub use fuel_pump::FuelPump;
+pub use generator::Generator;
+pub use gyroscope::Gyroscope;
+pub use cockpit::Cockpit;
+pub use life_support::LifeSupport;
+pub use material::Material;
+pub use plating::Plating;
+/// Runs gameplay systems after the engine has made a tick, in the order both the server
+/// and a local game use.
+pub fn systems_tick(world: &mut World, events: &Events, scripts: &mut Scripts) {
+    scripts.tick(world, events);
+    industry::tick(world, events);
+    supply::tick(world);
+    power::tick(world);
+    life_support::tick(world);
+    repair_arm::tick(world);
+    missions::tick(world);
+    combat::tick(world, events);
+    survival::tick(world);
+    tutorial::tick(world);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Engine;
+    use crate::math::polygon::Polygon;
+    use crate::paths::Paths;
+    use gamemath::Vec2;
+
+    #[test]
+    fn idle_asteroid_falls_asleep() {
+        let points = vec![
+            Vec2::new(-20.0, -20.0),
+            Vec2::new(20.0, -20.0),
+            Vec2::new(20.0, 20.0),
+            Vec2::new(-20.0, 20.0),
+        ];
+        let asteroid = Entity::new(Polygon::from(points), vec![]);
+        let grid = Grid::new(None, vec![asteroid]);
+        let id = grid.entities[0].get_id();
+        let mut world = World::default();
+        world.grids.insert(grid.get_id(), grid);
+
+        let dir = std::env::temp_dir().join("dgame-test-asleep");
+        let mut scripts = Scripts::load(&Paths {
+            data: dir.clone(),
+            config: dir,
+        });
+        let mut engine = Engine::default();
+        let mut events = Events::default();
+        // twice as long as an entity has to be still to fall asleep
+        for _ in 0..120 {
+            engine.tick(&mut world, &mut events);
+            systems_tick(&mut world, &events, &mut scripts);
+            events.poll();
+        }
+
+        let grid = world.grids.values().next().unwrap();
+        assert!(grid.get_entity(id).unwrap().is_asleep());
+    }
+}
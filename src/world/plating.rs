@@ -0,0 +1,74 @@
+use super::{Block, Material};
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
+use crate::ui::user_controls::Action;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Thickness of a plate, as a share of its width.
+const THICKNESS: f32 = 0.25;
+
+/// Density of plates made of a material without its own density.
+const DENSITY: f32 = 0.02;
+
+/// Plate that gives its part of the hull a material of its own, such as a bouncy bumper
+/// or heavy armor.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Plating {
+    shape: Shape,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    offset: Vec2<f32>,
+    angle: f32,
+    width: f32,
+    material: Material,
+}
+
+impl Plating {
+    pub fn new(width: f32, material: Material, offset: Vec2<f32>, angle: f32) -> Self {
+        Plating {
+            shape: Plating::shape(width).into(),
+            offset,
+            angle,
+            width,
+            material,
+        }
+    }
+
+    pub fn shape(width: f32) -> Polygon {
+        let p = Polygon::from(&[[-0.5, -0.5], [-0.5, 0.5], [0.5, 0.5], [0.5, -0.5]][..]);
+        Mat3::identity().scaled(Vec2::new(width, width * THICKNESS)) * p
+    }
+}
+
+#[typetag::serde]
+impl Block for Plating {
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+    fn set_offset(&mut self, offset: Vec2<f32>) {
+        self.offset = offset;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn mass(&self) -> f32 {
+        let density = self.material.density.unwrap_or(DENSITY);
+        self.width * self.width * THICKNESS * density
+    }
+
+    fn material(&self) -> Option<Material> {
+        Some(self.material)
+    }
+
+    fn apply_action(&mut self, _action: &Action) {}
+}
@@ -0,0 +1,109 @@
+use super::World;
+use serde::{Deserialize, Serialize};
+
+/// Kind of load. When there is not enough power, groups earlier in the priorities of the
+/// entity are powered first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerGroup {
+    LifeSupport,
+    Engines,
+    Gyros,
+    Industry,
+}
+
+pub const DEFAULT_PRIORITIES: [PowerGroup; 4] = [
+    PowerGroup::LifeSupport,
+    PowerGroup::Engines,
+    PowerGroup::Gyros,
+    PowerGroup::Industry,
+];
+
+/// Power budget of an entity in the last tick.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PowerStatus {
+    pub supply: f32,
+    pub demand: f32,
+    /// Share of demand of each group that was met, in order of priority.
+    pub groups: Vec<(PowerGroup, f32)>,
+}
+
+/// Share of power of blocks that are not limited by the power system.
+pub fn full_share() -> f32 {
+    1.0
+}
+
+/// Splits power of generators of each entity among its blocks, by priority of their groups.
+/// Groups that cannot be fully powered get the same share for all of their blocks.
+///
+/// Entities without generators are not part of the power system and run at full power.
+pub fn tick(world: &mut World) {
+    let components = &mut world.components;
+    for grid in world.grids.values_mut() {
+        for entity in &mut grid.entities {
+            let id = entity.get_id();
+            let supply: f32 = entity.blocks.iter().map(|b| b.power_output()).sum();
+            if supply == 0.0 {
+                for block in &mut entity.blocks {
+                    block.set_power(full_share());
+                }
+                components.power.remove(id);
+                continue;
+            }
+
+            let mut priorities = components
+                .power_priorities
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| DEFAULT_PRIORITIES.to_vec());
+            complete(&mut priorities);
+
+            let mut status = PowerStatus {
+                supply,
+                ..PowerStatus::default()
+            };
+            let mut remaining = supply;
+            for group in priorities {
+                let demand: f32 = entity
+                    .blocks
+                    .iter()
+                    .filter(|b| b.power_group() == Some(group))
+                    .map(|b| b.power_demand())
+                    .sum();
+                let share = if demand > 0.0 {
+                    (remaining / demand).min(1.0)
+                } else {
+                    1.0
+                };
+                remaining -= demand * share;
+                status.demand += demand;
+                status.groups.push((group, share));
+
+                for block in &mut entity.blocks {
+                    if block.power_group() == Some(group) {
+                        block.set_power(share);
+                    }
+                }
+            }
+            components.power.insert(id, status);
+        }
+    }
+}
+
+/// Moves the group one place up in the priorities, swapping it with the group before it.
+pub fn raise_priority(priorities: &mut Vec<PowerGroup>, group: PowerGroup) {
+    complete(priorities);
+    if let Some(index) = priorities.iter().position(|g| *g == group) {
+        if index > 0 {
+            priorities.swap(index, index - 1);
+        }
+    }
+}
+
+/// Appends groups that are missing from priorities, such as those added after they were saved.
+fn complete(priorities: &mut Vec<PowerGroup>) {
+    for missing in DEFAULT_PRIORITIES.iter() {
+        if !priorities.contains(missing) {
+            priorities.push(*missing);
+        }
+    }
+}
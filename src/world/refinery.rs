@@ -0,0 +1,91 @@
+use super::power::{self, PowerGroup};
+use super::Block;
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
+use crate::ui::user_controls::Action;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Ore refined per tick, for each width unit.
+const REFINING_RATE: f32 = 0.005;
+
+/// Power used per tick, for each width unit.
+const POWER: f32 = 0.5;
+
+/// Refines ore in cargo into building material.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Refinery {
+    shape: Shape,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    offset: Vec2<f32>,
+    angle: f32,
+    refining_rate: f32,
+    #[serde(skip, default = "power::full_share")]
+    power: f32,
+}
+
+impl Refinery {
+    pub fn new(width: f32, offset: Vec2<f32>, angle: f32) -> Self {
+        Refinery {
+            shape: Refinery::shape(width).into(),
+            offset,
+            angle,
+            refining_rate: width * REFINING_RATE,
+            power: power::full_share(),
+        }
+    }
+
+    pub fn shape(width: f32) -> Polygon {
+        let p = Polygon::from(
+            &[
+                [-0.25, -0.5],
+                [-0.5, 0.0],
+                [-0.25, 0.5],
+                [0.25, 0.5],
+                [0.5, 0.0],
+                [0.25, -0.5],
+            ][..],
+        );
+        Mat3::identity().scaled(Vec2::new(width, width)) * p
+    }
+}
+
+#[typetag::serde]
+impl Block for Refinery {
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+    fn set_offset(&mut self, offset: Vec2<f32>) {
+        self.offset = offset;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn refining_rate(&self) -> f32 {
+        self.refining_rate * self.power
+    }
+
+    fn power_group(&self) -> Option<PowerGroup> {
+        Some(PowerGroup::Industry)
+    }
+
+    fn power_demand(&self) -> f32 {
+        self.refining_rate / REFINING_RATE * POWER
+    }
+
+    fn set_power(&mut self, share: f32) {
+        self.power = share;
+    }
+
+    fn apply_action(&mut self, _action: &Action) {}
+}
@@ -0,0 +1,144 @@
+use super::combat::BLOCK_POINTS;
+use super::power::{self, PowerGroup};
+use super::{Block, World};
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
+use crate::ui::user_controls::Action;
+use gamemath::{Mat3, Vec2};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// Health restored per tick, for each width unit.
+const REPAIR_RATE: f32 = 0.01;
+
+/// Power used per tick, for each width unit.
+const POWER: f32 = 0.5;
+
+/// Distance from the arm to the blocks it can reach.
+const REACH: f32 = 60.0;
+
+/// Building material in cargo used for each health point restored.
+const MATERIAL_PER_POINT: f32 = 0.5;
+
+/// Restores damaged blocks of its entity within reach, using material from cargo.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RepairArm {
+    shape: Shape,
+    #[serde_as(as = "Vec2Serde<f32>")]
+    offset: Vec2<f32>,
+    angle: f32,
+    repair_rate: f32,
+    #[serde(skip, default = "power::full_share")]
+    power: f32,
+}
+
+impl RepairArm {
+    pub fn new(width: f32, offset: Vec2<f32>, angle: f32) -> Self {
+        RepairArm {
+            shape: RepairArm::shape(width).into(),
+            offset,
+            angle,
+            repair_rate: width * REPAIR_RATE,
+            power: power::full_share(),
+        }
+    }
+
+    pub fn shape(width: f32) -> Polygon {
+        let p = Polygon::from(
+            &[
+                [-0.5, 0.5],
+                [-0.5, 0.0],
+                [-0.1, -0.5],
+                [0.1, -0.5],
+                [0.5, 0.0],
+                [0.5, 0.5],
+            ][..],
+        );
+        Mat3::identity().scaled(Vec2::new(width * 0.5, width)) * p
+    }
+}
+
+#[typetag::serde]
+impl Block for RepairArm {
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+    fn set_offset(&mut self, offset: Vec2<f32>) {
+        self.offset = offset;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn repair_rate(&self) -> f32 {
+        self.repair_rate * self.power
+    }
+
+    fn power_group(&self) -> Option<PowerGroup> {
+        Some(PowerGroup::Industry)
+    }
+
+    fn power_demand(&self) -> f32 {
+        self.repair_rate / REPAIR_RATE * POWER
+    }
+
+    fn set_power(&mut self, share: f32) {
+        self.power = share;
+    }
+
+    fn apply_action(&mut self, _action: &Action) {}
+}
+
+/// Each arm repairs the most damaged block within its reach, as far as the material in
+/// cargo of its entity lasts, and restores as much health of the entity.
+pub fn tick(world: &mut World) {
+    let components = &mut world.components;
+    for grid in world.grids.values_mut() {
+        for entity in &mut grid.entities {
+            let cargo = match components.cargo.get_mut(entity.get_id()) {
+                Some(cargo) => cargo,
+                None => continue,
+            };
+
+            for arm in 0..entity.blocks.len() {
+                let rate = entity.blocks[arm].repair_rate();
+                if rate == 0.0 {
+                    continue;
+                }
+                let origin = entity.blocks[arm].offset();
+                let target = (0..entity.blocks.len())
+                    .filter(|index| (entity.blocks[*index].offset() - origin).length() <= REACH)
+                    .max_by(|a, b| {
+                        let a = entity.block_damage(*a);
+                        let b = entity.block_damage(*b);
+                        a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+                    });
+                let target = match target {
+                    Some(target) => target,
+                    None => continue,
+                };
+
+                let points = rate
+                    .min(entity.block_damage(target) * BLOCK_POINTS)
+                    .min(cargo.material / MATERIAL_PER_POINT);
+                if points <= 0.0 {
+                    continue;
+                }
+                cargo.material -= points * MATERIAL_PER_POINT;
+                entity.damage_block(target, -points / BLOCK_POINTS);
+
+                if let Some(health) = components.health.get_mut(entity.get_id()) {
+                    health.points = (health.points + points).min(health.max_points);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use super::gravity::MutualGravity;
+use super::grid::construct_demo_ship;
+use super::{Entity, Faction, Grid, World};
+use crate::math::polygon::construct_convex_hull;
+use gamemath::Vec2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::PI;
+
+/// Entities placed close together, so they are kept in one grid.
+const CLUSTER_SIZE: usize = 8;
+
+/// Distance between cluster centers, far enough for each cluster to get its own grid.
+const CLUSTER_SPACING: f32 = 1200.0;
+
+/// Entities of a cluster are spread over a square of this side.
+const CLUSTER_SIDE: f32 = 300.0;
+
+/// One in this many generated entities is a thrustered ship, the rest is debris.
+const SHIP_RATIO: usize = 3;
+
+/// World of `count` ships and debris fragments in clusters on a square lattice, moving and
+/// spinning so that they collide, for measuring performance on a reproducible workload.
+/// The player ship is at the origin.
+pub fn construct_stress_world(count: usize, seed: u64) -> World {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut world = World::default();
+
+    let player = construct_demo_ship();
+    world
+        .components
+        .factions
+        .insert(player.get_id(), Faction::Player);
+    let mut grid = Grid::new(None, vec![player]);
+
+    let clusters = count.div_ceil(CLUSTER_SIZE);
+    let side = (clusters as f32).sqrt().ceil() as usize;
+    for index in 0..count {
+        let cluster = index / CLUSTER_SIZE;
+        let center = Vec2::new(
+            (cluster % side + 1) as f32 * CLUSTER_SPACING,
+            (cluster / side + 1) as f32 * CLUSTER_SPACING,
+        );
+
+        let mut entity = if index % SHIP_RATIO == 0 {
+            construct_demo_ship()
+        } else {
+            construct_fragment(&mut rng)
+        };
+        entity.position.state = center
+            + Vec2::new(
+                rng.gen_range(-0.5, 0.5) * CLUSTER_SIDE,
+                rng.gen_range(-0.5, 0.5) * CLUSTER_SIDE,
+            );
+        entity.position.velocity = Vec2::new(rng.gen_range(-5.0, 5.0), rng.gen_range(-5.0, 5.0));
+        entity.angle.state = rng.gen_range(0.0, 2.0 * PI);
+        entity.angle.velocity = rng.gen_range(-0.05, 0.05);
+
+        grid.entities.push(entity);
+    }
+
+    // debris clumps together, and splitting puts clusters into their own grids
+    // during the first ticks
+    grid.mutual_gravity = Some(MutualGravity::default());
+    world.grids.insert(grid.get_id(), grid);
+    world
+}
+
+/// Small convex shard without blocks.
+fn construct_fragment(rng: &mut StdRng) -> Entity {
+    let radius = rng.gen_range(5.0, 25.0);
+    let points: Vec<Vec2<f32>> = (0..6)
+        .map(|_| {
+            let angle = rng.gen_range(0.0, 2.0 * PI);
+            Vec2::new(angle.cos(), angle.sin()) * radius * rng.gen_range(0.5, 1.0)
+        })
+        .collect();
+    Entity::new(construct_convex_hull(&points), vec![])
+}
@@ -0,0 +1,137 @@
+use super::trade;
+use super::World;
+use crate::client::EntityId;
+use crate::ui::user_controls::Action;
+use serde::{Deserialize, Serialize};
+
+/// Fuel per tick given by stations to entities docked to them.
+const STATION_FUEL_RATE: f32 = 1.0;
+
+/// Health per tick restored by stations to entities docked to them.
+const STATION_REPAIR_RATE: f32 = 0.1;
+
+/// Fuel of an entity. Entities without a tank burn no fuel.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Tank {
+    pub fuel: f32,
+    pub capacity: f32,
+}
+
+/// What the entity received in the last tick while docked.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Resupply {
+    pub fuel: f32,
+    pub repair: f32,
+}
+
+/// Burns fuel of thrusting entities and resupplies entities docked to stations or tankers.
+/// Thrusters of entities that run out of fuel are shut down.
+pub fn tick(world: &mut World) {
+    let supplied: Vec<EntityId> = world
+        .grids
+        .values()
+        .flat_map(|grid| {
+            let grid_id = grid.get_id();
+            grid.entities
+                .iter()
+                .map(move |e| EntityId::new(grid_id, e.get_id()))
+        })
+        .filter(|id| {
+            world.components.fuel.get(id.entity_id).is_some()
+                || world.components.health.get(id.entity_id).is_some()
+        })
+        .collect();
+
+    for id in supplied {
+        burn(world, &id);
+        resupply(world, &id);
+    }
+}
+
+fn burn(world: &mut World, id: &EntityId) {
+    let fuel_use = world.difficulty.fuel_use;
+    let tank = match world.components.fuel.get_mut(id.entity_id) {
+        Some(tank) => tank,
+        None => return,
+    };
+    let entity = match world.grids.get_mut(&id.grid_id) {
+        Some(grid) => grid.get_entity_mut(id.entity_id),
+        None => None,
+    };
+    let entity = match entity {
+        Some(entity) => entity,
+        None => return,
+    };
+
+    let used = entity.blocks.iter().map(|b| b.fuel_use()).sum::<f32>() * fuel_use;
+    tank.fuel = (tank.fuel - used).max(0.0);
+    if tank.fuel == 0.0 && used > 0.0 {
+        entity.apply_action(Action::Accelerate {
+            direction: Default::default(),
+            throttle: 0.0,
+        });
+        for index in 0..entity.blocks.len() {
+            entity.apply_action(Action::FireBlock {
+                index,
+                throttle: 0.0,
+            });
+        }
+    }
+}
+
+fn resupply(world: &mut World, id: &EntityId) {
+    let mut received = Resupply::default();
+    let space = world
+        .components
+        .fuel
+        .get(id.entity_id)
+        .map_or(0.0, |tank| (tank.capacity - tank.fuel).max(0.0));
+
+    if trade::docked_station(world, id).is_some() {
+        received.fuel = STATION_FUEL_RATE.min(space);
+        received.repair = STATION_REPAIR_RATE;
+    } else if let Some(tanker) = docked_tanker(world, id) {
+        // tankers only give what fits into the tank of the entity
+        let pump_rate = pump_rate(world, tanker);
+        if let Some(tank) = world.components.fuel.get_mut(tanker) {
+            received.fuel = pump_rate.min(tank.fuel).min(space);
+            tank.fuel -= received.fuel;
+        }
+    }
+
+    let components = &mut world.components;
+    if let Some(tank) = components.fuel.get_mut(id.entity_id) {
+        tank.fuel += received.fuel;
+    }
+    if let Some(health) = components.health.get_mut(id.entity_id) {
+        received.repair = received
+            .repair
+            .min(health.max_points - health.points)
+            .max(0.0);
+        health.points += received.repair;
+    } else {
+        received.repair = 0.0;
+    }
+
+    if received.fuel > 0.0 || received.repair > 0.0 {
+        components.resupply.insert(id.entity_id, received);
+    } else {
+        components.resupply.remove(id.entity_id);
+    }
+}
+
+/// Entity with fuel pumps and fuel to spare that the entity is docked to.
+fn docked_tanker(world: &World, id: &EntityId) -> Option<u64> {
+    trade::docked_to(world, id, |host| {
+        let pumps = host.blocks.iter().any(|b| b.pump_rate() > 0.0);
+        let fuel = world.components.fuel.get(host.get_id());
+        pumps && fuel.is_some_and(|tank| tank.fuel > 0.0)
+    })
+}
+
+fn pump_rate(world: &World, tanker: u64) -> f32 {
+    let entity = world.find_entity(&EntityId::new(0, tanker));
+    entity
+        .and_then(|id| world.get_entity(&id))
+        .map_or(0.0, |e| e.blocks.iter().map(|b| b.pump_rate()).sum())
+}
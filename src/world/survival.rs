@@ -0,0 +1,175 @@
+use super::combat::Health;
+use super::grid::{construct_demo_ship, construct_demo_world};
+use super::{life_support, missions, Faction, World};
+use crate::client::EntityId;
+use crate::ui::user_controls::Action;
+use gamemath::{Mat2, Vec2};
+use serde::{Deserialize, Serialize};
+
+/// Ticks between the last raider of a wave being destroyed and the next wave.
+const WAVE_PAUSE: u32 = 300;
+
+/// Raiders of a wave spawn on a circle of this radius around the first player entity.
+const SPAWN_DISTANCE: f32 = 900.0;
+
+const RAIDER_HEALTH: f32 = 20.0;
+const PLAYER_HEALTH: f32 = 200.0;
+
+/// Raiders are slower than the player, so they can be outrun.
+const RAIDER_THROTTLE: f32 = 0.6;
+
+/// Score for destroying a raider, multiplied by the number of the wave.
+const RAIDER_SCORE: u32 = 100;
+
+/// State of a wave-survival run, in which waves of raiders attack player entities
+/// until none are left.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Survival {
+    pub wave: u32,
+    pub score: u32,
+    pub destroyed: u32,
+    pub ticks: u64,
+    /// Set when all player entities are destroyed.
+    pub over: bool,
+    raiders: Vec<u64>,
+    pause: u32,
+}
+
+/// Demo world in which player entities can be destroyed and waves start right away.
+pub fn construct_survival_world(seed: u64) -> World {
+    let mut world = construct_demo_world(seed);
+
+    let defenders: Vec<u64> = world
+        .grids
+        .values()
+        .flat_map(|grid| grid.entities.iter().map(|e| e.get_id()))
+        .filter(|id| world.components.faction(*id) == Faction::Player)
+        .collect();
+    for defender in defenders {
+        world
+            .components
+            .health
+            .insert(defender, Health::new(PLAYER_HEALTH));
+    }
+
+    world.survival = Some(Survival::default());
+    world
+}
+
+/// Counts destroyed raiders, steers the remaining ones and spawns the next wave
+/// once a wave is cleared.
+pub fn tick(world: &mut World) {
+    let mut survival = match world.survival.take() {
+        Some(survival) => survival,
+        None => return,
+    };
+    if !survival.over {
+        tick_run(world, &mut survival);
+    }
+    world.survival = Some(survival);
+}
+
+fn tick_run(world: &mut World, survival: &mut Survival) {
+    let defenders: Vec<EntityId> = world
+        .grids
+        .values()
+        .flat_map(|grid| {
+            let grid_id = grid.get_id();
+            grid.entities
+                .iter()
+                .map(move |e| EntityId::new(grid_id, e.get_id()))
+        })
+        .filter(|id| world.components.faction(id.entity_id) == Faction::Player)
+        .collect();
+    if defenders.is_empty() {
+        survival.over = true;
+        return;
+    }
+    survival.ticks += 1;
+
+    let before = survival.raiders.len();
+    survival
+        .raiders
+        .retain(|raider| world.find_entity(&EntityId::new(0, *raider)).is_some());
+    let destroyed = (before - survival.raiders.len()) as u32;
+    survival.destroyed += destroyed;
+    survival.score += destroyed * RAIDER_SCORE * survival.wave;
+
+    for raider in &survival.raiders {
+        steer(world, *raider, &defenders);
+    }
+
+    if survival.raiders.is_empty() {
+        if survival.pause > 0 {
+            survival.pause -= 1;
+        } else {
+            survival.wave += 1;
+            survival.pause = (WAVE_PAUSE as f32 / world.difficulty.enemy_spawn_rate) as u32;
+            survival.raiders = spawn_wave(world, &defenders[0], survival.wave);
+        }
+    }
+}
+
+/// Accelerates the raider towards the closest defender.
+fn steer(world: &mut World, raider: u64, defenders: &[EntityId]) {
+    let id = match world.find_entity(&EntityId::new(0, raider)) {
+        Some(id) => id,
+        None => return,
+    };
+    if !life_support::can_control(world, &id) {
+        return;
+    }
+    let position = match world.get_entity(&id) {
+        Some(entity) => entity.position.state,
+        None => return,
+    };
+    let target = defenders
+        .iter()
+        .filter_map(|defender| missions::position_in(world, id.grid_id, defender.entity_id))
+        .min_by(|a, b| {
+            let a = (*a - position).length();
+            let b = (*b - position).length();
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+    if let (Some(target), Some(entity)) = (target, world.get_entity_mut(&id)) {
+        // thrusters take directions relative to the entity
+        let direction = Mat2::rotation(-entity.angle.state) * (target - position);
+        entity.apply_action(Action::Accelerate {
+            direction,
+            throttle: RAIDER_THROTTLE,
+        });
+    }
+}
+
+/// Spawns one more raider than the number of the wave, times the enemy spawn rate of
+/// the difficulty, evenly around the defender.
+fn spawn_wave(world: &mut World, defender: &EntityId, wave: u32) -> Vec<u64> {
+    let center = match world.get_entity(defender) {
+        Some(entity) => entity.position.state,
+        None => return Vec::new(),
+    };
+    let count = ((wave + 1) as f32 * world.difficulty.enemy_spawn_rate)
+        .round()
+        .max(1.0) as u32;
+
+    let mut raiders = Vec::new();
+    for index in 0..count {
+        let angle = index as f32 / count as f32 * std::f32::consts::PI * 2.0;
+        let mut raider = construct_demo_ship();
+        raider.position.state = center + Vec2::new(angle.cos(), angle.sin()) * SPAWN_DISTANCE;
+        raider.angle.state = angle;
+
+        let id = raider.get_id();
+        world.components.factions.insert(id, Faction::Hostile);
+        world
+            .components
+            .health
+            .insert(id, Health::new(RAIDER_HEALTH));
+        if let Some(grid) = world.grids.get_mut(&defender.grid_id) {
+            grid.entities.push(raider);
+            raiders.push(id);
+        }
+    }
+    raiders
+}
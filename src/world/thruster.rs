@@ -1,14 +1,30 @@
+use super::power::{self, PowerGroup};
 use super::{Block, ForcePoint};
-use crate::math::{polygon::Polygon, vec::*};
+use crate::math::{polygon::Polygon, shape::Shape, vec::*};
 use crate::ui::user_controls::Action;
 use gamemath::{Mat2, Mat3, Vec2};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
+/// Fuel burned per tick, for each unit of thrust at full throttle.
+const FUEL_PER_THRUST: f32 = 0.02;
+
+/// Power used per tick, for each unit of thrust at full throttle.
+const POWER_PER_THRUST: f32 = 1.0;
+
+/// Flow out of a block, in coordinates of the block.
+pub struct Exhaust {
+    pub nozzle: Vec2<f32>,
+    /// Unit vector along the flow.
+    pub direction: Vec2<f32>,
+    /// From 0 when idle to 1 at full throttle.
+    pub strength: f32,
+}
+
 #[serde_as]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Thruster {
-    shape: Polygon,
+    shape: Shape,
     #[serde_as(as = "Vec2Serde<f32>")]
     offset: Vec2<f32>,
     angle: f32,
@@ -17,18 +33,21 @@ pub struct Thruster {
 
     #[serde_as(as = "Vec2Serde<f32>")]
     thrust_vector: Vec2<f32>,
+    #[serde(skip, default = "power::full_share")]
+    power: f32,
 }
 
 impl Thruster {
     pub fn new(width: f32, offset: Vec2<f32>, angle: f32) -> Self {
         Thruster {
-            shape: Thruster::shape(width),
+            shape: Thruster::shape(width).into(),
             offset,
             angle,
             throttle: 0.0,
             throttle_target: 0.0,
 
             thrust_vector: Vec2::new(0.0, -width * width * 0.05),
+            power: power::full_share(),
         }
     }
 
@@ -58,7 +77,7 @@ impl Thruster {
 
 #[typetag::serde]
 impl Block for Thruster {
-    fn shape(&self) -> &Polygon {
+    fn shape(&self) -> &Shape {
         &self.shape
     }
     fn offset(&self) -> Vec2<f32> {
@@ -79,25 +98,76 @@ impl Block for Thruster {
     fn force(&self) -> ForcePoint {
         ForcePoint {
             torque: 0.0,
-            force: self.thrust_vector * self.throttle,
+            force: self.thrust_vector * self.throttle * self.power,
         }
     }
 
+    fn thrust(&self) -> Vec2<f32> {
+        self.thrust_vector
+    }
+
+    /// Out of the end of the shape opposite to the thrust.
+    fn exhaust(&self) -> Option<Exhaust> {
+        let strength = self.throttle * self.power;
+        if strength <= 0.0 || self.thrust_vector.length_squared() == 0.0 {
+            return None;
+        }
+        let direction = -self.thrust_vector.normalized();
+        let reach = self
+            .shape
+            .to_polygon()
+            .points
+            .iter()
+            .map(|point| point.into_cartesian().dot(direction))
+            .fold(0.0, f32::max);
+        Some(Exhaust {
+            nozzle: direction * reach,
+            direction,
+            strength,
+        })
+    }
+
+    fn texture(&self) -> Option<&'static str> {
+        Some("thruster")
+    }
+
+    fn fuel_use(&self) -> f32 {
+        self.thrust_vector.length() * self.throttle * self.power * FUEL_PER_THRUST
+    }
+
+    fn power_group(&self) -> Option<PowerGroup> {
+        Some(PowerGroup::Engines)
+    }
+
+    fn power_demand(&self) -> f32 {
+        self.thrust_vector.length() * self.throttle * POWER_PER_THRUST
+    }
+
+    fn set_power(&mut self, share: f32) {
+        self.power = share;
+    }
+
     fn tick(&mut self) {
         let change = (self.throttle_target - self.throttle).min(0.01);
         self.throttle = (self.throttle + change).min(1.0).max(0.0);
     }
 
     fn apply_action(&mut self, action: &Action) {
-        if let Action::Accelerate {
-            direction,
-            throttle,
-        } = action
-        {
-            let thrust = Mat2::rotation(self.angle) * self.thrust_vector;
-            let directional_factor = direction.dot(thrust) / direction.length() / thrust.length();
+        match action {
+            Action::Accelerate {
+                direction,
+                throttle,
+            } => {
+                let thrust = Mat2::rotation(self.angle) * self.thrust_vector;
+                let directional_factor =
+                    direction.dot(thrust) / direction.length() / thrust.length();
 
-            self.throttle_target = throttle * directional_factor.max(0.0);
+                self.throttle_target = throttle * directional_factor.max(0.0);
+            }
+            Action::FireBlock { throttle, .. } => {
+                self.throttle_target = *throttle;
+            }
+            _ => {}
         }
     }
 }
@@ -0,0 +1,217 @@
+use super::industry::Cargo;
+use super::{BlockRegistry, Entity, World};
+use crate::client::EntityId;
+use crate::events::{Events, WorldEvent};
+use serde::{Deserialize, Serialize};
+
+/// Entities closer than this to a station, measured between centers, can dock.
+pub const DOCKING_DISTANCE: f32 = 200.0;
+
+/// Docking requires the entity to move slower than this, relative to the station.
+const DOCKING_SPEED: f32 = 1.0;
+
+/// Stations buy resources and blueprints for this share of their selling price.
+const BUYBACK: f32 = 0.8;
+
+/// Blueprints are sold to stations for this many credits per unit of building cost.
+const BLUEPRINT_PRICE_FACTOR: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Resource {
+    Ore,
+    Material,
+}
+
+/// Entity that trades resources and blueprints with entities docked to it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Station {
+    /// Resources the station has for sale.
+    pub stock: Cargo,
+    /// Price of one unit of ore, in credits.
+    pub ore_price: f32,
+    /// Price of one unit of building material, in credits.
+    pub material_price: f32,
+    pub blueprints: Vec<Blueprint>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Blueprint {
+    pub price: f32,
+    pub entity: Entity,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Trade {
+    Buy {
+        resource: Resource,
+        amount: f32,
+    },
+    Sell {
+        resource: Resource,
+        amount: f32,
+    },
+    /// Buys a copy of the blueprint at `index` of the station's blueprints.
+    BuyBlueprint {
+        index: usize,
+    },
+    /// Sells the design of the docked entity.
+    SellBlueprint,
+}
+
+impl Station {
+    pub fn price(&self, resource: Resource) -> f32 {
+        match resource {
+            Resource::Ore => self.ore_price,
+            Resource::Material => self.material_price,
+        }
+    }
+
+    /// Credits paid by the station for a blueprint.
+    pub fn blueprint_offer(entity: &Entity, blocks: &BlockRegistry) -> f32 {
+        let cost: f32 = entity.blocks.iter().map(|b| blocks.cost(b.as_ref())).sum();
+        cost * BLUEPRINT_PRICE_FACTOR * BUYBACK
+    }
+}
+
+impl Cargo {
+    pub fn amount(&self, resource: Resource) -> f32 {
+        match resource {
+            Resource::Ore => self.ore,
+            Resource::Material => self.material,
+        }
+    }
+
+    pub fn amount_mut(&mut self, resource: Resource) -> &mut f32 {
+        match resource {
+            Resource::Ore => &mut self.ore,
+            Resource::Material => &mut self.material,
+        }
+    }
+}
+
+/// Copy of the entity, as a station buys it, placed at the origin.
+pub fn design_of(entity: &Entity) -> Entity {
+    let mut design = entity.clone();
+    design.position = Default::default();
+    design.angle = Default::default();
+    design
+}
+
+/// Station the entity is docked to, if any.
+pub fn docked_station(world: &World, id: &EntityId) -> Option<u64> {
+    docked_to(world, id, |station| {
+        world.components.stations.get(station.get_id()).is_some()
+    })
+}
+
+/// Other entity accepted by `is_host` that the entity is docked to, if any.
+pub fn docked_to(world: &World, id: &EntityId, is_host: impl Fn(&Entity) -> bool) -> Option<u64> {
+    let entity = world.get_entity(id)?;
+
+    for relation in world.get_relations(id.grid_id, Default::default()) {
+        let grid = match world.grids.get(&relation.id) {
+            Some(grid) => grid,
+            None => continue,
+        };
+        for host in &grid.entities {
+            if host.get_id() == id.entity_id || !is_host(host) {
+                continue;
+            }
+            let position = relation.position + host.position;
+            let distance = (position.state - entity.position.state).length();
+            let speed = (position.velocity - entity.position.velocity).length();
+            if distance < DOCKING_DISTANCE && speed < DOCKING_SPEED {
+                return Some(host.get_id());
+            }
+        }
+    }
+    None
+}
+
+/// Exchanges credits for resources or blueprints, if the entity is docked to the station.
+/// Amounts are reduced to what the station has, the entity can carry and both can afford.
+/// Bought blueprints are announced in `events`.
+pub fn apply(
+    world: &mut World,
+    blocks: &BlockRegistry,
+    id: &EntityId,
+    station_id: u64,
+    trade: Trade,
+    events: &mut Events,
+) {
+    if docked_station(world, id) != Some(station_id) {
+        return;
+    }
+    let entity = match world.get_entity(id) {
+        Some(entity) => entity,
+        None => return,
+    };
+    let capacity: f32 = entity.blocks.iter().map(|b| b.cargo_capacity()).sum();
+    let design = match trade {
+        Trade::SellBlueprint => Some(design_of(entity)),
+        _ => None,
+    };
+
+    let components = &mut world.components;
+    let (station, cargo) = match (
+        components.stations.get_mut(station_id),
+        components.cargo.get_mut(id.entity_id),
+    ) {
+        (Some(station), Some(cargo)) => (station, cargo),
+        _ => return,
+    };
+
+    match trade {
+        Trade::Buy { resource, amount } => {
+            let price = station.price(resource);
+            let free = capacity - cargo.ore - cargo.material;
+            let amount = amount
+                .min(*station.stock.amount_mut(resource))
+                .min(free)
+                .min(cargo.credits / price)
+                .max(0.0);
+
+            *station.stock.amount_mut(resource) -= amount;
+            *cargo.amount_mut(resource) += amount;
+            cargo.credits -= amount * price;
+        }
+        Trade::Sell { resource, amount } => {
+            let amount = amount.min(*cargo.amount_mut(resource)).max(0.0);
+
+            *cargo.amount_mut(resource) -= amount;
+            *station.stock.amount_mut(resource) += amount;
+            cargo.credits += amount * station.price(resource) * BUYBACK;
+        }
+        Trade::BuyBlueprint { index } => {
+            if let Some(blueprint) = station.blueprints.get(index) {
+                if cargo.credits >= blueprint.price {
+                    cargo.credits -= blueprint.price;
+                    events.publish(WorldEvent::BlueprintBought {
+                        buyer: id.entity_id,
+                        station: station_id,
+                        index,
+                    });
+                }
+            }
+        }
+        Trade::SellBlueprint => {
+            let entity = match design {
+                Some(entity) => entity,
+                None => return,
+            };
+            let known = station
+                .blueprints
+                .iter()
+                .any(|b| b.entity.get_id() == entity.get_id());
+            if known {
+                return;
+            }
+            let offer = Station::blueprint_offer(&entity, blocks);
+            cargo.credits += offer;
+            station.blueprints.push(Blueprint {
+                price: offer / BUYBACK,
+                entity,
+            });
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use super::grid::construct_demo_world;
+use super::{trade, Faction, World};
+use crate::client::EntityId;
+use crate::ui::user_controls::Action;
+use serde::{Deserialize, Serialize};
+
+/// Steps of the tutorial, in the order they are taught.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Step {
+    Fly,
+    Rotate,
+    BuildThruster,
+    SaveBlueprint,
+    Dock,
+    Done,
+}
+
+impl Step {
+    /// Tells the player what to do to finish the step.
+    pub fn prompt(&self) -> &'static str {
+        match self {
+            Step::Fly => "fly with W A S D",
+            Step::Rotate => "rotate with Q and E",
+            Step::BuildThruster => "press B and drag a thruster from the toolbar onto the ship",
+            Step::SaveBlueprint => "press Ctrl+S and name the ship to save it as a blueprint",
+            Step::Dock => "fly to the station and touch it to dock",
+            Step::Done => "tutorial complete",
+        }
+    }
+
+    fn next(self) -> Step {
+        match self {
+            Step::Fly => Step::Rotate,
+            Step::Rotate => Step::BuildThruster,
+            Step::BuildThruster => Step::SaveBlueprint,
+            Step::SaveBlueprint => Step::Dock,
+            Step::Dock | Step::Done => Step::Done,
+        }
+    }
+}
+
+/// Progress of the tutorial scenario. Each step is finished only when the player does
+/// what its prompt asks.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tutorial {
+    pub step: Step,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        Tutorial { step: Step::Fly }
+    }
+}
+
+/// Demo world that teaches the controls, one step at a time.
+pub fn construct_tutorial_world(seed: u64) -> World {
+    let mut world = construct_demo_world(seed);
+    world.tutorial = Some(Tutorial::default());
+    world
+}
+
+/// Advances the tutorial when a player performs the action its current step asks for.
+pub fn observe(world: &mut World, action: &Action) {
+    let tutorial = match &mut world.tutorial {
+        Some(tutorial) => tutorial,
+        None => return,
+    };
+    let done = match (tutorial.step, action) {
+        (
+            Step::Fly,
+            Action::Accelerate {
+                direction,
+                throttle,
+            },
+        ) => *throttle > 0.0 && direction.length() > 0.0,
+        (
+            Step::Rotate,
+            Action::Rotate {
+                direction,
+                throttle,
+            },
+        ) => *throttle > 0.0 && *direction != 0.0,
+        (Step::BuildThruster, Action::JoinEntity { entity }) => entity
+            .blocks
+            .iter()
+            .any(|block| block.typetag_name() == "Thruster"),
+        _ => false,
+    };
+    if done {
+        tutorial.step = tutorial.step.next();
+    }
+}
+
+/// Advances the tutorial when the player saves a blueprint, which happens on the client.
+pub fn blueprint_saved(world: &mut World) {
+    if let Some(tutorial) = &mut world.tutorial {
+        if tutorial.step == Step::SaveBlueprint {
+            tutorial.step = tutorial.step.next();
+        }
+    }
+}
+
+/// Advances the tutorial once a player entity docks to a station.
+pub fn tick(world: &mut World) {
+    if world.tutorial.as_ref().map(|t| t.step) != Some(Step::Dock) {
+        return;
+    }
+    let docked = world.grids.values().any(|grid| {
+        grid.entities.iter().any(|entity| {
+            let id = EntityId::new(grid.get_id(), entity.get_id());
+            world.components.faction(entity.get_id()) == Faction::Player
+                && trade::docked_station(world, &id).is_some()
+        })
+    });
+    if let Some(tutorial) = &mut world.tutorial {
+        if docked {
+            tutorial.step = tutorial.step.next();
+        }
+    }
+}